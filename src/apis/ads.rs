@@ -1,4 +1,4 @@
-use super::{PaperResult, PaperSource, SourceError};
+use super::{http_client, parse_year, AuthorQuery, AuthorWorks, HttpTuning, JsonExt, PaperResult, PaperSource, SendExt, SourceError};
 use async_trait::async_trait;
 use serde::Deserialize;
 
@@ -7,20 +7,26 @@ const BASE_URL: &str = "https://api.adsabs.harvard.edu/v1";
 pub struct AdsClient {
     client: reqwest::Client,
     api_key: String,
+    normalize_names: bool,
 }
 
 impl AdsClient {
-    pub fn new(api_key: String) -> Self {
+    /// With `normalize_names`, author names are rewritten from ADS's
+    /// "Last, First M." to "First M. Last".
+    pub fn new(http: &HttpTuning, api_key: String, normalize_names: bool) -> Self {
         Self {
-            client: reqwest::Client::builder()
-                .user_agent("paper-search-mcp/0.1")
-                .build()
-                .unwrap(),
+            client: http_client(http, "paper-search-mcp/0.1"),
             api_key,
+            normalize_names,
         }
     }
 }
 
+#[derive(Deserialize)]
+struct AdsExport {
+    export: String,
+}
+
 #[derive(Deserialize)]
 struct AdsResponse {
     response: AdsBody,
@@ -41,20 +47,57 @@ struct AdsDoc {
     citation_count: Option<u32>,
 }
 
-fn doc_to_paper(doc: &AdsDoc) -> PaperResult {
+const NAME_SUFFIXES: &[&str] = &["jr", "jr.", "sr", "sr.", "ii", "iii", "iv"];
+
+/// Turn "Last, First M." into "First M. Last", keeping a generational suffix
+/// ("Smith, John, Jr." or "Smith, Jr., John") at the end. Names without a
+/// comma are only whitespace-normalized.
+fn normalize_author(name: &str) -> String {
+    let parts: Vec<String> = name
+        .split(',')
+        .map(|p| p.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|p| !p.is_empty())
+        .collect();
+    let (suffixes, names): (Vec<&String>, Vec<&String>) = parts
+        .iter()
+        .partition(|p| NAME_SUFFIXES.contains(&p.to_lowercase().as_str()));
+    let mut out: Vec<&str> = match names.as_slice() {
+        [last, first, rest @ ..] => std::iter::once(first.as_str())
+            .chain(rest.iter().map(|s| s.as_str()))
+            .chain(std::iter::once(last.as_str()))
+            .collect(),
+        other => other.iter().map(|s| s.as_str()).collect(),
+    };
+    out.extend(suffixes.iter().map(|s| s.as_str()));
+    out.join(" ")
+}
+
+/// Bibcodes start with the publication year ("2019ApJ...882L..12A").
+fn bibcode_year(bibcode: &str) -> Option<u32> {
+    bibcode.get(..4)?.parse().ok()
+}
+
+fn doc_to_paper(doc: &AdsDoc, normalize_names: bool) -> PaperResult {
     let bibcode = doc.bibcode.clone().unwrap_or_default();
+    let authors = doc.author.clone().unwrap_or_default();
     PaperResult {
         id: format!("ads:{}", bibcode),
         title: doc.title.as_ref().and_then(|t| t.first()).cloned().unwrap_or_default(),
-        authors: doc.author.clone().unwrap_or_default(),
+        authors: if normalize_names {
+            authors.iter().map(|a| normalize_author(a)).collect()
+        } else {
+            authors
+        },
         abstract_text: doc.abstract_text.clone(),
-        year: doc.year.as_ref().and_then(|y| y.parse::<u32>().ok()),
+        year: doc.year.as_deref().and_then(parse_year).or_else(|| bibcode_year(&bibcode)),
         source: "ads".to_string(),
         doi: doc.doi.as_ref().and_then(|d| d.first()).cloned(),
         arxiv_id: None,
         url: format!("https://ui.adsabs.harvard.edu/abs/{}", bibcode),
         pdf_url: None,
         citation_count: doc.citation_count,
+        orcids: Vec::new(),
+        subjects: Vec::new(),
     }
 }
 
@@ -63,60 +106,128 @@ impl PaperSource for AdsClient {
     fn name(&self) -> &str { "ads" }
 
     async fn search(&self, query: &str, max_results: u32) -> Result<Vec<PaperResult>, SourceError> {
+        self.search_page(query, max_results, 0).await
+    }
+
+    async fn search_page(&self, query: &str, max_results: u32, offset: u32) -> Result<Vec<PaperResult>, SourceError> {
         let rows = max_results.min(200).to_string();
+        let start = offset.to_string();
         let resp: AdsResponse = self.client
-            .get(&format!("{}/search/query", BASE_URL))
+            .get(format!("{}/search/query", BASE_URL))
             .header("Authorization", format!("Bearer {}", self.api_key))
             .query(&[
                 ("q", query),
                 ("fl", "bibcode,title,author,abstract,year,doi,citation_count"),
                 ("rows", rows.as_str()),
+                ("start", start.as_str()),
             ])
-            .send().await?.json().await?;
-        Ok(resp.response.docs.iter().map(doc_to_paper).collect())
+            .send_checked().await?.json_checked("ads").await?;
+        Ok(resp.response.docs.iter().map(|d| doc_to_paper(d, self.normalize_names)).collect())
     }
 
     async fn get_paper(&self, id: &str) -> Result<Option<PaperResult>, SourceError> {
         let bibcode = id.strip_prefix("ads:").unwrap_or(id);
         let q = format!("bibcode:{}", bibcode);
         let resp: AdsResponse = self.client
-            .get(&format!("{}/search/query", BASE_URL))
+            .get(format!("{}/search/query", BASE_URL))
             .header("Authorization", format!("Bearer {}", self.api_key))
             .query(&[
                 ("q", q.as_str()),
                 ("fl", "bibcode,title,author,abstract,year,doi,citation_count"),
             ])
-            .send().await?.json().await?;
-        Ok(resp.response.docs.first().map(doc_to_paper))
+            .send_checked().await?.json_checked("ads").await?;
+        Ok(resp.response.docs.first().map(|d| doc_to_paper(d, self.normalize_names)))
+    }
+
+    async fn search_by_author(&self, query: &AuthorQuery, max_results: u32) -> Result<AuthorWorks, SourceError> {
+        let mut q = format!("author:\"{}\"", query.name);
+        if query.has_years() {
+            let (min, max) = query.year_range();
+            q.push_str(&format!(" year:[{} TO {}]", min, max));
+        }
+        Ok(self.search(&q, max_results).await?.into())
+    }
+
+    async fn get_bibtex(&self, id: &str) -> Result<Option<String>, SourceError> {
+        let bibcode = id.strip_prefix("ads:").unwrap_or(id);
+        let resp: AdsExport = self.client
+            .post(format!("{}/export/bibtex", BASE_URL))
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .json(&serde_json::json!({ "bibcode": [bibcode] }))
+            .send_checked().await?
+            .error_for_status()?
+            .json_checked("ads").await?;
+        let bibtex = resp.export.trim();
+        Ok((!bibtex.is_empty()).then(|| bibtex.to_string()))
     }
 
     async fn get_citations(&self, id: &str) -> Result<Vec<PaperResult>, SourceError> {
         let bibcode = id.strip_prefix("ads:").unwrap_or(id);
         let q = format!("citations(bibcode:{})", bibcode);
         let resp: AdsResponse = self.client
-            .get(&format!("{}/search/query", BASE_URL))
+            .get(format!("{}/search/query", BASE_URL))
             .header("Authorization", format!("Bearer {}", self.api_key))
             .query(&[
                 ("q", q.as_str()),
                 ("fl", "bibcode,title,author,abstract,year,doi,citation_count"),
                 ("rows", "25"),
             ])
-            .send().await?.json().await?;
-        Ok(resp.response.docs.iter().map(doc_to_paper).collect())
+            .send_checked().await?.json_checked("ads").await?;
+        Ok(resp.response.docs.iter().map(|d| doc_to_paper(d, self.normalize_names)).collect())
     }
 
     async fn get_references(&self, id: &str) -> Result<Vec<PaperResult>, SourceError> {
         let bibcode = id.strip_prefix("ads:").unwrap_or(id);
         let q = format!("references(bibcode:{})", bibcode);
         let resp: AdsResponse = self.client
-            .get(&format!("{}/search/query", BASE_URL))
+            .get(format!("{}/search/query", BASE_URL))
             .header("Authorization", format!("Bearer {}", self.api_key))
             .query(&[
                 ("q", q.as_str()),
                 ("fl", "bibcode,title,author,abstract,year,doi,citation_count"),
                 ("rows", "25"),
             ])
-            .send().await?.json().await?;
-        Ok(resp.response.docs.iter().map(doc_to_paper).collect())
+            .send_checked().await?.json_checked("ads").await?;
+        Ok(resp.response.docs.iter().map(|d| doc_to_paper(d, self.normalize_names)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc(bibcode: &str, year: Option<&str>, authors: &[&str]) -> AdsDoc {
+        AdsDoc {
+            bibcode: Some(bibcode.to_string()),
+            title: Some(vec!["A Title".to_string()]),
+            author: Some(authors.iter().map(|a| a.to_string()).collect()),
+            abstract_text: None,
+            year: year.map(str::to_string),
+            doi: None,
+            citation_count: None,
+        }
+    }
+
+    #[test]
+    fn test_normalize_author_names() {
+        assert_eq!(normalize_author("Einstein, A."), "A. Einstein");
+        assert_eq!(normalize_author("Maldacena,  Juan   Martin"), "Juan Martin Maldacena");
+        assert_eq!(normalize_author("van der Waals, J. D."), "J. D. van der Waals");
+        assert_eq!(normalize_author("Smith, John, Jr."), "John Smith Jr.");
+        assert_eq!(normalize_author("Smith, Jr., John"), "John Smith Jr.");
+        assert_eq!(normalize_author("LIGO Scientific Collaboration"), "LIGO Scientific Collaboration");
+    }
+
+    #[test]
+    fn test_year_falls_back_to_bibcode() {
+        let paper = doc_to_paper(&doc("2019ApJ...882L..12A", Some("2019"), &["Einstein, A."]), true);
+        assert_eq!(paper.year, Some(2019));
+        assert_eq!(paper.authors, vec!["A. Einstein"]);
+
+        let paper = doc_to_paper(&doc("1998AdTMP...2..231M", None, &["Maldacena, J."]), false);
+        assert_eq!(paper.year, Some(1998));
+        assert_eq!(paper.authors, vec!["Maldacena, J."]);
+
+        assert_eq!(doc_to_paper(&doc("bad", None, &[]), true).year, None);
     }
 }
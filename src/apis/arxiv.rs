@@ -1,22 +1,44 @@
-use super::{PaperResult, PaperSource, SourceError};
+use super::{http_client, AuthorQuery, AuthorWorks, HttpTuning, PaperResult, PaperSource, SearchField, SendExt, SourceError, TextExt};
 use async_trait::async_trait;
 use quick_xml::events::Event;
 use quick_xml::Reader;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
 
 const BASE_URL: &str = "https://export.arxiv.org/api/query";
+/// Field prefixes arXiv's `search_query` understands.
+const FIELD_PREFIXES: &[&str] = &["ti", "au", "abs", "co", "jr", "cat", "rn", "id", "all"];
+
+/// arXiv asks clients to wait 3 seconds between requests.
+pub const DEFAULT_MIN_INTERVAL: Duration = Duration::from_secs(3);
 
 pub struct ArxivClient {
     client: reqwest::Client,
+    min_interval: Duration,
+    last_request: Mutex<Option<Instant>>,
 }
 
 impl ArxivClient {
-    pub fn new() -> Self {
+    /// `min_interval` is the minimum spacing between consecutive arXiv requests.
+    pub fn new(http: &HttpTuning, min_interval: Duration) -> Self {
         Self {
-            client: reqwest::Client::builder()
-                .user_agent("paper-search-mcp/0.1")
-                .build()
-                .unwrap(),
+            client: http_client(http, "paper-search-mcp/0.1"),
+            min_interval,
+            last_request: Mutex::new(None),
+        }
+    }
+
+    /// Wait until `min_interval` has passed since the previous request, then
+    /// record this one. Isolated requests are never delayed.
+    async fn throttle(&self) {
+        let mut last = self.last_request.lock().await;
+        if let Some(prev) = *last {
+            let elapsed = prev.elapsed();
+            if elapsed < self.min_interval {
+                tokio::time::sleep(self.min_interval - elapsed).await;
+            }
         }
+        *last = Some(Instant::now());
     }
 }
 
@@ -27,26 +49,44 @@ impl PaperSource for ArxivClient {
     }
 
     async fn search(&self, query: &str, max_results: u32) -> Result<Vec<PaperResult>, SourceError> {
-        let url = format!(
-            "{}?search_query=all:{}&start=0&max_results={}&sortBy=relevance&sortOrder=descending",
-            BASE_URL,
-            urlencoded(query),
-            max_results
-        );
-        let resp = self.client.get(&url).send().await?.text().await?;
-        // Respect rate limit: 1 req / 3s
-        tokio::time::sleep(std::time::Duration::from_secs(3)).await;
+        self.search_page(query, max_results, 0).await
+    }
+
+    async fn search_page(&self, query: &str, max_results: u32, offset: u32) -> Result<Vec<PaperResult>, SourceError> {
+        let url = search_url(query, offset, max_results);
+        self.throttle().await;
+        let resp = self.client.get(&url).send_checked().await?.text_checked("arxiv").await?;
         parse_atom_feed(&resp)
     }
 
     async fn get_paper(&self, id: &str) -> Result<Option<PaperResult>, SourceError> {
         let arxiv_id = id.strip_prefix("arxiv:").unwrap_or(id);
         let url = format!("{}?id_list={}", BASE_URL, arxiv_id);
-        let resp = self.client.get(&url).send().await?.text().await?;
+        self.throttle().await;
+        let resp = self.client.get(&url).send_checked().await?.text_checked("arxiv").await?;
         let results = parse_atom_feed(&resp)?;
         Ok(results.into_iter().next())
     }
 
+    async fn search_by_author(&self, query: &AuthorQuery, max_results: u32) -> Result<AuthorWorks, SourceError> {
+        let dates = if query.has_years() {
+            let (min, max) = query.year_range();
+            format!("+AND+submittedDate:%5B{}01010000+TO+{}12312359%5D", min, max)
+        } else {
+            String::new()
+        };
+        let url = format!(
+            "{}?search_query=au:%22{}%22{}&start=0&max_results={}&sortBy=submittedDate&sortOrder=descending",
+            BASE_URL,
+            urlencoded(&query.name),
+            dates,
+            max_results
+        );
+        self.throttle().await;
+        let resp = self.client.get(&url).send_checked().await?.text_checked("arxiv").await?;
+        Ok(parse_atom_feed(&resp)?.into())
+    }
+
     async fn get_citations(&self, _id: &str) -> Result<Vec<PaperResult>, SourceError> {
         Ok(vec![]) // arXiv doesn't provide citation data
     }
@@ -54,12 +94,148 @@ impl PaperSource for ArxivClient {
     async fn get_references(&self, _id: &str) -> Result<Vec<PaperResult>, SourceError> {
         Ok(vec![]) // arXiv doesn't provide reference data
     }
+
+    fn field_query(&self, query: &str, field: SearchField) -> Option<String> {
+        let prefix = match field {
+            SearchField::Title => "ti",
+            SearchField::Author => "au",
+            SearchField::Abstract => "abs",
+        };
+        Some(prefix_terms(query, prefix))
+    }
+
+    fn category_query(&self, query: &str, categories: &[String]) -> Option<String> {
+        let cats: Vec<String> = categories
+            .iter()
+            .map(|c| c.trim())
+            .filter(|c| !c.is_empty())
+            .map(|c| format!("cat:{}", c))
+            .collect();
+        let cats = match cats.len() {
+            0 => return Some(query.to_string()),
+            1 => cats[0].clone(),
+            _ => format!("({})", cats.join(" OR ")),
+        };
+        // Lead with a field prefix so `search_url` sends the query as-is
+        let query = if is_fielded(query) { query.to_string() } else { prefix_terms(query, "all") };
+        // Keep an alternation from binding to the first category alone
+        let query = if query.contains(" OR ") { format!("({})", query) } else { query };
+        Some(format!("{} AND {}", query, cats))
+    }
+}
+
+/// Split a query into quoted phrases, parentheses, boolean operators and
+/// bare words.
+fn query_tokens(query: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = query.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' => {
+                tokens.push(c.to_string());
+                chars.next();
+            }
+            '"' => {
+                chars.next();
+                let phrase: String = chars.by_ref().take_while(|&c| c != '"').collect();
+                let phrase = phrase.split_whitespace().collect::<Vec<_>>().join(" ");
+                if !phrase.is_empty() {
+                    tokens.push(format!("\"{}\"", phrase));
+                }
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || matches!(c, '(' | ')' | '"') {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(word);
+            }
+        }
+    }
+    tokens
+}
+
+/// Rewrite a query so each word or quoted phrase searches `prefix`, keeping
+/// `AND`/`OR`/`ANDNOT` and grouping, and joining adjacent terms with `AND`.
+fn prefix_terms(query: &str, prefix: &str) -> String {
+    let mut out: Vec<String> = Vec::new();
+    for token in query_tokens(query) {
+        let operator = match token.to_ascii_uppercase().as_str() {
+            op @ ("AND" | "OR" | "ANDNOT" | "NOT") => Some(if op == "NOT" { "ANDNOT".to_string() } else { op.to_string() }),
+            _ => None,
+        };
+        let follows_term = out.last().is_some_and(|last| {
+            !matches!(last.as_str(), "AND" | "OR" | "ANDNOT" | "(")
+        });
+        match operator {
+            // Operators only join two terms; drop leading or doubled ones
+            Some(op) => {
+                if follows_term {
+                    out.push(op);
+                }
+            }
+            None if token == ")" => {
+                if matches!(out.last().map(String::as_str), Some("AND" | "OR" | "ANDNOT")) {
+                    out.pop();
+                }
+                out.push(token);
+            }
+            None => {
+                if follows_term {
+                    out.push("AND".to_string());
+                }
+                if token == "(" {
+                    out.push(token);
+                } else {
+                    out.push(format!("{}:{}", prefix, token));
+                }
+            }
+        }
+    }
+    if matches!(out.last().map(String::as_str), Some("AND" | "OR" | "ANDNOT")) {
+        out.pop();
+    }
+    out.join(" ").replace("( ", "(").replace(" )", ")")
+}
+
+/// Whether `query` starts with an arXiv field prefix such as `ti:` or `cat:`.
+fn is_fielded(query: &str) -> bool {
+    query
+        .split_once(':')
+        .is_some_and(|(prefix, _)| FIELD_PREFIXES.contains(&prefix))
+}
+
+/// Build a relevance-sorted search URL. Queries that already start with a
+/// field prefix (e.g. from `field_query`) are sent as-is; others search `all:`.
+fn search_url(query: &str, offset: u32, max_results: u32) -> String {
+    let search_query = if is_fielded(query) {
+        urlencoded(query)
+    } else {
+        format!("all:{}", urlencoded(query))
+    };
+    format!(
+        "{}?search_query={}&start={}&max_results={}&sortBy=relevance&sortOrder=descending",
+        BASE_URL,
+        search_query,
+        offset,
+        max_results
+    )
 }
 
 fn urlencoded(s: &str) -> String {
     s.replace(' ', "+")
         .replace(':', "%3A")
         .replace('/', "%2F")
+        .replace('(', "%28")
+        .replace(')', "%29")
+        .replace('"', "%22")
 }
 
 fn parse_atom_feed(xml: &str) -> Result<Vec<PaperResult>, SourceError> {
@@ -194,6 +370,8 @@ fn parse_atom_feed(xml: &str) -> Result<Vec<PaperResult>, SourceError> {
                                 Some(link_pdf.clone())
                             },
                             citation_count: None,
+                            orcids: Vec::new(),
+                            subjects: Vec::new(),
                         });
                     }
                 } else if tag == "author" && in_author {
@@ -244,4 +422,64 @@ mod tests {
         assert_eq!(p.year, Some(2023));
         assert!(p.pdf_url.is_some());
     }
+
+    #[test]
+    fn test_title_field_query_url() {
+        let client = ArxivClient::new(&HttpTuning::default(), Duration::ZERO);
+        let query = client.field_query("black hole", SearchField::Title).unwrap();
+        let url = search_url(&query, 0, 10);
+        assert!(url.contains("search_query=ti%3Ablack+AND+ti%3Ahole&"), "{}", url);
+
+        let url = search_url("AdS/CFT duality", 20, 5);
+        assert!(url.contains("search_query=all:AdS%2FCFT+duality&start=20&max_results=5"), "{}", url);
+    }
+
+    #[test]
+    fn test_category_query_url() {
+        let client = ArxivClient::new(&HttpTuning::default(), Duration::ZERO);
+        let query = client.category_query("black hole", &["hep-th".to_string()]).unwrap();
+        assert_eq!(query, "all:black AND all:hole AND cat:hep-th");
+        let url = search_url(&query, 0, 10);
+        assert!(url.contains("search_query=all%3Ablack+AND+all%3Ahole+AND+cat%3Ahep-th&"), "{}", url);
+
+        let titled = client.field_query("transformer", SearchField::Title).unwrap();
+        let query = client.category_query(&titled, &["cs.LG".to_string(), "stat.ML".to_string()]).unwrap();
+        assert_eq!(query, "ti:transformer AND (cat:cs.LG OR cat:stat.ML)");
+        let url = search_url(&query, 0, 10);
+        assert!(url.contains("search_query=ti%3Atransformer+AND+%28cat%3Acs.LG+OR+cat%3Astat.ML%29&"), "{}", url);
+    }
+
+    #[test]
+    fn test_prefixed_queries_keep_operators_and_phrases() {
+        let client = ArxivClient::new(&HttpTuning::default(), Duration::ZERO);
+        let query = client.field_query("\"black hole\" OR wormhole", SearchField::Title).unwrap();
+        assert_eq!(query, "ti:\"black hole\" OR ti:wormhole");
+        let url = search_url(&query, 0, 10);
+        assert!(url.contains("search_query=ti%3A%22black+hole%22+OR+ti%3Awormhole&"), "{}", url);
+
+        let query = client.field_query("(maldacena or witten) and susskind", SearchField::Author).unwrap();
+        assert_eq!(query, "(au:maldacena OR au:witten) AND au:susskind");
+
+        let query = client.category_query("entropy OR \"page curve\"", &["hep-th".to_string()]).unwrap();
+        assert_eq!(query, "(all:entropy OR all:\"page curve\") AND cat:hep-th");
+
+        // Stray operators don't leave a dangling AND/OR
+        assert_eq!(prefix_terms("AND qubit OR", "all"), "all:qubit");
+    }
+
+    #[tokio::test]
+    async fn test_throttle_only_delays_back_to_back_requests() {
+        let client = ArxivClient::new(&HttpTuning::default(), Duration::from_millis(200));
+        let start = Instant::now();
+        client.throttle().await;
+        assert!(start.elapsed() < Duration::from_millis(100));
+        client.throttle().await;
+        assert!(start.elapsed() >= Duration::from_millis(200));
+
+        let unthrottled = ArxivClient::new(&HttpTuning::default(), Duration::ZERO);
+        let start = Instant::now();
+        unthrottled.throttle().await;
+        unthrottled.throttle().await;
+        assert!(start.elapsed() < Duration::from_millis(100));
+    }
 }
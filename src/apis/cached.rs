@@ -0,0 +1,181 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use super::{AuthorQuery, AuthorWorks, PaperResult, PaperSource, SearchField, SourceError};
+use crate::cache::DiskCache;
+
+/// Wraps a source so `get_paper` and search results are answered from the
+/// on-disk cache while fresh. Misses and not-found lookups always reach the
+/// source; everything else is passed through unchanged.
+pub struct CachedSource {
+    inner: Arc<dyn PaperSource>,
+    cache: Arc<DiskCache>,
+}
+
+impl CachedSource {
+    pub fn new(inner: Arc<dyn PaperSource>, cache: Arc<DiskCache>) -> Self {
+        Self { inner, cache }
+    }
+}
+
+#[async_trait]
+impl PaperSource for CachedSource {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn search(&self, query: &str, max_results: u32) -> Result<Vec<PaperResult>, SourceError> {
+        self.search_page(query, max_results, 0).await
+    }
+
+    async fn search_page(&self, query: &str, max_results: u32, offset: u32) -> Result<Vec<PaperResult>, SourceError> {
+        let arg = format!("{}\u{1f}{}\u{1f}{}", query, max_results, offset);
+        if let Some(results) = self.cache.get(self.name(), "search", &arg) {
+            return Ok(results);
+        }
+        let results = self.inner.search_page(query, max_results, offset).await?;
+        self.cache.put(self.name(), "search", &arg, &results);
+        Ok(results)
+    }
+
+    async fn get_paper(&self, id: &str) -> Result<Option<PaperResult>, SourceError> {
+        if let Some(paper) = self.cache.get(self.name(), "get_paper", id) {
+            return Ok(Some(paper));
+        }
+        let paper = self.inner.get_paper(id).await?;
+        if let Some(paper) = &paper {
+            self.cache.put(self.name(), "get_paper", id, paper);
+        }
+        Ok(paper)
+    }
+
+    async fn get_papers(&self, ids: &[String]) -> Result<Vec<Option<PaperResult>>, SourceError> {
+        let mut papers: Vec<Option<PaperResult>> = ids
+            .iter()
+            .map(|id| self.cache.get(self.name(), "get_paper", id))
+            .collect();
+        let missing: Vec<usize> = (0..ids.len()).filter(|&i| papers[i].is_none()).collect();
+        if missing.is_empty() {
+            return Ok(papers);
+        }
+        let missing_ids: Vec<String> = missing.iter().map(|&i| ids[i].clone()).collect();
+        let fetched = self.inner.get_papers(&missing_ids).await?;
+        for (i, paper) in missing.into_iter().zip(fetched) {
+            if let Some(paper) = &paper {
+                self.cache.put(self.name(), "get_paper", &ids[i], paper);
+            }
+            papers[i] = paper;
+        }
+        Ok(papers)
+    }
+
+    async fn get_citations(&self, id: &str) -> Result<Vec<PaperResult>, SourceError> {
+        self.inner.get_citations(id).await
+    }
+
+    async fn get_references(&self, id: &str) -> Result<Vec<PaperResult>, SourceError> {
+        self.inner.get_references(id).await
+    }
+
+    async fn get_citations_limited(&self, id: &str, limit: u32) -> Result<Vec<PaperResult>, SourceError> {
+        self.inner.get_citations_limited(id, limit).await
+    }
+
+    async fn get_references_limited(&self, id: &str, limit: u32) -> Result<Vec<PaperResult>, SourceError> {
+        self.inner.get_references_limited(id, limit).await
+    }
+
+    async fn search_by_author(&self, query: &AuthorQuery, max_results: u32) -> Result<AuthorWorks, SourceError> {
+        self.inner.search_by_author(query, max_results).await
+    }
+
+    fn language_query(&self, query: &str, language: &str) -> Option<String> {
+        self.inner.language_query(query, language)
+    }
+
+    fn field_query(&self, query: &str, field: SearchField) -> Option<String> {
+        self.inner.field_query(query, field)
+    }
+
+    fn category_query(&self, query: &str, categories: &[String]) -> Option<String> {
+        self.inner.category_query(query, categories)
+    }
+
+    async fn get_bibtex(&self, id: &str) -> Result<Option<String>, SourceError> {
+        self.inner.get_bibtex(id).await
+    }
+
+    async fn get_paper_with_raw(&self, id: &str) -> Result<Option<(PaperResult, Option<serde_json::Value>)>, SourceError> {
+        self.inner.get_paper_with_raw(id).await
+    }
+
+    /// Never answered from the cache, which would hide an unreachable API.
+    async fn ping(&self) -> Result<(), SourceError> {
+        self.inner.ping().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_source::MockSource;
+    use std::sync::atomic::Ordering;
+    use std::time::Duration;
+
+    /// Source that finds every ID except `missing:*`.
+    fn counting_source() -> MockSource {
+        MockSource::new("counting")
+            .on_search(|query| vec![paper(&format!("counting:{}", query))])
+            .on_paper(|id| (!id.starts_with("missing:")).then(|| paper(id)))
+    }
+
+    fn paper(id: &str) -> PaperResult {
+        PaperResult {
+            id: id.to_string(),
+            title: "Cached Paper".to_string(),
+            authors: vec![],
+            abstract_text: None,
+            year: Some(2024),
+            source: "counting".to_string(),
+            doi: None,
+            arxiv_id: None,
+            url: String::new(),
+            pdf_url: None,
+            citation_count: None,
+            orcids: Vec::new(),
+            subjects: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_repeated_lookups_served_from_disk() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let inner = Arc::new(counting_source());
+        let cache = Arc::new(DiskCache::new(tmp.path().to_path_buf(), Duration::from_secs(60)));
+        let source = CachedSource::new(inner.clone(), cache.clone());
+
+        assert_eq!(source.get_paper("counting:1").await.unwrap().unwrap().id, "counting:1");
+        assert_eq!(source.get_paper("counting:1").await.unwrap().unwrap().id, "counting:1");
+        assert_eq!(inner.lookups.load(Ordering::SeqCst), 1);
+
+        // Not-found lookups are retried
+        assert!(source.get_paper("missing:1").await.unwrap().is_none());
+        assert!(source.get_paper("missing:1").await.unwrap().is_none());
+        assert_eq!(inner.lookups.load(Ordering::SeqCst), 3);
+
+        source.search("holography", 10).await.unwrap();
+        source.search_page("holography", 10, 0).await.unwrap();
+        assert_eq!(inner.lookups.load(Ordering::SeqCst), 4);
+        source.search_page("holography", 10, 10).await.unwrap();
+        assert_eq!(inner.lookups.load(Ordering::SeqCst), 5);
+
+        // A fresh wrapper over the same directory still hits, until cleared
+        let reopened = CachedSource::new(inner.clone(), cache.clone());
+        reopened.get_paper("counting:1").await.unwrap();
+        assert_eq!(inner.lookups.load(Ordering::SeqCst), 5);
+        cache.clear().unwrap();
+        reopened.get_paper("counting:1").await.unwrap();
+        assert_eq!(inner.lookups.load(Ordering::SeqCst), 6);
+    }
+}
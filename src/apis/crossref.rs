@@ -1,22 +1,52 @@
-use super::{PaperResult, PaperSource, SourceError};
+use super::{from_raw, http_client, known_orcids, normalize_orcid, AuthorQuery, AuthorWorks, HttpTuning, JsonExt, PaperResult, PaperSource, SendExt, SourceError, TextExt};
 use async_trait::async_trait;
 use serde::Deserialize;
 
 const BASE_URL: &str = "https://api.crossref.org/works";
+const DOI_RESOLVER: &str = "https://doi.org";
+const COCI_URL: &str = "https://opencitations.net/index/coci/api/v1/citations";
+/// Maximum citing works resolved per `get_citations` call.
+const MAX_CITATIONS: usize = 25;
 
 pub struct CrossRefClient {
     client: reqwest::Client,
+    subtitles: bool,
 }
 
 impl CrossRefClient {
-    pub fn new() -> Self {
+    pub fn new(http: &HttpTuning) -> Self {
         Self {
-            client: reqwest::Client::builder()
-                .user_agent("paper-search-mcp/0.1 (mailto:research@example.com)")
-                .build()
-                .unwrap(),
+            client: http_client(http, "paper-search-mcp/0.1 (mailto:research@example.com)"),
+            subtitles: true,
         }
     }
+
+    /// Append CrossRef's `subtitle` to the title as "Title: Subtitle"
+    /// (on by default).
+    pub fn with_subtitles(mut self, enabled: bool) -> Self {
+        self.subtitles = enabled;
+        self
+    }
+
+    fn to_paper(&self, item: &CRItem) -> PaperResult {
+        item_to_paper(item, self.subtitles)
+    }
+
+    /// Fetch a DOI's CSL-JSON item via doi.org content negotiation, which
+    /// works for CrossRef, DataCite and mEDRA DOIs alike.
+    pub async fn get_csl_json(&self, doi: &str) -> Result<Option<serde_json::Value>, SourceError> {
+        let doi = doi.strip_prefix("doi:").unwrap_or(doi);
+        let resp = self.client
+            .get(format!("{}/{}", DOI_RESOLVER, doi))
+            .header("Accept", "application/vnd.citationstyles.csl+json")
+            .send_checked()
+            .await?;
+        if resp.status() == 404 {
+            return Ok(None);
+        }
+        let item: serde_json::Value = resp.error_for_status()?.json_checked("crossref").await?;
+        Ok(item.is_object().then_some(item))
+    }
 }
 
 #[derive(Deserialize)]
@@ -30,16 +60,19 @@ struct CRMessage {
     #[serde(rename = "DOI")]
     doi: Option<String>,
     title: Option<Vec<String>>,
+    subtitle: Option<Vec<String>>,
     author: Option<Vec<CRAuthor>>,
     #[serde(rename = "is-referenced-by-count")]
     citation_count: Option<u32>,
     published: Option<CRDate>,
+    reference: Option<Vec<CRReference>>,
 }
 #[derive(Deserialize)]
 struct CRItem {
     #[serde(rename = "DOI")]
     doi: Option<String>,
     title: Option<Vec<String>>,
+    subtitle: Option<Vec<String>>,
     author: Option<Vec<CRAuthor>>,
     #[serde(rename = "is-referenced-by-count")]
     citation_count: Option<u32>,
@@ -50,12 +83,110 @@ struct CRItem {
 struct CRAuthor {
     given: Option<String>,
     family: Option<String>,
+    #[serde(rename = "ORCID")]
+    orcid: Option<String>,
 }
 #[derive(Deserialize)]
 struct CRDate {
     #[serde(rename = "date-parts")]
     date_parts: Option<Vec<Vec<u32>>>,
 }
+/// One row of the OpenCitations COCI citations response.
+#[derive(Deserialize)]
+struct CociCitation {
+    citing: String,
+}
+
+/// Extract citing DOIs from a COCI response, tolerating `coci => ` and `doi:` prefixes.
+fn citing_dois(rows: &[CociCitation], limit: usize) -> Vec<String> {
+    rows.iter()
+        .map(|r| {
+            let doi = r.citing.trim();
+            let doi = doi.rsplit("=> ").next().unwrap_or(doi);
+            doi.strip_prefix("doi:").unwrap_or(doi).to_string()
+        })
+        .filter(|d| !d.is_empty())
+        .take(limit)
+        .collect()
+}
+
+#[derive(Deserialize)]
+struct CRUpdateResponse {
+    message: CRUpdateMessage,
+}
+#[derive(Deserialize)]
+struct CRUpdateMessage {
+    items: Option<Vec<CRUpdateNotice>>,
+}
+/// A work (e.g. a retraction notice) that updates other works.
+#[derive(Deserialize)]
+struct CRUpdateNotice {
+    #[serde(rename = "DOI")]
+    doi: Option<String>,
+    #[serde(rename = "update-to")]
+    update_to: Option<Vec<CRUpdate>>,
+}
+#[derive(Deserialize)]
+struct CRUpdate {
+    #[serde(rename = "DOI")]
+    doi: Option<String>,
+    #[serde(rename = "type")]
+    update_type: Option<String>,
+    label: Option<String>,
+}
+
+/// Retraction of a work, as recorded by a CrossRef update notice.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Retraction {
+    /// DOI of the retraction notice.
+    pub notice_doi: Option<String>,
+    /// Update type, e.g. "retraction" or "withdrawal".
+    pub reason: String,
+}
+
+/// Find a notice retracting or withdrawing `doi` among CrossRef update notices.
+fn find_retraction(notices: &[CRUpdateNotice], doi: &str) -> Option<Retraction> {
+    notices.iter().find_map(|notice| {
+        notice.update_to.as_ref()?.iter().find_map(|update| {
+            let targets_doi = update.doi.as_deref().is_some_and(|d| d.eq_ignore_ascii_case(doi));
+            let kind = update.update_type.as_deref()?.to_lowercase();
+            if targets_doi && matches!(kind.as_str(), "retraction" | "withdrawal" | "removal") {
+                Some(Retraction {
+                    notice_doi: notice.doi.clone(),
+                    reason: update.label.clone().unwrap_or(kind),
+                })
+            } else {
+                None
+            }
+        })
+    })
+}
+
+impl CrossRefClient {
+    /// Check whether a DOI has been retracted by looking for CrossRef works
+    /// that update it with a retraction/withdrawal relation.
+    pub async fn retraction_status(&self, doi: &str) -> Result<Option<Retraction>, SourceError> {
+        let doi = doi.strip_prefix("doi:").unwrap_or(doi);
+        let filter = format!("updates:{}", doi);
+        let resp: CRUpdateResponse = self.client
+            .get(BASE_URL)
+            .query(&[("filter", filter.as_str()), ("select", "DOI,update-to")])
+            .send_checked().await?.json_checked("crossref").await?;
+        Ok(find_retraction(&resp.message.items.unwrap_or_default(), doi))
+    }
+}
+
+#[derive(Deserialize)]
+struct CRReference {
+    key: Option<String>,
+    #[serde(rename = "DOI")]
+    doi: Option<String>,
+    #[serde(rename = "article-title")]
+    article_title: Option<String>,
+    author: Option<String>,
+    year: Option<String>,
+    unstructured: Option<String>,
+}
 #[derive(Deserialize)]
 struct CRLink {
     #[serde(rename = "URL")]
@@ -64,12 +195,23 @@ struct CRLink {
     content_type: Option<String>,
 }
 
-fn item_to_paper(item: &CRItem) -> PaperResult {
+/// Join the main title and, when enabled, the first subtitle with ": ".
+fn full_title(item: &CRItem, subtitles: bool) -> String {
+    let first = |parts: &Option<Vec<String>>| {
+        parts.as_ref()
+            .and_then(|p| p.first())
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+    };
+    match (first(&item.title), first(&item.subtitle).filter(|_| subtitles)) {
+        (Some(title), Some(subtitle)) => format!("{}: {}", title, subtitle),
+        (title, _) => title.unwrap_or_default(),
+    }
+}
+
+fn item_to_paper(item: &CRItem, subtitles: bool) -> PaperResult {
     let doi = item.doi.clone();
-    let title = item.title.as_ref()
-        .and_then(|t| t.first())
-        .cloned()
-        .unwrap_or_default();
+    let title = full_title(item, subtitles);
     let authors = item.author.as_ref()
         .map(|a| a.iter().map(|a| {
             format!("{} {}",
@@ -77,6 +219,10 @@ fn item_to_paper(item: &CRItem) -> PaperResult {
                 a.family.as_deref().unwrap_or("")).trim().to_string()
         }).collect())
         .unwrap_or_default();
+    let orcids = item.author.iter()
+        .flatten()
+        .map(|a| a.orcid.as_deref().and_then(normalize_orcid))
+        .collect();
     let year = item.published.as_ref()
         .and_then(|d| d.date_parts.as_ref())
         .and_then(|p| p.first())
@@ -101,6 +247,38 @@ fn item_to_paper(item: &CRItem) -> PaperResult {
         url,
         pdf_url,
         citation_count: item.citation_count,
+        orcids: known_orcids(orcids),
+        subjects: Vec::new(),
+    }
+}
+
+/// Map entry `position` (0-based) of `parent_doi`'s reference list to a
+/// `PaperResult` without a secondary lookup. Entries without a DOI are kept
+/// as unresolved references: their ID names the citing work and the entry's
+/// key (or position), since keys like "ref12" are only unique per work.
+fn reference_to_paper(parent_doi: &str, position: usize, r: &CRReference) -> PaperResult {
+    let id = match (&r.doi, &r.key) {
+        (Some(doi), _) => format!("doi:{}", doi),
+        (None, Some(key)) => format!("crossref-ref:{}#{}", parent_doi, key),
+        (None, None) => format!("crossref-ref:{}#{}", parent_doi, position + 1),
+    };
+    let title = r.article_title.clone()
+        .or_else(|| r.unstructured.clone())
+        .unwrap_or_default();
+    PaperResult {
+        id,
+        title,
+        authors: r.author.iter().cloned().collect(),
+        abstract_text: None,
+        year: r.year.as_ref().and_then(|y| y.get(..4)).and_then(|y| y.parse::<u32>().ok()),
+        source: "crossref".to_string(),
+        doi: r.doi.clone(),
+        arxiv_id: None,
+        url: r.doi.as_ref().map(|d| format!("https://doi.org/{}", d)).unwrap_or_default(),
+        pdf_url: None,
+        citation_count: None,
+        orcids: Vec::new(),
+        subjects: Vec::new(),
     }
 }
 
@@ -109,41 +287,226 @@ impl PaperSource for CrossRefClient {
     fn name(&self) -> &str { "crossref" }
 
     async fn search(&self, query: &str, max_results: u32) -> Result<Vec<PaperResult>, SourceError> {
+        self.search_page(query, max_results, 0).await
+    }
+
+    async fn search_page(&self, query: &str, max_results: u32, offset: u32) -> Result<Vec<PaperResult>, SourceError> {
         let rows = max_results.min(100).to_string();
+        let offset = offset.to_string();
         let resp: CRResponse = self.client
             .get(BASE_URL)
             .query(&[
                 ("query", query),
                 ("rows", rows.as_str()),
-                ("select", "DOI,title,author,published,is-referenced-by-count,link"),
+                ("offset", offset.as_str()),
+                ("select", "DOI,title,subtitle,author,published,is-referenced-by-count,link"),
             ])
-            .send().await?.json().await?;
-        Ok(resp.message.items.unwrap_or_default().iter().map(item_to_paper).collect())
+            .send_checked().await?.json_checked("crossref").await?;
+        Ok(resp.message.items.unwrap_or_default().iter().map(|item| self.to_paper(item)).collect())
+    }
+
+    /// DOI content negotiation: the registration agency renders BibTeX itself.
+    async fn get_bibtex(&self, id: &str) -> Result<Option<String>, SourceError> {
+        let doi = id.strip_prefix("doi:").unwrap_or(id);
+        let resp = self.client
+            .get(format!("{}/{}", DOI_RESOLVER, doi))
+            .header("Accept", "application/x-bibtex")
+            .send_checked()
+            .await?;
+        if resp.status() == 404 {
+            return Ok(None);
+        }
+        let bibtex = resp.error_for_status()?.text_checked("crossref").await?;
+        let bibtex = bibtex.trim();
+        Ok(bibtex.starts_with('@').then(|| bibtex.to_string()))
     }
 
     async fn get_paper(&self, id: &str) -> Result<Option<PaperResult>, SourceError> {
+        Ok(self.get_paper_with_raw(id).await?.map(|(paper, _)| paper))
+    }
+
+    /// The raw record is the response's `message`.
+    async fn get_paper_with_raw(&self, id: &str) -> Result<Option<(PaperResult, Option<serde_json::Value>)>, SourceError> {
         let doi = id.strip_prefix("doi:").unwrap_or(id);
         let url = format!("{}/{}", BASE_URL, doi);
-        let resp = self.client.get(&url).send().await?;
+        let resp = self.client.get(&url).send_checked().await?;
         if resp.status() == 404 { return Ok(None); }
-        let cr: CRResponse = resp.json().await?;
+        let mut body: serde_json::Value = resp.json_checked("crossref").await?;
+        let cr: CRResponse = from_raw(&body, "crossref")?;
         // Single work returns in message directly
         let item = CRItem {
             doi: cr.message.doi,
             title: cr.message.title,
+            subtitle: cr.message.subtitle,
             author: cr.message.author,
             citation_count: cr.message.citation_count,
             published: cr.message.published,
             link: None,
         };
-        Ok(Some(item_to_paper(&item)))
+        let raw = body.get_mut("message").map(serde_json::Value::take).unwrap_or(body);
+        Ok(Some((self.to_paper(&item), Some(raw))))
     }
 
-    async fn get_citations(&self, _id: &str) -> Result<Vec<PaperResult>, SourceError> {
-        Ok(vec![]) // CrossRef doesn't easily provide citing papers
+    async fn search_by_author(&self, query: &AuthorQuery, max_results: u32) -> Result<AuthorWorks, SourceError> {
+        let rows = max_results.min(100).to_string();
+        let mut params = vec![
+            ("query.author", query.name.clone()),
+            ("rows", rows),
+            ("select", "DOI,title,subtitle,author,published,is-referenced-by-count,link".to_string()),
+        ];
+        let mut filters = Vec::new();
+        if let Some(min) = query.year_min {
+            filters.push(format!("from-pub-date:{}", min));
+        }
+        if let Some(max) = query.year_max {
+            filters.push(format!("until-pub-date:{}-12-31", max));
+        }
+        if !filters.is_empty() {
+            params.push(("filter", filters.join(",")));
+        }
+        let resp: CRResponse = self.client
+            .get(BASE_URL)
+            .query(&params)
+            .send_checked().await?.json_checked("crossref").await?;
+        Ok(resp.message.items.unwrap_or_default().iter().map(|item| self.to_paper(item)).collect::<Vec<_>>().into())
+    }
+
+    async fn get_citations(&self, id: &str) -> Result<Vec<PaperResult>, SourceError> {
+        // CrossRef doesn't expose citing works; ask OpenCitations COCI for
+        // citing DOIs, then resolve them through CrossRef concurrently.
+        let doi = id.strip_prefix("doi:").unwrap_or(id);
+        let resp = self.client
+            .get(format!("{}/{}", COCI_URL, doi))
+            .send_checked().await?;
+        if resp.status() == 404 { return Ok(vec![]); }
+        let rows: Vec<CociCitation> = resp.json_checked("crossref").await?;
+
+        let lookups = citing_dois(&rows, MAX_CITATIONS)
+            .into_iter()
+            .map(|citing| async move { self.get_paper(&citing).await });
+        let mut papers = Vec::new();
+        for result in futures::future::join_all(lookups).await {
+            match result {
+                Ok(Some(paper)) => papers.push(paper),
+                Ok(None) => {}
+                Err(e) => tracing::warn!("CrossRef lookup of citing DOI failed: {}", e),
+            }
+        }
+        Ok(papers)
+    }
+
+    async fn get_references(&self, id: &str) -> Result<Vec<PaperResult>, SourceError> {
+        let doi = id.strip_prefix("doi:").unwrap_or(id);
+        let url = format!("{}/{}", BASE_URL, doi);
+        let resp = self.client.get(&url).send_checked().await?;
+        if resp.status() == 404 { return Ok(vec![]); }
+        let cr: CRResponse = resp.json_checked("crossref").await?;
+        Ok(cr.message.reference.unwrap_or_default()
+            .iter()
+            .enumerate()
+            .map(|(i, r)| reference_to_paper(doi, i, r))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_WORK: &str = r#"{
+        "message": {
+            "DOI": "10.1000/parent",
+            "title": ["Parent Paper"],
+            "reference": [
+                {"key": "ref1", "DOI": "10.1000/child", "article-title": "Child Paper", "author": "Doe", "year": "2019"},
+                {"key": "ref2", "unstructured": "A. Smith, Some Old Book, 1987.", "year": "1987"},
+                {"unstructured": "B. Jones, Lecture notes (unpublished)."}
+            ]
+        }
+    }"#;
+
+    #[test]
+    fn test_parse_coci_citing_dois() {
+        let json = r#"[
+            {"oci": "1-2", "citing": "10.1000/a", "cited": "10.1000/x", "creation": "2020-01"},
+            {"oci": "3-4", "citing": "coci => 10.1000/b", "cited": "10.1000/x", "creation": "2021-05"},
+            {"oci": "5-6", "citing": "10.1000/c", "cited": "10.1000/x", "creation": "2022-07"}
+        ]"#;
+        let rows: Vec<CociCitation> = serde_json::from_str(json).unwrap();
+        assert_eq!(citing_dois(&rows, 25), vec!["10.1000/a", "10.1000/b", "10.1000/c"]);
+        assert_eq!(citing_dois(&rows, 2).len(), 2);
+    }
+
+    #[test]
+    fn test_detects_retraction_relation() {
+        let json = r#"{
+            "message": {
+                "items": [
+                    {
+                        "DOI": "10.1000/notice",
+                        "update-to": [
+                            {"DOI": "10.1000/ORIGINAL", "type": "retraction", "label": "Retraction"}
+                        ]
+                    },
+                    {
+                        "DOI": "10.1000/erratum",
+                        "update-to": [{"DOI": "10.1000/other", "type": "correction"}]
+                    }
+                ]
+            }
+        }"#;
+        let resp: CRUpdateResponse = serde_json::from_str(json).unwrap();
+        let notices = resp.message.items.unwrap();
+
+        let retraction = find_retraction(&notices, "10.1000/original").unwrap();
+        assert_eq!(retraction.notice_doi.as_deref(), Some("10.1000/notice"));
+        assert_eq!(retraction.reason, "Retraction");
+        assert!(find_retraction(&notices, "10.1000/other").is_none());
+    }
+
+    #[test]
+    fn test_title_combines_subtitle() {
+        let json = r#"{
+            "message": {
+                "DOI": "10.1000/book",
+                "title": ["Gravitation"],
+                "subtitle": ["Foundations and Frontiers", "Second subtitle"]
+            }
+        }"#;
+        let cr: CRResponse = serde_json::from_str(json).unwrap();
+        let item = CRItem {
+            doi: cr.message.doi,
+            title: cr.message.title,
+            subtitle: cr.message.subtitle,
+            author: None,
+            citation_count: None,
+            published: None,
+            link: None,
+        };
+        assert_eq!(item_to_paper(&item, true).title, "Gravitation: Foundations and Frontiers");
+        assert_eq!(item_to_paper(&item, false).title, "Gravitation");
+
+        let untitled = CRItem { title: None, ..item };
+        assert_eq!(item_to_paper(&untitled, true).title, "");
     }
 
-    async fn get_references(&self, _id: &str) -> Result<Vec<PaperResult>, SourceError> {
-        Ok(vec![]) // Would need a separate request
+    #[test]
+    fn test_parse_references() {
+        let cr: CRResponse = serde_json::from_str(SAMPLE_WORK).unwrap();
+        let refs: Vec<PaperResult> = cr.message.reference.unwrap_or_default()
+            .iter()
+            .enumerate()
+            .map(|(i, r)| reference_to_paper("10.1000/parent", i, r))
+            .collect();
+        assert_eq!(refs.len(), 3);
+        assert_eq!(refs[0].id, "doi:10.1000/child");
+        assert_eq!(refs[0].title, "Child Paper");
+        assert_eq!(refs[0].year, Some(2019));
+        assert_eq!(refs[1].id, "crossref-ref:10.1000/parent#ref2");
+        assert!(refs[1].doi.is_none());
+        assert_eq!(refs[1].year, Some(1987));
+        // Neither DOI nor key: still listed, identified by position
+        assert_eq!(refs[2].id, "crossref-ref:10.1000/parent#3");
+        assert_eq!(refs[2].title, "B. Jones, Lecture notes (unpublished).");
     }
 }
@@ -1,4 +1,4 @@
-use super::{PaperResult, PaperSource, SourceError};
+use super::{http_client, page_for_offset, search_with_skip, HttpTuning, JsonExt, PaperResult, PaperSource, SendExt, SourceError};
 use async_trait::async_trait;
 use serde::Deserialize;
 
@@ -9,12 +9,9 @@ pub struct DoajClient {
 }
 
 impl DoajClient {
-    pub fn new() -> Self {
+    pub fn new(http: &HttpTuning) -> Self {
         Self {
-            client: reqwest::Client::builder()
-                .user_agent("paper-search-mcp/0.1")
-                .build()
-                .unwrap(),
+            client: http_client(http, "paper-search-mcp/0.1"),
         }
     }
 }
@@ -81,6 +78,8 @@ fn doaj_to_paper(r: &DoajResult) -> PaperResult {
             .and_then(|links| links.iter().find(|l| l.link_type.as_deref() == Some("fulltext")))
             .and_then(|l| l.url.clone()),
         citation_count: None,
+        orcids: Vec::new(),
+        subjects: Vec::new(),
     }
 }
 
@@ -89,11 +88,19 @@ impl PaperSource for DoajClient {
     fn name(&self) -> &str { "doaj" }
 
     async fn search(&self, query: &str, max_results: u32) -> Result<Vec<PaperResult>, SourceError> {
+        self.search_page(query, max_results, 0).await
+    }
+
+    async fn search_page(&self, query: &str, max_results: u32, offset: u32) -> Result<Vec<PaperResult>, SourceError> {
+        let page_size = max_results.min(100);
+        let Some(page) = page_for_offset(offset, page_size) else {
+            return search_with_skip(self, query, max_results, offset).await;
+        };
         let url = format!("{}/{}", BASE_URL, urlencoded(query));
         let resp: DoajResponse = self.client
             .get(&url)
-            .query(&[("pageSize", &max_results.min(100).to_string())])
-            .send().await?.json().await?;
+            .query(&[("pageSize", page_size.to_string()), ("page", page.to_string())])
+            .send_checked().await?.json_checked("doaj").await?;
         Ok(resp.results.unwrap_or_default().iter().map(doaj_to_paper).collect())
     }
 
@@ -105,6 +112,11 @@ impl PaperSource for DoajClient {
 
     async fn get_citations(&self, _id: &str) -> Result<Vec<PaperResult>, SourceError> { Ok(vec![]) }
     async fn get_references(&self, _id: &str) -> Result<Vec<PaperResult>, SourceError> { Ok(vec![]) }
+
+    fn language_query(&self, query: &str, language: &str) -> Option<String> {
+        // DOAJ records journal languages as upper-case ISO 639-1 codes.
+        Some(format!("({}) AND bibjson.journal.language:{}", query, language.to_uppercase()))
+    }
 }
 
 fn urlencoded(s: &str) -> String {
@@ -1,4 +1,4 @@
-use super::{PaperResult, PaperSource, SourceError};
+use super::{http_client, AuthorQuery, AuthorWorks, HttpTuning, JsonExt, PaperResult, PaperSource, SendExt, SourceError};
 use async_trait::async_trait;
 use serde::Deserialize;
 
@@ -9,12 +9,9 @@ pub struct EuropePmcClient {
 }
 
 impl EuropePmcClient {
-    pub fn new() -> Self {
+    pub fn new(http: &HttpTuning) -> Self {
         Self {
-            client: reqwest::Client::builder()
-                .user_agent("paper-search-mcp/0.1")
-                .build()
-                .unwrap(),
+            client: http_client(http, "paper-search-mcp/0.1"),
         }
     }
 }
@@ -65,23 +62,48 @@ fn epmc_to_paper(r: &EpmcResult) -> PaperResult {
             .unwrap_or_default(),
         pdf_url: None,
         citation_count: r.cited_by_count,
+        orcids: Vec::new(),
+        subjects: Vec::new(),
     }
 }
 
+/// Europe PMC's `LANG` field uses three-letter (ISO 639-2/B) codes. Map the
+/// common two-letter codes; anything else is passed through as given.
+fn epmc_language(code: &str) -> String {
+    let code = code.to_lowercase();
+    let mapped = match code.as_str() {
+        "en" => "eng",
+        "fr" => "fre",
+        "de" => "ger",
+        "es" => "spa",
+        "it" => "ita",
+        "pt" => "por",
+        "ru" => "rus",
+        "zh" => "chi",
+        "ja" => "jpn",
+        "ko" => "kor",
+        "nl" => "dut",
+        "pl" => "pol",
+        "tr" => "tur",
+        _ => return code,
+    };
+    mapped.to_string()
+}
+
 #[async_trait]
 impl PaperSource for EuropePmcClient {
     fn name(&self) -> &str { "europepmc" }
 
     async fn search(&self, query: &str, max_results: u32) -> Result<Vec<PaperResult>, SourceError> {
         let resp: EpmcResponse = self.client
-            .get(&format!("{}/search", BASE_URL))
+            .get(format!("{}/search", BASE_URL))
             .query(&[
                 ("query", query),
                 ("resultType", "core"),
                 ("format", "json"),
                 ("pageSize", &max_results.min(100).to_string()),
             ])
-            .send().await?.json().await?;
+            .send_checked().await?.json_checked("europepmc").await?;
         Ok(resp.result_list
             .map(|rl| rl.result.iter().map(epmc_to_paper).collect())
             .unwrap_or_default())
@@ -93,6 +115,19 @@ impl PaperSource for EuropePmcClient {
         Ok(results.into_iter().next())
     }
 
+    async fn search_by_author(&self, query: &AuthorQuery, max_results: u32) -> Result<AuthorWorks, SourceError> {
+        let mut q = format!("AUTH:\"{}\"", query.name);
+        if query.has_years() {
+            let (min, max) = query.year_range();
+            q.push_str(&format!(" AND PUB_YEAR:[{} TO {}]", min, max));
+        }
+        Ok(self.search(&q, max_results).await?.into())
+    }
+
+    fn language_query(&self, query: &str, language: &str) -> Option<String> {
+        Some(format!("({}) AND LANG:\"{}\"", query, epmc_language(language)))
+    }
+
     async fn get_citations(&self, id: &str) -> Result<Vec<PaperResult>, SourceError> {
         let pmid = id.strip_prefix("pmid:").unwrap_or(id);
         self.search(&format!("CITES:{}", pmid), 25).await
@@ -1,24 +1,41 @@
-use super::{PaperResult, PaperSource, SourceError};
+use super::{from_raw, http_client, known_orcids, normalize_orcid, page_for_offset, parse_year, search_with_skip, AuthorQuery, AuthorWorks, HttpTuning, JsonExt, PaperResult, PaperSource, SendExt, SourceError, TextExt};
 use async_trait::async_trait;
 use serde::Deserialize;
 
 const BASE_URL: &str = "https://inspirehep.net/api/literature";
+/// Metadata fields requested from search-style endpoints.
+const FIELDS: &str = "titles,authors,abstracts,dois,arxiv_eprints,citation_count,earliest_date,publication_info,documents";
 
 pub struct InspireClient {
     client: reqwest::Client,
+    base_url: String,
+    abstract_source: String,
 }
 
 impl InspireClient {
-    pub fn new() -> Self {
+    /// `abstract_source` is the preferred abstract provenance (e.g. "arXiv")
+    /// used when a record carries several abstract variants.
+    pub fn new(http: &HttpTuning, abstract_source: Option<String>) -> Self {
         Self {
-            client: reqwest::Client::builder()
-                .user_agent("paper-search-mcp/0.1")
-                .build()
-                .unwrap(),
+            client: http_client(http, "paper-search-mcp/0.1"),
+            base_url: BASE_URL.to_string(),
+            abstract_source: abstract_source.unwrap_or_else(|| DEFAULT_ABSTRACT_SOURCE.to_string()),
         }
     }
+
+    #[cfg(test)]
+    fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    fn to_paper(&self, hit: &InspireHit) -> PaperResult {
+        hit_to_paper(hit, &self.abstract_source)
+    }
 }
 
+const DEFAULT_ABSTRACT_SOURCE: &str = "arXiv";
+
 #[derive(Deserialize)]
 struct InspireResponse {
     hits: InspireHits,
@@ -43,8 +60,9 @@ struct InspireMetadata {
     dois: Option<Vec<InspireDoi>>,
     arxiv_eprints: Option<Vec<InspireArxiv>>,
     citation_count: Option<u32>,
-    urls: Option<Vec<InspireUrl>>,
     earliest_date: Option<String>,
+    publication_info: Option<Vec<InspirePublicationInfo>>,
+    documents: Option<Vec<InspireDocument>>,
 }
 
 #[derive(Deserialize)]
@@ -54,10 +72,18 @@ struct InspireTitle {
 #[derive(Deserialize)]
 struct InspireAuthor {
     full_name: String,
+    #[serde(default)]
+    ids: Vec<InspireAuthorId>,
+}
+#[derive(Deserialize)]
+struct InspireAuthorId {
+    schema: String,
+    value: String,
 }
 #[derive(Deserialize)]
 struct InspireAbstract {
     value: String,
+    source: Option<String>,
 }
 #[derive(Deserialize)]
 struct InspireDoi {
@@ -68,21 +94,44 @@ struct InspireArxiv {
     value: String,
 }
 #[derive(Deserialize)]
-struct InspireUrl {
-    value: String,
+struct InspirePublicationInfo {
+    year: Option<u32>,
+}
+#[derive(Deserialize)]
+struct InspireDocument {
+    url: String,
+    #[serde(default)]
+    fulltext: bool,
+}
+
+/// Pick the best abstract among language/source variants: the preferred
+/// source if it is English, otherwise the longest English one, otherwise the first.
+fn select_abstract<'a>(abstracts: &'a [InspireAbstract], preferred_source: &str) -> Option<&'a InspireAbstract> {
+    let english: Vec<&InspireAbstract> = abstracts
+        .iter()
+        .filter(|a| crate::index::language::detect("", Some(&a.value)) == Some("en"))
+        .collect();
+    english
+        .iter()
+        .find(|a| a.source.as_deref().is_some_and(|s| s.eq_ignore_ascii_case(preferred_source)))
+        .or_else(|| english.iter().max_by_key(|a| a.value.len()))
+        .copied()
+        .or_else(|| abstracts.first())
 }
 
-fn hit_to_paper(hit: &InspireHit) -> PaperResult {
+fn hit_to_paper(hit: &InspireHit, abstract_source: &str) -> PaperResult {
     let m = &hit.metadata;
     let title = m.titles.as_ref()
         .and_then(|t| t.first())
         .map(|t| t.title.clone())
         .unwrap_or_default();
-    let authors = m.authors.as_ref()
-        .map(|a| a.iter().map(|a| a.full_name.clone()).collect())
-        .unwrap_or_default();
+    let authors: Vec<String> = m.authors.iter().flatten().map(|a| a.full_name.clone()).collect();
+    let orcids: Vec<Option<String>> = m.authors.iter()
+        .flatten()
+        .map(|a| a.ids.iter().find(|id| id.schema == "ORCID").and_then(|id| normalize_orcid(&id.value)))
+        .collect();
     let abstract_text = m.abstracts.as_ref()
-        .and_then(|a| a.first())
+        .and_then(|a| select_abstract(a, abstract_source))
         .map(|a| a.value.clone());
     let doi = m.dois.as_ref()
         .and_then(|d| d.first())
@@ -90,9 +139,13 @@ fn hit_to_paper(hit: &InspireHit) -> PaperResult {
     let arxiv_id = m.arxiv_eprints.as_ref()
         .and_then(|a| a.first())
         .map(|a| a.value.clone());
-    let year = m.earliest_date.as_ref()
-        .and_then(|d| d.get(..4))
-        .and_then(|y| y.parse::<u32>().ok());
+    // `earliest_date` is YYYY, YYYY-MM or YYYY-MM-DD; journal year as fallback
+    let year = m.earliest_date.as_deref()
+        .and_then(parse_year)
+        .or_else(|| m.publication_info.as_ref()?.iter().find_map(|p| p.year));
+    let pdf_url = m.documents.as_ref()
+        .and_then(|docs| docs.iter().find(|d| d.fulltext))
+        .map(|d| d.url.clone());
     let url = format!("https://inspirehep.net/literature/{}", hit.id);
 
     PaperResult {
@@ -105,8 +158,10 @@ fn hit_to_paper(hit: &InspireHit) -> PaperResult {
         doi,
         arxiv_id,
         url,
-        pdf_url: None,
+        pdf_url,
         citation_count: m.citation_count,
+        orcids: known_orcids(orcids),
+        subjects: Vec::new(),
     }
 }
 
@@ -117,59 +172,199 @@ impl PaperSource for InspireClient {
     }
 
     async fn search(&self, query: &str, max_results: u32) -> Result<Vec<PaperResult>, SourceError> {
+        self.search_page(query, max_results, 0).await
+    }
+
+    async fn search_page(&self, query: &str, max_results: u32, offset: u32) -> Result<Vec<PaperResult>, SourceError> {
+        let Some(page) = page_for_offset(offset, max_results) else {
+            return search_with_skip(self, query, max_results, offset).await;
+        };
         let size = max_results.to_string();
+        let page = page.to_string();
         let resp: InspireResponse = self.client
-            .get(BASE_URL)
+            .get(&self.base_url)
             .query(&[
                 ("q", query),
                 ("size", size.as_str()),
-                ("fields", "titles,authors,abstracts,dois,arxiv_eprints,citation_count,urls,earliest_date"),
+                ("page", page.as_str()),
+                ("fields", FIELDS),
             ])
-            .send()
+            .send_checked()
             .await?
-            .json()
+            .json_checked("inspire")
             .await?;
-        Ok(resp.hits.hits.iter().map(hit_to_paper).collect())
+        Ok(resp.hits.hits.iter().map(|h| self.to_paper(h)).collect())
     }
 
     async fn get_paper(&self, id: &str) -> Result<Option<PaperResult>, SourceError> {
+        Ok(self.get_paper_with_raw(id).await?.map(|(paper, _)| paper))
+    }
+
+    async fn get_paper_with_raw(&self, id: &str) -> Result<Option<(PaperResult, Option<serde_json::Value>)>, SourceError> {
+        let recid = id.strip_prefix("inspire:").unwrap_or(id);
+        let url = format!("{}/{}", self.base_url, recid);
+        let resp = self.client.get(&url).send_checked().await?;
+        if resp.status() == 404 {
+            return Ok(None);
+        }
+        let raw: serde_json::Value = resp.json_checked("inspire").await?;
+        let hit: InspireHit = from_raw(&raw, "inspire")?;
+        Ok(Some((self.to_paper(&hit), Some(raw))))
+    }
+
+    async fn search_by_author(&self, query: &AuthorQuery, max_results: u32) -> Result<AuthorWorks, SourceError> {
+        let mut q = format!("a {}", query.name);
+        if query.has_years() {
+            let (min, max) = query.year_range();
+            q.push_str(&format!(" and date {}->{}", min, max));
+        }
+        Ok(self.search(&q, max_results).await?.into())
+    }
+
+    async fn get_bibtex(&self, id: &str) -> Result<Option<String>, SourceError> {
         let recid = id.strip_prefix("inspire:").unwrap_or(id);
-        let url = format!("{}/{}", BASE_URL, recid);
-        let resp = self.client.get(&url).send().await?;
+        let url = format!("{}/{}", self.base_url, recid);
+        let resp = self.client
+            .get(&url)
+            .query(&[("format", "bibtex")])
+            .send_checked()
+            .await?;
         if resp.status() == 404 {
             return Ok(None);
         }
-        let hit: InspireHit = resp.json().await?;
-        Ok(Some(hit_to_paper(&hit)))
+        let bibtex = resp.error_for_status()?.text_checked("inspire").await?;
+        let bibtex = bibtex.trim();
+        Ok((!bibtex.is_empty()).then(|| bibtex.to_string()))
     }
 
     async fn get_citations(&self, id: &str) -> Result<Vec<PaperResult>, SourceError> {
         let recid = id.strip_prefix("inspire:").unwrap_or(id);
         let q = format!("refersto:recid:{}", recid);
         let resp: InspireResponse = self.client
-            .get(BASE_URL)
+            .get(&self.base_url)
             .query(&[
                 ("q", q.as_str()),
                 ("size", "25"),
-                ("fields", "titles,authors,abstracts,dois,arxiv_eprints,citation_count,urls,earliest_date"),
+                ("fields", FIELDS),
             ])
-            .send()
+            .send_checked()
             .await?
-            .json()
+            .json_checked("inspire")
             .await?;
-        Ok(resp.hits.hits.iter().map(hit_to_paper).collect())
+        Ok(resp.hits.hits.iter().map(|h| self.to_paper(h)).collect())
     }
 
     async fn get_references(&self, id: &str) -> Result<Vec<PaperResult>, SourceError> {
         let recid = id.strip_prefix("inspire:").unwrap_or(id);
-        let url = format!("{}/{}/references", BASE_URL, recid);
+        let url = format!("{}/{}/references", self.base_url, recid);
         let resp: InspireResponse = self.client
             .get(&url)
-            .query(&[("fields", "titles,authors,abstracts,dois,arxiv_eprints,citation_count,urls,earliest_date")])
-            .send()
+            .query(&[("fields", FIELDS)])
+            .send_checked()
             .await?
-            .json()
+            .json_checked("inspire")
             .await?;
-        Ok(resp.hits.hits.iter().map(hit_to_paper).collect())
+        Ok(resp.hits.hits.iter().map(|h| self.to_paper(h)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_http::{self, Response};
+
+    const SAMPLE_BIBTEX: &str = "@article{Maldacena:1997re,\n    author = \"Maldacena, Juan Martin\",\n    title = \"{The Large N limit of superconformal field theories and supergravity}\"\n}";
+
+    /// Stand-in for the INSPIRE API: answers `?format=bibtex` requests with
+    /// `SAMPLE_BIBTEX` and anything else with 404.
+    async fn mock_inspire() -> String {
+        let base = mock_http::serve(|request| {
+            Some(if request.target == "/api/literature/4221?format=bibtex" {
+                Response::new(200).header("Content-Type", "application/x-bibtex").body(SAMPLE_BIBTEX)
+            } else {
+                Response::new(404)
+            })
+        })
+        .await;
+        format!("{}/api/literature", base)
+    }
+
+    #[tokio::test]
+    async fn test_native_bibtex_for_inspire_id() {
+        let base = mock_inspire().await;
+        let client = InspireClient::new(&HttpTuning::default(), None).with_base_url(base);
+
+        let bibtex = client.get_bibtex("inspire:4221").await.unwrap();
+        assert_eq!(bibtex.as_deref(), Some(SAMPLE_BIBTEX));
+
+        let missing = client.get_bibtex("inspire:999").await.unwrap();
+        assert!(missing.is_none());
+    }
+
+    const SAMPLE_HIT: &str = r#"{
+        "id": "1234",
+        "metadata": {
+            "titles": [{"title": "Multi-abstract Paper"}],
+            "abstracts": [
+                {"source": "Springer", "value": "Мы изучаем энтропию запутанности в голографических теориях поля."},
+                {"source": "Springer", "value": "Wir untersuchen die Entropie der Verschraenkung in holographischen Feldtheorien und vergleichen sie mit dem Flaechengesetz fuer schwarze Loecher."},
+                {"source": "Springer", "value": "This is a short English abstract about the entropy of black holes."},
+                {"source": "arXiv", "value": "We study entanglement entropy in holographic field theories and compare it with the area law."}
+            ]
+        }
+    }"#;
+
+    #[test]
+    fn test_prefers_configured_abstract_source() {
+        let hit: InspireHit = serde_json::from_str(SAMPLE_HIT).unwrap();
+        let paper = hit_to_paper(&hit, "arXiv");
+        assert_eq!(paper.abstract_text.as_deref(), Some("We study entanglement entropy in holographic field theories and compare it with the area law."));
+    }
+
+    #[test]
+    fn test_pdf_url_from_first_fulltext_document() {
+        let hit: InspireHit = serde_json::from_str(SAMPLE_HIT).unwrap();
+        assert!(hit_to_paper(&hit, "arXiv").pdf_url.is_none());
+
+        let hit: InspireHit = serde_json::from_str(r#"{
+            "id": "5678",
+            "metadata": {
+                "titles": [{"title": "With Documents"}],
+                "documents": [
+                    {"key": "erratum.pdf", "url": "https://inspirehep.net/files/erratum.pdf"},
+                    {"key": "paper.pdf", "url": "https://inspirehep.net/files/paper.pdf", "fulltext": true}
+                ]
+            }
+        }"#).unwrap();
+        let paper = hit_to_paper(&hit, "arXiv");
+        assert_eq!(paper.pdf_url.as_deref(), Some("https://inspirehep.net/files/paper.pdf"));
+    }
+
+    #[test]
+    fn test_year_from_earliest_date_or_publication_info() {
+        let year = |metadata: &str| {
+            let hit: InspireHit = serde_json::from_str(&format!(r#"{{"id": "1", "metadata": {}}}"#, metadata)).unwrap();
+            hit_to_paper(&hit, "arXiv").year
+        };
+        assert_eq!(year(r#"{"earliest_date": "1997-11-27"}"#), Some(1997));
+        assert_eq!(year(r#"{"earliest_date": "1997-11"}"#), Some(1997));
+        assert_eq!(year(r#"{"earliest_date": "1997"}"#), Some(1997));
+        assert_eq!(year(r#"{"publication_info": [{"journal_title": "Adv.Theor.Math.Phys."}, {"year": 1998}]}"#), Some(1998));
+        assert_eq!(year(r#"{"earliest_date": "n.d.", "publication_info": [{"year": 1998}]}"#), Some(1998));
+        assert_eq!(year(r#"{}"#), None);
+    }
+
+    #[test]
+    fn test_falls_back_to_longest_english_abstract() {
+        let hit: InspireHit = serde_json::from_str(SAMPLE_HIT).unwrap();
+        let paper = hit_to_paper(&hit, "Elsevier");
+        assert_eq!(paper.abstract_text.as_deref(), Some("We study entanglement entropy in holographic field theories and compare it with the area law."));
+
+        let mut hit = hit;
+        if let Some(abstracts) = hit.metadata.abstracts.as_mut() {
+            abstracts.pop();
+        }
+        let paper = hit_to_paper(&hit, "arXiv");
+        assert_eq!(paper.abstract_text.as_deref(), Some("This is a short English abstract about the entropy of black holes."));
     }
 }
@@ -1,18 +1,88 @@
 pub mod ads;
 pub mod arxiv;
+pub mod cached;
 pub mod crossref;
 pub mod doaj;
 pub mod europepmc;
 pub mod inspire;
 pub mod openalex;
+pub mod pubmed;
+pub mod record;
+pub mod repec;
+pub mod scholar;
 pub mod semantic_scholar;
 pub mod unpaywall;
 pub mod vixra;
+pub mod zenodo;
+
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::time::Duration;
 
 use async_trait::async_trait;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+static STRICT_JSON: AtomicBool = AtomicBool::new(false);
+
+/// Report response fields our types don't model (schema drift) instead of
+/// silently ignoring them. Off by default.
+pub fn set_strict_json(enabled: bool) {
+    STRICT_JSON.store(enabled, Ordering::Relaxed);
+}
+
+static HTTP_RETRIES: AtomicU32 = AtomicU32::new(3);
+static HTTP_RETRY_BASE_MS: AtomicU64 = AtomicU64::new(200);
+
+/// How `request_with_retry` handles transient network errors: up to `retries`
+/// extra attempts, waiting `base_delay`, then twice that, and so on (see
+/// `backoff_delay`).
+pub fn set_retry_policy(retries: u32, base_delay: Duration) {
+    HTTP_RETRIES.store(retries, Ordering::Relaxed);
+    HTTP_RETRY_BASE_MS.store(base_delay.as_millis() as u64, Ordering::Relaxed);
+}
+
+/// Connection tuning shared by every source's HTTP client.
+///
+/// - `pool_max_idle_per_host`: idle keep-alive connections kept per host.
+/// - `pool_idle_timeout`: how long an idle pooled connection is kept.
+/// - `http2_keep_alive_interval`: HTTP/2 PING interval keeping connections warm (`None` disables).
+/// - `tcp_keepalive`: TCP keep-alive probe interval (`None` disables).
+#[derive(Debug, Clone)]
+pub struct HttpTuning {
+    pub pool_max_idle_per_host: usize,
+    pub pool_idle_timeout: Duration,
+    pub http2_keep_alive_interval: Option<Duration>,
+    pub tcp_keepalive: Option<Duration>,
+}
+
+impl Default for HttpTuning {
+    fn default() -> Self {
+        Self {
+            pool_max_idle_per_host: 8,
+            pool_idle_timeout: Duration::from_secs(90),
+            http2_keep_alive_interval: Some(Duration::from_secs(30)),
+            tcp_keepalive: Some(Duration::from_secs(60)),
+        }
+    }
+}
+
+/// Build an HTTP client with the given user agent and connection tuning.
+pub fn http_client(tuning: &HttpTuning, user_agent: impl Into<String>) -> reqwest::Client {
+    reqwest::Client::builder()
+        .user_agent(user_agent.into())
+        .pool_max_idle_per_host(tuning.pool_max_idle_per_host)
+        .pool_idle_timeout(tuning.pool_idle_timeout)
+        .http2_keep_alive_interval(tuning.http2_keep_alive_interval)
+        .http2_keep_alive_while_idle(tuning.http2_keep_alive_interval.is_some())
+        .tcp_keepalive(tuning.tcp_keepalive)
+        .build()
+        .unwrap()
+}
+
+/// Longest we will wait on a `Retry-After` before retrying a rate-limited request.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(10);
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PaperResult {
     pub id: String,
@@ -26,6 +96,21 @@ pub struct PaperResult {
     pub url: String,
     pub pdf_url: Option<String>,
     pub citation_count: Option<u32>,
+    /// ORCID iD of each entry in `authors`, where the source gives them;
+    /// empty when it gives none.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub orcids: Vec<Option<String>>,
+    /// Subject headings the source assigns (e.g. PubMed's MeSH descriptors);
+    /// empty when it gives none.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub subjects: Vec<String>,
+}
+
+impl PaperResult {
+    /// The ORCID iD of the `i`th author, if known.
+    pub fn orcid(&self, i: usize) -> Option<&str> {
+        self.orcids.get(i)?.as_deref()
+    }
 }
 
 #[derive(Debug, Error)]
@@ -36,15 +121,526 @@ pub enum SourceError {
     Parse(String),
     #[error("API error: {0}")]
     Api(String),
-    #[error("Missing API key: {0}")]
-    MissingKey(String),
+    #[error("Rate limited (retry after {retry_after:?})")]
+    RateLimited { retry_after: Option<Duration> },
+}
+
+/// Extract a plausible publication year: the first standalone run of four
+/// digits between 1500 and 2100 (e.g. "2019", "Nature, 1935 - APS").
+pub fn parse_year(text: &str) -> Option<u32> {
+    text.split(|c: char| !c.is_ascii_digit())
+        .filter(|t| t.len() == 4)
+        .filter_map(|t| t.parse::<u32>().ok())
+        .find(|y| (1500..=2100).contains(y))
+}
+
+/// Reduce an ORCID iD or ORCID URL ("https://orcid.org/0000-0002-1825-0097")
+/// to the bare uppercase iD, or `None` if it isn't one.
+pub fn normalize_orcid(value: &str) -> Option<String> {
+    let id = value.trim().rsplit('/').next()?.to_ascii_uppercase();
+    let digits: String = id.chars().filter(|c| *c != '-').collect();
+    (digits.len() == 16
+        && digits[..15].chars().all(|c| c.is_ascii_digit())
+        && digits.ends_with(|c: char| c.is_ascii_digit() || c == 'X'))
+    .then_some(id)
+}
+
+/// Per-author ORCID iDs for `PaperResult::orcids`: empty when none are known.
+pub fn known_orcids(orcids: Vec<Option<String>>) -> Vec<Option<String>> {
+    if orcids.iter().any(Option::is_some) { orcids } else { Vec::new() }
+}
+
+/// Reduce an HTML fragment to plain text: drop tags, decode entities, and
+/// collapse whitespace. Used by the scraping sources before text reaches output.
+pub fn strip_html(fragment: &str) -> String {
+    let text: String = scraper::Html::parse_fragment(fragment)
+        .root_element()
+        .text()
+        .collect();
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Emulate an offset by fetching `offset + max_results` from the start and skipping.
+pub async fn search_with_skip<S: PaperSource + ?Sized>(
+    source: &S,
+    query: &str,
+    max_results: u32,
+    offset: u32,
+) -> Result<Vec<PaperResult>, SourceError> {
+    let mut results = source.search(query, offset.saturating_add(max_results)).await?;
+    let skip = (offset as usize).min(results.len());
+    Ok(results.split_off(skip))
+}
+
+/// Translate an offset into a 1-based page number for page-numbered APIs,
+/// or `None` when the offset does not fall on a page boundary.
+pub fn page_for_offset(offset: u32, page_size: u32) -> Option<u32> {
+    if page_size == 0 || !offset.is_multiple_of(page_size) {
+        None
+    } else {
+        Some(offset / page_size + 1)
+    }
+}
+
+/// Send a request, retrying with exponential backoff when it fails to connect,
+/// times out, or the connection drops before a response arrives. HTTP error
+/// statuses are responses, not failures, so a 4xx is never retried here.
+pub async fn request_with_retry(request: reqwest::RequestBuilder) -> Result<reqwest::Response, reqwest::Error> {
+    let retries = HTTP_RETRIES.load(Ordering::Relaxed);
+    let base_delay = Duration::from_millis(HTTP_RETRY_BASE_MS.load(Ordering::Relaxed));
+    send_with_retries(request, retries, base_delay).await
+}
+
+/// `request_with_retry` with an explicit policy instead of the global one.
+async fn send_with_retries(
+    request: reqwest::RequestBuilder,
+    retries: u32,
+    base_delay: Duration,
+) -> Result<reqwest::Response, reqwest::Error> {
+    let mut request = request;
+    let mut attempt = 0;
+    loop {
+        // Streaming bodies can't be cloned; those requests get a single attempt.
+        let next = if attempt < retries { request.try_clone() } else { None };
+        match request.send().await {
+            Err(e) if is_transient(&e) && next.is_some() => {
+                attempt += 1;
+                let delay = backoff_delay(base_delay, attempt);
+                tracing::debug!("Transient HTTP error (attempt {}/{}), retrying in {:?}: {}", attempt, retries, delay, e);
+                tokio::time::sleep(delay).await;
+                request = next.unwrap();
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Wait before retry number `attempt` (1-based): `base` doubled for each
+/// earlier retry, plus up to 50% random jitter so clients that failed
+/// together don't retry in lockstep.
+pub fn backoff_delay(base: Duration, attempt: u32) -> Duration {
+    use std::hash::{BuildHasher, Hasher};
+    let delay = base.saturating_mul(1 << attempt.saturating_sub(1).min(16));
+    // RandomState is seeded randomly, which is all the jitter needs
+    let jitter = std::collections::hash_map::RandomState::new().build_hasher().finish() % 1000;
+    delay + delay.mul_f64(jitter as f64 / 2000.0)
+}
+
+fn is_transient(err: &reqwest::Error) -> bool {
+    err.is_connect() || err.is_timeout() || err.is_request()
+}
+
+/// Send a request through `request_with_retry`, then retry once after the
+/// server's `Retry-After` (capped at 10s) when it answers 429 Too Many Requests.
+#[async_trait]
+pub trait SendExt {
+    async fn send_checked(self) -> Result<reqwest::Response, SourceError>;
+}
+
+#[async_trait]
+impl SendExt for reqwest::RequestBuilder {
+    async fn send_checked(self) -> Result<reqwest::Response, SourceError> {
+        let retry = self.try_clone();
+        let resp = request_with_retry(self).await?;
+        if resp.status() != reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Ok(resp);
+        }
+        let retry_after = parse_retry_after(&resp);
+        let Some(retry) = retry else {
+            return Err(SourceError::RateLimited { retry_after });
+        };
+        tokio::time::sleep(retry_after.unwrap_or(Duration::from_secs(1)).min(MAX_RETRY_AFTER)).await;
+        let resp = request_with_retry(retry).await?;
+        if resp.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(SourceError::RateLimited { retry_after: parse_retry_after(&resp) });
+        }
+        Ok(resp)
+    }
+}
+
+/// Deserialize a source's JSON response body. In strict mode, fields the
+/// target type ignores are logged as warnings so schema drift gets noticed.
+#[async_trait]
+pub trait JsonExt {
+    async fn json_checked<T: DeserializeOwned + Send>(self, source: &str) -> Result<T, SourceError>;
+}
+
+#[async_trait]
+impl JsonExt for reqwest::Response {
+    async fn json_checked<T: DeserializeOwned + Send>(self, source: &str) -> Result<T, SourceError> {
+        let (url, status) = (self.url().clone(), self.status().as_u16());
+        let body = self.bytes().await?;
+        record::record(source, &url, status, &body, "json");
+        let (value, unknown) = parse_json(&body, STRICT_JSON.load(Ordering::Relaxed))?;
+        for path in &unknown {
+            tracing::warn!("{} response has unknown field `{}`", source, path);
+        }
+        Ok(value)
+    }
+}
+
+/// Read a text (XML/HTML) response body, recording it like JSON bodies.
+#[async_trait]
+pub trait TextExt {
+    async fn text_checked(self, source: &str) -> Result<String, SourceError>;
+}
+
+#[async_trait]
+impl TextExt for reqwest::Response {
+    async fn text_checked(self, source: &str) -> Result<String, SourceError> {
+        let (url, status) = (self.url().clone(), self.status().as_u16());
+        let body = self.text().await?;
+        record::record(source, &url, status, body.as_bytes(), "txt");
+        Ok(body)
+    }
+}
+
+/// Deserialize `body`. With `strict`, also return the paths of fields `T`
+/// ignored; otherwise unknown fields are skipped without tracking.
+fn parse_json<T: DeserializeOwned>(body: &[u8], strict: bool) -> Result<(T, Vec<String>), SourceError> {
+    let mut unknown = Vec::new();
+    let de = &mut serde_json::Deserializer::from_slice(body);
+    let value = if strict {
+        serde_ignored::deserialize(de, |path| unknown.push(path.to_string()))
+    } else {
+        T::deserialize(de)
+    }
+    .map_err(|e| SourceError::Parse(e.to_string()))?;
+    Ok((value, unknown))
+}
+
+/// Deserialize a record already read as a JSON value, warning about unknown
+/// fields in strict mode like `json_checked`.
+pub fn from_raw<T: DeserializeOwned>(raw: &serde_json::Value, source: &str) -> Result<T, SourceError> {
+    let mut unknown = Vec::new();
+    let value = if STRICT_JSON.load(Ordering::Relaxed) {
+        serde_ignored::deserialize(raw, |path| unknown.push(path.to_string()))
+    } else {
+        T::deserialize(raw)
+    }
+    .map_err(|e| SourceError::Parse(e.to_string()))?;
+    for path in &unknown {
+        tracing::warn!("{} response has unknown field `{}`", source, path);
+    }
+    Ok(value)
+}
+
+/// Parse a response's `Retry-After` header.
+fn parse_retry_after(resp: &reqwest::Response) -> Option<Duration> {
+    let value = resp.headers().get(reqwest::header::RETRY_AFTER)?.to_str().ok()?;
+    retry_after_delay(value, chrono::Utc::now())
+}
+
+/// A `Retry-After` value as a delay from `now`: either delta-seconds or an
+/// HTTP date (`Wed, 21 Oct 2015 07:28:00 GMT`). Dates in the past mean no wait.
+fn retry_after_delay(value: &str, now: chrono::DateTime<chrono::Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    Some((at.with_timezone(&chrono::Utc) - now).to_std().unwrap_or(Duration::ZERO))
+}
+
+/// A metadata field a search can be restricted to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SearchField {
+    Title,
+    Author,
+    Abstract,
+}
+
+impl SearchField {
+    /// Parse `title`, `author` or `abstract` (case-insensitive).
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "title" => Some(Self::Title),
+            "author" => Some(Self::Author),
+            "abstract" => Some(Self::Abstract),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Title => "title",
+            Self::Author => "author",
+            Self::Abstract => "abstract",
+        }
+    }
+}
+
+/// An author search: the name, and an inclusive publication year range
+/// sources apply in their query where they can.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuthorQuery {
+    pub name: String,
+    pub year_min: Option<u32>,
+    pub year_max: Option<u32>,
+}
+
+impl AuthorQuery {
+    pub fn new(name: &str) -> Self {
+        Self { name: name.to_string(), ..Default::default() }
+    }
+
+    pub fn with_years(mut self, year_min: Option<u32>, year_max: Option<u32>) -> Self {
+        self.year_min = year_min;
+        self.year_max = year_max;
+        self
+    }
+
+    pub fn has_years(&self) -> bool {
+        self.year_min.is_some() || self.year_max.is_some()
+    }
+
+    /// The year range with open ends widened to 1000 and 9999, for query
+    /// syntaxes that need both bounds.
+    pub fn year_range(&self) -> (u32, u32) {
+        (self.year_min.unwrap_or(1000), self.year_max.unwrap_or(9999))
+    }
+
+    /// Whether a paper from `year` falls in the range; papers without a
+    /// year don't when a bound is set.
+    pub fn contains_year(&self, year: Option<u32>) -> bool {
+        if !self.has_years() {
+            return true;
+        }
+        year.is_some_and(|y| self.year_min.is_none_or(|min| y >= min) && self.year_max.is_none_or(|max| y <= max))
+    }
+}
+
+/// Author profiles sources that resolve names to a profile look at, to
+/// report whether the chosen one had competitors.
+pub const AUTHOR_CANDIDATES: u32 = 5;
+
+/// Works found by an author search.
+#[derive(Debug, Clone, Default)]
+pub struct AuthorWorks {
+    pub papers: Vec<PaperResult>,
+    /// For sources that resolve the name to one author profile first: the
+    /// names of the profiles it was chosen from, the chosen one first.
+    pub candidates: Vec<String>,
+}
+
+impl From<Vec<PaperResult>> for AuthorWorks {
+    fn from(papers: Vec<PaperResult>) -> Self {
+        Self { papers, candidates: Vec::new() }
+    }
 }
 
 #[async_trait]
 pub trait PaperSource: Send + Sync {
     fn name(&self) -> &str;
     async fn search(&self, query: &str, max_results: u32) -> Result<Vec<PaperResult>, SourceError>;
+
+    /// Search starting `offset` results in. Sources whose API pages
+    /// server-side override this; by default the first `offset + max_results`
+    /// are fetched and the leading `offset` skipped.
+    async fn search_page(&self, query: &str, max_results: u32, offset: u32) -> Result<Vec<PaperResult>, SourceError> {
+        if offset == 0 {
+            return self.search(query, max_results).await;
+        }
+        search_with_skip(self, query, max_results, offset).await
+    }
     async fn get_paper(&self, id: &str) -> Result<Option<PaperResult>, SourceError>;
+
+    /// Look up several papers, returning one entry per ID in order (`None`
+    /// when not found). Sources with a batch endpoint (semantic_scholar)
+    /// override this; the rest fetch one at a time.
+    async fn get_papers(&self, ids: &[String]) -> Result<Vec<Option<PaperResult>>, SourceError> {
+        let mut papers = Vec::with_capacity(ids.len());
+        for id in ids {
+            papers.push(self.get_paper(id).await?);
+        }
+        Ok(papers)
+    }
+
     async fn get_citations(&self, id: &str) -> Result<Vec<PaperResult>, SourceError>;
     async fn get_references(&self, id: &str) -> Result<Vec<PaperResult>, SourceError>;
+
+    /// Fetch up to `limit` citing papers. Sources without relation paging
+    /// return their single page, truncated.
+    async fn get_citations_limited(&self, id: &str, limit: u32) -> Result<Vec<PaperResult>, SourceError> {
+        let mut results = self.get_citations(id).await?;
+        results.truncate(limit as usize);
+        Ok(results)
+    }
+
+    /// Fetch up to `limit` referenced papers; see `get_citations_limited`.
+    async fn get_references_limited(&self, id: &str, limit: u32) -> Result<Vec<PaperResult>, SourceError> {
+        let mut results = self.get_references(id).await?;
+        results.truncate(limit as usize);
+        Ok(results)
+    }
+
+    /// Search for works by a named author, within the query's year range
+    /// where the source can filter by year. Sources without author search
+    /// return nothing.
+    async fn search_by_author(&self, _query: &AuthorQuery, _max_results: u32) -> Result<AuthorWorks, SourceError> {
+        Ok(AuthorWorks::default())
+    }
+
+    /// Rewrite `query` to restrict results to `language` (ISO 639-1 code).
+    /// Sources without language filtering return `None` and are searched
+    /// unfiltered.
+    fn language_query(&self, _query: &str, _language: &str) -> Option<String> {
+        None
+    }
+
+    /// Rewrite `query` to match only within `field`. Sources without fielded
+    /// search return `None` and are searched across all fields.
+    fn field_query(&self, _query: &str, _field: SearchField) -> Option<String> {
+        None
+    }
+
+    /// Rewrite `query` to match only papers in any of `categories` (e.g.
+    /// arXiv's "hep-th", "cs.LG"). Sources without subject categories return
+    /// `None` and are searched unfiltered.
+    fn category_query(&self, _query: &str, _categories: &[String]) -> Option<String> {
+        None
+    }
+
+    /// Fetch the source's own BibTeX for a paper. Sources without a native
+    /// BibTeX endpoint return `None` and callers generate an entry instead.
+    async fn get_bibtex(&self, _id: &str) -> Result<Option<String>, SourceError> {
+        Ok(None)
+    }
+
+    /// Fetch a paper together with the source's own JSON record for it,
+    /// unparsed, from one response. Sources without a single-record JSON
+    /// endpoint return no record.
+    async fn get_paper_with_raw(&self, id: &str) -> Result<Option<(PaperResult, Option<serde_json::Value>)>, SourceError> {
+        Ok(self.get_paper(id).await?.map(|paper| (paper, None)))
+    }
+
+    /// A cheap live request proving the API answers; a one-result search
+    /// unless the source has something lighter.
+    async fn ping(&self) -> Result<(), SourceError> {
+        self.search("physics", 1).await.map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_http::{self, Response};
+
+    #[derive(Debug, Deserialize)]
+    struct Work {
+        title: String,
+        authors: Vec<Author>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Author {
+        name: String,
+    }
+
+    #[test]
+    fn test_normalize_orcid() {
+        assert_eq!(normalize_orcid("https://orcid.org/0000-0002-1694-233x").as_deref(), Some("0000-0002-1694-233X"));
+        assert_eq!(normalize_orcid(" 0000-0002-1825-0097 ").as_deref(), Some("0000-0002-1825-0097"));
+        assert_eq!(normalize_orcid("0000"), None);
+        assert_eq!(normalize_orcid("https://orcid.org/"), None);
+    }
+
+    #[test]
+    fn test_strict_json_reports_unknown_fields() {
+        let body = br#"{"title": "T", "venue": "PRL", "authors": [{"name": "A", "orcid": "0000"}]}"#;
+
+        let (work, unknown) = parse_json::<Work>(body, true).unwrap();
+        assert_eq!(work.title, "T");
+        assert_eq!(work.authors[0].name, "A");
+        assert_eq!(unknown, vec!["venue", "authors.0.orcid"]);
+
+        let (_, unknown) = parse_json::<Work>(body, false).unwrap();
+        assert!(unknown.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_transient_failures_retried_until_success() {
+        let requests = std::sync::Arc::new(AtomicU32::new(0));
+        let counter = requests.clone();
+        // Drop the first two connections without answering
+        let base = mock_http::serve(move |_| {
+            (counter.fetch_add(1, Ordering::SeqCst) >= 2).then(|| Response::new(200).body("ok"))
+        })
+        .await;
+
+        let client = http_client(&HttpTuning::default(), "paper-search-mcp/test");
+        let request = client.get(format!("{}/flaky", base));
+        let resp = send_with_retries(request, 3, Duration::from_millis(1)).await.unwrap();
+        assert_eq!(resp.text().await.unwrap(), "ok");
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_retry_after_accepts_seconds_and_http_dates() {
+        let now = chrono::DateTime::parse_from_rfc3339("2015-10-21T07:28:00Z").unwrap().with_timezone(&chrono::Utc);
+        assert_eq!(retry_after_delay(" 120 ", now), Some(Duration::from_secs(120)));
+        assert_eq!(retry_after_delay("Wed, 21 Oct 2015 07:28:30 GMT", now), Some(Duration::from_secs(30)));
+        assert_eq!(retry_after_delay("Wed, 21 Oct 2015 07:00:00 GMT", now), Some(Duration::ZERO));
+        assert_eq!(retry_after_delay("soon", now), None);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_request_retried_once_after_retry_after() {
+        let requests = std::sync::Arc::new(AtomicU32::new(0));
+        let counter = requests.clone();
+        let base = mock_http::serve(move |_| {
+            Some(match counter.fetch_add(1, Ordering::SeqCst) {
+                0 => Response::new(429).header("Retry-After", "0"),
+                _ => Response::new(200).body("ok"),
+            })
+        })
+        .await;
+        let client = http_client(&HttpTuning::default(), "paper-search-mcp/test");
+        let resp = client.get(format!("{}/limited", base)).send_checked().await.unwrap();
+        assert_eq!(resp.text().await.unwrap(), "ok");
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+        // A second 429 is reported with the server's delay
+        let base = mock_http::serve(|_| Some(Response::new(429).header("Retry-After", "0"))).await;
+        match client.get(format!("{}/limited", base)).send_checked().await {
+            Err(SourceError::RateLimited { retry_after }) => assert_eq!(retry_after, Some(Duration::ZERO)),
+            other => panic!("expected RateLimited, got {:?}", other.map(|r| r.status())),
+        }
+    }
+
+    #[test]
+    fn test_backoff_doubles_with_bounded_jitter() {
+        let base = Duration::from_millis(100);
+        for (attempt, floor) in [(1, 100), (2, 200), (3, 400)] {
+            let delay = backoff_delay(base, attempt);
+            let floor = Duration::from_millis(floor);
+            assert!(delay >= floor && delay <= floor.mul_f64(1.5), "attempt {}: {:?}", attempt, delay);
+        }
+    }
+
+    #[test]
+    fn test_strip_html() {
+        assert_eq!(
+            strip_html("The <i>Hubble</i> tension &amp; <b>dark\n energy</b>"),
+            "The Hubble tension & dark energy"
+        );
+        assert_eq!(strip_html("Plain title"), "Plain title");
+    }
+
+    #[tokio::test]
+    async fn test_tuned_client_serves_sequential_requests() {
+        let base = mock_http::serve(|_| Some(Response::new(200).body("ok"))).await;
+        let tuning = HttpTuning {
+            pool_max_idle_per_host: 2,
+            pool_idle_timeout: Duration::from_secs(5),
+            http2_keep_alive_interval: None,
+            tcp_keepalive: Some(Duration::from_secs(10)),
+        };
+        let client = http_client(&tuning, "paper-search-mcp/test");
+        for i in 0..3 {
+            let resp = client.get(format!("{}/req/{}", base, i)).send_checked().await.unwrap();
+            assert!(resp.status().is_success());
+            assert_eq!(resp.text().await.unwrap(), "ok");
+        }
+    }
 }
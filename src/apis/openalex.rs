@@ -1,31 +1,99 @@
-use super::{PaperResult, PaperSource, SourceError};
+use super::{from_raw, http_client, known_orcids, normalize_orcid, page_for_offset, search_with_skip, AuthorQuery, AuthorWorks, HttpTuning, AUTHOR_CANDIDATES, JsonExt, PaperResult, PaperSource, SendExt, SourceError};
 use async_trait::async_trait;
 use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::Duration;
 
 const BASE_URL: &str = "https://api.openalex.org";
+const SELECT: &str = "id,title,authorships,publication_year,doi,open_access,cited_by_count,abstract_inverted_index";
+/// OpenAlex caps `per_page` at 200.
+const MAX_PAGE_SIZE: u32 = 200;
+/// Spacing between cursor pages; the polite pool allows 10 requests per second.
+const PAGE_INTERVAL: Duration = Duration::from_millis(100);
 
 pub struct OpenAlexClient {
     client: reqwest::Client,
+    base_url: String,
+    page_size: u32,
 }
 
 impl OpenAlexClient {
-    pub fn new(email: Option<String>) -> Self {
+    pub fn new(http: &HttpTuning, email: Option<String>) -> Self {
         let ua = match email {
             Some(ref e) => format!("paper-search-mcp/0.1 (mailto:{})", e),
             None => "paper-search-mcp/0.1".to_string(),
         };
         Self {
-            client: reqwest::Client::builder()
-                .user_agent(ua)
-                .build()
-                .unwrap(),
+            client: http_client(http, ua),
+            base_url: BASE_URL.to_string(),
+            page_size: MAX_PAGE_SIZE,
         }
     }
+
+    #[cfg(test)]
+    fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    #[cfg(test)]
+    fn with_page_size(mut self, page_size: u32) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    /// Fetch up to `max_results` works, starting at `cursor=*` and following
+    /// `meta.next_cursor` until enough results arrive or the results run out.
+    async fn search_cursor(&self, query: &str, max_results: u32) -> Result<Vec<PaperResult>, SourceError> {
+        let max = max_results as usize;
+        let mut papers = Vec::new();
+        let mut cursor = "*".to_string();
+        while papers.len() < max {
+            if !papers.is_empty() {
+                tokio::time::sleep(PAGE_INTERVAL).await;
+            }
+            let per_page = (max - papers.len()).min(self.page_size as usize).to_string();
+            let resp: OAResponse = self.client
+                .get(format!("{}/works", self.base_url))
+                .query(&[
+                    ("search", query),
+                    ("per_page", per_page.as_str()),
+                    ("cursor", cursor.as_str()),
+                    ("select", SELECT),
+                ])
+                .send_checked().await?.json_checked("openalex").await?;
+            let fetched = resp.results.len();
+            papers.extend(resp.results.iter().map(oa_to_paper));
+            match resp.meta.and_then(|m| m.next_cursor) {
+                Some(next) if fetched > 0 => cursor = next,
+                _ => break,
+            }
+        }
+        papers.truncate(max);
+        Ok(papers)
+    }
 }
 
 #[derive(Deserialize)]
 struct OAResponse {
     results: Vec<OAWork>,
+    meta: Option<OAMeta>,
+}
+
+#[derive(Deserialize)]
+struct OAAuthorResponse {
+    results: Vec<OAAuthorRecord>,
+}
+
+#[derive(Deserialize)]
+struct OAAuthorRecord {
+    id: Option<String>,
+    display_name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct OAMeta {
+    next_cursor: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -37,6 +105,7 @@ struct OAWork {
     doi: Option<String>,
     open_access: Option<OAOpenAccess>,
     cited_by_count: Option<u32>,
+    abstract_inverted_index: Option<HashMap<String, Vec<usize>>>,
 }
 
 #[derive(Deserialize)]
@@ -46,21 +115,41 @@ struct OAAuthorship {
 #[derive(Deserialize)]
 struct OAAuthor {
     display_name: Option<String>,
+    orcid: Option<String>,
 }
 #[derive(Deserialize)]
 struct OAOpenAccess {
     oa_url: Option<String>,
 }
 
+/// Rebuild abstract text from OpenAlex's inverted index (word -> positions).
+/// Returns `None` for an empty index.
+pub(crate) fn reconstruct_abstract(index: &HashMap<String, Vec<usize>>) -> Option<String> {
+    let mut positioned: Vec<(usize, &str)> = index
+        .iter()
+        .flat_map(|(word, positions)| positions.iter().map(move |&pos| (pos, word.as_str())))
+        .collect();
+    if positioned.is_empty() {
+        return None;
+    }
+    positioned.sort_unstable_by_key(|&(pos, _)| pos);
+    Some(positioned.into_iter().map(|(_, word)| word).collect::<Vec<_>>().join(" "))
+}
+
 fn oa_to_paper(w: &OAWork) -> PaperResult {
     let doi = w.doi.as_ref().map(|d| d.replace("https://doi.org/", ""));
+    let (authors, orcids): (Vec<String>, Vec<Option<String>>) = w.authorships.iter()
+        .flatten()
+        .filter_map(|a| {
+            let name = a.author.display_name.clone()?;
+            Some((name, a.author.orcid.as_deref().and_then(normalize_orcid)))
+        })
+        .unzip();
     PaperResult {
         id: format!("openalex:{}", w.id.as_deref().unwrap_or("")),
         title: w.title.clone().unwrap_or_default(),
-        authors: w.authorships.as_ref()
-            .map(|a| a.iter().filter_map(|a| a.author.display_name.clone()).collect())
-            .unwrap_or_default(),
-        abstract_text: None, // OpenAlex doesn't return abstracts in search by default
+        authors,
+        abstract_text: w.abstract_inverted_index.as_ref().and_then(reconstruct_abstract),
         year: w.publication_year,
         source: "openalex".to_string(),
         doi,
@@ -68,6 +157,8 @@ fn oa_to_paper(w: &OAWork) -> PaperResult {
         url: w.id.clone().unwrap_or_default(),
         pdf_url: w.open_access.as_ref().and_then(|oa| oa.oa_url.clone()),
         citation_count: w.cited_by_count,
+        orcids: known_orcids(orcids),
+        subjects: Vec::new(),
     }
 }
 
@@ -76,39 +167,65 @@ impl PaperSource for OpenAlexClient {
     fn name(&self) -> &str { "openalex" }
 
     async fn search(&self, query: &str, max_results: u32) -> Result<Vec<PaperResult>, SourceError> {
-        let per_page = max_results.min(200).to_string();
+        self.search_page(query, max_results, 0).await
+    }
+
+    async fn search_page(&self, query: &str, max_results: u32, offset: u32) -> Result<Vec<PaperResult>, SourceError> {
+        if offset == 0 {
+            return self.search_cursor(query, max_results).await;
+        }
+        let per_page = max_results.min(MAX_PAGE_SIZE);
+        let Some(page) = page_for_offset(offset, per_page) else {
+            return search_with_skip(self, query, max_results, offset).await;
+        };
+        let per_page = per_page.to_string();
+        let page = page.to_string();
         let resp: OAResponse = self.client
-            .get(&format!("{}/works", BASE_URL))
+            .get(format!("{}/works", self.base_url))
             .query(&[
                 ("search", query),
                 ("per_page", per_page.as_str()),
-                ("select", "id,title,authorships,publication_year,doi,open_access,cited_by_count"),
+                ("page", page.as_str()),
+                ("select", SELECT),
             ])
-            .send().await?.json().await?;
+            .send_checked().await?.json_checked("openalex").await?;
         Ok(resp.results.iter().map(oa_to_paper).collect())
     }
 
     async fn get_paper(&self, id: &str) -> Result<Option<PaperResult>, SourceError> {
         let oa_id = id.strip_prefix("openalex:").unwrap_or(id);
         let resp = self.client
-            .get(&format!("{}/works/{}", BASE_URL, oa_id))
-            .send().await?;
+            .get(format!("{}/works/{}", self.base_url, oa_id))
+            .query(&[("select", SELECT)])
+            .send_checked().await?;
         if resp.status() == 404 { return Ok(None); }
-        let w: OAWork = resp.json().await?;
+        let w: OAWork = resp.json_checked("openalex").await?;
         Ok(Some(oa_to_paper(&w)))
     }
 
+    /// The full work record, not just the `select`ed fields.
+    async fn get_paper_with_raw(&self, id: &str) -> Result<Option<(PaperResult, Option<serde_json::Value>)>, SourceError> {
+        let oa_id = id.strip_prefix("openalex:").unwrap_or(id);
+        let resp = self.client
+            .get(format!("{}/works/{}", self.base_url, oa_id))
+            .send_checked().await?;
+        if resp.status() == 404 { return Ok(None); }
+        let raw: serde_json::Value = resp.json_checked("openalex").await?;
+        let w: OAWork = from_raw(&raw, "openalex")?;
+        Ok(Some((oa_to_paper(&w), Some(raw))))
+    }
+
     async fn get_citations(&self, id: &str) -> Result<Vec<PaperResult>, SourceError> {
         let oa_id = id.strip_prefix("openalex:").unwrap_or(id);
         let filter = format!("cites:{}", oa_id);
         let resp: OAResponse = self.client
-            .get(&format!("{}/works", BASE_URL))
+            .get(format!("{}/works", self.base_url))
             .query(&[
                 ("filter", filter.as_str()),
                 ("per_page", "25"),
-                ("select", "id,title,authorships,publication_year,doi,open_access,cited_by_count"),
+                ("select", SELECT),
             ])
-            .send().await?.json().await?;
+            .send_checked().await?.json_checked("openalex").await?;
         Ok(resp.results.iter().map(oa_to_paper).collect())
     }
 
@@ -116,13 +233,164 @@ impl PaperSource for OpenAlexClient {
         let oa_id = id.strip_prefix("openalex:").unwrap_or(id);
         let filter = format!("cited_by:{}", oa_id);
         let resp: OAResponse = self.client
-            .get(&format!("{}/works", BASE_URL))
+            .get(format!("{}/works", self.base_url))
             .query(&[
                 ("filter", filter.as_str()),
                 ("per_page", "25"),
-                ("select", "id,title,authorships,publication_year,doi,open_access,cited_by_count"),
+                ("select", SELECT),
             ])
-            .send().await?.json().await?;
+            .send_checked().await?.json_checked("openalex").await?;
         Ok(resp.results.iter().map(oa_to_paper).collect())
     }
+
+    /// Resolves the name to the best-matching OpenAlex author, then lists
+    /// that author's works, newest first. The other matching profiles are
+    /// reported as candidates.
+    async fn search_by_author(&self, query: &AuthorQuery, max_results: u32) -> Result<AuthorWorks, SourceError> {
+        let candidates = AUTHOR_CANDIDATES.to_string();
+        let authors: OAAuthorResponse = self.client
+            .get(format!("{}/authors", self.base_url))
+            .query(&[("search", query.name.as_str()), ("per_page", candidates.as_str()), ("select", "id,display_name")])
+            .send_checked().await?.json_checked("openalex").await?;
+        let Some(author_id) = authors.results.first().and_then(|a| a.id.clone()) else {
+            return Ok(AuthorWorks::default());
+        };
+        let candidates = authors.results.into_iter().filter_map(|a| a.display_name).collect();
+        let mut filter = format!("author.id:{}", author_id.rsplit('/').next().unwrap_or(&author_id));
+        if let Some(min) = query.year_min {
+            filter.push_str(&format!(",from_publication_date:{}-01-01", min));
+        }
+        if let Some(max) = query.year_max {
+            filter.push_str(&format!(",to_publication_date:{}-12-31", max));
+        }
+        let per_page = max_results.min(MAX_PAGE_SIZE).to_string();
+        let resp: OAResponse = self.client
+            .get(format!("{}/works", self.base_url))
+            .query(&[
+                ("filter", filter.as_str()),
+                ("sort", "publication_year:desc"),
+                ("per_page", per_page.as_str()),
+                ("select", SELECT),
+            ])
+            .send_checked().await?.json_checked("openalex").await?;
+        Ok(AuthorWorks { papers: resp.results.iter().map(oa_to_paper).collect(), candidates })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use crate::mock_http::{self, Response};
+
+    /// Stand-in for `/works`: serves `per_page` works per request, numbered
+    /// by page, with `next_cursor` always pointing at the following page.
+    async fn mock_openalex(requests: Arc<AtomicUsize>) -> String {
+        mock_http::serve(move |request| {
+            let per_page: usize = request.query("per_page").and_then(|p| p.parse().ok()).unwrap_or(25);
+            let page: usize = match request.query("cursor").as_deref() {
+                Some("*") => 0,
+                Some(c) => c.trim_start_matches("page").parse().unwrap(),
+                None => panic!("expected a cursor"),
+            };
+            requests.fetch_add(1, Ordering::SeqCst);
+
+            let works: Vec<String> = (0..per_page)
+                .map(|i| format!(r#"{{"id": "https://openalex.org/W{}{}", "title": "Work {}.{}"}}"#, page, i, page, i))
+                .collect();
+            Some(Response::json(format!(
+                r#"{{"meta": {{"next_cursor": "page{}"}}, "results": [{}]}}"#,
+                page + 1,
+                works.join(",")
+            )))
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_search_follows_cursor_until_max_results() {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let base = mock_openalex(requests.clone()).await;
+        let client = OpenAlexClient::new(&HttpTuning::default(), None)
+            .with_base_url(base)
+            .with_page_size(2);
+
+        let papers = client.search("holography", 3).await.unwrap();
+        let titles: Vec<&str> = papers.iter().map(|p| p.title.as_str()).collect();
+        assert_eq!(titles, vec!["Work 0.0", "Work 0.1", "Work 1.0"]);
+        assert_eq!(papers[2].id, "openalex:https://openalex.org/W10");
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_reconstruct_abstract_orders_by_position() {
+        let index: HashMap<String, Vec<usize>> = serde_json::from_str(
+            r#"{"the": [0, 3], "horizon": [1], "encodes": [2], "bulk": [4]}"#,
+        ).unwrap();
+        assert_eq!(reconstruct_abstract(&index).as_deref(), Some("the horizon encodes the bulk"));
+        assert_eq!(reconstruct_abstract(&HashMap::new()), None);
+    }
+
+    #[test]
+    fn test_orcids_follow_named_authors() {
+        let work: OAWork = serde_json::from_str(r#"{"authorships": [
+            {"author": {"display_name": "Juan Maldacena", "orcid": "https://orcid.org/0000-0002-9127-1687"}},
+            {"author": {"orcid": "https://orcid.org/0000-0001-0000-0001"}},
+            {"author": {"display_name": "Leonard Susskind", "orcid": null}}
+        ]}"#).unwrap();
+        let paper = oa_to_paper(&work);
+        assert_eq!(paper.authors, vec!["Juan Maldacena", "Leonard Susskind"]);
+        assert_eq!(paper.orcid(0), Some("0000-0002-9127-1687"));
+        assert_eq!(paper.orcid(1), None);
+
+        let anonymous: OAWork = serde_json::from_str(r#"{"authorships": [{"author": {"display_name": "A"}}]}"#).unwrap();
+        assert!(oa_to_paper(&anonymous).orcids.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_paper_selects_and_reconstructs_abstract() {
+        let base = mock_http::serve(|request| {
+            // Without the field in `select`, OpenAlex omits it
+            let body = if request.query("select").is_some_and(|s| s.contains("abstract_inverted_index")) {
+                r#"{"id": "https://openalex.org/W1", "title": "Holography", "abstract_inverted_index": {"Gravity": [0], "is": [1], "holographic.": [2]}}"#
+            } else {
+                r#"{"id": "https://openalex.org/W1", "title": "Holography"}"#
+            };
+            Some(Response::json(body))
+        })
+        .await;
+        let client = OpenAlexClient::new(&HttpTuning::default(), None).with_base_url(base);
+
+        let paper = client.get_paper("openalex:W1").await.unwrap().unwrap();
+        assert_eq!(paper.abstract_text.as_deref(), Some("Gravity is holographic."));
+    }
+
+    #[tokio::test]
+    async fn test_author_search_filters_years_and_lists_candidates() {
+        let base = mock_http::serve(|request| {
+            Some(match request.path() {
+                "/authors" => Response::json(
+                    r#"{"results": [{"id": "https://openalex.org/A1", "display_name": "Juan Maldacena"}, {"id": "https://openalex.org/A2", "display_name": "J. Maldacena"}]}"#,
+                ),
+                "/works" => {
+                    let filter = request.query("filter").unwrap_or_default();
+                    let body = if filter == "author.id:A1,from_publication_date:2000-01-01,to_publication_date:2010-12-31" {
+                        r#"{"results": [{"id": "https://openalex.org/W1", "title": "Wormholes", "publication_year": 2005}]}"#
+                    } else {
+                        r#"{"results": []}"#
+                    };
+                    Response::json(body)
+                }
+                _ => Response::new(404),
+            })
+        })
+        .await;
+        let client = OpenAlexClient::new(&HttpTuning::default(), None).with_base_url(base);
+
+        let query = AuthorQuery::new("Juan Maldacena").with_years(Some(2000), Some(2010));
+        let works = client.search_by_author(&query, 10).await.unwrap();
+        assert_eq!(works.papers.len(), 1);
+        assert_eq!(works.candidates, vec!["Juan Maldacena", "J. Maldacena"]);
+    }
 }
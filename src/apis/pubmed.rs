@@ -0,0 +1,399 @@
+use super::{http_client, AuthorQuery, AuthorWorks, HttpTuning, JsonExt, PaperResult, PaperSource, SendExt, SourceError, TextExt};
+use async_trait::async_trait;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+const BASE_URL: &str = "https://eutils.ncbi.nlm.nih.gov/entrez/eutils";
+/// Links fetched for citation/reference lookups.
+const MAX_LINKS: usize = 25;
+
+/// NCBI E-utilities client for canonical PubMed metadata.
+pub struct PubmedClient {
+    client: reqwest::Client,
+    api_key: Option<String>,
+    min_interval: Duration,
+    last_request: Mutex<Option<Instant>>,
+}
+
+impl PubmedClient {
+    /// NCBI allows 3 requests/second without an API key and 10 with one.
+    pub fn new(http: &HttpTuning, api_key: Option<String>) -> Self {
+        let min_interval = if api_key.is_some() {
+            Duration::from_millis(100)
+        } else {
+            Duration::from_millis(334)
+        };
+        Self {
+            client: http_client(http, "paper-search-mcp/0.1"),
+            api_key,
+            min_interval,
+            last_request: Mutex::new(None),
+        }
+    }
+
+    /// Wait until `min_interval` has passed since the previous request.
+    async fn throttle(&self) {
+        let mut last = self.last_request.lock().await;
+        if let Some(prev) = *last {
+            let elapsed = prev.elapsed();
+            if elapsed < self.min_interval {
+                tokio::time::sleep(self.min_interval - elapsed).await;
+            }
+        }
+        *last = Some(Instant::now());
+    }
+
+    async fn get(&self, endpoint: &str, params: &[(&str, &str)]) -> Result<reqwest::Response, SourceError> {
+        self.throttle().await;
+        let mut req = self.client
+            .get(format!("{}/{}", BASE_URL, endpoint))
+            .query(&[("db", "pubmed")])
+            .query(params);
+        if let Some(ref key) = self.api_key {
+            req = req.query(&[("api_key", key.as_str())]);
+        }
+        req.send_checked().await
+    }
+
+    async fn esearch(&self, term: &str, max_results: u32, offset: u32) -> Result<Vec<String>, SourceError> {
+        let retmax = max_results.min(200).to_string();
+        let retstart = offset.to_string();
+        let resp: ESearchResponse = self
+            .get("esearch.fcgi", &[
+                ("term", term),
+                ("retmax", retmax.as_str()),
+                ("retstart", retstart.as_str()),
+                ("retmode", "json"),
+            ])
+            .await?
+            .json_checked("pubmed")
+            .await?;
+        Ok(resp.esearchresult.idlist)
+    }
+
+    async fn efetch(&self, pmids: &[String]) -> Result<Vec<PaperResult>, SourceError> {
+        if pmids.is_empty() {
+            return Ok(vec![]);
+        }
+        let ids = pmids.join(",");
+        let xml = self
+            .get("efetch.fcgi", &[("id", ids.as_str()), ("retmode", "xml")])
+            .await?
+            .text_checked("pubmed")
+            .await?;
+        parse_efetch(&xml)
+    }
+
+    async fn linked(&self, id: &str, linkname: &str) -> Result<Vec<PaperResult>, SourceError> {
+        let pmid = id.strip_prefix("pmid:").unwrap_or(id);
+        let resp: ELinkResponse = self
+            .get("elink.fcgi", &[
+                ("dbfrom", "pubmed"),
+                ("id", pmid),
+                ("linkname", linkname),
+                ("retmode", "json"),
+            ])
+            .await?
+            .json_checked("pubmed")
+            .await?;
+        let mut pmids: Vec<String> = resp.linksets.into_iter()
+            .flat_map(|set| set.linksetdbs)
+            .filter(|db| db.linkname == linkname)
+            .flat_map(|db| db.links)
+            .collect();
+        pmids.truncate(MAX_LINKS);
+        self.efetch(&pmids).await
+    }
+}
+
+#[derive(Deserialize)]
+struct ESearchResponse {
+    esearchresult: ESearchResult,
+}
+#[derive(Deserialize)]
+struct ESearchResult {
+    #[serde(default)]
+    idlist: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct ELinkResponse {
+    #[serde(default)]
+    linksets: Vec<ELinkSet>,
+}
+#[derive(Deserialize)]
+struct ELinkSet {
+    #[serde(default)]
+    linksetdbs: Vec<ELinkSetDb>,
+}
+#[derive(Deserialize)]
+struct ELinkSetDb {
+    linkname: String,
+    #[serde(default)]
+    links: Vec<String>,
+}
+
+/// Fields accumulated for one `<PubmedArticle>`.
+#[derive(Default)]
+struct ArticleFields {
+    pmid: String,
+    title: String,
+    abstract_parts: Vec<String>,
+    authors: Vec<String>,
+    last_name: String,
+    fore_name: String,
+    year: Option<u32>,
+    doi: Option<String>,
+    mesh_terms: Vec<String>,
+}
+
+impl ArticleFields {
+    fn into_paper(self) -> PaperResult {
+        let abstract_text = self.abstract_parts.iter()
+            .map(|part| part.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let abstract_text = Some(abstract_text).filter(|a| !a.is_empty());
+        PaperResult {
+            id: format!("pmid:{}", self.pmid),
+            title: self.title.trim().to_string(),
+            authors: self.authors,
+            abstract_text,
+            year: self.year,
+            source: "pubmed".to_string(),
+            doi: self.doi,
+            arxiv_id: None,
+            url: format!("https://pubmed.ncbi.nlm.nih.gov/{}/", self.pmid),
+            pdf_url: None,
+            citation_count: None,
+            orcids: Vec::new(),
+            subjects: self.mesh_terms.iter()
+                .map(|term| term.trim().to_string())
+                .filter(|term| !term.is_empty())
+                .collect(),
+        }
+    }
+}
+
+/// Parse an efetch `PubmedArticleSet` document.
+fn parse_efetch(xml: &str) -> Result<Vec<PaperResult>, SourceError> {
+    let mut reader = Reader::from_str(xml);
+    let mut papers = Vec::new();
+    let mut article: Option<ArticleFields> = None;
+    // Open elements inside the current article, innermost last.
+    let mut path: Vec<String> = Vec::new();
+    let mut doi_attr = false;
+    let mut buf = Vec::new();
+
+    loop {
+        buf.clear();
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => {
+                let tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                if tag == "PubmedArticle" {
+                    article = Some(ArticleFields::default());
+                    path.clear();
+                    continue;
+                }
+                let Some(fields) = article.as_mut() else { continue };
+                if tag == "AbstractText" {
+                    fields.abstract_parts.push(String::new());
+                }
+                if tag == "DescriptorName" && in_path(&path, "MeshHeadingList") {
+                    fields.mesh_terms.push(String::new());
+                }
+                if tag == "ELocationID" || tag == "ArticleId" {
+                    doi_attr = e.attributes().flatten().any(|attr| {
+                        matches!(attr.key.as_ref(), b"EIdType" | b"IdType") && attr.value.as_ref() == b"doi"
+                    });
+                }
+                path.push(tag);
+            }
+            Ok(Event::End(e)) => {
+                let tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                if tag == "PubmedArticle" {
+                    if let Some(fields) = article.take() {
+                        papers.push(fields.into_paper());
+                    }
+                    continue;
+                }
+                let Some(fields) = article.as_mut() else { continue };
+                if tag == "Author" && in_path(&path, "AuthorList") {
+                    let name = [fields.fore_name.trim(), fields.last_name.trim()]
+                        .into_iter()
+                        .filter(|s| !s.is_empty())
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    if !name.is_empty() {
+                        fields.authors.push(name);
+                    }
+                    fields.fore_name.clear();
+                    fields.last_name.clear();
+                }
+                path.pop();
+            }
+            Ok(Event::Text(e)) => {
+                let Some(fields) = article.as_mut() else { continue };
+                let text = e.unescape().unwrap_or_default().to_string();
+                append_text(fields, &path, doi_attr, &text);
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(SourceError::Parse(format!("PubMed XML error: {}", e))),
+            _ => {}
+        }
+    }
+
+    Ok(papers)
+}
+
+fn in_path(path: &[String], tag: &str) -> bool {
+    path.iter().any(|t| t == tag)
+}
+
+/// Route a text node to the field its element path belongs to.
+fn append_text(fields: &mut ArticleFields, path: &[String], doi_attr: bool, text: &str) {
+    let Some(current) = path.last().map(String::as_str) else { return };
+    // Cited works carry their own IDs; only the article's own metadata counts.
+    if in_path(path, "ReferenceList") || in_path(path, "CommentsCorrectionsList") {
+        return;
+    }
+    if in_path(path, "ArticleTitle") {
+        fields.title.push_str(text);
+    } else if in_path(path, "AbstractText") {
+        // Includes text inside inline markup (<i>, <sup>) within a section
+        if let Some(section) = fields.abstract_parts.last_mut() {
+            section.push_str(text);
+        }
+    } else if current == "DescriptorName" && in_path(path, "MeshHeadingList") {
+        if let Some(term) = fields.mesh_terms.last_mut() {
+            term.push_str(text);
+        }
+    } else if in_path(path, "AuthorList") {
+        match current {
+            "LastName" | "CollectiveName" => fields.last_name.push_str(text),
+            "ForeName" => fields.fore_name.push_str(text),
+            _ => {}
+        }
+    } else if current == "PMID" && fields.pmid.is_empty() {
+        fields.pmid = text.trim().to_string();
+    } else if in_path(path, "PubDate") && fields.year.is_none() && matches!(current, "Year" | "MedlineDate") {
+        fields.year = text.trim().get(..4).and_then(|y| y.parse().ok());
+    } else if doi_attr && matches!(current, "ELocationID" | "ArticleId") && fields.doi.is_none() {
+        fields.doi = Some(text.trim().to_string());
+    }
+}
+
+#[async_trait]
+impl PaperSource for PubmedClient {
+    fn name(&self) -> &str { "pubmed" }
+
+    async fn search(&self, query: &str, max_results: u32) -> Result<Vec<PaperResult>, SourceError> {
+        self.search_page(query, max_results, 0).await
+    }
+
+    async fn search_page(&self, query: &str, max_results: u32, offset: u32) -> Result<Vec<PaperResult>, SourceError> {
+        let pmids = self.esearch(query, max_results, offset).await?;
+        self.efetch(&pmids).await
+    }
+
+    async fn get_paper(&self, id: &str) -> Result<Option<PaperResult>, SourceError> {
+        let pmid = id.strip_prefix("pmid:").unwrap_or(id);
+        Ok(self.efetch(&[pmid.to_string()]).await?.into_iter().next())
+    }
+
+    async fn get_citations(&self, id: &str) -> Result<Vec<PaperResult>, SourceError> {
+        self.linked(id, "pubmed_pubmed_citedin").await
+    }
+
+    async fn get_references(&self, id: &str) -> Result<Vec<PaperResult>, SourceError> {
+        self.linked(id, "pubmed_pubmed_refs").await
+    }
+
+    async fn search_by_author(&self, query: &AuthorQuery, max_results: u32) -> Result<AuthorWorks, SourceError> {
+        let mut q = format!("{}[Author]", query.name);
+        if query.has_years() {
+            let (min, max) = query.year_range();
+            q.push_str(&format!(" AND {}:{}[dp]", min, max));
+        }
+        Ok(self.search(&q, max_results).await?.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_EFETCH: &str = r#"<?xml version="1.0" ?>
+<!DOCTYPE PubmedArticleSet PUBLIC "-//NLM//DTD PubMedArticle, 1st January 2024//EN" "https://dtd.nlm.nih.gov/ncbi/pubmed/out/pubmed_240101.dtd">
+<PubmedArticleSet>
+  <PubmedArticle>
+    <MedlineCitation Status="MEDLINE" Owner="NLM">
+      <PMID Version="1">31978945</PMID>
+      <Article PubModel="Print-Electronic">
+        <Journal>
+          <JournalIssue CitedMedium="Internet">
+            <PubDate><Year>2020</Year><Month>Feb</Month></PubDate>
+          </JournalIssue>
+          <Title>The New England journal of medicine</Title>
+        </Journal>
+        <ArticleTitle>A Novel Coronavirus from Patients with Pneumonia in China, 2019.</ArticleTitle>
+        <ELocationID EIdType="pii" ValidYN="Y">NEJMoa2001017</ELocationID>
+        <ELocationID EIdType="doi" ValidYN="Y">10.1056/NEJMoa2001017</ELocationID>
+        <Abstract>
+          <AbstractText Label="BACKGROUND">In December 2019, a cluster of patients with pneumonia of unknown cause was linked to a seafood market.</AbstractText>
+          <AbstractText Label="METHODS">Samples were analyzed by <i>next-generation</i> sequencing.</AbstractText>
+        </Abstract>
+        <AuthorList CompleteYN="Y">
+          <Author ValidYN="Y"><LastName>Zhu</LastName><ForeName>Na</ForeName><Initials>N</Initials></Author>
+          <Author ValidYN="Y"><LastName>Zhang</LastName><ForeName>Dingyu</ForeName><Initials>D</Initials></Author>
+          <Author ValidYN="Y"><CollectiveName>China Novel Coronavirus Investigating and Research Team</CollectiveName></Author>
+        </AuthorList>
+      </Article>
+      <CommentsCorrectionsList>
+        <CommentsCorrections RefType="CommentIn"><PMID Version="1">32004427</PMID></CommentsCorrections>
+      </CommentsCorrectionsList>
+      <MeshHeadingList>
+        <MeshHeading><DescriptorName UI="D006801" MajorTopicYN="N">Humans</DescriptorName></MeshHeading>
+        <MeshHeading>
+          <DescriptorName UI="D045169" MajorTopicYN="N">Severe Acute Respiratory Syndrome</DescriptorName>
+          <QualifierName UI="Q000821" MajorTopicYN="Y">virology</QualifierName>
+        </MeshHeading>
+      </MeshHeadingList>
+    </MedlineCitation>
+    <PubmedData>
+      <ArticleIdList>
+        <ArticleId IdType="pubmed">31978945</ArticleId>
+        <ArticleId IdType="doi">10.1056/NEJMoa2001017</ArticleId>
+      </ArticleIdList>
+      <ReferenceList>
+        <Reference><ArticleIdList><ArticleId IdType="doi">10.1000/cited</ArticleId></ArticleIdList></Reference>
+      </ReferenceList>
+    </PubmedData>
+  </PubmedArticle>
+</PubmedArticleSet>"#;
+
+    #[test]
+    fn test_parse_efetch_record() {
+        let papers = parse_efetch(SAMPLE_EFETCH).unwrap();
+        assert_eq!(papers.len(), 1);
+        let p = &papers[0];
+        assert_eq!(p.id, "pmid:31978945");
+        assert_eq!(p.title, "A Novel Coronavirus from Patients with Pneumonia in China, 2019.");
+        assert_eq!(p.authors, vec![
+            "Na Zhu",
+            "Dingyu Zhang",
+            "China Novel Coronavirus Investigating and Research Team",
+        ]);
+        assert_eq!(p.year, Some(2020));
+        assert_eq!(p.doi.as_deref(), Some("10.1056/NEJMoa2001017"));
+        let abstract_text = p.abstract_text.as_deref().unwrap();
+        assert!(abstract_text.starts_with("In December 2019, a cluster"));
+        assert!(abstract_text.ends_with("Samples were analyzed by next-generation sequencing."));
+        assert_eq!(p.url, "https://pubmed.ncbi.nlm.nih.gov/31978945/");
+        assert_eq!(p.subjects, vec!["Humans", "Severe Acute Respiratory Syndrome"]);
+    }
+}
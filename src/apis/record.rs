@@ -0,0 +1,125 @@
+//! Debug recording of raw source responses, for building replay fixtures.
+//! Off unless `PAPER_SEARCH_RECORD_DIR` is set.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+
+static RECORD_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// Query parameters whose values are replaced before the URL is recorded.
+const SECRET_PARAMS: &[&str] = &["key", "token", "secret", "password", "email", "mailto"];
+
+/// Record every source response body under `dir` (`None` turns recording off).
+pub fn set_record_dir(dir: Option<PathBuf>) {
+    *RECORD_DIR.write().unwrap_or_else(|e| e.into_inner()) = dir;
+}
+
+/// Write `body` and its request metadata to the record directory, if set.
+/// Failures are logged and never affect the request.
+pub(crate) fn record(source: &str, url: &reqwest::Url, status: u16, body: &[u8], extension: &str) {
+    let dir = RECORD_DIR.read().unwrap_or_else(|e| e.into_inner()).clone();
+    let Some(dir) = dir else { return };
+    if let Err(e) = write_fixture(&dir, source, url, status, body, extension) {
+        tracing::warn!("Failed to record {} response: {}", source, e);
+    }
+}
+
+fn write_fixture(
+    dir: &Path,
+    source: &str,
+    url: &reqwest::Url,
+    status: u16,
+    body: &[u8],
+    extension: &str,
+) -> std::io::Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let now = chrono::Utc::now();
+    let stem = format!(
+        "{}-{}-{}",
+        source,
+        now.format("%Y%m%dT%H%M%S%.3fZ"),
+        SEQUENCE.fetch_add(1, Ordering::Relaxed),
+    );
+    let path = dir.join(format!("{}.{}", stem, extension));
+    std::fs::write(&path, body)?;
+    let meta = serde_json::json!({
+        "source": source,
+        "url": scrub_url(url),
+        "status": status,
+        "recorded_at": now.to_rfc3339(),
+        "body_file": path.file_name().map(|n| n.to_string_lossy().into_owned()),
+    });
+    std::fs::write(
+        dir.join(format!("{}.meta.json", stem)),
+        serde_json::to_vec_pretty(&meta).unwrap_or_default(),
+    )?;
+    Ok(path)
+}
+
+/// The URL with credentials and secret-looking query values redacted.
+fn scrub_url(url: &reqwest::Url) -> String {
+    let mut url = url.clone();
+    let _ = url.set_username("");
+    let _ = url.set_password(None);
+    if url.query().is_some() {
+        let pairs: Vec<(String, String)> = url
+            .query_pairs()
+            .map(|(k, v)| {
+                let lower = k.to_lowercase();
+                let secret = SECRET_PARAMS.iter().any(|s| lower.contains(s));
+                (k.into_owned(), if secret { "REDACTED".to_string() } else { v.into_owned() })
+            })
+            .collect();
+        url.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+    url.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apis::JsonExt;
+    use crate::mock_http::{self, Response};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_scrub_url_redacts_secrets() {
+        let url = reqwest::Url::parse(
+            "https://user:pw@api.example.org/v2/10.1/x?email=me@example.org&api_key=abc&q=black+holes",
+        )
+        .unwrap();
+        assert_eq!(
+            scrub_url(&url),
+            "https://api.example.org/v2/10.1/x?email=REDACTED&api_key=REDACTED&q=black+holes"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_recording_writes_fixture_with_body() {
+        let body = r#"{"title": "Recorded Work"}"#;
+        let base = mock_http::serve(move |_| Some(Response::json(body))).await;
+
+        let tmp = TempDir::new().unwrap();
+        set_record_dir(Some(tmp.path().to_path_buf()));
+        let resp = reqwest::get(format!("{}/works?api_key=secret", base)).await.unwrap();
+        let value: serde_json::Value = resp.json_checked("record_test").await.unwrap();
+        set_record_dir(None);
+        assert_eq!(value["title"], "Recorded Work");
+
+        let files: Vec<PathBuf> = std::fs::read_dir(tmp.path())
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .filter(|p| p.file_name().unwrap().to_string_lossy().starts_with("record_test-"))
+            .collect();
+        let (meta, fixture): (Vec<&PathBuf>, Vec<&PathBuf>) = files
+            .iter()
+            .partition(|p| p.to_string_lossy().ends_with(".meta.json"));
+        assert_eq!((meta.len(), fixture.len()), (1, 1));
+        assert_eq!(std::fs::read_to_string(fixture[0]).unwrap(), body);
+        let meta: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(meta[0]).unwrap()).unwrap();
+        assert_eq!(meta["status"], 200);
+        assert!(meta["url"].as_str().unwrap().ends_with("/works?api_key=REDACTED"));
+    }
+}
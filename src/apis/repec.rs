@@ -0,0 +1,239 @@
+use super::{http_client, parse_year, strip_html, HttpTuning, PaperResult, PaperSource, SendExt, SourceError, TextExt};
+use async_trait::async_trait;
+use scraper::{ElementRef, Html, Selector};
+
+const BASE_URL: &str = "https://ideas.repec.org";
+
+/// IDEAS/RePEc client covering SSRN/RePEc economics and social-science
+/// working papers and articles. IDs are IDEAS paths, e.g. `repec:p/nbr/nberwo/1234`.
+pub struct RepecClient {
+    client: reqwest::Client,
+}
+
+impl RepecClient {
+    pub fn new(http: &HttpTuning) -> Self {
+        Self {
+            client: http_client(http, "paper-search-mcp/0.1"),
+        }
+    }
+}
+
+#[async_trait]
+impl PaperSource for RepecClient {
+    fn name(&self) -> &str { "repec" }
+
+    async fn search(&self, query: &str, max_results: u32) -> Result<Vec<PaperResult>, SourceError> {
+        let html = self.client
+            .get(format!("{}/cgi-bin/htsearch", BASE_URL))
+            .query(&[("q", query), ("cmd", "Search!"), ("form", "extended"), ("fmt", "long")])
+            .send_checked().await?
+            .text_checked("repec").await?;
+        parse_search_html(&html, max_results)
+    }
+
+    async fn get_paper(&self, id: &str) -> Result<Option<PaperResult>, SourceError> {
+        let path = id.strip_prefix("repec:").unwrap_or(id);
+        let resp = self.client
+            .get(format!("{}/{}.html", BASE_URL, path))
+            .send_checked().await?;
+        if resp.status() == 404 {
+            return Ok(None);
+        }
+        let html = resp.text_checked("repec").await?;
+        parse_item_html(path, &html)
+    }
+
+    async fn get_citations(&self, _id: &str) -> Result<Vec<PaperResult>, SourceError> { Ok(vec![]) }
+    async fn get_references(&self, _id: &str) -> Result<Vec<PaperResult>, SourceError> { Ok(vec![]) }
+}
+
+/// Extract the IDEAS item path (e.g. `p/nbr/nberwo/1234`) from a result link.
+/// Item pages live under one-letter type directories: a(rticle), p(aper), b(ook), h (chapter), c(ode).
+fn item_path(href: &str) -> Option<String> {
+    let path = href
+        .trim_start_matches(BASE_URL)
+        .trim_start_matches('/')
+        .strip_suffix(".html")?;
+    let mut parts = path.split('/');
+    let kind = parts.next()?;
+    if !matches!(kind, "a" | "p" | "b" | "h" | "c") || parts.count() < 2 {
+        return None;
+    }
+    Some(path.to_string())
+}
+
+/// The text of the `<dd>` that follows the `<dt>` holding a result link,
+/// whitespace-collapsed; `None` when the result has no details.
+fn result_details(link: ElementRef) -> Option<String> {
+    let dt = link.ancestors().filter_map(ElementRef::wrap).find(|e| e.value().name() == "dt")?;
+    let dd = dt.next_siblings().filter_map(ElementRef::wrap).next()?;
+    if dd.value().name() != "dd" {
+        return None;
+    }
+    Some(dd.text().collect::<String>().split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+/// Authors and year from a result's details, which IDEAS writes as a
+/// reference: `Jane Economist & Bob Theorist, 2021. "Title," Series 29000.`
+/// The authors are whatever precedes `, <year>`, so none are taken when no
+/// year is found.
+fn parse_details(details: &str) -> (Vec<String>, Option<u32>) {
+    let Some(year) = parse_year(details) else {
+        return (Vec::new(), None);
+    };
+    let authors = details
+        .find(&format!(", {}", year))
+        .map(|end| details[..end].trim_start_matches("By "))
+        .map(|names| {
+            names
+                .split(" & ")
+                .flat_map(|n| n.split(" and "))
+                .flat_map(|n| n.split(';'))
+                .map(str::trim)
+                .filter(|n| !n.is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default();
+    (authors, Some(year))
+}
+
+fn parse_search_html(html: &str, max_results: u32) -> Result<Vec<PaperResult>, SourceError> {
+    let document = Html::parse_document(html);
+    let link_sel = Selector::parse("a[href$='.html']").map_err(|e| SourceError::Parse(format!("{:?}", e)))?;
+
+    let mut papers: Vec<PaperResult> = Vec::new();
+    for link in document.select(&link_sel) {
+        if papers.len() >= max_results as usize {
+            break;
+        }
+        let Some(path) = link.value().attr("href").and_then(item_path) else {
+            continue;
+        };
+        let title = strip_html(&link.text().collect::<String>());
+        if title.is_empty() || papers.iter().any(|p| p.id.ends_with(&path)) {
+            continue;
+        }
+        let (authors, year) = result_details(link).map(|d| parse_details(&d)).unwrap_or_default();
+        papers.push(PaperResult {
+            id: format!("repec:{}", path),
+            title,
+            authors,
+            abstract_text: None,
+            year,
+            source: "repec".to_string(),
+            doi: None,
+            arxiv_id: None,
+            url: format!("{}/{}.html", BASE_URL, path),
+            pdf_url: None,
+            citation_count: None,
+            orcids: Vec::new(),
+            subjects: Vec::new(),
+        });
+    }
+    Ok(papers)
+}
+
+/// Parse an IDEAS item page from its Highwire-style `citation_*` meta tags.
+/// IDEAS uses `citation_authors` (semicolon-separated) rather than repeated
+/// `citation_author`, and `citation_year` alongside `citation_publication_date`.
+fn parse_item_html(path: &str, html: &str) -> Result<Option<PaperResult>, SourceError> {
+    let document = Html::parse_document(html);
+    let meta_sel = Selector::parse("meta[name]").map_err(|e| SourceError::Parse(format!("{:?}", e)))?;
+
+    let mut title = None;
+    let mut authors: Vec<String> = Vec::new();
+    let mut abstract_text = None;
+    let mut year = None;
+    let mut doi = None;
+    let mut pdf_url = None;
+    for meta in document.select(&meta_sel) {
+        let name = meta.value().attr("name").unwrap_or("").to_lowercase();
+        let Some(content) = meta.value().attr("content").map(str::trim).filter(|c| !c.is_empty()) else {
+            continue;
+        };
+        match name.as_str() {
+            "citation_title" => title = Some(strip_html(content)),
+            "citation_author" => authors.push(content.to_string()),
+            "citation_authors" => authors.extend(
+                content.split(';').map(str::trim).filter(|a| !a.is_empty()).map(String::from),
+            ),
+            "citation_abstract" | "description" if abstract_text.is_none() => {
+                abstract_text = Some(strip_html(content))
+            }
+            "citation_year" | "citation_publication_date" | "citation_date" if year.is_none() => {
+                year = content.get(..4).and_then(|y| y.parse::<u32>().ok())
+            }
+            "citation_doi" => doi = Some(content.trim_start_matches("doi:").to_string()),
+            "citation_pdf_url" => pdf_url = Some(content.to_string()),
+            _ => {}
+        }
+    }
+
+    let Some(title) = title else {
+        return Ok(None);
+    };
+    Ok(Some(PaperResult {
+        id: format!("repec:{}", path),
+        title,
+        authors,
+        abstract_text,
+        year,
+        source: "repec".to_string(),
+        doi,
+        arxiv_id: None,
+        url: format!("{}/{}.html", BASE_URL, path),
+        pdf_url,
+        citation_count: None,
+        orcids: Vec::new(),
+        subjects: Vec::new(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_SEARCH: &str = r#"<html><body>
+<dl>
+  <dt><a href="https://ideas.repec.org/p/nbr/nberwo/29000.html">Inflation Expectations and Monetary Policy</a></dt>
+  <dd><i>Jane Economist &amp; Bob Theorist</i>, 2021.
+    "<b>Inflation Expectations and Monetary Policy</b>," NBER Working Papers 29000,
+    National Bureau of Economic Research, Inc.</dd>
+  <dt><a href="/a/aea/aecrev/v110y2020i1p1-30.html">Labor Markets in Transition</a></dt>
+  <dt><a href="/e/pjo123.html">An author profile, not an item</a></dt>
+</dl>
+</body></html>"#;
+
+    const SAMPLE_ITEM: &str = r#"<html><head>
+<meta name="citation_title" content="Inflation Expectations and Monetary Policy">
+<meta name="citation_authors" content="Economist, Jane; Theorist, Bob">
+<meta name="citation_abstract" content="We study how expectations shape policy.">
+<meta name="citation_year" content="2021">
+<meta name="citation_doi" content="10.3386/w29000">
+</head><body></body></html>"#;
+
+    #[test]
+    fn test_parse_search_html() {
+        let papers = parse_search_html(SAMPLE_SEARCH, 10).unwrap();
+        assert_eq!(papers.len(), 2);
+        assert_eq!(papers[0].id, "repec:p/nbr/nberwo/29000");
+        assert_eq!(papers[0].url, "https://ideas.repec.org/p/nbr/nberwo/29000.html");
+        assert_eq!(papers[0].authors, vec!["Jane Economist", "Bob Theorist"]);
+        assert_eq!(papers[0].year, Some(2021));
+        // A result without details doesn't borrow its neighbour's
+        assert_eq!(papers[1].id, "repec:a/aea/aecrev/v110y2020i1p1-30");
+        assert!(papers[1].authors.is_empty());
+        assert_eq!(papers[1].year, None);
+    }
+
+    #[test]
+    fn test_parse_item_meta_tags() {
+        let paper = parse_item_html("p/nbr/nberwo/29000", SAMPLE_ITEM).unwrap().unwrap();
+        assert_eq!(paper.title, "Inflation Expectations and Monetary Policy");
+        assert_eq!(paper.authors, vec!["Economist, Jane", "Theorist, Bob"]);
+        assert_eq!(paper.abstract_text.as_deref(), Some("We study how expectations shape policy."));
+        assert_eq!(paper.year, Some(2021));
+        assert_eq!(paper.doi.as_deref(), Some("10.3386/w29000"));
+    }
+}
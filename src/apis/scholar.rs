@@ -0,0 +1,253 @@
+use super::{http_client, parse_year, AuthorQuery, AuthorWorks, HttpTuning, JsonExt, PaperResult, PaperSource, SendExt, SourceError};
+use async_trait::async_trait;
+use serde::Deserialize;
+
+const BASE_URL: &str = "https://serpapi.com/search.json";
+/// SerpAPI returns at most 20 Google Scholar results per request.
+const MAX_PAGE_SIZE: u32 = 20;
+
+/// Google Scholar via SerpAPI's `google_scholar` engine.
+pub struct ScholarClient {
+    client: reqwest::Client,
+    api_key: String,
+}
+
+impl ScholarClient {
+    pub fn new(http: &HttpTuning, api_key: String) -> Self {
+        Self {
+            client: http_client(http, "paper-search-mcp/0.1"),
+            api_key,
+        }
+    }
+
+    async fn query(&self, params: &[(&str, &str)]) -> Result<Vec<PaperResult>, SourceError> {
+        let resp: ScholarResponse = self.client
+            .get(BASE_URL)
+            .query(&[("engine", "google_scholar"), ("api_key", self.api_key.as_str())])
+            .query(params)
+            .send_checked()
+            .await?
+            .json_checked("scholar")
+            .await?;
+        if let Some(error) = resp.error {
+            return Err(SourceError::Api(error));
+        }
+        Ok(resp.organic_results.iter().map(result_to_paper).collect())
+    }
+}
+
+#[derive(Deserialize)]
+struct ScholarResponse {
+    #[serde(default)]
+    organic_results: Vec<ScholarResult>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ScholarResult {
+    title: String,
+    result_id: String,
+    link: Option<String>,
+    snippet: Option<String>,
+    publication_info: Option<PublicationInfo>,
+    #[serde(default)]
+    resources: Vec<Resource>,
+    inline_links: Option<InlineLinks>,
+}
+
+#[derive(Deserialize)]
+struct PublicationInfo {
+    summary: Option<String>,
+    #[serde(default)]
+    authors: Vec<ScholarAuthor>,
+}
+
+#[derive(Deserialize)]
+struct ScholarAuthor {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct Resource {
+    file_format: Option<String>,
+    link: String,
+}
+
+#[derive(Deserialize)]
+struct InlineLinks {
+    cited_by: Option<CitedBy>,
+}
+
+#[derive(Deserialize)]
+struct CitedBy {
+    total: Option<u32>,
+    cites_id: Option<String>,
+}
+
+/// Split a publication summary ("A Einstein, B Podolsky - Physical review, 1935 - APS")
+/// into its author list and year.
+fn parse_summary(summary: &str) -> (Vec<String>, Option<u32>) {
+    let mut parts = summary.split(" - ");
+    let authors = parts
+        .next()
+        .unwrap_or_default()
+        .split(',')
+        .map(|a| a.trim().trim_end_matches('…').trim())
+        .filter(|a| !a.is_empty())
+        .map(str::to_string)
+        .collect();
+    let year = parts.next().and_then(parse_year);
+    (authors, year)
+}
+
+/// Marks a paper ID built from a result ID, which `cites=` doesn't accept.
+const RESULT_ID_PREFIX: &str = "result:";
+
+fn result_to_paper(r: &ScholarResult) -> PaperResult {
+    let summary = r.publication_info.as_ref().and_then(|p| p.summary.as_deref()).unwrap_or_default();
+    let (summary_authors, year) = parse_summary(summary);
+    // Profile entries cover only authors with Scholar profiles, so the
+    // summary is the author list; profiles stand in when it names nobody
+    let authors = match r.publication_info.as_ref() {
+        Some(info) if summary_authors.is_empty() => info.authors.iter().map(|a| a.name.clone()).collect(),
+        _ => summary_authors,
+    };
+    let cited_by = r.inline_links.as_ref().and_then(|l| l.cited_by.as_ref());
+    // The cites ID is what `get_citations` needs, so it is the paper ID when
+    // present; otherwise the result ID is marked so it is never sent as one.
+    let id = match cited_by.and_then(|c| c.cites_id.clone()) {
+        Some(cites_id) => cites_id,
+        None => format!("{}{}", RESULT_ID_PREFIX, r.result_id),
+    };
+    let pdf_url = r.resources.iter()
+        .find(|res| res.file_format.as_deref().is_some_and(|f| f.eq_ignore_ascii_case("pdf")))
+        .map(|res| res.link.clone());
+
+    PaperResult {
+        id: format!("scholar:{}", id),
+        title: r.title.clone(),
+        authors,
+        abstract_text: r.snippet.clone(),
+        year,
+        source: "scholar".to_string(),
+        doi: None,
+        arxiv_id: None,
+        url: r.link.clone().unwrap_or_default(),
+        pdf_url,
+        citation_count: cited_by.and_then(|c| c.total),
+        orcids: Vec::new(),
+        subjects: Vec::new(),
+    }
+}
+
+#[async_trait]
+impl PaperSource for ScholarClient {
+    fn name(&self) -> &str { "scholar" }
+
+    async fn search(&self, query: &str, max_results: u32) -> Result<Vec<PaperResult>, SourceError> {
+        self.search_page(query, max_results, 0).await
+    }
+
+    async fn search_page(&self, query: &str, max_results: u32, offset: u32) -> Result<Vec<PaperResult>, SourceError> {
+        let num = max_results.min(MAX_PAGE_SIZE).to_string();
+        let start = offset.to_string();
+        self.query(&[("q", query), ("num", num.as_str()), ("start", start.as_str())]).await
+    }
+
+    async fn get_paper(&self, _id: &str) -> Result<Option<PaperResult>, SourceError> {
+        // SerpAPI has no lookup by result ID.
+        Ok(None)
+    }
+
+    async fn get_citations(&self, id: &str) -> Result<Vec<PaperResult>, SourceError> {
+        let cites_id = id.strip_prefix("scholar:").unwrap_or(id);
+        if cites_id.starts_with(RESULT_ID_PREFIX) {
+            // Scholar lists no citing papers for results without a cites ID
+            return Ok(vec![]);
+        }
+        self.query(&[("cites", cites_id), ("num", "20")]).await
+    }
+
+    async fn get_references(&self, _id: &str) -> Result<Vec<PaperResult>, SourceError> {
+        Ok(vec![])
+    }
+
+    async fn search_by_author(&self, query: &AuthorQuery, max_results: u32) -> Result<AuthorWorks, SourceError> {
+        let q = format!("author:\"{}\"", query.name);
+        let num = max_results.min(MAX_PAGE_SIZE).to_string();
+        let (year_min, year_max) = (query.year_min.map(|y| y.to_string()), query.year_max.map(|y| y.to_string()));
+        let mut params = vec![("q", q.as_str()), ("num", num.as_str())];
+        if let Some(min) = &year_min {
+            params.push(("as_ylo", min));
+        }
+        if let Some(max) = &year_max {
+            params.push(("as_yhi", max));
+        }
+        Ok(self.query(&params).await?.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"{
+        "search_metadata": {"status": "Success"},
+        "organic_results": [
+            {
+                "position": 0,
+                "title": "Can quantum-mechanical description of physical reality be considered complete?",
+                "result_id": "nS7m6mVJz8kJ",
+                "link": "https://journals.aps.org/pr/abstract/10.1103/PhysRev.47.777",
+                "snippet": "In a complete theory there is an element corresponding to each element of reality.",
+                "publication_info": {
+                    "summary": "A Einstein, B Podolsky, N Rosen - Physical review, 1935 - APS",
+                    "authors": [
+                        {"name": "A Einstein", "author_id": "qc6CJjYAAAAJ"}
+                    ]
+                },
+                "resources": [
+                    {"title": "aps.org", "file_format": "PDF", "link": "https://example.org/epr.pdf"}
+                ],
+                "inline_links": {
+                    "cited_by": {"total": 27165, "cites_id": "9795930137286889118"},
+                    "versions": {"total": 43}
+                }
+            },
+            {
+                "title": "Quantum entanglement",
+                "result_id": "abc123",
+                "publication_info": {"summary": "R Horodecki, P Horodecki, M Horodecki… - Reviews of modern physics, 2009 - APS"}
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn test_parse_organic_results() {
+        let resp: ScholarResponse = serde_json::from_str(SAMPLE).unwrap();
+        let papers: Vec<PaperResult> = resp.organic_results.iter().map(result_to_paper).collect();
+        assert_eq!(papers.len(), 2);
+
+        let epr = &papers[0];
+        assert_eq!(epr.id, "scholar:9795930137286889118");
+        // Co-authors without Scholar profiles are kept
+        assert_eq!(epr.authors, vec!["A Einstein", "B Podolsky", "N Rosen"]);
+        assert_eq!(epr.year, Some(1935));
+        assert_eq!(epr.citation_count, Some(27165));
+        assert_eq!(epr.pdf_url.as_deref(), Some("https://example.org/epr.pdf"));
+
+        let review = &papers[1];
+        assert_eq!(review.id, "scholar:result:abc123");
+        assert_eq!(review.authors, vec!["R Horodecki", "P Horodecki", "M Horodecki"]);
+        assert_eq!(review.year, Some(2009));
+        assert_eq!(review.citation_count, None);
+        assert!(review.pdf_url.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_result_ids_never_sent_as_cites() {
+        // No request is made, so the key and network are never used
+        let client = ScholarClient::new(&HttpTuning::default(), String::new());
+        assert!(client.get_citations("scholar:result:abc123").await.unwrap().is_empty());
+    }
+}
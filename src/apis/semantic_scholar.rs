@@ -1,22 +1,83 @@
-use super::{PaperResult, PaperSource, SourceError};
+use super::{from_raw, http_client, AuthorQuery, AuthorWorks, HttpTuning, AUTHOR_CANDIDATES, JsonExt, PaperResult, PaperSource, SendExt, SourceError};
 use async_trait::async_trait;
 use serde::Deserialize;
 
 const BASE_URL: &str = "https://api.semanticscholar.org/graph/v1";
+/// S2 returns at most 1000 edges per citations/references page.
+const MAX_RELATION_PAGE: u32 = 1000;
+/// Citations/references returned when no limit is given.
+pub const DEFAULT_RELATION_LIMIT: u32 = 25;
+/// Most IDs `/paper/batch` accepts per request.
+const MAX_BATCH_IDS: usize = 500;
 
 pub struct SemanticScholarClient {
     client: reqwest::Client,
     api_key: Option<String>,
+    base_url: String,
+    relation_page_size: u32,
 }
 
 impl SemanticScholarClient {
-    pub fn new(api_key: Option<String>) -> Self {
+    pub fn new(http: &HttpTuning, api_key: Option<String>) -> Self {
         Self {
-            client: reqwest::Client::builder()
-                .user_agent("paper-search-mcp/0.1")
-                .build()
-                .unwrap(),
+            client: http_client(http, "paper-search-mcp/0.1"),
             api_key,
+            base_url: BASE_URL.to_string(),
+            relation_page_size: MAX_RELATION_PAGE,
+        }
+    }
+
+    #[cfg(test)]
+    fn with_base_url(mut self, base_url: impl Into<String>, relation_page_size: u32) -> Self {
+        self.base_url = base_url.into();
+        self.relation_page_size = relation_page_size;
+        self
+    }
+
+    /// Page through a `/citations` or `/references` endpoint, collecting the
+    /// `edge_key` paper of each edge, until `limit` papers or a short page.
+    async fn relation_pages(
+        &self,
+        id: &str,
+        endpoint: &str,
+        edge_key: &str,
+        limit: u32,
+    ) -> Result<Vec<PaperResult>, SourceError> {
+        let paper_id = id.strip_prefix("s2:").unwrap_or(id);
+        let url = format!("{}/paper/{}/{}", self.base_url, paper_id, endpoint);
+        let fields = format!("{}.{}", edge_key, FIELDS);
+        let mut papers = Vec::new();
+        let mut offset = 0;
+        while offset < limit {
+            let page_size = (limit - offset).min(self.relation_page_size);
+            let resp: S2CitationResponse = self.add_auth(
+                self.client.get(&url)
+                    .query(&[("fields", fields.as_str())])
+                    .query(&[("offset", offset), ("limit", page_size)])
+            ).send_checked().await?.json_checked("semantic_scholar").await?;
+            let edges = resp.data.unwrap_or_default();
+            let received = edges.len() as u32;
+            papers.extend(edges.iter().filter_map(|edge| {
+                let val = edge.paper.get(edge_key)?;
+                let p: S2Paper = serde_json::from_value(val.clone()).ok()?;
+                Some(s2_to_paper(&p))
+            }));
+            if received < page_size {
+                break;
+            }
+            offset += received;
+        }
+        Ok(papers)
+    }
+
+    /// Identifier in the form S2 batch lookups accept.
+    fn batch_id(id: &str) -> String {
+        if let Some(doi) = id.strip_prefix("doi:") {
+            format!("DOI:{}", doi)
+        } else if let Some(arxiv) = id.strip_prefix("arxiv:") {
+            format!("ARXIV:{}", arxiv)
+        } else {
+            id.strip_prefix("s2:").unwrap_or(id).to_string()
         }
     }
 
@@ -33,6 +94,18 @@ struct S2SearchResponse {
     data: Option<Vec<S2Paper>>,
 }
 
+#[derive(Deserialize)]
+struct S2AuthorSearchResponse {
+    data: Option<Vec<S2AuthorRecord>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct S2AuthorRecord {
+    author_id: Option<String>,
+    name: Option<String>,
+}
+
 #[derive(Deserialize)]
 struct S2CitationResponse {
     data: Option<Vec<S2CitationEdge>>,
@@ -94,6 +167,8 @@ fn s2_to_paper(p: &S2Paper) -> PaperResult {
         url: p.url.clone().unwrap_or_default(),
         pdf_url: p.open_access_pdf.as_ref().and_then(|pdf| pdf.url.clone()),
         citation_count: p.citation_count,
+        orcids: Vec::new(),
+        subjects: Vec::new(),
     }
 }
 
@@ -106,67 +181,170 @@ impl PaperSource for SemanticScholarClient {
     }
 
     async fn search(&self, query: &str, max_results: u32) -> Result<Vec<PaperResult>, SourceError> {
-        let url = format!("{}/paper/search", BASE_URL);
+        self.search_page(query, max_results, 0).await
+    }
+
+    async fn search_page(&self, query: &str, max_results: u32, offset: u32) -> Result<Vec<PaperResult>, SourceError> {
+        let url = format!("{}/paper/search", self.base_url);
         let limit = max_results.min(100).to_string();
+        let offset = offset.to_string();
         let resp: S2SearchResponse = self.add_auth(
             self.client.get(&url)
                 .query(&[
                     ("query", query),
                     ("limit", limit.as_str()),
+                    ("offset", offset.as_str()),
                     ("fields", FIELDS),
                 ])
-        ).send().await?.json().await?;
+        ).send_checked().await?.json_checked("semantic_scholar").await?;
         Ok(resp.data.unwrap_or_default().iter().map(s2_to_paper).collect())
     }
 
     async fn get_paper(&self, id: &str) -> Result<Option<PaperResult>, SourceError> {
+        Ok(self.get_paper_with_raw(id).await?.map(|(paper, _)| paper))
+    }
+
+    async fn get_paper_with_raw(&self, id: &str) -> Result<Option<(PaperResult, Option<serde_json::Value>)>, SourceError> {
         let paper_id = id.strip_prefix("s2:").unwrap_or(id);
-        let url = format!("{}/paper/{}", BASE_URL, paper_id);
+        let url = format!("{}/paper/{}", self.base_url, paper_id);
         let resp = self.add_auth(
             self.client.get(&url).query(&[("fields", FIELDS)])
-        ).send().await?;
+        ).send_checked().await?;
         if resp.status() == 404 {
             return Ok(None);
         }
-        let paper: S2Paper = resp.json().await?;
-        Ok(Some(s2_to_paper(&paper)))
+        let raw: serde_json::Value = resp.json_checked("semantic_scholar").await?;
+        let paper: S2Paper = from_raw(&raw, "semantic_scholar")?;
+        Ok(Some((s2_to_paper(&paper), Some(raw))))
     }
 
-    async fn get_citations(&self, id: &str) -> Result<Vec<PaperResult>, SourceError> {
-        let paper_id = id.strip_prefix("s2:").unwrap_or(id);
-        let url = format!("{}/paper/{}/citations", BASE_URL, paper_id);
-        let fields = format!("citingPaper.{}", FIELDS);
-        let resp: S2CitationResponse = self.add_auth(
-            self.client.get(&url)
-                .query(&[("fields", fields.as_str()), ("limit", "25")])
-        ).send().await?.json().await?;
-        let papers: Vec<PaperResult> = resp.data.unwrap_or_default()
-            .iter()
-            .filter_map(|edge| {
-                let val = edge.paper.get("citingPaper")?;
-                let p: S2Paper = serde_json::from_value(val.clone()).ok()?;
-                Some(s2_to_paper(&p))
-            })
-            .collect();
+    /// POSTs the IDs to `/paper/batch`, up to `MAX_BATCH_IDS` per request.
+    /// Unknown IDs come back as `null` entries.
+    async fn get_papers(&self, ids: &[String]) -> Result<Vec<Option<PaperResult>>, SourceError> {
+        let url = format!("{}/paper/batch", self.base_url);
+        let mut papers = Vec::with_capacity(ids.len());
+        for chunk in ids.chunks(MAX_BATCH_IDS) {
+            let body = serde_json::json!({
+                "ids": chunk.iter().map(|id| Self::batch_id(id)).collect::<Vec<_>>(),
+            });
+            let resp: Vec<Option<S2Paper>> = self.add_auth(
+                self.client.post(&url).query(&[("fields", FIELDS)]).json(&body)
+            ).send_checked().await?.json_checked("semantic_scholar").await?;
+            if resp.len() != chunk.len() {
+                return Err(SourceError::Parse(format!(
+                    "batch returned {} entries for {} IDs",
+                    resp.len(),
+                    chunk.len()
+                )));
+            }
+            papers.extend(resp.iter().map(|p| p.as_ref().map(s2_to_paper)));
+        }
         Ok(papers)
     }
 
+    async fn get_citations(&self, id: &str) -> Result<Vec<PaperResult>, SourceError> {
+        self.get_citations_limited(id, DEFAULT_RELATION_LIMIT).await
+    }
+
     async fn get_references(&self, id: &str) -> Result<Vec<PaperResult>, SourceError> {
-        let paper_id = id.strip_prefix("s2:").unwrap_or(id);
-        let url = format!("{}/paper/{}/references", BASE_URL, paper_id);
-        let fields = format!("citedPaper.{}", FIELDS);
-        let resp: S2CitationResponse = self.add_auth(
-            self.client.get(&url)
-                .query(&[("fields", fields.as_str()), ("limit", "25")])
-        ).send().await?.json().await?;
-        let papers: Vec<PaperResult> = resp.data.unwrap_or_default()
-            .iter()
-            .filter_map(|edge| {
-                let val = edge.paper.get("citedPaper")?;
-                let p: S2Paper = serde_json::from_value(val.clone()).ok()?;
-                Some(s2_to_paper(&p))
-            })
-            .collect();
-        Ok(papers)
+        self.get_references_limited(id, DEFAULT_RELATION_LIMIT).await
+    }
+
+    async fn get_citations_limited(&self, id: &str, limit: u32) -> Result<Vec<PaperResult>, SourceError> {
+        self.relation_pages(id, "citations", "citingPaper", limit).await
+    }
+
+    async fn get_references_limited(&self, id: &str, limit: u32) -> Result<Vec<PaperResult>, SourceError> {
+        self.relation_pages(id, "references", "citedPaper", limit).await
+    }
+
+    /// Resolves the name with `/author/search`, then lists the top match's
+    /// papers from `/author/{id}/papers`; the other matching profiles are
+    /// reported as candidates. That endpoint can't filter by year, so with a
+    /// year range a full page is fetched and filtered here.
+    async fn search_by_author(&self, query: &AuthorQuery, max_results: u32) -> Result<AuthorWorks, SourceError> {
+        let url = format!("{}/author/search", self.base_url);
+        let candidates = AUTHOR_CANDIDATES.to_string();
+        let authors: S2AuthorSearchResponse = self.add_auth(
+            self.client.get(&url).query(&[("query", query.name.as_str()), ("limit", candidates.as_str()), ("fields", "name")])
+        ).send_checked().await?.json_checked("semantic_scholar").await?;
+        let authors = authors.data.unwrap_or_default();
+        let Some(author_id) = authors.iter().find_map(|a| a.author_id.clone()) else {
+            return Ok(AuthorWorks::default());
+        };
+        let candidates = authors.into_iter().filter_map(|a| a.name).collect();
+        let url = format!("{}/author/{}/papers", self.base_url, author_id);
+        let limit = if query.has_years() { 1000 } else { max_results.min(1000) }.to_string();
+        let resp: S2SearchResponse = self.add_auth(
+            self.client.get(&url).query(&[("fields", FIELDS), ("limit", limit.as_str())])
+        ).send_checked().await?.json_checked("semantic_scholar").await?;
+        let mut papers: Vec<PaperResult> = resp.data.unwrap_or_default().iter().map(s2_to_paper).collect();
+        papers.retain(|p| query.contains_year(p.year));
+        papers.truncate(max_results as usize);
+        Ok(AuthorWorks { papers, candidates })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use crate::mock_http::{self, Response};
+
+    /// Stand-in for `/paper/{id}/citations` with `total` citing papers,
+    /// honouring `offset` and `limit`.
+    async fn mock_s2(total: usize, requests: Arc<AtomicUsize>) -> String {
+        mock_http::serve(move |request| {
+            requests.fetch_add(1, Ordering::SeqCst);
+            let param = |name: &str| -> usize { request.query(name).unwrap().parse().unwrap() };
+            let (offset, limit) = (param("offset"), param("limit"));
+            let edges: Vec<String> = (offset..total.min(offset + limit))
+                .map(|i| format!(r#"{{"citingPaper": {{"paperId": "c{}", "title": "Citing {}"}}}}"#, i, i))
+                .collect();
+            Some(Response::json(format!(r#"{{"offset": {}, "data": [{}]}}"#, offset, edges.join(","))))
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_citations_page_until_limit_or_short_page() {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let base = mock_s2(5, requests.clone()).await;
+        let client = SemanticScholarClient::new(&HttpTuning::default(), None).with_base_url(base, 2);
+
+        let papers = client.get_citations_limited("s2:abc", 4).await.unwrap();
+        let ids: Vec<&str> = papers.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, vec!["s2:c0", "s2:c1", "s2:c2", "s2:c3"]);
+        assert_eq!(requests.swap(0, Ordering::SeqCst), 2);
+
+        // A short page ends paging before the limit
+        let papers = client.get_citations_limited("s2:abc", 10).await.unwrap();
+        assert_eq!(papers.len(), 5);
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_batch_lookup_posts_ids_and_maps_nulls() {
+        let received = Arc::new(std::sync::Mutex::new(None));
+        let seen = received.clone();
+        let base = mock_http::serve(move |request| {
+            *seen.lock().unwrap() = Some(request);
+            Some(Response::json(r#"[{"paperId": "abc", "title": "Found Paper"}, null]"#))
+        })
+        .await;
+        let client = SemanticScholarClient::new(&HttpTuning::default(), None).with_base_url(base, MAX_RELATION_PAGE);
+
+        let ids = vec!["s2:abc".to_string(), "doi:10.1234/missing".to_string()];
+        let papers = client.get_papers(&ids).await.unwrap();
+        assert_eq!(papers.len(), 2);
+        assert_eq!(papers[0].as_ref().unwrap().title, "Found Paper");
+        assert!(papers[1].is_none());
+
+        let request = received.lock().unwrap().take().unwrap();
+        assert_eq!((request.method.as_str(), request.path()), ("POST", "/paper/batch"));
+        assert!(request.query("fields").is_some());
+        let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+        assert_eq!(body["ids"], serde_json::json!(["abc", "DOI:10.1234/missing"]));
     }
 }
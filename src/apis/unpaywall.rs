@@ -1,4 +1,4 @@
-use super::SourceError;
+use super::{http_client, HttpTuning, JsonExt, SendExt, SourceError};
 use serde::Deserialize;
 
 const BASE_URL: &str = "https://api.unpaywall.org/v2";
@@ -9,23 +9,20 @@ pub struct UnpaywallClient {
 }
 
 impl UnpaywallClient {
-    pub fn new(email: String) -> Self {
+    pub fn new(http: &HttpTuning, email: String) -> Self {
         Self {
-            client: reqwest::Client::builder()
-                .user_agent("paper-search-mcp/0.1")
-                .build()
-                .unwrap(),
+            client: http_client(http, "paper-search-mcp/0.1"),
             email,
         }
     }
 
     pub async fn get_pdf_url(&self, doi: &str) -> Result<Option<String>, SourceError> {
         let url = format!("{}/{}?email={}", BASE_URL, doi, self.email);
-        let resp = self.client.get(&url).send().await?;
+        let resp = self.client.get(&url).send_checked().await?;
         if resp.status() == 404 {
             return Ok(None);
         }
-        let data: UnpaywallResponse = resp.json().await?;
+        let data: UnpaywallResponse = resp.json_checked("unpaywall").await?;
         Ok(data.best_oa_location.and_then(|loc| loc.url_for_pdf))
     }
 }
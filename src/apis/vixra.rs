@@ -1,4 +1,4 @@
-use super::{PaperResult, PaperSource, SourceError};
+use super::{http_client, strip_html, HttpTuning, PaperResult, PaperSource, SendExt, SourceError, TextExt};
 use async_trait::async_trait;
 use scraper::{Html, Selector};
 
@@ -9,12 +9,9 @@ pub struct VixraClient {
 }
 
 impl VixraClient {
-    pub fn new() -> Self {
+    pub fn new(http: &HttpTuning) -> Self {
         Self {
-            client: reqwest::Client::builder()
-                .user_agent("paper-search-mcp/0.1")
-                .build()
-                .unwrap(),
+            client: http_client(http, "paper-search-mcp/0.1"),
         }
     }
 }
@@ -25,27 +22,27 @@ impl PaperSource for VixraClient {
 
     async fn search(&self, query: &str, max_results: u32) -> Result<Vec<PaperResult>, SourceError> {
         let url = format!("{}/find?text={}", BASE_URL, urlencoded(query));
-        let html = self.client.get(&url).send().await?.text().await?;
+        let html = self.client.get(&url).send_checked().await?.text_checked("vixra").await?;
         parse_vixra_html(&html, max_results)
     }
 
     async fn get_paper(&self, id: &str) -> Result<Option<PaperResult>, SourceError> {
         let vixra_id = id.strip_prefix("vixra:").unwrap_or(id);
         let url = format!("{}/abs/{}", BASE_URL, vixra_id);
-        let html = self.client.get(&url).send().await?.text().await?;
+        let html = self.client.get(&url).send_checked().await?.text_checked("vixra").await?;
         let document = Html::parse_document(&html);
         // Parse single paper page
         let title_sel = Selector::parse("h1").map_err(|e| SourceError::Parse(format!("{:?}", e)))?;
         let title = document.select(&title_sel)
             .next()
-            .map(|el| el.text().collect::<String>())
+            .map(|el| strip_html(&el.text().collect::<String>()))
             .unwrap_or_default();
         if title.is_empty() {
             return Ok(None);
         }
         Ok(Some(PaperResult {
             id: format!("vixra:{}", vixra_id),
-            title: title.trim().to_string(),
+            title,
             authors: vec![],
             abstract_text: None,
             year: None,
@@ -55,6 +52,8 @@ impl PaperSource for VixraClient {
             url: format!("{}/abs/{}", BASE_URL, vixra_id),
             pdf_url: Some(format!("{}/pdf/{}.pdf", BASE_URL, vixra_id)),
             citation_count: None,
+            orcids: Vec::new(),
+            subjects: Vec::new(),
         }))
     }
 
@@ -75,8 +74,8 @@ fn parse_vixra_html(html: &str, max_results: u32) -> Result<Vec<PaperResult>, So
 
     for link in document.select(&link_sel).take(max_results as usize) {
         let href = link.value().attr("href").unwrap_or("");
-        let title = link.text().collect::<String>();
-        let title = title.trim().to_string();
+        // Link text can still carry escaped markup (`&lt;i&gt;`) from the listing.
+        let title = strip_html(&link.text().collect::<String>());
 
         if title.is_empty() || !href.contains("/abs/") {
             continue;
@@ -99,8 +98,26 @@ fn parse_vixra_html(html: &str, max_results: u32) -> Result<Vec<PaperResult>, So
             url: format!("{}/abs/{}", BASE_URL, vixra_id),
             pdf_url: Some(format!("{}/pdf/{}.pdf", BASE_URL, vixra_id)),
             citation_count: None,
+            orcids: Vec::new(),
+            subjects: Vec::new(),
         });
     }
 
     Ok(papers)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_titles_are_stripped_of_html() {
+        let html = r#"<html><body>
+<p><b><a href="/abs/2401.0001">The &lt;i&gt;Hubble&lt;/i&gt; Tension &amp;amp; <i>Dark</i> Energy</a></b></p>
+</body></html>"#;
+        let papers = parse_vixra_html(html, 10).unwrap();
+        assert_eq!(papers.len(), 1);
+        assert_eq!(papers[0].id, "vixra:2401.0001");
+        assert_eq!(papers[0].title, "The Hubble Tension & Dark Energy");
+    }
+}
@@ -0,0 +1,255 @@
+use super::{from_raw, http_client, page_for_offset, search_with_skip, strip_html, AuthorQuery, AuthorWorks, HttpTuning, JsonExt, PaperResult, PaperSource, SendExt, SourceError};
+use async_trait::async_trait;
+use serde::Deserialize;
+
+const BASE_URL: &str = "https://zenodo.org/api/records";
+/// Largest page Zenodo serves to anonymous clients.
+const MAX_PAGE_SIZE: u32 = 25;
+
+/// Zenodo deposits: datasets, software and other research artifacts.
+pub struct ZenodoClient {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl ZenodoClient {
+    pub fn new(http: &HttpTuning) -> Self {
+        Self {
+            client: http_client(http, "paper-search-mcp/0.1"),
+            base_url: BASE_URL.to_string(),
+        }
+    }
+
+    #[cfg(test)]
+    fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Fetch `max_results` records starting at `offset`, a page of at most
+    /// `MAX_PAGE_SIZE` at a time.
+    async fn query(&self, query: &str, max_results: u32, offset: u32) -> Result<Vec<PaperResult>, SourceError> {
+        let page_size = max_results.clamp(1, MAX_PAGE_SIZE);
+        let Some(mut page) = page_for_offset(offset, page_size) else {
+            return search_with_skip(self, query, max_results, offset).await;
+        };
+        let mut papers = Vec::new();
+        while papers.len() < max_results as usize {
+            let resp: ZenodoResponse = self.client
+                .get(&self.base_url)
+                .query(&[
+                    ("q", query.to_string()),
+                    ("size", page_size.to_string()),
+                    ("page", page.to_string()),
+                ])
+                .send_checked().await?.json_checked("zenodo").await?;
+            let received = resp.hits.hits.len();
+            papers.extend(resp.hits.hits.iter().map(zenodo_to_paper));
+            if received < page_size as usize {
+                break;
+            }
+            page += 1;
+        }
+        papers.truncate(max_results as usize);
+        Ok(papers)
+    }
+}
+
+#[derive(Deserialize)]
+struct ZenodoResponse {
+    hits: ZenodoHits,
+}
+#[derive(Deserialize)]
+struct ZenodoHits {
+    #[serde(default)]
+    hits: Vec<ZenodoRecord>,
+}
+#[derive(Deserialize)]
+struct ZenodoRecord {
+    id: serde_json::Value,
+    doi: Option<String>,
+    links: Option<ZenodoLinks>,
+    metadata: ZenodoMetadata,
+    #[serde(default)]
+    files: Vec<ZenodoFile>,
+}
+#[derive(Deserialize)]
+struct ZenodoLinks {
+    html: Option<String>,
+}
+#[derive(Deserialize)]
+struct ZenodoMetadata {
+    title: Option<String>,
+    #[serde(default)]
+    creators: Vec<ZenodoCreator>,
+    description: Option<String>,
+    publication_date: Option<String>,
+}
+#[derive(Deserialize)]
+struct ZenodoCreator {
+    name: Option<String>,
+}
+#[derive(Deserialize)]
+struct ZenodoFile {
+    key: Option<String>,
+    links: Option<ZenodoFileLinks>,
+}
+#[derive(Deserialize)]
+struct ZenodoFileLinks {
+    #[serde(rename = "self")]
+    self_link: Option<String>,
+}
+
+fn zenodo_to_paper(r: &ZenodoRecord) -> PaperResult {
+    // Record IDs are numbers in current responses and strings in older ones
+    let id = match &r.id {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    let meta = &r.metadata;
+    let pdf_url = r.files.iter()
+        .find(|f| f.key.as_deref().is_some_and(|k| k.to_lowercase().ends_with(".pdf")))
+        .and_then(|f| f.links.as_ref()?.self_link.clone());
+
+    PaperResult {
+        url: r.links.as_ref()
+            .and_then(|l| l.html.clone())
+            .unwrap_or_else(|| format!("https://zenodo.org/records/{}", id)),
+        id: format!("zenodo:{}", id),
+        title: meta.title.as_deref().map(strip_html).unwrap_or_default(),
+        authors: meta.creators.iter().filter_map(|c| c.name.clone()).collect(),
+        abstract_text: meta.description.as_deref().map(strip_html).filter(|d| !d.is_empty()),
+        year: meta.publication_date.as_ref().and_then(|d| d.get(..4)?.parse::<u32>().ok()),
+        source: "zenodo".to_string(),
+        doi: r.doi.clone().filter(|d| !d.is_empty()),
+        arxiv_id: None,
+        pdf_url,
+        citation_count: None,
+        orcids: Vec::new(),
+        subjects: Vec::new(),
+    }
+}
+
+#[async_trait]
+impl PaperSource for ZenodoClient {
+    fn name(&self) -> &str { "zenodo" }
+
+    async fn search(&self, query: &str, max_results: u32) -> Result<Vec<PaperResult>, SourceError> {
+        self.query(query, max_results, 0).await
+    }
+
+    async fn search_page(&self, query: &str, max_results: u32, offset: u32) -> Result<Vec<PaperResult>, SourceError> {
+        self.query(query, max_results, offset).await
+    }
+
+    async fn get_paper(&self, id: &str) -> Result<Option<PaperResult>, SourceError> {
+        Ok(self.get_paper_with_raw(id).await?.map(|(paper, _)| paper))
+    }
+
+    async fn get_paper_with_raw(&self, id: &str) -> Result<Option<(PaperResult, Option<serde_json::Value>)>, SourceError> {
+        let record_id = id.strip_prefix("zenodo:").unwrap_or(id);
+        let resp = self.client.get(format!("{}/{}", self.base_url, record_id)).send_checked().await?;
+        if resp.status() == 404 { return Ok(None); }
+        let raw: serde_json::Value = resp.json_checked("zenodo").await?;
+        let record: ZenodoRecord = from_raw(&raw, "zenodo")?;
+        Ok(Some((zenodo_to_paper(&record), Some(raw))))
+    }
+
+    async fn get_citations(&self, _id: &str) -> Result<Vec<PaperResult>, SourceError> { Ok(vec![]) }
+    async fn get_references(&self, _id: &str) -> Result<Vec<PaperResult>, SourceError> { Ok(vec![]) }
+
+    async fn search_by_author(&self, query: &AuthorQuery, max_results: u32) -> Result<AuthorWorks, SourceError> {
+        let mut q = format!("creators.name:\"{}\"", query.name.replace('"', ""));
+        if query.has_years() {
+            let (min, max) = query.year_range();
+            q.push_str(&format!(" AND publication_date:[{}-01-01 TO {}-12-31]", min, max));
+        }
+        Ok(self.query(&q, max_results, 0).await?.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_http::{self, Response};
+
+    /// A mock Zenodo serving `total` records, numbered from 0.
+    async fn mock_zenodo(total: u32) -> String {
+        let base = mock_http::serve(move |request| {
+            let size: u32 = request.query("size")?.parse().ok()?;
+            let page: u32 = request.query("page")?.parse().ok()?;
+            let start = (page - 1) * size;
+            let hits: Vec<String> = (start..total.min(start + size))
+                .map(|i| format!(r#"{{"id": {}, "metadata": {{"title": "Record {}"}}}}"#, i, i))
+                .collect();
+            Some(Response::json(format!(r#"{{"hits": {{"hits": [{}], "total": {}}}}}"#, hits.join(","), total)))
+        })
+        .await;
+        format!("{}/api/records", base)
+    }
+
+    #[tokio::test]
+    async fn test_query_pages_past_max_page_size() {
+        let base = mock_zenodo(70).await;
+        let client = ZenodoClient::new(&HttpTuning::default()).with_base_url(base);
+
+        let papers = client.search("data", 60).await.unwrap();
+        let ids: Vec<&str> = papers.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids.len(), 60);
+        assert_eq!(ids[0], "zenodo:0");
+        assert_eq!(ids[59], "zenodo:59");
+
+        let papers = client.search_page("data", 30, 50).await.unwrap();
+        let ids: Vec<&str> = papers.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, (50..70).map(|i| format!("zenodo:{}", i)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_parse_search_response() {
+        let body = r#"{
+            "hits": {
+                "hits": [
+                    {
+                        "id": 7654321,
+                        "doi": "10.5281/zenodo.7654321",
+                        "links": { "html": "https://zenodo.org/records/7654321" },
+                        "metadata": {
+                            "title": "Simulation data for <i>holographic</i> codes",
+                            "creators": [{ "name": "Doe, Jane", "affiliation": "CERN" }, { "name": "Roe, Richard" }],
+                            "description": "<p>Raw outputs &amp; analysis scripts.</p>",
+                            "publication_date": "2023-02-14",
+                            "resource_type": { "type": "dataset" }
+                        },
+                        "files": [
+                            { "key": "data.h5", "links": { "self": "https://zenodo.org/api/records/7654321/files/data.h5/content" } },
+                            { "key": "Paper.PDF", "links": { "self": "https://zenodo.org/api/records/7654321/files/Paper.PDF/content" } }
+                        ]
+                    },
+                    { "id": "12", "metadata": { "title": "Old record" } }
+                ],
+                "total": 2
+            }
+        }"#;
+        let resp: ZenodoResponse = serde_json::from_str(body).unwrap();
+        let papers: Vec<PaperResult> = resp.hits.hits.iter().map(zenodo_to_paper).collect();
+
+        let paper = &papers[0];
+        assert_eq!(paper.id, "zenodo:7654321");
+        assert_eq!(paper.title, "Simulation data for holographic codes");
+        assert_eq!(paper.authors, vec!["Doe, Jane", "Roe, Richard"]);
+        assert_eq!(paper.abstract_text.as_deref(), Some("Raw outputs & analysis scripts."));
+        assert_eq!(paper.year, Some(2023));
+        assert_eq!(paper.doi.as_deref(), Some("10.5281/zenodo.7654321"));
+        assert_eq!(paper.url, "https://zenodo.org/records/7654321");
+        assert_eq!(
+            paper.pdf_url.as_deref(),
+            Some("https://zenodo.org/api/records/7654321/files/Paper.PDF/content"),
+        );
+
+        let sparse = &papers[1];
+        assert_eq!(sparse.id, "zenodo:12");
+        assert_eq!(sparse.url, "https://zenodo.org/records/12");
+        assert!(sparse.authors.is_empty());
+        assert_eq!((sparse.abstract_text.as_deref(), sparse.year, sparse.doi.as_deref()), (None, None, None));
+    }
+}
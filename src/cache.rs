@@ -0,0 +1,417 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::apis::{PaperResult, SearchField};
+use crate::search::RankBy;
+
+/// Normalized identity of a `search_papers` call. Equivalent requests (case,
+/// whitespace, or source order differences) produce equal signatures.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SearchSignature {
+    query: String,
+    sources: Vec<String>,
+    max_results: u32,
+    offset: u32,
+    language: Option<String>,
+    field: Option<&'static str>,
+    categories: Vec<String>,
+    rank_by: RankBy,
+}
+
+impl SearchSignature {
+    pub fn new(query: &str, sources: Option<&[String]>, max_results: u32, offset: u32) -> Self {
+        let query = query
+            .split_whitespace()
+            .map(|w| w.to_lowercase())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mut sources: Vec<String> = sources
+            .unwrap_or_default()
+            .iter()
+            .map(|s| s.trim().to_lowercase())
+            .filter(|s| !s.is_empty())
+            .collect();
+        sources.sort();
+        sources.dedup();
+        Self {
+            query,
+            sources,
+            max_results,
+            offset,
+            language: None,
+            field: None,
+            categories: Vec::new(),
+            rank_by: RankBy::default(),
+        }
+    }
+
+    /// Distinguish searches restricted to a language.
+    pub fn with_language(mut self, language: Option<&str>) -> Self {
+        self.language = language.map(|l| l.trim().to_lowercase());
+        self
+    }
+
+    /// Distinguish searches restricted to a metadata field.
+    pub fn with_field(mut self, field: Option<SearchField>) -> Self {
+        self.field = field.map(|f| f.as_str());
+        self
+    }
+
+    /// Distinguish searches ranked differently.
+    pub fn with_rank_by(mut self, rank_by: RankBy) -> Self {
+        self.rank_by = rank_by;
+        self
+    }
+
+    /// Distinguish searches restricted to subject categories, in any order.
+    pub fn with_categories(mut self, categories: Option<&[String]>) -> Self {
+        let mut categories: Vec<String> = categories
+            .unwrap_or_default()
+            .iter()
+            .map(|c| c.trim().to_string())
+            .filter(|c| !c.is_empty())
+            .collect();
+        categories.sort();
+        categories.dedup();
+        self.categories = categories;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+    pub ttl_secs: u64,
+}
+
+/// Short-lived cache of federated search result sets. A zero TTL disables it.
+pub struct SearchCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<SearchSignature, (Instant, Vec<PaperResult>)>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl SearchCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Return the cached results for `key` if present and fresh.
+    pub fn get(&self, key: &SearchSignature) -> Option<Vec<PaperResult>> {
+        if self.ttl.is_zero() {
+            return None;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        let fresh = match entries.get(key) {
+            Some((stored, results)) if stored.elapsed() < self.ttl => Some(results.clone()),
+            _ => None,
+        };
+        if fresh.is_some() {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            entries.remove(key);
+            self.misses.fetch_add(1, Ordering::Relaxed);
+        }
+        fresh
+    }
+
+    pub fn insert(&self, key: SearchSignature, results: Vec<PaperResult>) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, (stored, _)| stored.elapsed() < self.ttl);
+        entries.insert(key, (Instant::now(), results));
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.entries.lock().unwrap().len(),
+            ttl_secs: self.ttl.as_secs(),
+        }
+    }
+}
+
+/// On-disk cache of source responses, one JSON file per entry, keyed by
+/// source, method and argument. Entries older than the TTL miss and are
+/// deleted; a zero TTL disables the cache. Every `PRUNE_EVERY` writes, expired
+/// entries are swept and the oldest removed until the cache fits `max_bytes`.
+pub struct DiskCache {
+    dir: PathBuf,
+    ttl: Duration,
+    /// Size the cache is trimmed to; 0 means unbounded.
+    max_bytes: u64,
+    /// Writes since the cache was opened.
+    writes: AtomicU64,
+}
+
+/// Writes between automatic `DiskCache::prune` sweeps.
+const PRUNE_EVERY: u64 = 100;
+
+#[derive(Serialize, Deserialize)]
+struct DiskEntry<T> {
+    /// Full key, checked on read so hash collisions miss instead of mixing entries.
+    key: String,
+    stored_at: i64,
+    value: T,
+}
+
+impl DiskCache {
+    pub fn new(dir: PathBuf, ttl: Duration) -> Self {
+        Self { dir, ttl, max_bytes: 0, writes: AtomicU64::new(0) }
+    }
+
+    /// Trim the cache to `max_bytes` (0 for no limit) when pruning.
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    pub fn enabled(&self) -> bool {
+        !self.ttl.is_zero()
+    }
+
+    fn entry_key(source: &str, method: &str, arg: &str) -> String {
+        format!("{}\u{1f}{}\u{1f}{}", source, method, arg)
+    }
+
+    fn path_for(&self, key: &str) -> PathBuf {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        self.dir.join(format!("{:016x}.json", hasher.finish()))
+    }
+
+    /// The fresh cached value for this call, if any.
+    pub fn get<T: DeserializeOwned>(&self, source: &str, method: &str, arg: &str) -> Option<T> {
+        if !self.enabled() {
+            return None;
+        }
+        let key = Self::entry_key(source, method, arg);
+        let path = self.path_for(&key);
+        let bytes = std::fs::read(&path).ok()?;
+        let entry: DiskEntry<T> = serde_json::from_slice(&bytes).ok()?;
+        if entry.key != key {
+            return None;
+        }
+        let age = chrono::Utc::now().timestamp().saturating_sub(entry.stored_at);
+        if age < 0 || age as u64 >= self.ttl.as_secs() {
+            let _ = std::fs::remove_file(&path);
+            return None;
+        }
+        Some(entry.value)
+    }
+
+    /// Drop the entry for this call, so the next one reaches the source.
+    pub fn remove(&self, source: &str, method: &str, arg: &str) {
+        let path = self.path_for(&Self::entry_key(source, method, arg));
+        if let Err(e) = std::fs::remove_file(&path) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                tracing::warn!("Failed to remove cache entry {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    /// Store a value; failures are logged and otherwise ignored.
+    pub fn put<T: Serialize>(&self, source: &str, method: &str, arg: &str, value: &T) {
+        if !self.enabled() {
+            return;
+        }
+        let key = Self::entry_key(source, method, arg);
+        let entry = DiskEntry { key, stored_at: chrono::Utc::now().timestamp(), value };
+        let result = std::fs::create_dir_all(&self.dir)
+            .and_then(|_| serde_json::to_vec(&entry).map_err(std::io::Error::other))
+            .and_then(|bytes| std::fs::write(self.path_for(&entry.key), bytes));
+        if let Err(e) = result {
+            tracing::warn!("Failed to write cache entry under {}: {}", self.dir.display(), e);
+        }
+        if (self.writes.fetch_add(1, Ordering::Relaxed) + 1).is_multiple_of(PRUNE_EVERY) {
+            if let Err(e) = self.prune() {
+                tracing::warn!("Failed to prune cache under {}: {}", self.dir.display(), e);
+            }
+        }
+    }
+
+    /// Delete expired entries, then the least recently written ones until the
+    /// rest fit `max_bytes`. Ages come from file modification times, which
+    /// are set when an entry is written. Returns how many were removed.
+    pub fn prune(&self) -> std::io::Result<usize> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e),
+        };
+        let now = std::time::SystemTime::now();
+        let mut removed = 0;
+        let mut kept = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let metadata = entry.metadata()?;
+            let modified = metadata.modified()?;
+            let expired = now.duration_since(modified).is_ok_and(|age| age >= self.ttl);
+            if expired {
+                std::fs::remove_file(&path)?;
+                removed += 1;
+            } else {
+                kept.push((modified, metadata.len(), path));
+            }
+        }
+        if self.max_bytes > 0 {
+            kept.sort_by_key(|(modified, _, _)| *modified);
+            let mut total: u64 = kept.iter().map(|(_, len, _)| len).sum();
+            for (_, len, path) in &kept {
+                if total <= self.max_bytes {
+                    break;
+                }
+                std::fs::remove_file(path)?;
+                total -= len;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Delete every entry, returning how many were removed.
+    pub fn clear(&self) -> std::io::Result<usize> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e),
+        };
+        let mut removed = 0;
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                std::fs::remove_file(&path)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature_normalization() {
+        let a = SearchSignature::new(
+            "  Holographic   Entanglement ",
+            Some(&["inspire".to_string(), "arxiv".to_string()]),
+            10,
+            0,
+        );
+        let b = SearchSignature::new(
+            "holographic entanglement",
+            Some(&["ArXiv".to_string(), "inspire".to_string(), "arxiv".to_string()]),
+            10,
+            0,
+        );
+        assert_eq!(a, b);
+        assert_ne!(a, SearchSignature::new("holographic entanglement", None, 10, 0));
+        assert_ne!(a, SearchSignature::new("holographic entanglement", Some(&["arxiv".to_string(), "inspire".to_string()]), 20, 0));
+        assert_ne!(a, a.clone().with_language(Some("en")));
+        assert_ne!(a, a.clone().with_rank_by(RankBy::Year));
+        assert_eq!(a.clone().with_language(Some("EN")), a.clone().with_language(Some(" en")));
+        let cats = |c: &[&str]| c.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_ne!(a, a.clone().with_categories(Some(&cats(&["hep-th"]))));
+        assert_eq!(
+            a.clone().with_categories(Some(&cats(&["hep-th", "gr-qc"]))),
+            a.clone().with_categories(Some(&cats(&["gr-qc", " hep-th"]))),
+        );
+    }
+
+    #[test]
+    fn test_expired_and_disabled_entries_miss() {
+        let key = SearchSignature::new("q", None, 10, 0);
+
+        let disabled = SearchCache::new(Duration::ZERO);
+        disabled.insert(key.clone(), vec![]);
+        assert!(disabled.get(&key).is_none());
+        assert_eq!(disabled.stats().entries, 0);
+
+        let cache = SearchCache::new(Duration::from_millis(20));
+        cache.insert(key.clone(), vec![]);
+        assert!(cache.get(&key).is_some());
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(cache.get(&key).is_none());
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (1, 1, 0));
+    }
+
+    #[test]
+    fn test_disk_cache_roundtrip_expiry_and_clear() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let cache = DiskCache::new(tmp.path().join("cache"), Duration::from_secs(60));
+        assert_eq!(cache.clear().unwrap(), 0);
+        assert!(cache.get::<String>("arxiv", "get_paper", "arxiv:1").is_none());
+
+        cache.put("arxiv", "get_paper", "arxiv:1", &"cached".to_string());
+        assert_eq!(cache.get::<String>("arxiv", "get_paper", "arxiv:1").as_deref(), Some("cached"));
+        assert!(cache.get::<String>("inspire", "get_paper", "arxiv:1").is_none());
+
+        // Entries older than the TTL miss
+        let expired = DiskCache::new(tmp.path().join("cache"), Duration::from_secs(1));
+        let key = DiskCache::entry_key("arxiv", "get_paper", "arxiv:2");
+        let stale = DiskEntry { key: key.clone(), stored_at: chrono::Utc::now().timestamp() - 5, value: "old" };
+        std::fs::write(expired.path_for(&key), serde_json::to_vec(&stale).unwrap()).unwrap();
+        assert!(expired.get::<String>("arxiv", "get_paper", "arxiv:2").is_none());
+
+        // ...and are deleted
+        assert!(!expired.path_for(&key).exists());
+
+        cache.remove("arxiv", "get_paper", "arxiv:1");
+        assert!(cache.get::<String>("arxiv", "get_paper", "arxiv:1").is_none());
+        cache.put("arxiv", "get_paper", "arxiv:1", &"cached".to_string());
+        assert_eq!(cache.clear().unwrap(), 1);
+        assert!(cache.get::<String>("arxiv", "get_paper", "arxiv:1").is_none());
+    }
+
+    #[test]
+    fn test_disk_cache_prune_drops_expired_then_oldest() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let cache = DiskCache::new(tmp.path().join("cache"), Duration::from_secs(3600));
+        let set_age = |id: &str, secs: u64| {
+            let file = std::fs::File::options().write(true).open(cache.path_for(&DiskCache::entry_key("arxiv", "get_paper", id))).unwrap();
+            file.set_modified(std::time::SystemTime::now() - Duration::from_secs(secs)).unwrap();
+        };
+        for (id, age) in [("old", 7200), ("a", 300), ("b", 200), ("c", 100)] {
+            cache.put("arxiv", "get_paper", id, &"x".repeat(100));
+            set_age(id, age);
+        }
+        let entry_len = std::fs::metadata(cache.path_for(&DiskCache::entry_key("arxiv", "get_paper", "c"))).unwrap().len();
+
+        // Only the expired entry goes while there's no size limit
+        assert_eq!(cache.prune().unwrap(), 1);
+
+        // Room for two entries: the least recently written goes
+        let cache = cache.with_max_bytes(entry_len * 2 + 10);
+        assert_eq!(cache.prune().unwrap(), 1);
+        assert!(cache.get::<String>("arxiv", "get_paper", "a").is_none());
+        assert!(cache.get::<String>("arxiv", "get_paper", "b").is_some());
+        assert!(cache.get::<String>("arxiv", "get_paper", "c").is_some());
+    }
+}
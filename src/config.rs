@@ -1,5 +1,6 @@
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::apis::{self, PaperSource};
 
@@ -9,36 +10,316 @@ pub struct Config {
     pub data_dir: PathBuf,
     pub semantic_scholar_api_key: Option<String>,
     pub ads_api_key: Option<String>,
+    pub serpapi_key: Option<String>,
+    pub ncbi_api_key: Option<String>,
     pub openalex_email: Option<String>,
     pub unpaywall_email: Option<String>,
+    pub inspire_abstract_source: Option<String>,
+    /// Rewrite ADS author names from "Last, First" to "First Last".
+    pub ads_normalize_authors: bool,
+    /// Append CrossRef subtitles to titles ("Title: Subtitle").
+    pub crossref_subtitles: bool,
+    pub arxiv_min_interval: Duration,
+    /// Connection pooling/keep-alive tuning for all source HTTP clients.
+    pub http: apis::HttpTuning,
     pub enabled_source_names: Vec<String>,
+    /// Serve the local index without modifying it: every tool that writes
+    /// (to the index, the cache, or files) is removed, and auto-indexing and
+    /// refresh write-backs are off.
+    pub read_only: bool,
+    /// Index every `search_papers` result set in the background.
+    pub auto_index: bool,
+    /// Maximum papers indexed per auto-index batch.
+    pub auto_index_limit: usize,
+    /// Papers written per index write-lock acquisition by the indexing queue.
+    pub index_batch_size: usize,
+    /// Let queued index requests take turns batch by batch instead of running
+    /// each to completion in arrival order.
+    pub index_fair_queue: bool,
+    /// Maximum external lookups (e.g. retraction checks) per enrichment pass.
+    pub enrichment_budget: usize,
+    /// Look up missing `search_papers` years on CrossRef by DOI.
+    pub fill_missing_years: bool,
+    /// Wrap tool results in a `{kind, count, results, diagnostics}` envelope.
+    pub json_envelope: bool,
+    /// How long identical `search_papers` result sets are reused in memory
+    /// (`PAPER_SEARCH_RESULT_CACHE_TTL_SECS`; zero disables).
+    pub search_cache_ttl: Duration,
+    /// How long source `get_paper`/search responses are kept on disk under
+    /// `data_dir/cache` (`PAPER_SEARCH_CACHE_TTL_SECS`; zero disables).
+    pub disk_cache_ttl: Duration,
+    /// Size the on-disk response cache is trimmed to
+    /// (`PAPER_SEARCH_CACHE_MAX_MB`; zero for no limit).
+    pub disk_cache_max_bytes: u64,
+    /// Largest PDF `download_pdf` stores under `data_dir/pdfs`.
+    pub pdf_max_bytes: u64,
+    /// How long federated search waits for each source before reporting it as timed out.
+    pub source_timeout: Duration,
+    /// Strip structured-abstract section labels before embedding and keyword indexing.
+    pub clean_abstracts: bool,
+    /// Detect each indexed paper's language from its title and abstract, for
+    /// `search_local` filtering and `index_stats`. On unless
+    /// `PAPER_SEARCH_LANGUAGE_TAGS` is false.
+    pub tag_languages: bool,
+    /// Length of stored and query embeddings; must match the embedding model
+    /// and any existing vector store.
+    pub embedding_dim: usize,
+    /// When most indexed embeddings are mock ones, run `search_local`'s
+    /// vector and hybrid modes as keyword searches instead of only warning.
+    pub mock_embedding_keyword_fallback: bool,
+    /// Vector search metric (`PAPER_SEARCH_VECTOR_DISTANCE`: cosine, l2 or dot).
+    pub vector_distance: lancedb::DistanceType,
+    /// Model embeddings kept in memory, keyed by input text (0 disables).
+    #[cfg_attr(not(feature = "onnx"), allow(dead_code))]
+    pub embedding_cache_size: usize,
+    /// Results that keep their full author list when a tool is asked to
+    /// `summarize_authors`; later ones are cut to the first author.
+    pub full_author_results: usize,
+    /// Log source response fields our types don't model, to catch API schema drift.
+    pub strict_json: bool,
+    /// Extra attempts for requests that hit a connection error or timeout.
+    pub http_retries: u32,
+    /// Wait before the first such retry; doubled for each one after.
+    pub http_retry_base_delay: Duration,
+    /// Write every raw source response under this directory, for replay fixtures.
+    pub record_dir: Option<PathBuf>,
+    /// Indexed-paper count at which the ANN vector index is built (0 disables).
+    pub vector_index_threshold: usize,
+    /// IVF partitions for the vector index (`None`: LanceDB's default).
+    pub vector_index_partitions: Option<u32>,
+    /// MCP tools removed from the router: neither advertised nor callable.
+    pub disabled_tools: Vec<String>,
+    /// Fields whose absence makes `get_paper` refetch a locally indexed paper
+    /// from its source (empty: always answer from the local index).
+    pub refresh_missing_fields: Vec<String>,
+    /// Keep the source JSON record of papers indexed by ID (where the source
+    /// has one) for `get_raw`. Off by default to save space.
+    pub store_raw_json: bool,
+    /// Result count used when a tool call doesn't give one.
+    pub default_max_results: u32,
+    /// Upper bound on requested result counts for tools capped at 100 by default.
+    pub max_results_cap: u32,
+    /// Papers of one BFS level `citation_graph` expands at a time.
+    pub graph_concurrency: usize,
+    /// Citation/reference requests in flight to any one source at a time,
+    /// across all tools (`PAPER_SEARCH_GRAPH_PER_SOURCE`). The cap is keyed by
+    /// source name, not host: sources that share an API host each get their
+    /// own allowance.
+    pub relation_concurrency_per_source: usize,
 }
 
 impl Config {
     /// Load configuration from environment variables.
     pub fn from_env() -> Self {
-        let data_dir = std::env::var("PAPER_SEARCH_DATA_DIR")
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// Configuration with every variable unset: the built-in defaults, rooted
+    /// at `data_dir`. Unlike `from_env`, this ignores the caller's environment.
+    #[cfg(test)]
+    pub fn defaults_in(data_dir: &std::path::Path) -> Self {
+        Self {
+            data_dir: data_dir.to_path_buf(),
+            ..Self::from_vars(|_| None)
+        }
+    }
+
+    /// Load configuration from `var`, which looks up a variable by name.
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Self {
+        let data_dir = var("PAPER_SEARCH_DATA_DIR")
             .map(PathBuf::from)
-            .unwrap_or_else(|_| {
+            .unwrap_or_else(|| {
                 dirs_or_default().join(".paper-search")
             });
 
-        let semantic_scholar_api_key = std::env::var("SEMANTIC_SCHOLAR_API_KEY").ok();
-        let ads_api_key = std::env::var("ADS_API_KEY").ok();
-        let openalex_email = std::env::var("OPENALEX_EMAIL").ok();
-        let unpaywall_email = std::env::var("UNPAYWALL_EMAIL").ok();
+        let semantic_scholar_api_key = var("SEMANTIC_SCHOLAR_API_KEY");
+        let ads_api_key = var("ADS_API_KEY");
+        let serpapi_key = var("SERPAPI_KEY");
+        let ncbi_api_key = var("NCBI_API_KEY");
+        let openalex_email = var("OPENALEX_EMAIL");
+        let unpaywall_email = var("UNPAYWALL_EMAIL");
+        let inspire_abstract_source = var("INSPIRE_ABSTRACT_SOURCE");
+        let ads_normalize_authors = !env_flag(&var, "ADS_RAW_AUTHOR_NAMES");
+        let crossref_subtitles = !env_flag(&var, "CROSSREF_MAIN_TITLE_ONLY");
+        let arxiv_min_interval = var("ARXIV_MIN_INTERVAL_MS")
+            .and_then(|s| s.parse().ok())
+            .map(Duration::from_millis)
+            .unwrap_or(apis::arxiv::DEFAULT_MIN_INTERVAL);
 
-        let enabled_source_names = std::env::var("PAPER_SEARCH_SOURCES")
+        let enabled_source_names = var("PAPER_SEARCH_SOURCES")
             .map(|s| s.split(',').map(|s| s.trim().to_lowercase()).collect())
             .unwrap_or_default();
 
+        let read_only = env_flag(&var, "PAPER_SEARCH_READONLY");
+        let auto_index = env_flag(&var, "PAPER_SEARCH_AUTO_INDEX") && !read_only;
+        let auto_index_limit = var("PAPER_SEARCH_AUTO_INDEX_LIMIT")
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(25);
+        let index_batch_size = var("PAPER_SEARCH_INDEX_BATCH_SIZE")
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(crate::index::queue::DEFAULT_BATCH_SIZE);
+        let index_fair_queue = !env_flag(&var, "PAPER_SEARCH_INDEX_FIFO");
+
+        let json_envelope = env_flag(&var, "PAPER_SEARCH_JSON_ENVELOPE");
+        let clean_abstracts = env_flag(&var, "PAPER_SEARCH_CLEAN_ABSTRACTS");
+        let tag_languages = env_bool(&var, "PAPER_SEARCH_LANGUAGE_TAGS", true);
+        let embedding_dim = var("PAPER_SEARCH_EMBEDDING_DIM")
+            .and_then(|s| s.parse().ok())
+            .filter(|&dim: &usize| dim > 0)
+            .unwrap_or(crate::embed::specter::EMBEDDING_DIMENSION);
+        let mock_embedding_keyword_fallback = env_flag(&var, "PAPER_SEARCH_MOCK_EMBEDDING_KEYWORD_FALLBACK");
+        let vector_distance = var("PAPER_SEARCH_VECTOR_DISTANCE")
+            .and_then(|s| {
+                let parsed = crate::index::vectordb::parse_distance_type(&s);
+                if parsed.is_none() {
+                    tracing::warn!("Unknown PAPER_SEARCH_VECTOR_DISTANCE '{}'; using cosine", s);
+                }
+                parsed
+            })
+            .unwrap_or(lancedb::DistanceType::Cosine);
+        let embedding_cache_size = var("PAPER_SEARCH_EMBEDDING_CACHE_SIZE")
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(1024);
+        let full_author_results = var("PAPER_SEARCH_FULL_AUTHOR_RESULTS")
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(5);
+        let strict_json = env_flag(&var, "PAPER_SEARCH_STRICT_JSON");
+        let http_retries = var("PAPER_SEARCH_HTTP_RETRIES")
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(3);
+        let http_retry_base_delay = var("PAPER_SEARCH_HTTP_RETRY_DELAY_MS")
+            .and_then(|s| s.parse().ok())
+            .map(Duration::from_millis)
+            .unwrap_or(Duration::from_millis(200));
+        let record_dir = var("PAPER_SEARCH_RECORD_DIR")
+            .filter(|s| !s.trim().is_empty())
+            .map(PathBuf::from);
+        let vector_index_threshold = var("PAPER_SEARCH_VECTOR_INDEX_THRESHOLD")
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(1000);
+        let vector_index_partitions = var("PAPER_SEARCH_VECTOR_INDEX_PARTITIONS")
+            .and_then(|s| s.parse().ok());
+        let refresh_missing_fields = var("PAPER_SEARCH_REFRESH_MISSING_FIELDS")
+            .map(|s| {
+                s.split(',')
+                    .map(|f| f.trim().to_lowercase())
+                    .filter(|f| !f.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let store_raw_json = env_flag(&var, "PAPER_SEARCH_STORE_RAW_JSON");
+        let default_max_results = var("PAPER_SEARCH_DEFAULT_MAX")
+            .and_then(|s| s.parse().ok())
+            .filter(|&n: &u32| n > 0)
+            .unwrap_or(10);
+        let max_results_cap = var("PAPER_SEARCH_MAX_RESULTS_CAP")
+            .and_then(|s| s.parse().ok())
+            .filter(|&n: &u32| n > 0)
+            .unwrap_or(100);
+        let graph_concurrency = var("PAPER_SEARCH_GRAPH_CONCURRENCY")
+            .and_then(|s| s.parse().ok())
+            .filter(|&n: &usize| n > 0)
+            .unwrap_or(4);
+        let relation_concurrency_per_source = var("PAPER_SEARCH_GRAPH_PER_SOURCE")
+            .and_then(|s| s.parse().ok())
+            .filter(|&n: &usize| n > 0)
+            .unwrap_or(2);
+        let disabled_tools = var("PAPER_SEARCH_DISABLED_TOOLS")
+            .map(|s| {
+                s.split(',')
+                    .map(|t| t.trim().to_lowercase())
+                    .filter(|t| !t.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let fill_missing_years = env_flag(&var, "PAPER_SEARCH_FILL_MISSING_YEARS");
+        let enrichment_budget = var("PAPER_SEARCH_ENRICHMENT_BUDGET")
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(20);
+        let search_cache_ttl = var("PAPER_SEARCH_RESULT_CACHE_TTL_SECS")
+            .and_then(|s| s.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(Duration::from_secs(300));
+        let disk_cache_ttl = var("PAPER_SEARCH_CACHE_TTL_SECS")
+            .and_then(|s| s.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(Duration::from_secs(86400));
+        let disk_cache_max_bytes = env_megabytes(&var, "PAPER_SEARCH_CACHE_MAX_MB").unwrap_or(256 * 1024 * 1024);
+        let pdf_max_bytes = env_megabytes(&var, "PAPER_SEARCH_PDF_MAX_MB").unwrap_or(crate::pdf::DEFAULT_MAX_BYTES);
+        let source_timeout = var("PAPER_SEARCH_SOURCE_TIMEOUT_SECS")
+            .and_then(|s| s.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(crate::search::DEFAULT_SOURCE_TIMEOUT);
+
+        // HTTP connection tuning; durations in seconds, 0 disables keep-alives.
+        let defaults = apis::HttpTuning::default();
+        let secs = |name: &str| var(name).and_then(|s| s.parse::<u64>().ok());
+        let optional_secs = |name: &str, default: Option<Duration>| match secs(name) {
+            Some(0) => None,
+            Some(s) => Some(Duration::from_secs(s)),
+            None => default,
+        };
+        let http = apis::HttpTuning {
+            pool_max_idle_per_host: var("PAPER_SEARCH_HTTP_POOL_MAX_IDLE")
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(defaults.pool_max_idle_per_host),
+            pool_idle_timeout: secs("PAPER_SEARCH_HTTP_POOL_IDLE_TIMEOUT_SECS")
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.pool_idle_timeout),
+            http2_keep_alive_interval: optional_secs(
+                "PAPER_SEARCH_HTTP2_KEEPALIVE_SECS",
+                defaults.http2_keep_alive_interval,
+            ),
+            tcp_keepalive: optional_secs("PAPER_SEARCH_TCP_KEEPALIVE_SECS", defaults.tcp_keepalive),
+        };
+
         Self {
             data_dir,
             semantic_scholar_api_key,
             ads_api_key,
+            serpapi_key,
+            ncbi_api_key,
             openalex_email,
             unpaywall_email,
+            inspire_abstract_source,
+            ads_normalize_authors,
+            crossref_subtitles,
+            arxiv_min_interval,
+            http,
             enabled_source_names,
+            read_only,
+            auto_index,
+            auto_index_limit,
+            index_batch_size,
+            index_fair_queue,
+            enrichment_budget,
+            fill_missing_years,
+            json_envelope,
+            search_cache_ttl,
+            disk_cache_ttl,
+            disk_cache_max_bytes,
+            pdf_max_bytes,
+            source_timeout,
+            clean_abstracts,
+            tag_languages,
+            embedding_dim,
+            mock_embedding_keyword_fallback,
+            vector_distance,
+            embedding_cache_size,
+            full_author_results,
+            strict_json,
+            http_retries,
+            http_retry_base_delay,
+            record_dir,
+            vector_index_threshold,
+            vector_index_partitions,
+            disabled_tools,
+            refresh_missing_fields,
+            store_raw_json,
+            default_max_results,
+            max_results_cap,
+            graph_concurrency,
+            relation_concurrency_per_source,
         }
     }
 
@@ -54,32 +335,50 @@ impl Config {
 
         // Sources that don't need API keys
         if should_enable("arxiv") {
-            sources.push(Arc::new(apis::arxiv::ArxivClient::new()));
+            sources.push(Arc::new(apis::arxiv::ArxivClient::new(&self.http, self.arxiv_min_interval)));
         }
         if should_enable("inspire") {
-            sources.push(Arc::new(apis::inspire::InspireClient::new()));
+            sources.push(Arc::new(apis::inspire::InspireClient::new(
+                &self.http,
+                self.inspire_abstract_source.clone(),
+            )));
         }
         if should_enable("crossref") {
-            sources.push(Arc::new(apis::crossref::CrossRefClient::new()));
+            sources.push(Arc::new(
+                apis::crossref::CrossRefClient::new(&self.http).with_subtitles(self.crossref_subtitles),
+            ));
         }
         if should_enable("doaj") {
-            sources.push(Arc::new(apis::doaj::DoajClient::new()));
+            sources.push(Arc::new(apis::doaj::DoajClient::new(&self.http)));
         }
         if should_enable("europepmc") {
-            sources.push(Arc::new(apis::europepmc::EuropePmcClient::new()));
+            sources.push(Arc::new(apis::europepmc::EuropePmcClient::new(&self.http)));
+        }
+        if should_enable("pubmed") {
+            sources.push(Arc::new(apis::pubmed::PubmedClient::new(&self.http, self.ncbi_api_key.clone())));
         }
         if should_enable("vixra") {
-            sources.push(Arc::new(apis::vixra::VixraClient::new()));
+            sources.push(Arc::new(apis::vixra::VixraClient::new(&self.http)));
+        }
+        if should_enable("repec") {
+            sources.push(Arc::new(apis::repec::RepecClient::new(&self.http)));
+        }
+        // Opt-in: deposits are mostly datasets and software, which crowd
+        // papers out of general searches
+        if filter.iter().any(|name| name == "zenodo") {
+            sources.push(Arc::new(apis::zenodo::ZenodoClient::new(&self.http)));
         }
 
         // Sources with optional API keys
         if should_enable("semantic_scholar") {
             sources.push(Arc::new(apis::semantic_scholar::SemanticScholarClient::new(
+                &self.http,
                 self.semantic_scholar_api_key.clone(),
             )));
         }
         if should_enable("openalex") {
             sources.push(Arc::new(apis::openalex::OpenAlexClient::new(
+                &self.http,
                 self.openalex_email.clone(),
             )));
         }
@@ -87,11 +386,22 @@ impl Config {
         // Sources requiring API keys
         if should_enable("ads") {
             if let Some(ref key) = self.ads_api_key {
-                sources.push(Arc::new(apis::ads::AdsClient::new(key.clone())));
+                sources.push(Arc::new(apis::ads::AdsClient::new(
+                    &self.http,
+                    key.clone(),
+                    self.ads_normalize_authors,
+                )));
             } else {
                 tracing::warn!("NASA ADS disabled: ADS_API_KEY not set");
             }
         }
+        if should_enable("scholar") {
+            if let Some(ref key) = self.serpapi_key {
+                sources.push(Arc::new(apis::scholar::ScholarClient::new(&self.http, key.clone())));
+            } else {
+                tracing::warn!("Google Scholar disabled: SERPAPI_KEY not set");
+            }
+        }
 
         sources
     }
@@ -99,7 +409,7 @@ impl Config {
     /// Build an Unpaywall client if configured.
     pub fn build_unpaywall(&self) -> Option<apis::unpaywall::UnpaywallClient> {
         self.unpaywall_email.as_ref().map(|email| {
-            apis::unpaywall::UnpaywallClient::new(email.clone())
+            apis::unpaywall::UnpaywallClient::new(&self.http, email.clone())
         })
     }
 
@@ -116,8 +426,15 @@ impl Config {
             SourceStatus { name: "ads".into(), enabled: self.ads_api_key.is_some(),
                 note: if self.ads_api_key.is_some() { "API key set".into() } else { "Disabled: ADS_API_KEY not set".into() } },
             SourceStatus { name: "europepmc".into(), enabled: true, note: "No API key required".into() },
+            SourceStatus { name: "pubmed".into(), enabled: true,
+                note: if self.ncbi_api_key.is_some() { "NCBI API key set (10 req/s)".into() } else { "No API key (3 req/s)".into() } },
             SourceStatus { name: "doaj".into(), enabled: true, note: "No API key required".into() },
             SourceStatus { name: "vixra".into(), enabled: true, note: "HTML scraping".into() },
+            SourceStatus { name: "repec".into(), enabled: true, note: "IDEAS/RePEc HTML scraping".into() },
+            SourceStatus { name: "zenodo".into(), enabled: self.enabled_source_names.iter().any(|n| n == "zenodo"),
+                note: "Datasets and software; opt in by listing it in PAPER_SEARCH_SOURCES".into() },
+            SourceStatus { name: "scholar".into(), enabled: self.serpapi_key.is_some(),
+                note: if self.serpapi_key.is_some() { "Google Scholar via SerpAPI".into() } else { "Disabled: SERPAPI_KEY not set".into() } },
         ];
 
         // Apply filter
@@ -141,8 +458,58 @@ pub struct SourceStatus {
     pub note: String,
 }
 
+/// Interpret an environment variable as a boolean flag ("1", "true", "yes", "on").
+fn env_flag(var: &impl Fn(&str) -> Option<String>, name: &str) -> bool {
+    env_bool(var, name, false)
+}
+
+/// A boolean env var that may be switched either way; unset or unrecognized
+/// values give `default`.
+fn env_bool(var: &impl Fn(&str) -> Option<String>, name: &str, default: bool) -> bool {
+    match var(name).map(|v| v.trim().to_lowercase()).as_deref() {
+        Some("1" | "true" | "yes" | "on") => true,
+        Some("0" | "false" | "no" | "off") => false,
+        _ => default,
+    }
+}
+
+/// A size given in megabytes, in bytes. Sizes too large to count in bytes
+/// are rejected with a warning, like unparsable ones are ignored.
+fn env_megabytes(var: &impl Fn(&str) -> Option<String>, name: &str) -> Option<u64> {
+    let mb: u64 = var(name)?.trim().parse().ok()?;
+    let bytes = mb.checked_mul(1024 * 1024);
+    if bytes.is_none() {
+        tracing::warn!("{} = {} is too large; using the default", name, mb);
+    }
+    bytes
+}
+
 fn dirs_or_default() -> PathBuf {
     std::env::var("HOME")
         .map(PathBuf::from)
         .unwrap_or_else(|_| PathBuf::from("."))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_megabyte_sizes_reject_overflow() {
+        let config = Config::from_vars(|name| match name {
+            "PAPER_SEARCH_CACHE_MAX_MB" => Some("64".to_string()),
+            _ => None,
+        });
+        assert_eq!(config.disk_cache_max_bytes, 64 * 1024 * 1024);
+        let config = Config::from_vars(|name| match name {
+            "PAPER_SEARCH_CACHE_MAX_MB" => Some(u64::MAX.to_string()),
+            _ => None,
+        });
+        assert_eq!(config.disk_cache_max_bytes, 256 * 1024 * 1024);
+        let config = Config::from_vars(|name| match name {
+            "PAPER_SEARCH_PDF_MAX_MB" => Some(u64::MAX.to_string()),
+            _ => None,
+        });
+        assert_eq!(config.pdf_max_bytes, crate::pdf::DEFAULT_MAX_BYTES);
+    }
+}
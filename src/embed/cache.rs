@@ -0,0 +1,153 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use anyhow::Result;
+
+use super::specter::{compose_input, Embedding};
+
+/// An embedding model that can sit behind `CachedEmbedder`.
+#[cfg_attr(not(feature = "onnx"), allow(dead_code))]
+pub trait TextEmbedder {
+    /// Identifies the backend and model. Cached vectors are dropped when it changes.
+    fn model_id(&self) -> &str;
+    fn embed_text(&mut self, text: &str) -> Result<Embedding>;
+}
+
+/// LRU cache of embeddings keyed by a hash of the composed model input, so
+/// re-indexing a paper or repeating a query doesn't rerun the model.
+/// A capacity of 0 disables caching.
+#[cfg_attr(not(feature = "onnx"), allow(dead_code))]
+pub struct CachedEmbedder<E> {
+    inner: E,
+    capacity: usize,
+    /// Model the cached entries came from.
+    model_id: String,
+    /// Input hash -> (embedding, last-use tick).
+    entries: HashMap<u64, (Embedding, u64)>,
+    tick: u64,
+}
+
+#[cfg_attr(not(feature = "onnx"), allow(dead_code))]
+impl<E: TextEmbedder> CachedEmbedder<E> {
+    pub fn new(inner: E, capacity: usize) -> Self {
+        let model_id = inner.model_id().to_string();
+        Self { inner, capacity, model_id, entries: HashMap::new(), tick: 0 }
+    }
+
+    /// Embed a paper from its title and optional abstract.
+    pub fn embed(&mut self, title: &str, abstract_text: Option<&str>) -> Result<Embedding> {
+        self.embed_text(&compose_input(title, abstract_text))
+    }
+
+    /// Embed raw text, answering from the cache when the same input was
+    /// embedded by the same model before.
+    pub fn embed_text(&mut self, text: &str) -> Result<Embedding> {
+        if self.capacity == 0 {
+            return self.inner.embed_text(text);
+        }
+        if self.inner.model_id() != self.model_id {
+            tracing::info!("Embedding model changed; clearing {} cached embeddings", self.entries.len());
+            self.entries.clear();
+            self.model_id = self.inner.model_id().to_string();
+        }
+
+        self.tick += 1;
+        let key = input_hash(text);
+        if let Some((embedding, last_used)) = self.entries.get_mut(&key) {
+            *last_used = self.tick;
+            return Ok(embedding.clone());
+        }
+
+        let embedding = self.inner.embed_text(text)?;
+        if self.entries.len() >= self.capacity {
+            // Linear scan for the least recently used entry; cheap next to inference.
+            if let Some(oldest) = self.entries.iter().min_by_key(|(_, (_, used))| *used).map(|(k, _)| *k) {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.entries.insert(key, (embedding.clone(), self.tick));
+        Ok(embedding)
+    }
+
+    /// The wrapped model, e.g. to swap its weights; a changed `model_id`
+    /// invalidates the cache on the next call.
+    pub fn inner_mut(&mut self) -> &mut E {
+        &mut self.inner
+    }
+}
+
+fn input_hash(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embed::specter::mock_embedding;
+
+    /// Embedder that counts how often the "model" actually runs.
+    struct CountingEmbedder {
+        model: String,
+        calls: usize,
+    }
+
+    impl TextEmbedder for CountingEmbedder {
+        fn model_id(&self) -> &str {
+            &self.model
+        }
+
+        fn embed_text(&mut self, text: &str) -> Result<Embedding> {
+            self.calls += 1;
+            Ok(Embedding { vector: mock_embedding(text, 8), truncated: false })
+        }
+    }
+
+    fn counting(capacity: usize) -> CachedEmbedder<CountingEmbedder> {
+        CachedEmbedder::new(CountingEmbedder { model: "dummy-v1".to_string(), calls: 0 }, capacity)
+    }
+
+    #[test]
+    fn test_repeated_input_served_from_cache() {
+        let mut embedder = counting(16);
+        let first = embedder.embed("Holographic Entanglement", Some("Ryu-Takayanagi")).unwrap();
+        let second = embedder.embed("Holographic Entanglement", Some("Ryu-Takayanagi")).unwrap();
+        assert_eq!(first.vector, second.vector);
+        assert_eq!(embedder.inner_mut().calls, 1);
+
+        embedder.embed("Holographic Entanglement", None).unwrap();
+        assert_eq!(embedder.inner_mut().calls, 2);
+    }
+
+    #[test]
+    fn test_model_change_invalidates_cache() {
+        let mut embedder = counting(16);
+        embedder.embed_text("surface codes").unwrap();
+        embedder.inner_mut().model = "dummy-v2".to_string();
+        embedder.embed_text("surface codes").unwrap();
+        assert_eq!(embedder.inner_mut().calls, 2);
+        assert_eq!(embedder.entries.len(), 1);
+    }
+
+    #[test]
+    fn test_least_recently_used_entry_evicted() {
+        let mut embedder = counting(2);
+        embedder.embed_text("a").unwrap();
+        embedder.embed_text("b").unwrap();
+        embedder.embed_text("a").unwrap();
+        embedder.embed_text("c").unwrap(); // evicts "b"
+        assert_eq!(embedder.inner_mut().calls, 3);
+
+        embedder.embed_text("a").unwrap();
+        assert_eq!(embedder.inner_mut().calls, 3);
+        embedder.embed_text("b").unwrap();
+        assert_eq!(embedder.inner_mut().calls, 4);
+
+        let mut disabled = counting(0);
+        disabled.embed_text("a").unwrap();
+        disabled.embed_text("a").unwrap();
+        assert_eq!(disabled.inner_mut().calls, 2);
+    }
+}
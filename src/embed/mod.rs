@@ -1 +1,3 @@
+pub mod cache;
+pub mod preprocess;
 pub mod specter;
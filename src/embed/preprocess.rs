@@ -0,0 +1,85 @@
+//! Text cleanup applied before embedding and keyword indexing.
+
+/// Section headers used by structured abstracts (matched case-insensitively
+/// at the start of a line when followed by a colon).
+const SECTION_LABELS: &[&str] = &[
+    "background",
+    "context",
+    "introduction",
+    "importance",
+    "objective",
+    "objectives",
+    "aim",
+    "aims",
+    "purpose",
+    "design",
+    "setting",
+    "participants",
+    "method",
+    "methods",
+    "methodology",
+    "materials and methods",
+    "results",
+    "findings",
+    "main outcome measures",
+    "conclusion",
+    "conclusions",
+    "interpretation",
+    "significance",
+];
+
+/// Longest label above, in words.
+const MAX_LABEL_WORDS: usize = 3;
+
+/// Strip structured-abstract section labels ("BACKGROUND:", "Main outcome
+/// measures:") that open a line or paragraph, and collapse runs of
+/// whitespace, keeping the sentence text. A label word mid-sentence ("our
+/// results: ...") is left alone.
+pub fn strip_section_labels(text: &str) -> String {
+    let mut words: Vec<&str> = Vec::new();
+    for line in text.lines() {
+        let line_words: Vec<&str> = line.split_whitespace().collect();
+        let label_len = (1..=MAX_LABEL_WORDS.min(line_words.len())).find(|&len| {
+            let Some(stem) = line_words[len - 1].strip_suffix(':') else {
+                return false;
+            };
+            let phrase = line_words[..len - 1]
+                .iter()
+                .copied()
+                .chain(std::iter::once(stem))
+                .collect::<Vec<_>>()
+                .join(" ")
+                .to_lowercase();
+            SECTION_LABELS.contains(&phrase.as_str())
+        });
+        words.extend(&line_words[label_len.unwrap_or(0)..]);
+    }
+    words.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strips_structured_abstract_labels() {
+        let abstract_text = "BACKGROUND: Sleep loss impairs memory.\n\nMETHODS:  We recruited 40 adults.\n\
+            Main Outcome Measures: Recall accuracy.\nRESULTS: Recall fell by 20%.\n  CONCLUSIONS: Sleep matters.";
+        assert_eq!(
+            strip_section_labels(abstract_text),
+            "Sleep loss impairs memory. We recruited 40 adults. Recall accuracy. Recall fell by 20%. Sleep matters."
+        );
+    }
+
+    #[test]
+    fn test_keeps_ordinary_colons() {
+        let text = "We find two regimes: weak and strong coupling.";
+        assert_eq!(strip_section_labels(text), text);
+    }
+
+    #[test]
+    fn test_keeps_label_words_mid_sentence() {
+        let text = "We summarize the main results: a gap opens. Two methods: exact and numerical.";
+        assert_eq!(strip_section_labels(text), text);
+    }
+}
@@ -1,17 +1,19 @@
 use std::path::{Path, PathBuf};
 use anyhow::{Context, Result};
 
+/// SPECTER2's output size; the default for `PAPER_SEARCH_EMBEDDING_DIM`.
 pub const EMBEDDING_DIMENSION: usize = 768;
 
-/// Generate a mock embedding for testing (deterministic based on text hash).
-pub fn mock_embedding(text: &str) -> Vec<f32> {
+/// Generate a mock embedding of `dim` values for testing (deterministic based
+/// on text hash).
+pub fn mock_embedding(text: &str, dim: usize) -> Vec<f32> {
     use std::collections::hash_map::DefaultHasher;
     use std::hash::{Hash, Hasher};
     let mut hasher = DefaultHasher::new();
     text.hash(&mut hasher);
     let seed = hasher.finish();
     let mut rng_state = seed;
-    (0..EMBEDDING_DIMENSION)
+    (0..dim)
         .map(|_| {
             rng_state ^= rng_state << 13;
             rng_state ^= rng_state >> 7;
@@ -21,7 +23,55 @@ pub fn mock_embedding(text: &str) -> Vec<f32> {
         .collect()
 }
 
+/// An embedding vector plus whether its input was cut to fit the model.
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "onnx"), allow(dead_code))]
+pub struct Embedding {
+    pub vector: Vec<f32>,
+    pub truncated: bool,
+}
+
+/// Model input ids and attention mask, limited to the model's sequence length.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(not(feature = "onnx"), allow(dead_code))]
+pub struct ModelInput {
+    pub token_ids: Vec<i64>,
+    pub attention_mask: Vec<i64>,
+    pub truncated: bool,
+}
+
+/// Model input for a paper: title and abstract joined by `[SEP]`.
+#[cfg_attr(not(feature = "onnx"), allow(dead_code))]
+pub fn compose_input(title: &str, abstract_text: Option<&str>) -> String {
+    match abstract_text {
+        Some(abs) if !abs.is_empty() => format!("{} [SEP] {}", title, abs),
+        _ => title.to_string(),
+    }
+}
+
+/// Cut tokenizer output to `max_len` tokens, recording whether anything was dropped.
+#[cfg_attr(not(feature = "onnx"), allow(dead_code))]
+pub fn truncate_input(ids: &[u32], mask: &[u32], max_len: usize) -> ModelInput {
+    let len = ids.len().min(max_len);
+    ModelInput {
+        token_ids: ids[..len].iter().map(|&x| x as i64).collect(),
+        attention_mask: mask[..len.min(mask.len())].iter().map(|&x| x as i64).collect(),
+        truncated: ids.len() > max_len,
+    }
+}
+
+/// Attempts `download_file` makes before giving up; each retry resumes.
+#[cfg_attr(not(feature = "onnx"), allow(dead_code))]
+const DOWNLOAD_ATTEMPTS: u32 = 3;
+/// Wait before the first download retry; later retries back off from it.
+#[cfg_attr(not(feature = "onnx"), allow(dead_code))]
+const DOWNLOAD_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(1);
+/// Log download progress every this many bytes.
+#[cfg_attr(not(feature = "onnx"), allow(dead_code))]
+const PROGRESS_STEP: u64 = 50 * 1024 * 1024;
+
 /// Download the SPECTER2 ONNX model from HuggingFace to the given directory.
+#[cfg_attr(not(feature = "onnx"), allow(dead_code))]
 pub async fn download_model(model_dir: &Path) -> Result<PathBuf> {
     let model_path = model_dir.join("specter2.onnx");
     if model_path.exists() {
@@ -36,16 +86,101 @@ pub async fn download_model(model_dir: &Path) -> Result<PathBuf> {
     tracing::info!("Downloading SPECTER2 model from {}", url);
 
     let client = reqwest::Client::new();
-    let resp = client.get(url).send().await
+    let bytes = download_file(&client, url, &model_path).await
         .context("Failed to download SPECTER2 model")?;
+
+    tracing::info!("SPECTER2 model saved to {:?} ({} bytes)", model_path, bytes);
+    Ok(model_path)
+}
+
+/// Stream `url` into `dest` without buffering it in memory. Data goes to
+/// `<dest>.part` first, which is renamed into place once complete; a `.part`
+/// left by an interrupted run is resumed with an HTTP range request.
+/// Returns the final file size.
+#[cfg_attr(not(feature = "onnx"), allow(dead_code))]
+async fn download_file(client: &reqwest::Client, url: &str, dest: &Path) -> Result<u64> {
+    let file_name = dest.file_name().context("Download destination has no file name")?;
+    let part = dest.with_file_name(format!("{}.part", file_name.to_string_lossy()));
+
+    let mut attempt = 1;
+    loop {
+        match download_part(client, url, &part).await {
+            Ok(size) => {
+                tokio::fs::rename(&part, dest).await
+                    .context("Failed to move downloaded file into place")?;
+                return Ok(size);
+            }
+            Err(e) if attempt < DOWNLOAD_ATTEMPTS => {
+                let delay = crate::apis::backoff_delay(DOWNLOAD_RETRY_DELAY, attempt);
+                tracing::warn!("Download of {} failed (attempt {}): {:#}; resuming in {:?}", url, attempt, e, delay);
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Append the rest of `url` to `part`, or restart it if the server ignores
+/// the range request.
+#[cfg_attr(not(feature = "onnx"), allow(dead_code))]
+async fn download_part(client: &reqwest::Client, url: &str, part: &Path) -> Result<u64> {
+    use reqwest::StatusCode;
+    use tokio::io::AsyncWriteExt;
+
+    let existing = tokio::fs::metadata(part).await.map(|m| m.len()).unwrap_or(0);
+    let mut req = client.get(url);
+    if existing > 0 {
+        req = req.header(reqwest::header::RANGE, format!("bytes={}-", existing));
+    }
+    let mut resp = req.send().await.context("Request failed")?;
+    if resp.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        // The partial file is already complete (or larger than the remote file).
+        let total = resp.headers()
+            .get(reqwest::header::CONTENT_RANGE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.rsplit('/').next())
+            .and_then(|v| v.parse::<u64>().ok());
+        if total == Some(existing) {
+            return Ok(existing);
+        }
+        tokio::fs::remove_file(part).await.context("Failed to discard partial download")?;
+        anyhow::bail!("Partial download did not match the remote file; restarting");
+    }
     anyhow::ensure!(resp.status().is_success(), "Download failed with status: {}", resp.status());
 
-    let bytes = resp.bytes().await.context("Failed to read model bytes")?;
-    std::fs::write(&model_path, &bytes)
-        .context("Failed to write model file")?;
+    let resuming = resp.status() == StatusCode::PARTIAL_CONTENT;
+    let mut written = if resuming { existing } else { 0 };
+    if resuming {
+        tracing::info!("Resuming download at byte {}", existing);
+    }
+    let total = resp.content_length().map(|len| len + written);
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(resuming)
+        .truncate(!resuming)
+        .open(part)
+        .await
+        .context("Failed to open partial download file")?;
 
-    tracing::info!("SPECTER2 model saved to {:?} ({} bytes)", model_path, bytes.len());
-    Ok(model_path)
+    let mut next_progress = written + PROGRESS_STEP;
+    while let Some(chunk) = resp.chunk().await.context("Download interrupted")? {
+        file.write_all(&chunk).await.context("Failed to write download chunk")?;
+        written += chunk.len() as u64;
+        if written >= next_progress {
+            match total {
+                Some(total) => tracing::info!("Downloaded {} / {} MB", written >> 20, total >> 20),
+                None => tracing::info!("Downloaded {} MB", written >> 20),
+            }
+            next_progress += PROGRESS_STEP;
+        }
+    }
+    file.sync_all().await.context("Failed to flush download")?;
+    if let Some(total) = total {
+        anyhow::ensure!(written == total, "Download ended early ({} of {} bytes)", written, total);
+    }
+    Ok(written)
 }
 
 // ── ONNX-based embedder (requires `onnx` feature) ──────────────────────────
@@ -63,6 +198,7 @@ mod onnx_impl {
     pub struct SpecterEmbedder {
         session: ort::session::Session,
         tokenizer: tokenizers::Tokenizer,
+        model_id: String,
     }
 
     impl SpecterEmbedder {
@@ -87,29 +223,31 @@ mod onnx_impl {
                 tok
             };
 
-            Ok(Self { session, tokenizer })
+            let model_id = format!("specter2:{}", model_path.display());
+            Ok(Self { session, tokenizer, model_id })
         }
 
         /// Embed a paper from its title and optional abstract.
-        pub fn embed(&mut self, title: &str, abstract_text: Option<&str>) -> Result<Vec<f32>> {
-            let text = match abstract_text {
-                Some(abs) if !abs.is_empty() => format!("{} [SEP] {}", title, abs),
-                _ => title.to_string(),
-            };
-            self.embed_text(&text)
+        pub fn embed(&mut self, title: &str, abstract_text: Option<&str>) -> Result<Embedding> {
+            self.embed_text(&compose_input(title, abstract_text))
         }
 
-        /// Embed raw text. Returns a 768-dimensional f32 vector.
-        pub fn embed_text(&mut self, text: &str) -> Result<Vec<f32>> {
+        /// Embed raw text. Returns a 768-dimensional f32 vector and whether
+        /// the input exceeded the model's 512-token limit.
+        pub fn embed_text(&mut self, text: &str) -> Result<Embedding> {
             let encoding = self.tokenizer.encode(text, true)
                 .map_err(|e| anyhow::anyhow!("Tokenization failed: {}", e))?;
 
-            let ids = encoding.get_ids();
-            let mask = encoding.get_attention_mask();
-
-            let len = ids.len().min(MAX_SEQ_LEN);
-            let token_ids: Vec<i64> = ids[..len].iter().map(|&x| x as i64).collect();
-            let attention_mask: Vec<i64> = mask[..len].iter().map(|&x| x as i64).collect();
+            let ModelInput { token_ids, attention_mask, truncated } =
+                truncate_input(encoding.get_ids(), encoding.get_attention_mask(), MAX_SEQ_LEN);
+            let len = token_ids.len();
+            if truncated {
+                tracing::debug!(
+                    "Input truncated from {} to {} tokens for embedding",
+                    encoding.get_ids().len(),
+                    len
+                );
+            }
 
             let input_ids = ort::value::Tensor::from_array(([1, len], token_ids.into_boxed_slice()))
                 .context("Failed to create input_ids tensor")?;
@@ -125,18 +263,101 @@ mod onnx_impl {
             let (shape, data) = outputs[0].try_extract_tensor::<f32>()
                 .context("Failed to extract output tensor")?;
 
-            let embedding = if shape.len() == 3 {
-                data[..EMBEDDING_DIMENSION].to_vec()
-            } else if shape.len() == 2 {
-                data[..EMBEDDING_DIMENSION].to_vec()
+            // First token ([CLS]) of the last hidden state, sized by the model
+            let hidden = shape.last().copied().unwrap_or(0) as usize;
+            let embedding = if shape.len() == 3 || shape.len() == 2 {
+                data[..hidden].to_vec()
             } else {
                 anyhow::bail!("Unexpected output shape: {:?}", shape);
             };
 
-            Ok(embedding)
+            Ok(Embedding { vector: embedding, truncated })
+        }
+    }
+
+    impl crate::embed::cache::TextEmbedder for SpecterEmbedder {
+        fn model_id(&self) -> &str {
+            &self.model_id
+        }
+
+        fn embed_text(&mut self, text: &str) -> Result<Embedding> {
+            SpecterEmbedder::embed_text(self, text)
         }
     }
 }
 
 #[cfg(feature = "onnx")]
 pub use onnx_impl::SpecterEmbedder;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Whitespace "tokenizer" standing in for the real one.
+    fn dummy_tokenize(text: &str) -> (Vec<u32>, Vec<u32>) {
+        let ids: Vec<u32> = text.split_whitespace().enumerate().map(|(i, _)| i as u32 + 1).collect();
+        let mask = vec![1; ids.len()];
+        (ids, mask)
+    }
+
+    #[test]
+    fn test_truncation_flag() {
+        let long_abstract = "word ".repeat(600);
+        let (ids, mask) = dummy_tokenize(&long_abstract);
+        let input = truncate_input(&ids, &mask, 512);
+        assert!(input.truncated);
+        assert_eq!(input.token_ids.len(), 512);
+        assert_eq!(input.attention_mask.len(), 512);
+
+        let (ids, mask) = dummy_tokenize("A short abstract");
+        let input = truncate_input(&ids, &mask, 512);
+        assert!(!input.truncated);
+        assert_eq!(input.token_ids, vec![1, 2, 3]);
+    }
+
+    /// Stand-in file server honouring `Range: bytes=N-`. Records the range
+    /// header of each request.
+    async fn mock_file_server(content: Vec<u8>, ranges: std::sync::Arc<std::sync::Mutex<Vec<String>>>) -> String {
+        use crate::mock_http::{self, Response};
+        let base = mock_http::serve(move |request| {
+            let start = request.header("range").and_then(|r| r.strip_prefix("bytes=")).map(|r| {
+                ranges.lock().unwrap().push(r.to_string());
+                r.trim_end_matches('-').parse::<usize>().unwrap()
+            });
+            Some(match start {
+                Some(s) => Response::new(206)
+                    .header("Content-Range", format!("bytes {}-{}/{}", s, content.len() - 1, content.len()))
+                    .body(&content[s..]),
+                None => Response::new(200).body(content.clone()),
+            })
+        })
+        .await;
+        format!("{}/model.onnx", base)
+    }
+
+    fn content_hash(bytes: &[u8]) -> u64 {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        bytes.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[tokio::test]
+    async fn test_download_resumes_partial_file() {
+        let content: Vec<u8> = (0..200_000u32).map(|i| (i * 31 % 251) as u8).collect();
+        let ranges = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let url = mock_file_server(content.clone(), ranges.clone()).await;
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dest = tmp.path().join("model.onnx");
+        let part = tmp.path().join("model.onnx.part");
+        std::fs::write(&part, &content[..75_000]).unwrap();
+
+        let size = download_file(&reqwest::Client::new(), &url, &dest).await.unwrap();
+        assert_eq!(size, content.len() as u64);
+        assert_eq!(ranges.lock().unwrap().as_slice(), ["75000-"]);
+        assert!(!part.exists());
+        let downloaded = std::fs::read(&dest).unwrap();
+        assert_eq!(content_hash(&downloaded), content_hash(&content));
+    }
+}
@@ -0,0 +1,151 @@
+use futures::future::join_all;
+use serde::Serialize;
+
+use crate::apis::crossref::{CrossRefClient, Retraction};
+use crate::apis::{PaperResult, PaperSource};
+
+/// A paper with optional enrichment annotations. Unset annotations were not checked.
+#[derive(Debug, Clone, Serialize)]
+pub struct AnnotatedPaper {
+    #[serde(flatten)]
+    pub paper: PaperResult,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retracted: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retraction: Option<Retraction>,
+}
+
+impl From<PaperResult> for AnnotatedPaper {
+    fn from(paper: PaperResult) -> Self {
+        Self { paper, retracted: None, retraction: None }
+    }
+}
+
+/// Check each paper's DOI for a CrossRef retraction notice. At most `budget`
+/// DOIs are looked up; papers without a DOI or beyond the budget stay unchecked.
+pub async fn annotate_retractions(
+    crossref: &CrossRefClient,
+    papers: Vec<PaperResult>,
+    budget: usize,
+) -> Vec<AnnotatedPaper> {
+    let mut annotated: Vec<AnnotatedPaper> = papers.into_iter().map(AnnotatedPaper::from).collect();
+    let to_check: Vec<usize> = annotated
+        .iter()
+        .enumerate()
+        .filter(|(_, a)| a.paper.doi.is_some())
+        .map(|(i, _)| i)
+        .take(budget)
+        .collect();
+
+    let lookups = to_check.iter().map(|&i| {
+        let doi = annotated[i].paper.doi.clone().unwrap_or_default();
+        async move { crossref.retraction_status(&doi).await }
+    });
+    let statuses = join_all(lookups).await;
+
+    for (i, status) in to_check.into_iter().zip(statuses) {
+        match status {
+            Ok(retraction) => {
+                annotated[i].retracted = Some(retraction.is_some());
+                annotated[i].retraction = retraction;
+            }
+            Err(e) => tracing::warn!("Retraction check failed for {}: {}", annotated[i].paper.id, e),
+        }
+    }
+    annotated
+}
+
+/// Fill in `year` for papers that have a DOI but no year by looking the DOI
+/// up on `doi_source` (CrossRef in production). At most `budget` DOIs are
+/// looked up. Returns how many years were filled.
+pub async fn fill_missing_years(
+    doi_source: &dyn PaperSource,
+    papers: &mut [PaperResult],
+    budget: usize,
+) -> usize {
+    let to_fill: Vec<usize> = papers
+        .iter()
+        .enumerate()
+        .filter(|(_, p)| p.year.is_none() && p.doi.is_some())
+        .map(|(i, _)| i)
+        .take(budget)
+        .collect();
+
+    let lookups = to_fill.iter().map(|&i| {
+        let doi = papers[i].doi.clone().unwrap_or_default();
+        async move { doi_source.get_paper(&format!("doi:{}", doi)).await }
+    });
+    let found = join_all(lookups).await;
+
+    let mut filled = 0;
+    for (i, lookup) in to_fill.into_iter().zip(found) {
+        match lookup {
+            Ok(Some(record)) if record.year.is_some() => {
+                papers[i].year = record.year;
+                filled += 1;
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Year lookup failed for {}: {}", papers[i].id, e),
+        }
+    }
+    filled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_source::MockSource;
+    use std::sync::atomic::Ordering;
+
+    /// Stand-in for CrossRef that knows the year of one DOI.
+    fn mock_crossref() -> MockSource {
+        MockSource::new("crossref").on_paper(|id| {
+            (id == "doi:10.1000/known").then(|| PaperResult {
+                year: Some(1998),
+                ..paper("doi:10.1000/known", Some("10.1000/known"), None)
+            })
+        })
+    }
+
+    fn paper(id: &str, doi: Option<&str>, year: Option<u32>) -> PaperResult {
+        PaperResult {
+            id: id.to_string(),
+            title: "Title".to_string(),
+            authors: vec![],
+            abstract_text: None,
+            year,
+            source: "doaj".to_string(),
+            doi: doi.map(str::to_string),
+            arxiv_id: None,
+            url: String::new(),
+            pdf_url: None,
+            citation_count: None,
+            orcids: Vec::new(),
+            subjects: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_missing_year_filled_from_doi_lookup() {
+        let crossref = mock_crossref();
+        let mut papers = vec![
+            paper("doaj:1", Some("10.1000/known"), None),
+            paper("doaj:2", Some("10.1000/other"), Some(2020)),
+            paper("doaj:3", None, None),
+            paper("doaj:4", Some("10.1000/unknown"), None),
+        ];
+
+        let filled = fill_missing_years(&crossref, &mut papers, 10).await;
+        assert_eq!(filled, 1);
+        assert_eq!(papers[0].year, Some(1998));
+        assert_eq!(papers[1].year, Some(2020));
+        assert_eq!(papers[2].year, None);
+        assert_eq!(papers[3].year, None);
+        // Only year-less papers with a DOI are looked up
+        assert_eq!(crossref.lookups.load(Ordering::SeqCst), 2);
+
+        let mut papers = vec![paper("doaj:1", Some("10.1000/known"), None)];
+        assert_eq!(fill_missing_years(&crossref, &mut papers, 0).await, 0);
+        assert_eq!(papers[0].year, None);
+    }
+}
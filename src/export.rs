@@ -0,0 +1,288 @@
+use std::collections::HashSet;
+
+use crate::apis::PaperResult;
+
+/// Render papers as BibTeX entries. `@article` when a DOI exists, else `@misc`.
+pub fn to_bibtex(papers: &[PaperResult]) -> String {
+    let mut used_keys = HashSet::new();
+    let mut out = String::new();
+    for paper in papers {
+        let key = unique_key(citation_key(paper), &mut used_keys);
+        let entry_type = if paper.doi.is_some() { "article" } else { "misc" };
+
+        let mut fields: Vec<(&str, String)> = Vec::new();
+        fields.push(("title", format!("{{{}}}", escape_bibtex(&paper.title))));
+        if !paper.authors.is_empty() {
+            let authors = paper.authors.iter().map(|a| escape_bibtex(a)).collect::<Vec<_>>();
+            fields.push(("author", authors.join(" and ")));
+        }
+        if let Some(year) = paper.year {
+            fields.push(("year", year.to_string()));
+        }
+        if let Some(ref doi) = paper.doi {
+            fields.push(("doi", escape_bibtex(doi)));
+        }
+        if let Some(ref arxiv_id) = paper.arxiv_id {
+            fields.push(("eprint", escape_bibtex(arxiv_id)));
+            fields.push(("archivePrefix", "arXiv".to_string()));
+        }
+        if !paper.url.is_empty() {
+            fields.push(("url", paper.url.clone()));
+        }
+
+        out.push_str(&format!("@{}{{{},\n", entry_type, key));
+        for (name, value) in &fields {
+            out.push_str(&format!("  {} = {{{}}},\n", name, value));
+        }
+        out.push_str("}\n\n");
+    }
+    out
+}
+
+/// Render papers as RIS records. Missing fields are omitted.
+pub fn to_ris(papers: &[PaperResult]) -> String {
+    let mut out = String::new();
+    for paper in papers {
+        let mut line = |tag: &str, value: &str| {
+            let value = value.replace(['\r', '\n'], " ");
+            if !value.trim().is_empty() {
+                out.push_str(&format!("{}  - {}\n", tag, value.trim()));
+            }
+        };
+        line("TY", "JOUR");
+        line("TI", &paper.title);
+        for author in &paper.authors {
+            line("AU", author);
+        }
+        if let Some(year) = paper.year {
+            line("PY", &year.to_string());
+        }
+        if let Some(ref doi) = paper.doi {
+            line("DO", doi);
+        }
+        line("UR", &paper.url);
+        if let Some(ref abstract_text) = paper.abstract_text {
+            line("AB", abstract_text);
+        }
+        out.push_str("ER  - \n\n");
+    }
+    out
+}
+
+/// Fields a CSL-JSON item must carry to be useful to a citation processor.
+pub const REQUIRED_CSL_FIELDS: &[&str] = &["type", "title", "author", "issued"];
+
+/// Render a paper as a CSL-JSON item: `article-journal` when a DOI exists,
+/// else `article` (CSL's type for preprints). Missing fields are omitted.
+pub fn to_csl_json(paper: &PaperResult) -> serde_json::Value {
+    let mut item = serde_json::Map::new();
+    item.insert("id".into(), paper.id.clone().into());
+    let csl_type = if paper.doi.is_some() { "article-journal" } else { "article" };
+    item.insert("type".into(), csl_type.into());
+    item.insert("title".into(), paper.title.clone().into());
+    if !paper.authors.is_empty() {
+        let authors: Vec<serde_json::Value> = paper.authors.iter().map(|a| csl_name(a)).collect();
+        item.insert("author".into(), authors.into());
+    }
+    if let Some(year) = paper.year {
+        item.insert("issued".into(), serde_json::json!({ "date-parts": [[year]] }));
+    }
+    if let Some(ref doi) = paper.doi {
+        item.insert("DOI".into(), doi.clone().into());
+    }
+    if let Some(ref arxiv_id) = paper.arxiv_id {
+        item.insert("archive".into(), "arXiv".into());
+        item.insert("number".into(), arxiv_id.clone().into());
+    }
+    if !paper.url.is_empty() {
+        item.insert("URL".into(), paper.url.clone().into());
+    }
+    if let Some(ref abstract_text) = paper.abstract_text {
+        item.insert("abstract".into(), abstract_text.clone().into());
+    }
+    serde_json::Value::Object(item)
+}
+
+/// Required CSL fields that `item` lacks or leaves empty.
+pub fn missing_csl_fields(item: &serde_json::Value) -> Vec<&'static str> {
+    REQUIRED_CSL_FIELDS
+        .iter()
+        .copied()
+        .filter(|field| match item.get(*field) {
+            None | Some(serde_json::Value::Null) => true,
+            Some(serde_json::Value::String(s)) => s.trim().is_empty(),
+            Some(serde_json::Value::Array(a)) => a.is_empty(),
+            Some(_) => false,
+        })
+        .collect()
+}
+
+/// CSL name object from "Last, First" or "First Last"; single-word names
+/// become a `literal`.
+fn csl_name(author: &str) -> serde_json::Value {
+    let (family, given) = match author.split_once(',') {
+        Some((last, first)) => (last.trim(), first.trim()),
+        None => match author.trim().rsplit_once(' ') {
+            Some((first, last)) => (last.trim(), first.trim()),
+            None => return serde_json::json!({ "literal": author.trim() }),
+        },
+    };
+    serde_json::json!({ "family": family, "given": given })
+}
+
+/// Citation key from first author surname + year + first significant title word.
+fn citation_key(paper: &PaperResult) -> String {
+    let surname = paper.authors.first()
+        .map(|a| surname(a))
+        .map(|s| ascii_word(&s))
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "anon".to_string());
+    let year = paper.year.map(|y| y.to_string()).unwrap_or_else(|| "nd".to_string());
+    let word = paper.title
+        .split_whitespace()
+        .map(ascii_word)
+        .find(|w| w.len() > 3 && !STOPWORDS.contains(&w.as_str()))
+        .unwrap_or_default();
+    format!("{}{}{}", surname, year, word)
+}
+
+const STOPWORDS: &[&str] = &["about", "from", "into", "over", "that", "their", "these", "this", "with", "when", "where"];
+
+/// Surname from "First Last" or "Last, First".
+fn surname(author: &str) -> String {
+    match author.split_once(',') {
+        Some((last, _)) => last.trim().to_string(),
+        None => author.split_whitespace().last().unwrap_or("").to_string(),
+    }
+}
+
+/// Lowercase ASCII alphanumerics only.
+fn ascii_word(s: &str) -> String {
+    s.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// Disambiguate repeated keys with a, b, c... suffixes.
+fn unique_key(key: String, used: &mut HashSet<String>) -> String {
+    if used.insert(key.clone()) {
+        return key;
+    }
+    for suffix in 'a'..='z' {
+        let candidate = format!("{}{}", key, suffix);
+        if used.insert(candidate.clone()) {
+            return candidate;
+        }
+    }
+    key
+}
+
+/// Escape characters that are special in BibTeX field values.
+fn escape_bibtex(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\textbackslash{}"),
+            '{' | '}' | '%' | '&' | '$' | '#' | '_' => {
+                out.push('\\');
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paper() -> PaperResult {
+        PaperResult {
+            id: "arxiv:2301.12345".to_string(),
+            title: "The Holographic {Entanglement} & 100% Entropy".to_string(),
+            authors: vec!["Juan Maldacena".to_string(), "Smith, Jane".to_string()],
+            abstract_text: None,
+            year: Some(2023),
+            source: "arxiv".to_string(),
+            doi: Some("10.1000/xyz_1".to_string()),
+            arxiv_id: Some("2301.12345".to_string()),
+            url: "https://arxiv.org/abs/2301.12345".to_string(),
+            pdf_url: None,
+            citation_count: None,
+            orcids: Vec::new(),
+            subjects: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_bibtex_fields() {
+        let bib = to_bibtex(&[paper()]);
+        assert!(bib.starts_with("@article{maldacena2023holographic,\n"));
+        assert!(bib.contains("  title = {{The Holographic \\{Entanglement\\} \\& 100\\% Entropy}},\n"));
+        assert!(bib.contains("  author = {Juan Maldacena and Smith, Jane},\n"));
+        assert!(bib.contains("  year = {2023},\n"));
+        assert!(bib.contains("  doi = {10.1000/xyz\\_1},\n"));
+        assert!(bib.contains("  eprint = {2301.12345},\n"));
+        assert!(bib.contains("  archivePrefix = {arXiv},\n"));
+        assert!(bib.contains("  url = {https://arxiv.org/abs/2301.12345},\n"));
+    }
+
+    #[test]
+    fn test_csl_json_type_and_issued() {
+        let item = to_csl_json(&paper());
+        assert_eq!(item["type"], "article-journal");
+        assert_eq!(item["issued"]["date-parts"], serde_json::json!([[2023]]));
+        assert_eq!(item["author"][0], serde_json::json!({ "family": "Maldacena", "given": "Juan" }));
+        assert_eq!(item["author"][1], serde_json::json!({ "family": "Smith", "given": "Jane" }));
+        assert_eq!(item["DOI"], "10.1000/xyz_1");
+        assert!(missing_csl_fields(&item).is_empty());
+
+        let mut preprint = paper();
+        preprint.doi = None;
+        preprint.year = None;
+        let item = to_csl_json(&preprint);
+        assert_eq!(item["type"], "article");
+        assert_eq!(item["number"], "2301.12345");
+        assert_eq!(missing_csl_fields(&item), vec!["issued"]);
+    }
+
+    #[test]
+    fn test_ris_record() {
+        let mut p = paper();
+        p.abstract_text = Some("Line one\nline two".to_string());
+        let ris = to_ris(&[p]);
+        let lines: Vec<&str> = ris.lines().collect();
+        assert_eq!(lines[0], "TY  - JOUR");
+        assert_eq!(lines[1], "TI  - The Holographic {Entanglement} & 100% Entropy");
+        assert_eq!(lines[2], "AU  - Juan Maldacena");
+        assert_eq!(lines[3], "AU  - Smith, Jane");
+        assert!(lines.contains(&"PY  - 2023"));
+        assert!(lines.contains(&"DO  - 10.1000/xyz_1"));
+        assert!(lines.contains(&"AB  - Line one line two"));
+        assert!(lines.contains(&"ER  - "));
+    }
+
+    #[test]
+    fn test_ris_omits_missing_fields() {
+        let mut p = paper();
+        p.doi = None;
+        p.year = None;
+        p.url = String::new();
+        let ris = to_ris(&[p]);
+        assert!(!ris.contains("DO  -"));
+        assert!(!ris.contains("PY  -"));
+        assert!(!ris.contains("UR  -"));
+        assert!(!ris.contains("AB  -"));
+    }
+
+    #[test]
+    fn test_misc_without_doi_and_unique_keys() {
+        let mut p = paper();
+        p.doi = None;
+        let bib = to_bibtex(&[p.clone(), p]);
+        assert!(bib.contains("@misc{maldacena2023holographic,"));
+        assert!(bib.contains("@misc{maldacena2023holographica,"));
+    }
+}
@@ -1,14 +1,48 @@
+use std::collections::HashMap;
 use std::path::Path;
 use anyhow::{Context, Result};
+use crate::apis::PaperResult;
 use tantivy::{
     collector::TopDocs,
     doc,
-    query::QueryParser,
+    query::{EmptyQuery, Query, QueryParser},
     schema::*,
+    snippet::SnippetGenerator,
     Index, IndexReader, IndexWriter, ReloadPolicy, Term,
 };
 
+/// BM25 weight of matches in each searched field.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FieldBoosts {
+    pub title: f32,
+    pub abstract_text: f32,
+    pub authors: f32,
+    /// PDF body text, weighted low so a term that appears only deep in the
+    /// body ranks below title and abstract matches.
+    pub body: f32,
+}
+
+impl Default for FieldBoosts {
+    fn default() -> Self {
+        Self { title: 3.0, abstract_text: 1.0, authors: 0.5, body: 0.3 }
+    }
+}
+
+/// The schema papers are indexed with; an index built with any other is
+/// outdated.
+fn paper_schema() -> Schema {
+    let mut schema_builder = Schema::builder();
+    schema_builder.add_text_field("id", STRING | STORED);
+    schema_builder.add_text_field("title", TEXT | STORED);
+    schema_builder.add_text_field("abstract_text", TEXT | STORED);
+    schema_builder.add_text_field("authors", TEXT | STORED);
+    schema_builder.add_i64_field("year", NumericOptions::default().set_stored().set_indexed());
+    schema_builder.add_text_field("fulltext", TEXT);
+    schema_builder.build()
+}
+
 /// Tantivy-based BM25 full-text search index for papers.
+#[derive(Clone)]
 pub struct FulltextIndex {
     index: Index,
     reader: IndexReader,
@@ -18,29 +52,90 @@ pub struct FulltextIndex {
     f_abstract: Field,
     f_authors: Field,
     f_year: Field,
+    /// PDF body text; indexed but not stored.
+    f_body: Field,
+    boosts: FieldBoosts,
+    /// The on-disk index had an outdated schema and was recreated empty.
+    needs_rebuild: bool,
+}
+
+/// A query parsed once by `FulltextIndex::parse`, for searching and
+/// highlighting alike.
+pub struct ParsedQuery {
+    query: Box<dyn Query>,
+    /// The input wasn't valid query syntax and was parsed as a plain phrase.
+    pub sanitized: bool,
+}
+
+/// The fields Tantivy stores for a paper.
+#[cfg(test)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredPaper {
+    pub id: String,
+    pub title: String,
+    pub abstract_text: Option<String>,
+    pub authors: Vec<String>,
+    pub year: Option<u32>,
 }
 
 impl FulltextIndex {
     /// Create or open a Tantivy index at the given directory.
     pub fn create_or_open(path: &Path) -> Result<Self> {
+        Self::create_or_open_with_boosts(path, FieldBoosts::default())
+    }
+
+    /// Create or open a Tantivy index that weights fields by `boosts`.
+    pub fn create_or_open_with_boosts(path: &Path, boosts: FieldBoosts) -> Result<Self> {
         std::fs::create_dir_all(path)
             .context("Failed to create tantivy index directory")?;
 
-        let mut schema_builder = Schema::builder();
-        let f_id = schema_builder.add_text_field("id", STRING | STORED);
-        let f_title = schema_builder.add_text_field("title", TEXT | STORED);
-        let f_abstract = schema_builder.add_text_field("abstract_text", TEXT);
-        let f_authors = schema_builder.add_text_field("authors", TEXT);
-        let f_year = schema_builder.add_i64_field(
-            "year",
-            NumericOptions::default().set_stored().set_indexed(),
-        );
-        let schema = schema_builder.build();
-
+        let schema = paper_schema();
+        let dir = tantivy::directory::MmapDirectory::open(path)
+            .context("Failed to open MmapDirectory")?;
+        let mut needs_rebuild = false;
+        if Index::exists(&dir).context("Failed to check for tantivy index")? {
+            let existing = Index::open(dir.clone()).context("Failed to open tantivy index")?;
+            if existing.schema() != schema {
+                tracing::info!("Tantivy schema changed; recreating the index at {}", path.display());
+                drop(existing);
+                std::fs::remove_dir_all(path).context("Failed to remove outdated tantivy index")?;
+                std::fs::create_dir_all(path).context("Failed to create tantivy index directory")?;
+                needs_rebuild = true;
+            }
+        }
         let dir = tantivy::directory::MmapDirectory::open(path)
             .context("Failed to open MmapDirectory")?;
         let index = Index::open_or_create(dir, schema)
             .context("Failed to open or create tantivy index")?;
+        Self::from_index(index, boosts, needs_rebuild)
+    }
+
+    /// Open the Tantivy index at `path` without writing to it. An index that
+    /// is missing or has an outdated schema is replaced by an empty one in
+    /// memory, flagged by `needs_rebuild` so the caller can fill it.
+    pub fn open_read_only(path: &Path) -> Result<Self> {
+        let schema = paper_schema();
+        let on_disk = match tantivy::directory::MmapDirectory::open(path) {
+            Ok(dir) if Index::exists(&dir).context("Failed to check for tantivy index")? => {
+                let index = Index::open(dir).context("Failed to open tantivy index")?;
+                (index.schema() == schema).then_some(index)
+            }
+            _ => None,
+        };
+        match on_disk {
+            Some(index) => Self::from_index(index, FieldBoosts::default(), false),
+            None => {
+                tracing::info!("No usable tantivy index at {}; building one in memory", path.display());
+                Self::from_index(Index::create_in_ram(schema), FieldBoosts::default(), true)
+            }
+        }
+    }
+
+    fn from_index(index: Index, boosts: FieldBoosts, needs_rebuild: bool) -> Result<Self> {
+        let schema = index.schema();
+        let field = |name: &str| schema.get_field(name).with_context(|| format!("Missing tantivy field {}", name));
+        let (f_id, f_title, f_abstract) = (field("id")?, field("title")?, field("abstract_text")?);
+        let (f_authors, f_year, f_body) = (field("authors")?, field("year")?, field("fulltext")?);
 
         let reader = index
             .reader_builder()
@@ -56,9 +151,18 @@ impl FulltextIndex {
             f_abstract,
             f_authors,
             f_year,
+            f_body,
+            boosts,
+            needs_rebuild,
         })
     }
 
+    /// Whether opening replaced an index built with an older schema, so its
+    /// documents must be re-added (see `LocalIndex::create_or_open`).
+    pub fn needs_rebuild(&self) -> bool {
+        self.needs_rebuild
+    }
+
     fn writer(&self) -> Result<IndexWriter> {
         self.index
             .writer(50_000_000)
@@ -66,6 +170,7 @@ impl FulltextIndex {
     }
 
     /// Add a paper to the index.
+    #[cfg(test)]
     pub fn add_paper(
         &self,
         id: &str,
@@ -73,12 +178,69 @@ impl FulltextIndex {
         abstract_text: Option<&str>,
         authors: &[String],
         year: Option<u32>,
+    ) -> Result<()> {
+        self.add_paper_with_body(id, title, abstract_text, authors, year, None)
+    }
+
+    /// Add a paper along with its body text (e.g. extracted from the PDF),
+    /// searchable at the `body` boost. The body isn't stored here, so adding
+    /// the paper again without one drops it; `LocalIndex` keeps it in the
+    /// vector store and passes it back in.
+    pub fn add_paper_with_body(
+        &self,
+        id: &str,
+        title: &str,
+        abstract_text: Option<&str>,
+        authors: &[String],
+        year: Option<u32>,
+        body: Option<&str>,
     ) -> Result<()> {
         let mut writer = self.writer()?;
 
         // Delete existing document with same ID first
         writer.delete_term(Term::from_field_text(self.f_id, id));
+        let mut doc = self.make_doc(id, title, abstract_text, authors, year);
+        if let Some(body) = body {
+            doc.add_text(self.f_body, body);
+        }
+        writer.add_document(doc)
+            .context("Failed to add document")?;
+        writer.commit().context("Failed to commit")?;
+        self.reader.reload().context("Failed to reload reader")?;
+        Ok(())
+    }
 
+    /// Re-add many papers in one commit, replacing any with the same ID,
+    /// with body text from `bodies` by ID.
+    pub fn add_papers(&self, papers: &[PaperResult], bodies: &HashMap<String, String>) -> Result<()> {
+        let mut writer = self.writer()?;
+        for paper in papers {
+            writer.delete_term(Term::from_field_text(self.f_id, &paper.id));
+            let mut doc = self.make_doc(
+                &paper.id,
+                &paper.title,
+                paper.abstract_text.as_deref(),
+                &paper.authors,
+                paper.year,
+            );
+            if let Some(body) = bodies.get(&paper.id) {
+                doc.add_text(self.f_body, body);
+            }
+            writer.add_document(doc).context("Failed to add document")?;
+        }
+        writer.commit().context("Failed to commit")?;
+        self.reader.reload().context("Failed to reload reader")?;
+        Ok(())
+    }
+
+    fn make_doc(
+        &self,
+        id: &str,
+        title: &str,
+        abstract_text: Option<&str>,
+        authors: &[String],
+        year: Option<u32>,
+    ) -> TantivyDocument {
         let mut doc = doc!(
             self.f_id => id,
             self.f_title => title,
@@ -88,40 +250,105 @@ impl FulltextIndex {
             doc.add_text(self.f_abstract, abs);
         }
 
-        if !authors.is_empty() {
-            doc.add_text(self.f_authors, &authors.join(", "));
+        // One value per author, so stored authors round-trip
+        for author in authors {
+            doc.add_text(self.f_authors, author);
         }
 
         if let Some(y) = year {
             doc.add_i64(self.f_year, y as i64);
         }
+        doc
+    }
 
-        writer.add_document(doc)
-            .context("Failed to add document")?;
-        writer.commit().context("Failed to commit")?;
-        self.reader.reload().context("Failed to reload reader")?;
-        Ok(())
+    /// Read a paper's stored fields by ID.
+    #[cfg(test)]
+    pub fn get_stored(&self, id: &str) -> Result<Option<StoredPaper>> {
+        let searcher = self.reader.searcher();
+        let query = tantivy::query::TermQuery::new(
+            Term::from_field_text(self.f_id, id),
+            IndexRecordOption::Basic,
+        );
+        let top = searcher
+            .search(&query, &TopDocs::with_limit(1))
+            .context("Search failed")?;
+        let Some((_, address)) = top.into_iter().next() else {
+            return Ok(None);
+        };
+        let doc: TantivyDocument = searcher
+            .doc(address)
+            .context("Failed to retrieve document")?;
+        let text = |field: Field| doc.get_first(field).and_then(|v| v.as_str()).map(str::to_string);
+        Ok(Some(StoredPaper {
+            id: id.to_string(),
+            title: text(self.f_title).unwrap_or_default(),
+            abstract_text: text(self.f_abstract),
+            authors: doc
+                .get_all(self.f_authors)
+                .filter_map(|v| v.as_str().map(str::to_string))
+                .collect(),
+            year: doc.get_first(self.f_year).and_then(|v| v.as_i64()).map(|y| y as u32),
+        }))
     }
 
     /// Compatibility shim for older call sites. Writes now commit eagerly.
+    #[cfg(test)]
     pub fn commit(&self) -> Result<()> {
         self.reader.reload().context("Failed to reload reader")?;
         Ok(())
     }
 
-    /// Search the index. Returns (id, score) pairs ranked by BM25.
-    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<(String, f32)>> {
-        let searcher = self.reader.searcher();
-        let query_parser = QueryParser::for_index(
+    fn query_parser(&self) -> QueryParser {
+        let mut parser = QueryParser::for_index(
             &self.index,
-            vec![self.f_title, self.f_abstract, self.f_authors],
+            vec![self.f_title, self.f_abstract, self.f_authors, self.f_body],
         );
-        let parsed = query_parser
-            .parse_query(query)
-            .context("Failed to parse query")?;
+        parser.set_field_boost(self.f_title, self.boosts.title);
+        parser.set_field_boost(self.f_abstract, self.boosts.abstract_text);
+        parser.set_field_boost(self.f_authors, self.boosts.authors);
+        parser.set_field_boost(self.f_body, self.boosts.body);
+        parser
+    }
 
+    /// Parse `query` with Tantivy's syntax (phrases in quotes, `title:` and
+    /// other field prefixes, `AND`/`OR`/`-`). Input that doesn't parse, like
+    /// an unbalanced quote or a stray `:`, is searched as one phrase with the
+    /// syntax characters and field prefixes dropped instead, and is marked
+    /// `sanitized`.
+    pub fn parse(&self, query: &str) -> Result<ParsedQuery> {
+        let parser = self.query_parser();
+        if let Ok(parsed) = parser.parse_query(query) {
+            return Ok(ParsedQuery { query: parsed, sanitized: false });
+        }
+        let schema = self.index.schema();
+        let words: Vec<&str> = query
+            .split_whitespace()
+            .map(|token| match token.split_once(':') {
+                Some((field, rest)) if schema.get_field(field).is_ok() => rest,
+                _ => token,
+            })
+            .flat_map(|token| token.split(|c: char| !c.is_alphanumeric()))
+            .filter(|w| !w.is_empty())
+            .collect();
+        if words.is_empty() {
+            return Ok(ParsedQuery { query: Box::new(EmptyQuery), sanitized: true });
+        }
+        let phrase = format!("\"{}\"", words.join(" "));
+        let parsed = parser.parse_query(&phrase).context("Failed to parse sanitized query")?;
+        Ok(ParsedQuery { query: parsed, sanitized: true })
+    }
+
+    /// Search the index. Returns (id, score) pairs ranked by BM25.
+    #[cfg(test)]
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<(String, f32)>> {
+        self.search_parsed(&self.parse(query)?, limit)
+    }
+
+    /// `search` with a query already parsed by `parse`.
+    pub fn search_parsed(&self, query: &ParsedQuery, limit: usize) -> Result<Vec<(String, f32)>> {
+        let searcher = self.reader.searcher();
         let top_docs = searcher
-            .search(&parsed, &TopDocs::with_limit(limit))
+            .search(&*query.query, &TopDocs::with_limit(limit))
             .context("Search failed")?;
 
         let mut results = Vec::with_capacity(top_docs.len());
@@ -136,6 +363,25 @@ impl FulltextIndex {
         Ok(results)
     }
 
+    /// Highlight where `query` matches a paper: the best abstract fragment,
+    /// else the title, with matched terms wrapped in `<b>`. Callers pass in
+    /// the text they already hold for the paper rather than re-reading the
+    /// stored document. `None` when neither field matches.
+    pub fn snippet(&self, query: &ParsedQuery, title: &str, abstract_text: Option<&str>) -> Result<Option<String>> {
+        let searcher = self.reader.searcher();
+        let fields = [(self.f_abstract, abstract_text), (self.f_title, Some(title))];
+        for (field, text) in fields {
+            let Some(text) = text else { continue };
+            let snippet = SnippetGenerator::create(&searcher, &*query.query, field)
+                .context("Failed to create snippet generator")?
+                .snippet(text);
+            if !snippet.is_empty() {
+                return Ok(Some(snippet.to_html()));
+            }
+        }
+        Ok(None)
+    }
+
     /// Delete a paper by ID.
     pub fn delete(&self, id: &str) -> Result<()> {
         let mut writer = self.writer()?;
@@ -145,6 +391,16 @@ impl FulltextIndex {
         Ok(())
     }
 
+    /// Delete every document and commit. Returns how many were removed.
+    pub fn clear(&self) -> Result<u64> {
+        let removed = self.count();
+        let mut writer = self.writer()?;
+        writer.delete_all_documents().context("Failed to delete documents")?;
+        writer.commit().context("Failed to commit")?;
+        self.reader.reload().context("Failed to reload reader")?;
+        Ok(removed)
+    }
+
     /// Get the total number of indexed documents.
     pub fn count(&self) -> u64 {
         self.reader.searcher().num_docs()
@@ -194,6 +450,144 @@ mod tests {
         assert_eq!(idx.count(), 1);
     }
 
+    #[test]
+    fn test_snippet_highlights_abstract_then_title() {
+        let tmp = TempDir::new().unwrap();
+        let idx = FulltextIndex::create_or_open(tmp.path()).unwrap();
+        let abstract_text = "We study the entanglement entropy in anti-de Sitter spacetime.";
+        idx.add_paper(
+            "arxiv:2301.00001",
+            "Holographic Entanglement",
+            Some(abstract_text),
+            &["Alice Physicist".to_string()],
+            Some(2023),
+        ).unwrap();
+
+        let snippet = idx.snippet(&idx.parse("entropy").unwrap(), "Holographic Entanglement", Some(abstract_text)).unwrap().unwrap();
+        assert!(snippet.contains("<b>entropy</b>"), "{}", snippet);
+
+        // No abstract match: fall back to the title
+        let snippet = idx.snippet(&idx.parse("holographic").unwrap(), "Holographic Entanglement", Some(abstract_text)).unwrap().unwrap();
+        assert_eq!(snippet, "<b>Holographic</b> Entanglement");
+
+        assert!(idx.snippet(&idx.parse("alice").unwrap(), "Holographic Entanglement", None).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_stored_fields_roundtrip() {
+        let tmp = TempDir::new().unwrap();
+        let idx = FulltextIndex::create_or_open(tmp.path()).unwrap();
+        assert!(!idx.needs_rebuild());
+        let authors = vec!["Alice Physicist".to_string(), "Bob Theorist".to_string()];
+        idx.add_paper("p1", "Holographic Entanglement", Some("Entropy in AdS."), &authors, Some(2023)).unwrap();
+
+        let stored = idx.get_stored("p1").unwrap().unwrap();
+        assert_eq!(stored, StoredPaper {
+            id: "p1".to_string(),
+            title: "Holographic Entanglement".to_string(),
+            abstract_text: Some("Entropy in AdS.".to_string()),
+            authors,
+            year: Some(2023),
+        });
+        assert!(idx.get_stored("missing").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_invalid_syntax_falls_back_to_phrase() {
+        let tmp = TempDir::new().unwrap();
+        let idx = FulltextIndex::create_or_open(tmp.path()).unwrap();
+        idx.add_paper("p1", "Black Hole Information Paradox", None, &[], None).unwrap();
+        idx.add_paper("p2", "Information Theory", Some("Black boxes and hole punching."), &[], None).unwrap();
+
+        let parsed = idx.parse("title:\"black hole\"").unwrap();
+        assert!(!parsed.sanitized);
+        assert_eq!(idx.search_parsed(&parsed, 10).unwrap().len(), 1);
+
+        for query in ["\"black hole", "black hole:", "title:(black hole"] {
+            let parsed = idx.parse(query).unwrap();
+            assert!(parsed.sanitized, "{}", query);
+            let results = idx.search_parsed(&parsed, 10).unwrap();
+            assert_eq!(results.iter().map(|(id, _)| id.as_str()).collect::<Vec<_>>(), vec!["p1"], "{}", query);
+        }
+        let parsed = idx.parse("\"").unwrap();
+        assert!(parsed.sanitized);
+        assert!(idx.search_parsed(&parsed, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_title_matches_outrank_abstract_matches() {
+        let add = |idx: &FulltextIndex| {
+            idx.add_paper("abstract", "Quantum codes review", Some("Wormholes in gravity theory."), &[], None).unwrap();
+            idx.add_paper("title", "Wormholes in gravity theory", Some("A review of quantum codes."), &[], None).unwrap();
+        };
+        let ids = |idx: &FulltextIndex| -> Vec<String> {
+            idx.search("wormholes", 10).unwrap().into_iter().map(|(id, _)| id).collect()
+        };
+
+        let tmp = TempDir::new().unwrap();
+        let idx = FulltextIndex::create_or_open(tmp.path()).unwrap();
+        add(&idx);
+        assert_eq!(ids(&idx), vec!["title", "abstract"]);
+
+        let tmp = TempDir::new().unwrap();
+        let boosts = FieldBoosts { title: 0.5, abstract_text: 3.0, ..FieldBoosts::default() };
+        let idx = FulltextIndex::create_or_open_with_boosts(tmp.path(), boosts).unwrap();
+        add(&idx);
+        assert_eq!(ids(&idx), vec!["abstract", "title"]);
+    }
+
+    #[test]
+    fn test_body_text_matches_below_title() {
+        let tmp = TempDir::new().unwrap();
+        let idx = FulltextIndex::create_or_open(tmp.path()).unwrap();
+        idx.add_paper_with_body(
+            "body",
+            "Quantum Error Correction Codes",
+            Some("A review of stabilizer codes."),
+            &[],
+            None,
+            Some("Appendix B applies the construction to wormholes in anti-de Sitter space."),
+        ).unwrap();
+        idx.add_paper("title", "Traversable Wormholes", None, &[], None).unwrap();
+
+        let results = idx.search("wormholes", 10).unwrap();
+        let ids: Vec<&str> = results.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, vec!["title", "body"]);
+
+        // Re-adding without a body drops it
+        idx.add_paper("body", "Quantum Error Correction Codes", None, &[], None).unwrap();
+        assert_eq!(idx.search("wormholes", 10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_outdated_schema_is_recreated() {
+        let tmp = TempDir::new().unwrap();
+        {
+            // The original schema: abstract and authors indexed but not stored
+            let mut builder = Schema::builder();
+            let f_id = builder.add_text_field("id", STRING | STORED);
+            let f_title = builder.add_text_field("title", TEXT | STORED);
+            builder.add_text_field("abstract_text", TEXT);
+            builder.add_text_field("authors", TEXT);
+            builder.add_i64_field("year", NumericOptions::default().set_stored().set_indexed());
+            let index = Index::create_in_dir(tmp.path(), builder.build()).unwrap();
+            let mut writer: IndexWriter = index.writer_with_num_threads(1, 15_000_000).unwrap();
+            writer.add_document(doc!(f_id => "old", f_title => "Old Paper")).unwrap();
+            writer.commit().unwrap();
+        }
+
+        let idx = FulltextIndex::create_or_open(tmp.path()).unwrap();
+        assert!(idx.needs_rebuild());
+        assert_eq!(idx.count(), 0);
+        idx.add_paper("new", "New Paper", Some("Stored now."), &[], None).unwrap();
+        assert_eq!(idx.get_stored("new").unwrap().unwrap().abstract_text.as_deref(), Some("Stored now."));
+
+        // Reopening with the current schema keeps the documents
+        let idx = FulltextIndex::create_or_open(tmp.path()).unwrap();
+        assert!(!idx.needs_rebuild());
+        assert_eq!(idx.count(), 1);
+    }
+
     #[test]
     fn test_reopen_same_directory_without_holding_writer_lock() {
         let tmp = TempDir::new().unwrap();
@@ -1,39 +1,69 @@
 use std::collections::HashMap;
 use anyhow::Result;
+use serde::Serialize;
 
 use crate::apis::PaperResult;
-use super::fulltext::FulltextIndex;
+use super::fulltext::{FulltextIndex, ParsedQuery};
 use super::vectordb::VectorStore;
 
 /// RRF constant (standard value from the original paper).
 const RRF_K: f32 = 60.0;
+/// Extra BM25 candidates fetched when a filter is set. Tantivy can't evaluate
+/// the filter, so keyword hits are filtered after retrieval and would
+/// otherwise leave the channel short of `fetch_limit`.
+const FILTERED_KEYWORD_OVERFETCH: usize = 4;
 
 /// Search mode for hybrid queries.
 pub enum SearchMode<'a> {
     /// Only keyword/BM25 search (no embedding needed).
-    KeywordOnly { query: &'a str },
+    KeywordOnly { query: &'a ParsedQuery },
     /// Only vector similarity search.
     VectorOnly { embedding: &'a [f32] },
-    /// Hybrid: BM25 + vector with reciprocal rank fusion.
-    Hybrid { query: &'a str, embedding: &'a [f32] },
+    /// Hybrid: BM25 + vector with reciprocal rank fusion. Each channel's
+    /// RRF contribution is multiplied by its weight (1.0 weights them equally).
+    Hybrid {
+        query: &'a ParsedQuery,
+        embedding: &'a [f32],
+        keyword_weight: f32,
+        vector_weight: f32,
+    },
 }
 
 /// Perform hybrid search combining Tantivy BM25 and LanceDB vector results
 /// via reciprocal rank fusion (RRF).
 ///
-/// RRF score for a document = sum over rankings r: 1 / (k + rank_in_r)
+/// RRF score for a document = sum over rankings r: w_r / (k + rank_in_r)
+#[cfg(test)]
 pub async fn hybrid_search(
     fulltext: &FulltextIndex,
     vector: &VectorStore,
     mode: SearchMode<'_>,
     limit: usize,
+) -> Result<Vec<ScoredResult>> {
+    hybrid_search_filtered(fulltext, vector, mode, limit, None).await
+}
+
+/// `hybrid_search` over only the papers matching a LanceDB SQL `filter`.
+///
+/// Both channels are filtered before fusion, so the `limit * 3` over-fetch
+/// applies to matching papers and results aren't under-filled: the vector
+/// channel pushes the filter into LanceDB, and the keyword channel fetches
+/// `FILTERED_KEYWORD_OVERFETCH` times as many BM25 hits and drops the ones
+/// that don't match. Very selective filters can still leave the keyword
+/// channel short.
+pub async fn hybrid_search_filtered(
+    fulltext: &FulltextIndex,
+    vector: &VectorStore,
+    mode: SearchMode<'_>,
+    limit: usize,
+    filter: Option<&str>,
 ) -> Result<Vec<ScoredResult>> {
     // Fetch more candidates than needed to improve fusion quality
     let fetch_limit = limit * 3;
 
     match mode {
         SearchMode::KeywordOnly { query } => {
-            let bm25_results = fulltext.search(query, fetch_limit)?;
+            let bm25_results = keyword_candidates(fulltext, vector, query, fetch_limit, filter).await?;
             let mut scored: Vec<ScoredResult> = bm25_results
                 .into_iter()
                 .enumerate()
@@ -48,7 +78,7 @@ pub async fn hybrid_search(
             Ok(scored)
         }
         SearchMode::VectorOnly { embedding } => {
-            let vec_results = vector.search_similar(embedding, fetch_limit).await?;
+            let vec_results = vector.search_similar_where(embedding, fetch_limit, &[], filter).await?;
             let mut scored: Vec<ScoredResult> = vec_results
                 .into_iter()
                 .enumerate()
@@ -62,24 +92,20 @@ pub async fn hybrid_search(
             scored.truncate(limit);
             Ok(scored)
         }
-        SearchMode::Hybrid { query, embedding } => {
+        SearchMode::Hybrid { query, embedding, keyword_weight, vector_weight } => {
             // Run both searches in parallel (BM25 is sync, vector is async)
-            let bm25_results = fulltext.search(query, fetch_limit)?;
-            let vec_results = vector.search_similar(embedding, fetch_limit).await?;
+            let bm25_results = keyword_candidates(fulltext, vector, query, fetch_limit, filter).await?;
+            let vec_results = vector.search_similar_where(embedding, fetch_limit, &[], filter).await?;
 
             // Build RRF scores
             let mut doc_scores: HashMap<String, RrfAccumulator> = HashMap::new();
 
             for (rank, (id, score)) in bm25_results.into_iter().enumerate() {
-                let entry = doc_scores.entry(id).or_default();
-                entry.rrf_score += 1.0 / (RRF_K + rank as f32 + 1.0);
-                entry.bm25_score = Some(score);
+                doc_scores.entry(id).or_default().add_keyword(rank, score, keyword_weight);
             }
 
             for (rank, (id, distance)) in vec_results.into_iter().enumerate() {
-                let entry = doc_scores.entry(id).or_default();
-                entry.rrf_score += 1.0 / (RRF_K + rank as f32 + 1.0);
-                entry.vector_distance = Some(distance);
+                doc_scores.entry(id).or_default().add_vector(rank, distance, vector_weight);
             }
 
             // Sort by RRF score descending
@@ -99,7 +125,27 @@ pub async fn hybrid_search(
     }
 }
 
+/// Top BM25 hits for `query`, keeping only papers that match `filter`.
+async fn keyword_candidates(
+    fulltext: &FulltextIndex,
+    vector: &VectorStore,
+    query: &ParsedQuery,
+    fetch_limit: usize,
+    filter: Option<&str>,
+) -> Result<Vec<(String, f32)>> {
+    let Some(filter) = filter else {
+        return fulltext.search_parsed(query, fetch_limit);
+    };
+    let mut hits = fulltext.search_parsed(query, fetch_limit * FILTERED_KEYWORD_OVERFETCH)?;
+    let ids: Vec<String> = hits.iter().map(|(id, _)| id.clone()).collect();
+    let matching = vector.filter_ids(&ids, filter).await?;
+    hits.retain(|(id, _)| matching.contains(id));
+    hits.truncate(fetch_limit);
+    Ok(hits)
+}
+
 /// Resolve scored results to full PaperResult structs by looking them up in the vector store.
+#[cfg(test)]
 pub async fn resolve_results(
     vector: &VectorStore,
     scored: &[ScoredResult],
@@ -113,6 +159,54 @@ pub async fn resolve_results(
     Ok(papers)
 }
 
+/// Resolve scored results to papers while keeping the fusion breakdown.
+pub async fn resolve_scored(
+    vector: &VectorStore,
+    scored: &[ScoredResult],
+) -> Result<Vec<ScoredPaper>> {
+    let mut papers = Vec::with_capacity(scored.len());
+    for result in scored {
+        if let Some(paper) = vector.get_paper(&result.id).await? {
+            papers.push(ScoredPaper {
+                paper,
+                rrf_score: result.rrf_score,
+                bm25_score: result.bm25_score,
+                vector_distance: result.vector_distance,
+                data_source: result.data_source(),
+                snippet: None,
+            });
+        }
+    }
+    Ok(papers)
+}
+
+/// A resolved paper with the scores that ranked it.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScoredPaper {
+    pub paper: PaperResult,
+    pub rrf_score: f32,
+    pub bm25_score: Option<f32>,
+    pub vector_distance: Option<f32>,
+    /// Which channel(s) matched: "keyword", "vector", or "both".
+    pub data_source: &'static str,
+    /// Matched context with `<b>` markers, for keyword matches.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
+}
+
+/// Fill in `snippet` for each keyword-matched paper, highlighting `query`
+/// in its stored title and abstract.
+pub fn add_snippets(fulltext: &FulltextIndex, query: &ParsedQuery, papers: &mut [ScoredPaper]) -> Result<()> {
+    for scored in papers.iter_mut().filter(|s| s.bm25_score.is_some()) {
+        scored.snippet = fulltext.snippet(
+            query,
+            &scored.paper.title,
+            scored.paper.abstract_text.as_deref(),
+        )?;
+    }
+    Ok(())
+}
+
 #[derive(Debug, Clone)]
 pub struct ScoredResult {
     pub id: String,
@@ -121,6 +215,17 @@ pub struct ScoredResult {
     pub vector_distance: Option<f32>,
 }
 
+impl ScoredResult {
+    /// Which retrieval channel(s) produced this result.
+    pub fn data_source(&self) -> &'static str {
+        match (self.bm25_score.is_some(), self.vector_distance.is_some()) {
+            (true, true) => "both",
+            (false, true) => "vector",
+            _ => "keyword",
+        }
+    }
+}
+
 #[derive(Default)]
 struct RrfAccumulator {
     rrf_score: f32,
@@ -128,11 +233,23 @@ struct RrfAccumulator {
     vector_distance: Option<f32>,
 }
 
+impl RrfAccumulator {
+    fn add_keyword(&mut self, rank: usize, bm25_score: f32, weight: f32) {
+        self.rrf_score += weight / (RRF_K + rank as f32 + 1.0);
+        self.bm25_score = Some(bm25_score);
+    }
+
+    fn add_vector(&mut self, rank: usize, distance: f32, weight: f32) {
+        self.rrf_score += weight / (RRF_K + rank as f32 + 1.0);
+        self.vector_distance = Some(distance);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::apis::PaperResult;
-    use crate::embed::specter::mock_embedding;
+    use crate::embed::specter::{mock_embedding, EMBEDDING_DIMENSION};
     use crate::index::fulltext::FulltextIndex;
     use crate::index::vectordb::VectorStore;
     use tempfile::TempDir;
@@ -150,6 +267,8 @@ mod tests {
             url: "https://example.com".to_string(),
             pdf_url: None,
             citation_count: None,
+            orcids: Vec::new(),
+            subjects: Vec::new(),
         }
     }
 
@@ -158,8 +277,8 @@ mod tests {
         let ft_dir = TempDir::new().unwrap();
         let vec_dir = TempDir::new().unwrap();
 
-        let mut ft_index = FulltextIndex::create_or_open(ft_dir.path()).unwrap();
-        let vec_store = VectorStore::create_or_open(vec_dir.path()).await.unwrap();
+        let ft_index = FulltextIndex::create_or_open(ft_dir.path()).unwrap();
+        let vec_store = VectorStore::create_or_open(vec_dir.path(), EMBEDDING_DIMENSION).await.unwrap();
 
         let papers = vec![
             sample_paper("p1", "Holographic Entanglement Entropy in AdS/CFT", "We compute entanglement entropy using the Ryu-Takayanagi formula in anti-de Sitter spacetime."),
@@ -168,7 +287,7 @@ mod tests {
         ];
 
         for paper in &papers {
-            let emb = mock_embedding(&paper.title);
+            let emb = mock_embedding(&paper.title, EMBEDDING_DIMENSION);
             ft_index.add_paper(
                 &paper.id,
                 &paper.title,
@@ -181,10 +300,11 @@ mod tests {
         ft_index.commit().unwrap();
 
         // Keyword-only search
+        let query = ft_index.parse("holographic entanglement").unwrap();
         let results = hybrid_search(
             &ft_index,
             &vec_store,
-            SearchMode::KeywordOnly { query: "holographic entanglement" },
+            SearchMode::KeywordOnly { query: &query },
             10,
         ).await.unwrap();
         assert!(!results.is_empty());
@@ -192,7 +312,7 @@ mod tests {
         assert!(results[0].bm25_score.is_some());
 
         // Vector-only search
-        let query_emb = mock_embedding("Holographic Entanglement Entropy in AdS/CFT");
+        let query_emb = mock_embedding("Holographic Entanglement Entropy in AdS/CFT", EMBEDDING_DIMENSION);
         let results = hybrid_search(
             &ft_index,
             &vec_store,
@@ -206,8 +326,10 @@ mod tests {
             &ft_index,
             &vec_store,
             SearchMode::Hybrid {
-                query: "holographic entanglement",
+                query: &query,
                 embedding: &query_emb,
+                keyword_weight: 1.0,
+                vector_weight: 1.0,
             },
             10,
         ).await.unwrap();
@@ -218,5 +340,33 @@ mod tests {
         // Resolve to full papers
         let resolved = resolve_results(&vec_store, &results).await.unwrap();
         assert!(!resolved.is_empty());
+
+        // Scored resolution keeps the fusion breakdown
+        let scored = resolve_scored(&vec_store, &results).await.unwrap();
+        assert_eq!(scored.len(), resolved.len());
+        let top = scored.iter().find(|s| s.paper.id == "p1").unwrap();
+        assert!(top.bm25_score.is_some());
+        assert!(top.vector_distance.is_some());
+        assert_eq!(top.data_source, "both");
+
+        let mut scored = scored;
+        add_snippets(&ft_index, &query, &mut scored).unwrap();
+        let top = scored.iter().find(|s| s.paper.id == "p1").unwrap();
+        assert!(top.snippet.as_deref().unwrap().contains("<b>"));
+    }
+
+    #[test]
+    fn test_channel_weights_scale_rrf_contributions() {
+        let mut keyword_hit = RrfAccumulator::default();
+        keyword_hit.add_keyword(0, 3.5, 2.0);
+        let mut vector_hit = RrfAccumulator::default();
+        vector_hit.add_vector(0, 0.1, 0.5);
+        assert!(keyword_hit.rrf_score > vector_hit.rrf_score);
+        assert!((keyword_hit.rrf_score - 4.0 * vector_hit.rrf_score).abs() < 1e-6);
+
+        let mut muted = RrfAccumulator::default();
+        muted.add_vector(0, 0.1, 0.0);
+        assert_eq!(muted.rrf_score, 0.0);
+        assert_eq!(muted.vector_distance, Some(0.1));
     }
 }
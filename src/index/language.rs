@@ -0,0 +1,73 @@
+use whatlang::Lang;
+
+/// Detect the language of a paper from its title and abstract, as an
+/// ISO 639-1 code (ISO 639-3 for languages without one). Text too short or
+/// mixed for a reliable guess gives `None`.
+pub fn detect(title: &str, abstract_text: Option<&str>) -> Option<&'static str> {
+    let text = match abstract_text {
+        Some(abstract_text) => format!("{}. {}", title, abstract_text),
+        None => title.to_string(),
+    };
+    let info = whatlang::detect(&text).filter(|info| info.is_reliable())?;
+    Some(iso_639_1(info.lang()))
+}
+
+fn iso_639_1(lang: Lang) -> &'static str {
+    match lang {
+        Lang::Eng => "en",
+        Lang::Spa => "es",
+        Lang::Fra => "fr",
+        Lang::Deu => "de",
+        Lang::Ita => "it",
+        Lang::Por => "pt",
+        Lang::Nld => "nl",
+        Lang::Rus => "ru",
+        Lang::Ukr => "uk",
+        Lang::Pol => "pl",
+        Lang::Ces => "cs",
+        Lang::Swe => "sv",
+        Lang::Dan => "da",
+        Lang::Nob => "no",
+        Lang::Fin => "fi",
+        Lang::Tur => "tr",
+        Lang::Ell => "el",
+        Lang::Cmn => "zh",
+        Lang::Jpn => "ja",
+        Lang::Kor => "ko",
+        Lang::Ara => "ar",
+        Lang::Heb => "he",
+        Lang::Hin => "hi",
+        Lang::Pes => "fa",
+        Lang::Ind => "id",
+        Lang::Vie => "vi",
+        Lang::Cat => "ca",
+        Lang::Ron => "ro",
+        Lang::Hun => "hu",
+        Lang::Lat => "la",
+        other => other.code(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_abstract_language() {
+        assert_eq!(
+            detect(
+                "Black hole entropy",
+                Some("We compute the entropy of extremal black holes in string theory and compare it with the area law."),
+            ),
+            Some("en"),
+        );
+        assert_eq!(
+            detect(
+                "Entropía de agujeros negros",
+                Some("Calculamos la entropía de los agujeros negros extremos en la teoría de cuerdas y la comparamos con la ley del área."),
+            ),
+            Some("es"),
+        );
+        assert_eq!(detect("", None), None);
+    }
+}
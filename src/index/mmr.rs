@@ -0,0 +1,114 @@
+use anyhow::Result;
+
+use super::vectordb::VectorStore;
+
+/// Default relevance/novelty tradeoff: 1.0 is pure relevance, 0.0 pure novelty.
+pub const DEFAULT_LAMBDA: f32 = 0.5;
+/// Candidates retrieved per requested result before diversifying.
+pub const CANDIDATE_FACTOR: usize = 3;
+
+/// Maximal Marginal Relevance: greedily pick up to `k` candidates, each time
+/// taking the one maximizing
+/// `lambda * relevance(c) - (1 - lambda) * max sim(c, already picked)`.
+/// `relevance` is each candidate's ranking score on any non-negative scale;
+/// it's divided by the best so it weighs like a cosine similarity. Returns
+/// indices into `candidates` in pick order.
+pub fn mmr_select(relevance: &[f32], candidates: &[Vec<f32>], k: usize, lambda: f32) -> Vec<usize> {
+    let lambda = lambda.clamp(0.0, 1.0);
+    let best = relevance.iter().copied().fold(0.0_f32, f32::max);
+    let relevance: Vec<f32> = relevance
+        .iter()
+        .map(|&r| if best > 0.0 { r / best } else { 0.0 })
+        .collect();
+    let mut selected: Vec<usize> = Vec::with_capacity(k.min(candidates.len()));
+    let mut remaining: Vec<usize> = (0..candidates.len()).collect();
+
+    while selected.len() < k && !remaining.is_empty() {
+        let score = |i: usize| {
+            let redundancy = selected
+                .iter()
+                .map(|&j| cosine_similarity(&candidates[i], &candidates[j]))
+                .fold(0.0_f32, f32::max);
+            lambda * relevance[i] - (1.0 - lambda) * redundancy
+        };
+        let (pos, _) = remaining
+            .iter()
+            .enumerate()
+            .map(|(pos, &i)| (pos, score(i)))
+            .fold((0, f32::NEG_INFINITY), |best, cur| if cur.1 > best.1 { cur } else { best });
+        selected.push(remaining.remove(pos));
+    }
+    selected
+}
+
+/// Diversify candidate papers `ids`, scored by the ranking that found them
+/// (`relevance`, parallel to `ids`), with MMR over their stored embeddings.
+/// Returns indices into `ids`; candidates without a stored embedding are
+/// dropped.
+pub async fn diversify(
+    vector: &VectorStore,
+    ids: &[String],
+    relevance: &[f32],
+    k: usize,
+    lambda: f32,
+) -> Result<Vec<usize>> {
+    let mut positions = Vec::with_capacity(ids.len());
+    let mut scores = Vec::with_capacity(ids.len());
+    let mut embeddings = Vec::with_capacity(ids.len());
+    for (pos, (id, &score)) in ids.iter().zip(relevance).enumerate() {
+        if let Some(embedding) = vector.get_embedding(id).await? {
+            positions.push(pos);
+            scores.push(score);
+            embeddings.push(embedding);
+        }
+    }
+    Ok(mmr_select(&scores, &embeddings, k, lambda)
+        .into_iter()
+        .map(|i| positions[i])
+        .collect())
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mmr_prefers_diverse_set_over_near_duplicates() {
+        // Cosine similarity of each to the query (1, 0, 0)
+        let relevance = [0.995, 0.994, 0.993, 0.707];
+        let candidates = vec![
+            vec![1.0, 0.10, 0.0],  // most relevant
+            vec![1.0, 0.11, 0.0],  // near-duplicate of the first
+            vec![1.0, 0.12, 0.0],  // another near-duplicate
+            vec![0.7, 0.0, 0.7],   // less relevant, different direction
+        ];
+
+        // Pure relevance keeps the duplicates
+        assert_eq!(mmr_select(&relevance, &candidates, 2, 1.0), vec![0, 1]);
+
+        // Favoring diversity picks the distinct vector second
+        assert_eq!(mmr_select(&relevance, &candidates, 2, 0.3), vec![0, 3]);
+
+        assert_eq!(mmr_select(&relevance, &candidates, 10, 0.5).len(), 4);
+        assert!(mmr_select(&[], &[], 3, 0.5).is_empty());
+    }
+
+    #[test]
+    fn test_mmr_ranks_by_given_scores_not_embeddings() {
+        // BM25-style scores from a keyword ranking that put the last first
+        let relevance = [2.0, 1.5, 9.0];
+        let candidates = vec![vec![1.0, 0.0], vec![0.0, 1.0], vec![1.0, 1.0]];
+        assert_eq!(mmr_select(&relevance, &candidates, 3, 1.0), vec![2, 0, 1]);
+    }
+}
@@ -1,24 +1,34 @@
 pub mod fulltext;
 pub mod hybrid;
+pub mod language;
+pub mod mmr;
+pub mod queue;
 pub mod vectordb;
 
-use std::path::{Path, PathBuf};
-use anyhow::{Context, Result};
+use std::path::Path;
+use anyhow::{bail, Context, Result};
 
 use crate::apis::PaperResult;
-use crate::embed::specter::mock_embedding;
+use crate::embed::preprocess;
 
 /// Unified local index owning both Tantivy (fulltext) and LanceDB (vector) components.
 pub struct LocalIndex {
     pub fulltext: fulltext::FulltextIndex,
     pub vector: vectordb::VectorStore,
-    data_dir: PathBuf,
+    clean_abstracts: bool,
+    /// Row count at which the ANN vector index is built (0: never automatically).
+    vector_index_threshold: usize,
+    vector_index_partitions: Option<u32>,
+    /// Set once the threshold check has run to completion, so inserts don't
+    /// re-list indices (or retry a failed build) every time.
+    vector_index_checked: bool,
 }
 
 impl LocalIndex {
-    /// Create or open the local index at the given data directory.
+    /// Create or open the local index at the given data directory, for
+    /// embeddings of `embedding_dim` values.
     /// Creates subdirectories `tantivy/` and `lance/` under data_dir.
-    pub async fn create_or_open(data_dir: &Path) -> Result<Self> {
+    pub async fn create_or_open(data_dir: &Path, embedding_dim: usize) -> Result<Self> {
         std::fs::create_dir_all(data_dir)
             .context("Failed to create data directory")?;
 
@@ -27,51 +37,213 @@ impl LocalIndex {
 
         let fulltext = fulltext::FulltextIndex::create_or_open(&tantivy_path)
             .context("Failed to open fulltext index")?;
-        let vector = vectordb::VectorStore::create_or_open(&lance_path)
+        let vector = vectordb::VectorStore::create_or_open(&lance_path, embedding_dim)
             .await
             .context("Failed to open vector store")?;
+        if fulltext.needs_rebuild() {
+            let papers = vector.all_papers().await
+                .context("Failed to read papers for fulltext rebuild")?;
+            let bodies = vector.all_bodies().await
+                .context("Failed to read body text for fulltext rebuild")?;
+            tracing::info!("Rebuilding fulltext index from {} stored papers", papers.len());
+            fulltext.add_papers(&papers, &bodies)
+                .context("Failed to rebuild fulltext index")?;
+        }
+
+        Ok(Self {
+            fulltext,
+            vector,
+            clean_abstracts: false,
+            vector_index_threshold: 0,
+            vector_index_partitions: None,
+            vector_index_checked: false,
+        })
+    }
+
+    /// Open an existing index at `data_dir` without writing to it: the
+    /// vector store is neither created nor migrated, and a keyword index
+    /// that is missing or outdated is rebuilt in memory instead of on disk.
+    /// Fails if there is no vector store to serve.
+    pub async fn open_read_only(data_dir: &Path, embedding_dim: usize) -> Result<Self> {
+        let vector = vectordb::VectorStore::open_read_only(&data_dir.join("lance"), embedding_dim)
+            .await
+            .context("Read-only mode needs an existing index")?;
+        let fulltext = fulltext::FulltextIndex::open_read_only(&data_dir.join("tantivy"))
+            .context("Failed to open fulltext index")?;
+        if fulltext.needs_rebuild() {
+            let papers = vector.all_papers().await
+                .context("Failed to read papers for fulltext rebuild")?;
+            // Tables from before body text was stored have no body column
+            let bodies = vector.all_bodies().await.unwrap_or_else(|e| {
+                tracing::warn!("Rebuilding the fulltext index without body text: {}", e);
+                Default::default()
+            });
+            fulltext.add_papers(&papers, &bodies)
+                .context("Failed to rebuild fulltext index")?;
+        }
 
         Ok(Self {
             fulltext,
             vector,
-            data_dir: data_dir.to_path_buf(),
+            clean_abstracts: false,
+            vector_index_threshold: 0,
+            vector_index_partitions: None,
+            vector_index_checked: false,
         })
     }
 
+    /// Strip structured-abstract section labels from the keyword-indexed
+    /// abstract. The stored paper keeps its raw abstract for display.
+    pub fn with_clean_abstracts(mut self, enabled: bool) -> Self {
+        self.clean_abstracts = enabled;
+        self
+    }
+
+    /// Build the ANN vector index automatically once the store holds
+    /// `threshold` papers (0 disables). See `VectorStore::create_vector_index`
+    /// for the recall/speed tradeoff of `num_partitions`.
+    pub fn with_vector_index(mut self, threshold: usize, num_partitions: Option<u32>) -> Self {
+        self.vector_index_threshold = threshold;
+        self.vector_index_partitions = num_partitions;
+        self
+    }
+
+    /// Record `model` as the source of embeddings indexed from now on.
+    pub fn with_embedding_model(mut self, model: &str) -> Self {
+        self.vector = self.vector.with_embedding_model(model);
+        self
+    }
+
+    /// Tag indexed papers with their detected language.
+    pub fn with_language_tagging(mut self, enabled: bool) -> Self {
+        self.vector = self.vector.with_language_tagging(enabled);
+        self
+    }
+
+    /// Compare embeddings with `distance_type` in vector search.
+    pub fn with_distance_type(mut self, distance_type: lancedb::DistanceType) -> Self {
+        self.vector = self.vector.with_distance_type(distance_type);
+        self
+    }
+
     /// Index a paper with a precomputed embedding.
+    #[cfg(test)]
     pub async fn index_paper(&mut self, paper: &PaperResult, embedding: &[f32]) -> Result<()> {
-        self.vector.add_paper(paper, embedding).await?;
-        if let Err(err) = self.fulltext.add_paper(
+        self.index_paper_with(paper, embedding, vectordb::StoredText::default()).await
+    }
+
+    /// Index a paper with `text` to store in its row; fields of `text` left
+    /// unset keep what is already stored. Keyword search matches the body
+    /// text at a lower weight than the title and abstract. An already
+    /// indexed paper is replaced in place. If the keyword index can't take
+    /// the paper, a row this call created is removed again; a replaced row
+    /// keeps the new record.
+    pub async fn index_paper_with(
+        &mut self,
+        paper: &PaperResult,
+        embedding: &[f32],
+        text: vectordb::StoredText,
+    ) -> Result<()> {
+        let text = text.or(self.vector.stored_text(&paper.id).await?);
+        let created = self.vector.upsert(paper, embedding, &text).await?;
+        let cleaned = paper.abstract_text.as_deref()
+            .filter(|_| self.clean_abstracts)
+            .map(preprocess::strip_section_labels);
+        if let Err(err) = self.fulltext.add_paper_with_body(
             &paper.id,
             &paper.title,
-            paper.abstract_text.as_deref(),
+            cleaned.as_deref().or(paper.abstract_text.as_deref()),
             &paper.authors,
             paper.year,
+            text.body.as_deref(),
         ) {
-            let _ = self.vector.delete(&paper.id).await;
+            if created {
+                let _ = self.vector.delete(&paper.id).await;
+            }
             return Err(err);
         }
+        self.maybe_build_vector_index().await;
         Ok(())
     }
 
-    /// Index a paper using a mock embedding (for when no SPECTER2 model is available).
-    pub async fn index_paper_mock(&mut self, paper: &PaperResult) -> Result<()> {
-        let text = format!(
-            "{} {}",
-            paper.title,
-            paper.abstract_text.as_deref().unwrap_or("")
-        );
-        let embedding = mock_embedding(&text);
-        self.index_paper(paper, &embedding).await
+    /// Replace an indexed paper with a fresh record: the old row is deleted
+    /// from both indices and `paper` indexed again with `embedding`, keeping
+    /// the stored raw record and body text. If re-indexing fails, the old
+    /// record is restored. Errors if the paper isn't indexed.
+    pub async fn update_paper(&mut self, paper: &PaperResult, embedding: &[f32]) -> Result<()> {
+        let Some(old) = self.vector.get_paper(&paper.id).await? else {
+            bail!("Paper not indexed: {}", paper.id);
+        };
+        let old_embedding = self.vector.get_embedding(&paper.id).await?
+            .context("Indexed paper has no embedding")?;
+        let text = self.vector.stored_text(&paper.id).await?;
+        self.delete(&paper.id).await?;
+        if let Err(err) = self.index_paper_with(paper, embedding, text.clone()).await {
+            if let Err(restore) = self.index_paper_with(&old, &old_embedding, text).await {
+                tracing::warn!("Failed to restore {} after a failed update: {}", paper.id, restore);
+            }
+            return Err(err);
+        }
+        Ok(())
     }
 
-    /// Hybrid search over the local index.
-    pub async fn search(
-        &self,
-        mode: hybrid::SearchMode<'_>,
-        limit: usize,
-    ) -> Result<Vec<hybrid::ScoredResult>> {
-        hybrid::hybrid_search(&self.fulltext, &self.vector, mode, limit).await
+    /// Rebuild a vector index built for another distance metric than the
+    /// configured one, or build it the first time the row count reaches the
+    /// threshold. Runs after writes; call it at startup so searches don't
+    /// use a stale index until then.
+    pub async fn maybe_build_vector_index(&mut self) {
+        if self.vector_index_checked {
+            return;
+        }
+        match self.vector.vector_index_distance().await {
+            Ok(Some(built)) => {
+                self.vector_index_checked = true;
+                let configured = self.vector.distance_type();
+                if built != configured {
+                    tracing::info!("Rebuilding vector index built for {:?} distance with {}", built, self.vector.distance_name());
+                    if let Err(e) = self.vector.create_vector_index(self.vector_index_partitions).await {
+                        tracing::warn!("Failed to rebuild vector index: {}", e);
+                    }
+                }
+                return;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                tracing::warn!("Failed to inspect vector index: {}", e);
+                return;
+            }
+        }
+        if self.vector_index_threshold == 0 {
+            return;
+        }
+        match self.vector.count().await {
+            Ok(rows) if rows >= self.vector_index_threshold => {}
+            _ => return,
+        }
+        self.vector_index_checked = true;
+        tracing::info!("Building vector index at {} papers", self.vector_index_threshold);
+        if let Err(e) = self.vector.create_vector_index(self.vector_index_partitions).await {
+            tracing::warn!("Failed to build vector index: {}", e);
+        }
+    }
+
+    /// Rebuild the vector index over all current rows, using the configured
+    /// partition count unless `num_partitions` overrides it.
+    pub async fn rebuild_vector_index(&mut self, num_partitions: Option<u32>) -> Result<()> {
+        self.vector
+            .create_vector_index(num_partitions.or(self.vector_index_partitions))
+            .await?;
+        self.vector_index_checked = true;
+        Ok(())
+    }
+
+    /// A read-only view sharing this index's Tantivy reader and LanceDB
+    /// connection. Reads through it never wait on the write lock.
+    pub fn read_handle(&self) -> ReadHandle {
+        ReadHandle {
+            fulltext: self.fulltext.clone(),
+            vector: self.vector.clone(),
+        }
     }
 
     /// Get total number of indexed papers.
@@ -91,7 +263,487 @@ impl LocalIndex {
         self.vector.get_paper(id).await
     }
 
-    pub fn data_dir(&self) -> &Path {
-        &self.data_dir
+    /// Delete papers matching `criteria` from both indices. With `dry_run`,
+    /// only report what would be removed. Returns the matching IDs.
+    pub async fn prune(&mut self, criteria: &PruneCriteria, dry_run: bool) -> Result<Vec<String>> {
+        let predicate = criteria
+            .to_predicate()
+            .context("At least one prune criterion is required")?;
+        let ids = self.vector.ids_matching(&predicate).await?;
+        if dry_run || ids.is_empty() {
+            return Ok(ids);
+        }
+
+        self.vector.delete_where(&predicate).await?;
+        for id in &ids {
+            self.fulltext.delete(id)?;
+        }
+        Ok(ids)
+    }
+
+    /// Remove every paper from both indices. Returns the number of papers
+    /// removed from the vector store and documents removed from Tantivy.
+    pub async fn clear(&mut self) -> Result<(usize, u64)> {
+        let papers = self.vector.clear().await?;
+        let documents = self.fulltext.clear()?;
+        self.vector_index_checked = false;
+        Ok((papers, documents))
+    }
+}
+
+/// Cheaply clonable read access to the local index, independent of the
+/// `Mutex<LocalIndex>` that serializes writes.
+#[derive(Clone)]
+pub struct ReadHandle {
+    pub fulltext: fulltext::FulltextIndex,
+    pub vector: vectordb::VectorStore,
+}
+
+impl ReadHandle {
+    /// Hybrid search over the local index.
+    #[cfg(test)]
+    pub async fn search(
+        &self,
+        mode: hybrid::SearchMode<'_>,
+        limit: usize,
+    ) -> Result<Vec<hybrid::ScoredResult>> {
+        hybrid::hybrid_search(&self.fulltext, &self.vector, mode, limit).await
+    }
+
+    /// Hybrid search over the papers matching `filter`.
+    pub async fn search_filtered(
+        &self,
+        mode: hybrid::SearchMode<'_>,
+        limit: usize,
+        filter: &LocalFilter,
+    ) -> Result<Vec<hybrid::ScoredResult>> {
+        let predicate = filter.to_predicate();
+        hybrid::hybrid_search_filtered(&self.fulltext, &self.vector, mode, limit, predicate.as_deref()).await
+    }
+
+    /// Get a paper by ID from the vector store.
+    pub async fn get_paper(&self, id: &str) -> Result<Option<PaperResult>> {
+        self.vector.get_paper(id).await
+    }
+}
+
+/// Restricts local searches by provenance and date. Unset fields don't filter.
+#[derive(Debug, Default, Clone)]
+pub struct LocalFilter {
+    /// Source the paper was indexed from (e.g. "arxiv").
+    pub source: Option<String>,
+    /// Earliest publication year, inclusive.
+    pub year_min: Option<i32>,
+    /// Latest publication year, inclusive.
+    pub year_max: Option<i32>,
+    /// Detected language (ISO 639-1). Untagged papers never match.
+    pub language: Option<String>,
+}
+
+impl LocalFilter {
+    /// Build a LanceDB SQL predicate, or `None` when nothing is filtered.
+    /// Papers without a year never match a year bound.
+    fn to_predicate(&self) -> Option<String> {
+        let mut clauses = Vec::new();
+        if let Some(source) = &self.source {
+            clauses.push(format!("source = {}", vectordb::sql_string(source)));
+        }
+        if let Some(min) = self.year_min {
+            clauses.push(format!("year >= {}", min));
+        }
+        if let Some(max) = self.year_max {
+            clauses.push(format!("year <= {}", max));
+        }
+        if let Some(language) = &self.language {
+            clauses.push(format!("language = {}", vectordb::sql_string(language)));
+        }
+        if clauses.is_empty() {
+            None
+        } else {
+            Some(clauses.join(" AND "))
+        }
+    }
+}
+
+/// Criteria selecting papers to prune. All given criteria must match.
+#[derive(Debug, Default, Clone)]
+pub struct PruneCriteria {
+    /// Indexed before this Unix timestamp (seconds). Papers indexed before
+    /// timestamps were recorded always match.
+    pub indexed_before: Option<i64>,
+    /// Fewer citations than this. Papers without a citation count match.
+    pub min_citations: Option<u32>,
+    /// Came from one of these sources.
+    pub sources: Vec<String>,
+}
+
+impl PruneCriteria {
+    /// Whether no criterion is set; pruning requires at least one.
+    pub fn is_empty(&self) -> bool {
+        self.indexed_before.is_none() && self.min_citations.is_none() && self.sources.is_empty()
+    }
+
+    /// Build a LanceDB SQL predicate, or `None` when no criterion is set.
+    fn to_predicate(&self) -> Option<String> {
+        let mut clauses = Vec::new();
+        if let Some(ts) = self.indexed_before {
+            clauses.push(format!("(indexed_at IS NULL OR indexed_at < {})", ts));
+        }
+        if let Some(min) = self.min_citations {
+            clauses.push(format!("(citation_count IS NULL OR citation_count < {})", min));
+        }
+        if !self.sources.is_empty() {
+            let list = self.sources
+                .iter()
+                .map(|s| vectordb::sql_string(s))
+                .collect::<Vec<_>>()
+                .join(", ");
+            clauses.push(format!("source IN ({})", list));
+        }
+        if clauses.is_empty() {
+            None
+        } else {
+            Some(clauses.join(" AND "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embed::specter::{mock_embedding, EMBEDDING_DIMENSION};
+    use tempfile::TempDir;
+
+    fn sample_paper(id: &str, title: &str, citations: Option<u32>) -> PaperResult {
+        PaperResult {
+            id: id.to_string(),
+            title: title.to_string(),
+            authors: vec!["Test Author".to_string()],
+            abstract_text: Some(format!("Abstract for {}", title)),
+            year: Some(2024),
+            source: "test".to_string(),
+            doi: None,
+            arxiv_id: None,
+            url: "https://example.com".to_string(),
+            pdf_url: None,
+            citation_count: citations,
+            orcids: Vec::new(),
+            subjects: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reindexing_keeps_one_row() {
+        let tmp = TempDir::new().unwrap();
+        let mut idx = LocalIndex::create_or_open(tmp.path(), EMBEDDING_DIMENSION).await.unwrap();
+        let paper = sample_paper("p1", "Holographic Entanglement Entropy", Some(120));
+        let embedding = mock_embedding(&paper.title, EMBEDDING_DIMENSION);
+        idx.index_paper(&paper, &embedding).await.unwrap();
+        idx.index_paper(&paper, &embedding).await.unwrap();
+        assert_eq!(idx.count().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_reindexing_keeps_stored_text() {
+        let tmp = TempDir::new().unwrap();
+        let mut idx = LocalIndex::create_or_open(tmp.path(), EMBEDDING_DIMENSION).await.unwrap();
+        let paper = sample_paper("p1", "Holographic Entanglement Entropy", Some(120));
+        let embedding = mock_embedding(&paper.title, EMBEDDING_DIMENSION);
+        let text = vectordb::StoredText {
+            raw_json: Some(r#"{"id":"p1"}"#.to_string()),
+            body: Some("Appendix C treats traversable wormholes.".to_string()),
+        };
+        idx.index_paper_with(&paper, &embedding, text.clone()).await.unwrap();
+
+        let updated = PaperResult { citation_count: Some(130), ..paper };
+        idx.index_paper(&updated, &embedding).await.unwrap();
+        assert_eq!(idx.vector.stored_text("p1").await.unwrap(), text);
+        assert_eq!(idx.get_paper("p1").await.unwrap().unwrap().citation_count, Some(130));
+        assert_eq!(idx.fulltext.search("wormholes", 10).unwrap().len(), 1);
+
+        // A keyword index rebuilt after a schema change gets the body back
+        // from the vector store
+        drop(idx);
+        let tantivy_dir = tmp.path().join("tantivy");
+        std::fs::remove_dir_all(&tantivy_dir).unwrap();
+        std::fs::create_dir_all(&tantivy_dir).unwrap();
+        let mut outdated = tantivy::schema::Schema::builder();
+        outdated.add_text_field("id", tantivy::schema::STRING);
+        tantivy::Index::create_in_dir(&tantivy_dir, outdated.build()).unwrap();
+        let idx = LocalIndex::create_or_open(tmp.path(), EMBEDDING_DIMENSION).await.unwrap();
+        assert_eq!(idx.fulltext.search("wormholes", 10).unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_update_paper_replaces_row_and_keeps_stored_text() {
+        let tmp = TempDir::new().unwrap();
+        let mut idx = LocalIndex::create_or_open(tmp.path(), EMBEDDING_DIMENSION).await.unwrap();
+        let paper = sample_paper("p1", "Holographic Entanglement Entropy", Some(120));
+        let text = vectordb::StoredText {
+            raw_json: Some(r#"{"id":"p1"}"#.to_string()),
+            body: Some("Appendix C treats traversable wormholes.".to_string()),
+        };
+        idx.index_paper_with(&paper, &mock_embedding(&paper.title, EMBEDDING_DIMENSION), text.clone()).await.unwrap();
+
+        let fresh = PaperResult {
+            title: "Holographic Entanglement Entropy Revisited".to_string(),
+            citation_count: Some(130),
+            ..paper
+        };
+        let embedding = mock_embedding(&fresh.title, EMBEDDING_DIMENSION);
+        idx.update_paper(&fresh, &embedding).await.unwrap();
+        assert_eq!(idx.count().await.unwrap(), 1);
+        assert_eq!(idx.fulltext.count(), 1);
+        let stored = idx.get_paper("p1").await.unwrap().unwrap();
+        assert_eq!(stored.citation_count, Some(130));
+        assert_eq!(stored.title, fresh.title);
+        assert_eq!(idx.vector.get_embedding("p1").await.unwrap().unwrap(), embedding);
+        assert_eq!(idx.vector.stored_text("p1").await.unwrap(), text);
+        assert_eq!(idx.fulltext.search("revisited", 10).unwrap().len(), 1);
+        assert_eq!(idx.fulltext.search("wormholes", 10).unwrap().len(), 1);
+
+        let unknown = sample_paper("p2", "Not Indexed", None);
+        assert!(idx.update_paper(&unknown, &embedding).await.is_err());
+        assert_eq!(idx.count().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_open_read_only_rebuilds_fulltext_in_memory() {
+        let tmp = TempDir::new().unwrap();
+        assert!(LocalIndex::open_read_only(tmp.path(), EMBEDDING_DIMENSION).await.is_err());
+        {
+            let mut idx = LocalIndex::create_or_open(tmp.path(), EMBEDDING_DIMENSION).await.unwrap();
+            let paper = sample_paper("p1", "Holographic Entanglement Entropy", Some(120));
+            idx.index_paper(&paper, &mock_embedding(&paper.title, EMBEDDING_DIMENSION)).await.unwrap();
+        }
+
+        let idx = LocalIndex::open_read_only(tmp.path(), EMBEDDING_DIMENSION).await.unwrap();
+        assert_eq!(idx.fulltext.search("holographic", 10).unwrap().len(), 1);
+        drop(idx);
+
+        // A missing keyword index is rebuilt in memory, not on disk
+        let tantivy_dir = tmp.path().join("tantivy");
+        std::fs::remove_dir_all(&tantivy_dir).unwrap();
+        let idx = LocalIndex::open_read_only(tmp.path(), EMBEDDING_DIMENSION).await.unwrap();
+        assert_eq!(idx.fulltext.search("holographic", 10).unwrap().len(), 1);
+        assert!(idx.get_paper("p1").await.unwrap().is_some());
+        assert!(!tantivy_dir.exists());
+    }
+
+    #[tokio::test]
+    async fn test_prune_by_citation_threshold() {
+        let tmp = TempDir::new().unwrap();
+        let mut idx = LocalIndex::create_or_open(tmp.path(), EMBEDDING_DIMENSION).await.unwrap();
+
+        let papers = vec![
+            sample_paper("p1", "Holographic Entanglement Entropy", Some(120)),
+            sample_paper("p2", "Surface Codes for Quantum Memory", Some(2)),
+            sample_paper("p3", "Black Hole Information Paradox", None),
+            sample_paper("p4", "Topological Quantum Field Theory", Some(45)),
+        ];
+        for paper in &papers {
+            idx.index_paper(paper, &mock_embedding(&paper.title, EMBEDDING_DIMENSION)).await.unwrap();
+        }
+
+        let criteria = PruneCriteria { min_citations: Some(10), ..Default::default() };
+
+        let mut would_remove = idx.prune(&criteria, true).await.unwrap();
+        would_remove.sort();
+        assert_eq!(would_remove, vec!["p2", "p3"]);
+        assert_eq!(idx.count().await.unwrap(), 4);
+
+        let mut removed = idx.prune(&criteria, false).await.unwrap();
+        removed.sort();
+        assert_eq!(removed, vec!["p2", "p3"]);
+        assert_eq!(idx.count().await.unwrap(), 2);
+        assert_eq!(idx.fulltext.count(), 2);
+
+        assert!(idx.fulltext.search("surface codes", 10).unwrap().is_empty());
+        let results = idx.fulltext.search("holographic entanglement", 10).unwrap();
+        assert_eq!(results[0].0, "p1");
+        assert!(idx.get_paper("p4").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_clear_empties_both_indices() {
+        let tmp = TempDir::new().unwrap();
+        let mut idx = LocalIndex::create_or_open(tmp.path(), EMBEDDING_DIMENSION).await.unwrap();
+        for paper in [
+            sample_paper("p1", "Holographic Entanglement Entropy", Some(120)),
+            sample_paper("p2", "Surface Codes for Quantum Memory", Some(2)),
+        ] {
+            idx.index_paper(&paper, &mock_embedding(&paper.title, EMBEDDING_DIMENSION)).await.unwrap();
+        }
+
+        assert_eq!(idx.clear().await.unwrap(), (2, 2));
+        assert_eq!(idx.count().await.unwrap(), 0);
+        assert_eq!(idx.fulltext.count(), 0);
+        assert!(idx.get_paper("p1").await.unwrap().is_none());
+
+        // The emptied index is immediately usable
+        let paper = sample_paper("p3", "Black Hole Information Paradox", None);
+        idx.index_paper(&paper, &mock_embedding(&paper.title, EMBEDDING_DIMENSION)).await.unwrap();
+        assert_eq!(idx.count().await.unwrap(), 1);
+        assert_eq!(idx.fulltext.search("black hole", 10).unwrap()[0].0, "p3");
+    }
+
+    #[tokio::test]
+    async fn test_outdated_fulltext_schema_rebuilt_from_vector_store() {
+        let tmp = TempDir::new().unwrap();
+        {
+            let mut idx = LocalIndex::create_or_open(tmp.path(), EMBEDDING_DIMENSION).await.unwrap();
+            let paper = sample_paper("p1", "Holographic Entanglement Entropy", Some(120));
+            idx.index_paper(&paper, &mock_embedding(&paper.title, EMBEDDING_DIMENSION)).await.unwrap();
+        }
+        // Replace the Tantivy index with one using a different schema
+        let tantivy_path = tmp.path().join("tantivy");
+        std::fs::remove_dir_all(&tantivy_path).unwrap();
+        std::fs::create_dir_all(&tantivy_path).unwrap();
+        let mut builder = tantivy::schema::Schema::builder();
+        builder.add_text_field("id", tantivy::schema::STRING | tantivy::schema::STORED);
+        tantivy::Index::create_in_dir(&tantivy_path, builder.build()).unwrap();
+
+        let idx = LocalIndex::create_or_open(tmp.path(), EMBEDDING_DIMENSION).await.unwrap();
+        assert_eq!(idx.fulltext.count(), 1);
+        let stored = idx.fulltext.get_stored("p1").unwrap().unwrap();
+        assert_eq!(stored.abstract_text.as_deref(), Some("Abstract for Holographic Entanglement Entropy"));
+        assert_eq!(idx.fulltext.search("holographic", 10).unwrap()[0].0, "p1");
+    }
+
+    #[tokio::test]
+    async fn test_language_tagging_and_breakdown() {
+        let tmp = TempDir::new().unwrap();
+        let mut idx = LocalIndex::create_or_open(tmp.path(), EMBEDDING_DIMENSION)
+            .await
+            .unwrap()
+            .with_language_tagging(true);
+        let papers = [
+            ("en:1", "Black hole entropy", "We compute the entropy of extremal black holes in string theory and compare it with the area law."),
+            ("en:2", "Holographic entanglement", "The entanglement entropy of a boundary region is given by the area of a minimal surface in the bulk."),
+            ("es:1", "Entropía de agujeros negros", "Calculamos la entropía de los agujeros negros extremos en la teoría de cuerdas y la comparamos con la ley del área."),
+        ];
+        for (id, title, abstract_text) in papers {
+            let paper = PaperResult { abstract_text: Some(abstract_text.to_string()), ..sample_paper(id, title, None) };
+            idx.index_paper(&paper, &mock_embedding(title, EMBEDDING_DIMENSION)).await.unwrap();
+        }
+        // Nothing to detect from
+        let untagged = PaperResult { abstract_text: None, ..sample_paper("none:1", "42", None) };
+        idx.index_paper(&untagged, &mock_embedding("42", EMBEDDING_DIMENSION)).await.unwrap();
+
+        assert_eq!(idx.vector.ids_matching("language = 'es'").await.unwrap(), vec!["es:1"]);
+        let mut english = idx.vector.ids_matching("language = 'en'").await.unwrap();
+        english.sort();
+        assert_eq!(english, vec!["en:1", "en:2"]);
+
+        let counts = idx.vector.language_counts().await.unwrap();
+        let expected: std::collections::BTreeMap<Option<String>, usize> = [
+            (None, 1),
+            (Some("en".to_string()), 2),
+            (Some("es".to_string()), 1),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(counts, expected);
+
+        let filter = LocalFilter { language: Some("es".to_string()), ..Default::default() };
+        let reader = idx.read_handle();
+        let query = reader.fulltext.parse("entropy OR entropía").unwrap();
+        let hits = reader
+            .search_filtered(hybrid::SearchMode::KeywordOnly { query: &query }, 10, &filter)
+            .await
+            .unwrap();
+        assert_eq!(hits.iter().map(|h| h.id.as_str()).collect::<Vec<_>>(), vec!["es:1"]);
+    }
+
+    #[tokio::test]
+    async fn test_filtered_search_by_source_and_year() {
+        let tmp = TempDir::new().unwrap();
+        let mut idx = LocalIndex::create_or_open(tmp.path(), EMBEDDING_DIMENSION).await.unwrap();
+        let rows = [
+            ("p1", "Holographic Entanglement Entropy", "arxiv", Some(2006)),
+            ("p2", "Holographic Complexity Growth", "arxiv", Some(2021)),
+            ("p3", "Holographic Codes and Bulk Reconstruction", "inspire", Some(2022)),
+            ("p4", "Holographic Duality Without a Date", "arxiv", None),
+        ];
+        for (id, title, source, year) in rows {
+            let mut paper = sample_paper(id, title, None);
+            paper.source = source.to_string();
+            paper.year = year;
+            idx.index_paper(&paper, &mock_embedding(title, EMBEDDING_DIMENSION)).await.unwrap();
+        }
+        let reader = idx.read_handle();
+        let embedding = mock_embedding("holographic", EMBEDDING_DIMENSION);
+        let query = reader.fulltext.parse("holographic").unwrap();
+        let filter = LocalFilter {
+            source: Some("arxiv".to_string()),
+            year_min: Some(2010),
+            year_max: None,
+            language: None,
+        };
+
+        let keyword = reader
+            .search_filtered(hybrid::SearchMode::KeywordOnly { query: &query }, 10, &filter)
+            .await
+            .unwrap();
+        let fused = reader
+            .search_filtered(
+                hybrid::SearchMode::Hybrid {
+                    query: &query,
+                    embedding: &embedding,
+                    keyword_weight: 1.0,
+                    vector_weight: 1.0,
+                },
+                10,
+                &filter,
+            )
+            .await
+            .unwrap();
+        for results in [keyword, fused] {
+            let ids: Vec<&str> = results.iter().map(|r| r.id.as_str()).collect();
+            assert_eq!(ids, vec!["p2"]);
+        }
+
+        let unfiltered = reader
+            .search_filtered(hybrid::SearchMode::KeywordOnly { query: &query }, 10, &LocalFilter::default())
+            .await
+            .unwrap();
+        assert_eq!(unfiltered.len(), 4);
+    }
+
+    #[test]
+    fn test_prune_requires_a_criterion() {
+        assert!(PruneCriteria::default().to_predicate().is_none());
+        let criteria = PruneCriteria {
+            sources: vec!["vixra".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(criteria.to_predicate().unwrap(), "source IN ('vixra')");
+    }
+
+    #[tokio::test]
+    async fn test_vector_index_rebuilt_for_new_distance() {
+        let tmp = TempDir::new().unwrap();
+        // Small vectors keep index training quick
+        let dim = 16;
+        {
+            let mut idx = LocalIndex::create_or_open(tmp.path(), dim)
+                .await
+                .unwrap()
+                .with_distance_type(lancedb::DistanceType::L2);
+            for i in 0..vectordb::MIN_ROWS_FOR_VECTOR_INDEX {
+                let paper = sample_paper(&format!("p{}", i), &format!("Paper {}", i), None);
+                idx.vector.add_paper(&paper, &mock_embedding(&paper.title, dim)).await.unwrap();
+            }
+            idx.rebuild_vector_index(Some(2)).await.unwrap();
+            assert_eq!(idx.vector.vector_index_distance().await.unwrap(), Some(lancedb::DistanceType::L2));
+        }
+
+        let mut idx = LocalIndex::create_or_open(tmp.path(), dim)
+            .await
+            .unwrap()
+            .with_distance_type(lancedb::DistanceType::Cosine)
+            .with_vector_index(0, Some(2));
+        idx.maybe_build_vector_index().await;
+        assert_eq!(idx.vector.vector_index_distance().await.unwrap(), Some(lancedb::DistanceType::Cosine));
     }
 }
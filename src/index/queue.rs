@@ -0,0 +1,334 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use anyhow::{anyhow, Result};
+use tokio::sync::{mpsc, oneshot, Mutex};
+
+use super::vectordb::StoredText;
+use super::LocalIndex;
+use crate::apis::PaperResult;
+
+/// Papers per write-lock acquisition when the batch size is not configured.
+pub const DEFAULT_BATCH_SIZE: usize = 16;
+
+/// Result of one enqueued indexing request.
+#[derive(Debug, Default)]
+pub struct IndexOutcome {
+    /// IDs written to the index, in request order.
+    pub indexed: Vec<String>,
+    /// IDs skipped because they were already indexed.
+    pub skipped: Vec<String>,
+    /// IDs that failed, with the error message.
+    pub failed: Vec<(String, String)>,
+}
+
+/// A paper to write, with its embedding and the text to store alongside it.
+#[derive(Debug, Clone)]
+pub struct IndexEntry {
+    pub paper: PaperResult,
+    pub embedding: Vec<f32>,
+    /// Unset fields keep what is already stored for the paper.
+    pub text: StoredText,
+    /// IDs of duplicate records this paper supersedes, deleted under the
+    /// same lock once it is written.
+    pub replaces: Vec<String>,
+}
+
+impl From<(PaperResult, Vec<f32>)> for IndexEntry {
+    fn from((paper, embedding): (PaperResult, Vec<f32>)) -> Self {
+        Self { paper, embedding, text: StoredText::default(), replaces: Vec::new() }
+    }
+}
+
+struct IndexJob {
+    papers: Vec<IndexEntry>,
+    skip_existing: bool,
+    done: oneshot::Sender<IndexOutcome>,
+}
+
+/// A job the worker has started, with its progress so far.
+struct ActiveJob {
+    job: IndexJob,
+    next: usize,
+    outcome: IndexOutcome,
+    cancelled: bool,
+}
+
+impl ActiveJob {
+    fn new(job: IndexJob) -> Self {
+        Self { job, next: 0, outcome: IndexOutcome::default(), cancelled: false }
+    }
+
+    /// Write the next batch under one lock acquisition, returning whether the
+    /// job is done. A job whose caller has gone away stops before its next
+    /// paper; each paper is committed on its own, so everything written up to
+    /// that point stays consistent.
+    async fn run_batch(&mut self, index: &Mutex<LocalIndex>, batch_size: usize) -> bool {
+        let mut idx = index.lock().await;
+        let end = (self.next + batch_size).min(self.job.papers.len());
+        while self.next < end {
+            if self.job.done.is_closed() {
+                self.cancelled = true;
+                return true;
+            }
+            let entry = &self.job.papers[self.next];
+            let paper = &entry.paper;
+            self.next += 1;
+            if self.job.skip_existing && matches!(idx.get_paper(&paper.id).await, Ok(Some(_))) {
+                self.outcome.skipped.push(paper.id.clone());
+                continue;
+            }
+            if let Err(e) = idx.index_paper_with(paper, &entry.embedding, entry.text.clone()).await {
+                self.outcome.failed.push((paper.id.clone(), e.to_string()));
+                continue;
+            }
+            let mut deleted = Ok(());
+            for id in entry.replaces.iter().filter(|id| **id != paper.id) {
+                if let Err(e) = idx.delete(id).await {
+                    deleted = Err(format!("Failed to delete {}: {}", id, e));
+                    break;
+                }
+            }
+            match deleted {
+                Ok(()) => self.outcome.indexed.push(paper.id.clone()),
+                Err(e) => self.outcome.failed.push((paper.id.clone(), e)),
+            }
+        }
+        self.next >= self.job.papers.len()
+    }
+
+    fn finish(self) {
+        if self.cancelled {
+            tracing::info!(
+                "Index request cancelled after {} of {} papers",
+                self.outcome.indexed.len(),
+                self.job.papers.len(),
+            );
+        } else {
+            let _ = self.job.done.send(self.outcome);
+        }
+    }
+}
+
+/// Serializes index writes through a background worker. The worker takes the
+/// `LocalIndex` write lock once per batch rather than once per request, so
+/// bulk indexing never holds it for long. With `fair` set, pending requests
+/// take turns one batch at a time, so a short request isn't stuck behind a
+/// long bulk job; otherwise they run to completion in arrival order.
+/// Dropping the future returned by `index` cancels the rest of its request.
+#[derive(Clone)]
+pub struct IndexQueue {
+    tx: mpsc::UnboundedSender<IndexJob>,
+}
+
+impl IndexQueue {
+    /// Start the worker. Must be called from within a Tokio runtime.
+    pub fn spawn(index: Arc<Mutex<LocalIndex>>, batch_size: usize, fair: bool) -> Self {
+        Self::spawn_with(index, batch_size, fair, || {})
+    }
+
+    /// Like `spawn`, calling `on_batch` as each batch starts to wait for the lock.
+    fn spawn_with(
+        index: Arc<Mutex<LocalIndex>>,
+        batch_size: usize,
+        fair: bool,
+        on_batch: impl Fn() + Send + 'static,
+    ) -> Self {
+        let (tx, mut rx) = mpsc::unbounded_channel::<IndexJob>();
+        let batch_size = batch_size.max(1);
+        tokio::spawn(async move {
+            let mut active: VecDeque<ActiveJob> = VecDeque::new();
+            loop {
+                let mut current = match active.pop_front() {
+                    Some(current) => current,
+                    None => match rx.recv().await {
+                        Some(job) => ActiveJob::new(job),
+                        None => break,
+                    },
+                };
+                on_batch();
+                let done = current.run_batch(&index, batch_size).await;
+                // Pick up requests that arrived during the batch before choosing who goes next
+                while let Ok(job) = rx.try_recv() {
+                    active.push_back(ActiveJob::new(job));
+                }
+                if done {
+                    current.finish();
+                } else if fair {
+                    active.push_back(current);
+                } else {
+                    active.push_front(current);
+                }
+                // Let waiting writers (and anything else on the lock) in between batches
+                tokio::task::yield_now().await;
+            }
+        });
+        Self { tx }
+    }
+
+    /// Enqueue papers with precomputed embeddings and wait until they are written.
+    pub async fn index<E: Into<IndexEntry>>(
+        &self,
+        papers: Vec<E>,
+        skip_existing: bool,
+    ) -> Result<IndexOutcome> {
+        let rx = self.submit(papers, skip_existing)?;
+        rx.await.map_err(|_| anyhow!("Index worker dropped the request"))
+    }
+
+    /// Enqueue papers, returning a receiver for the outcome. Dropping it
+    /// cancels the rest of the request.
+    fn submit<E: Into<IndexEntry>>(
+        &self,
+        papers: Vec<E>,
+        skip_existing: bool,
+    ) -> Result<oneshot::Receiver<IndexOutcome>> {
+        let papers = papers.into_iter().map(Into::into).collect();
+        let (done, rx) = oneshot::channel();
+        self.tx
+            .send(IndexJob { papers, skip_existing, done })
+            .map_err(|_| anyhow!("Index worker has stopped"))?;
+        Ok(rx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embed::specter::{mock_embedding, EMBEDDING_DIMENSION};
+    use crate::index::hybrid::SearchMode;
+    use std::time::Duration;
+    use tempfile::TempDir;
+    use tokio::sync::watch;
+
+    fn paper(i: usize) -> PaperResult {
+        PaperResult {
+            id: format!("p{}", i),
+            title: format!("Holographic entanglement study number {}", i),
+            authors: vec!["Test Author".to_string()],
+            abstract_text: Some("Entanglement entropy in anti-de Sitter space.".to_string()),
+            year: Some(2024),
+            source: "test".to_string(),
+            doi: None,
+            arxiv_id: None,
+            url: "https://example.com".to_string(),
+            pdf_url: None,
+            citation_count: None,
+            orcids: Vec::new(),
+            subjects: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reads_not_blocked_by_pending_writes() {
+        let tmp = TempDir::new().unwrap();
+        let mut local = LocalIndex::create_or_open(tmp.path(), EMBEDDING_DIMENSION).await.unwrap();
+        let seed = paper(0);
+        local.index_paper(&seed, &mock_embedding(&seed.title, EMBEDDING_DIMENSION)).await.unwrap();
+
+        let reader = local.read_handle();
+        let index = Arc::new(Mutex::new(local));
+        let queue = IndexQueue::spawn(index.clone(), 8, true);
+
+        // Hold the write lock to simulate a long-running write, then enqueue a bulk job
+        let guard = index.lock().await;
+        let bulk: Vec<_> = (1..=100)
+            .map(|i| {
+                let p = paper(i);
+                let embedding = mock_embedding(&p.title, EMBEDDING_DIMENSION);
+                (p, embedding)
+            })
+            .collect();
+        let pending = tokio::spawn({
+            let queue = queue.clone();
+            async move { queue.index(bulk, false).await }
+        });
+
+        let query = reader.fulltext.parse("holographic").unwrap();
+        let results = tokio::time::timeout(
+            Duration::from_secs(5),
+            reader.search(SearchMode::KeywordOnly { query: &query }, 10),
+        )
+        .await
+        .expect("search blocked behind the write lock")
+        .unwrap();
+        assert_eq!(results[0].id, "p0");
+        assert!(!pending.is_finished());
+
+        drop(guard);
+        let outcome = pending.await.unwrap().unwrap();
+        assert_eq!(outcome.indexed.len(), 100);
+        assert!(outcome.failed.is_empty());
+        assert_eq!(index.lock().await.count().await.unwrap(), 101);
+
+        // Already-indexed papers are skipped when requested
+        let again = queue.index(vec![(seed.clone(), mock_embedding(&seed.title, EMBEDDING_DIMENSION))], true).await.unwrap();
+        assert_eq!(again.skipped, vec!["p0".to_string()]);
+    }
+
+    fn bulk(ids: std::ops::Range<usize>) -> Vec<(PaperResult, Vec<f32>)> {
+        ids.map(|i| {
+            let p = paper(i);
+            let embedding = mock_embedding(&p.title, EMBEDDING_DIMENSION);
+            (p, embedding)
+        })
+        .collect()
+    }
+
+    /// A queue that counts the batches that have started waiting for the lock.
+    fn counting_queue(index: Arc<Mutex<LocalIndex>>, batch_size: usize) -> (IndexQueue, watch::Receiver<usize>) {
+        let (tx, rx) = watch::channel(0);
+        let queue = IndexQueue::spawn_with(index, batch_size, true, move || tx.send_modify(|n| *n += 1));
+        (queue, rx)
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_bulk_keeps_committed_papers() {
+        let tmp = TempDir::new().unwrap();
+        let local = LocalIndex::create_or_open(tmp.path(), EMBEDDING_DIMENSION).await.unwrap();
+        let index = Arc::new(Mutex::new(local));
+        let (queue, mut batches) = counting_queue(index.clone(), 4);
+
+        let guard = index.lock().await;
+        let pending = queue.submit(bulk(0..40), false).unwrap();
+        // Once the worker waits on the lock, the mutex is fair, so it writes
+        // the first batch before this test gets the lock back
+        batches.wait_for(|&n| n >= 1).await.unwrap();
+        drop(guard);
+        let guard = index.lock().await;
+        assert_eq!(guard.count().await.unwrap(), 4);
+
+        // Cancel mid-request, while the worker waits for the lock again
+        batches.wait_for(|&n| n >= 2).await.unwrap();
+        drop(pending);
+        drop(guard);
+
+        // A later request still goes through; the cancelled one writes nothing more
+        let next = queue.index(bulk(100..101), false).await.unwrap();
+        assert_eq!(next.indexed, vec!["p100".to_string()]);
+        let idx = index.lock().await;
+        assert_eq!(idx.count().await.unwrap(), 5);
+        for i in 0..4 {
+            assert!(idx.get_paper(&format!("p{}", i)).await.unwrap().is_some());
+        }
+        assert!(idx.get_paper("p4").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_fair_queue_interleaves_short_requests() {
+        let tmp = TempDir::new().unwrap();
+        let local = LocalIndex::create_or_open(tmp.path(), EMBEDDING_DIMENSION).await.unwrap();
+        let index = Arc::new(Mutex::new(local));
+        let (queue, mut batches) = counting_queue(index.clone(), 2);
+
+        // Queue a long bulk job and a single paper behind it while the lock is held
+        let guard = index.lock().await;
+        let mut long = queue.submit(bulk(0..40), false).unwrap();
+        batches.wait_for(|&n| n >= 1).await.unwrap();
+        let short = queue.submit(bulk(100..101), false).unwrap();
+        drop(guard);
+
+        short.await.unwrap();
+        assert!(matches!(long.try_recv(), Err(oneshot::error::TryRecvError::Empty)));
+        assert_eq!(long.await.unwrap().indexed.len(), 40);
+    }
+}
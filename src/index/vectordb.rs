@@ -1,27 +1,72 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::Path;
 use std::sync::Arc;
 use anyhow::{Context, Result};
 use arrow_array::{
-    types::Float32Type, FixedSizeListArray, Int32Array, RecordBatch, RecordBatchIterator,
-    StringArray,
+    types::Float32Type, FixedSizeListArray, Int32Array, Int64Array, RecordBatch,
+    RecordBatchIterator, StringArray,
 };
 use arrow_array::Array;
 use arrow_schema::{DataType, Field, Schema};
 use futures::stream::StreamExt;
-use lancedb::query::{ExecutableQuery, QueryBase};
+use lancedb::index::vector::IvfPqIndexBuilder;
+use lancedb::index::Index;
+use lancedb::query::{ExecutableQuery, QueryBase, Select};
+use lancedb::table::NewColumnTransform;
+use lancedb::DistanceType;
 
 use crate::apis::PaperResult;
-use crate::embed::specter::EMBEDDING_DIMENSION;
+use crate::index::language;
 
 const TABLE_NAME: &str = "papers";
+/// `embedding_model` label for deterministic hash-based mock embeddings.
+pub const MOCK_EMBEDDING_MODEL: &str = "mock";
+/// PQ codebooks train 256 centroids, so smaller tables can't be indexed.
+pub const MIN_ROWS_FOR_VECTOR_INDEX: usize = 256;
 
 /// LanceDB-based vector store for papers with SPECTER2 embeddings.
+#[derive(Clone)]
 pub struct VectorStore {
     db: lancedb::Connection,
     schema: Arc<Schema>,
+    embedding_dim: usize,
+    distance_type: DistanceType,
+    /// Recorded with each paper added, to tell real embeddings from mock ones.
+    embedding_model: String,
+    /// Detect and store each added paper's language.
+    tag_languages: bool,
 }
 
-fn make_schema() -> Arc<Schema> {
+/// Text kept in a paper's row besides its metadata.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StoredText {
+    /// The source's JSON record, kept when raw storage is on.
+    pub raw_json: Option<String>,
+    /// Body text extracted from the paper's PDF, for keyword search.
+    pub body: Option<String>,
+}
+
+impl StoredText {
+    /// These fields, with any left unset taken from `stored`.
+    pub fn or(self, stored: StoredText) -> Self {
+        Self {
+            raw_json: self.raw_json.or(stored.raw_json),
+            body: self.body.or(stored.body),
+        }
+    }
+}
+
+/// Parse a distance metric name: "cosine", "l2" or "dot".
+pub fn parse_distance_type(name: &str) -> Option<DistanceType> {
+    match name.trim().to_lowercase().as_str() {
+        "cosine" => Some(DistanceType::Cosine),
+        "l2" | "euclidean" => Some(DistanceType::L2),
+        "dot" => Some(DistanceType::Dot),
+        _ => None,
+    }
+}
+
+fn make_schema(embedding_dim: usize) -> Arc<Schema> {
     Arc::new(Schema::new(vec![
         Field::new("id", DataType::Utf8, false),
         Field::new("title", DataType::Utf8, false),
@@ -38,16 +83,84 @@ fn make_schema() -> Arc<Schema> {
             "embedding",
             DataType::FixedSizeList(
                 Arc::new(Field::new("item", DataType::Float32, true)),
-                EMBEDDING_DIMENSION as i32,
+                embedding_dim as i32,
             ),
             true,
         ),
+        // Columns below were added after the initial schema; `migrate_table`
+        // back-fills them with nulls on older tables. Append new columns here.
+        Field::new("indexed_at", DataType::Int64, true),
+        Field::new("embedding_model", DataType::Utf8, true),
+        Field::new("raw_json", DataType::Utf8, true),
+        Field::new("language", DataType::Utf8, true),
+        Field::new("body", DataType::Utf8, true),
+        Field::new("subjects_json", DataType::Utf8, true),
     ]))
 }
 
+/// Length of the `embedding` vectors a table schema holds.
+fn schema_embedding_dim(schema: &Schema) -> Option<usize> {
+    match schema.field_with_name("embedding").ok()?.data_type() {
+        DataType::FixedSizeList(_, size) => Some(*size as usize),
+        _ => None,
+    }
+}
+
+/// Fail with an actionable message if an existing table was built for
+/// embeddings of a different size than configured.
+async fn check_embedding_dim(db: &lancedb::Connection, embedding_dim: usize) -> Result<()> {
+    let table = db
+        .open_table(TABLE_NAME)
+        .execute()
+        .await
+        .context("Failed to open papers table")?;
+    let existing = table.schema().await.context("Failed to read papers table schema")?;
+    match schema_embedding_dim(&existing) {
+        Some(stored) if stored != embedding_dim => anyhow::bail!(
+            "Embedding dimension mismatch: the vector store holds {}-dimensional embeddings but \
+             PAPER_SEARCH_EMBEDDING_DIM is {}. Set PAPER_SEARCH_EMBEDDING_DIM={} or use a fresh \
+             PAPER_SEARCH_DATA_DIR and re-index.",
+            stored,
+            embedding_dim,
+            stored
+        ),
+        _ => Ok(()),
+    }
+}
+
+/// Add any columns introduced since the table was created, filled with nulls.
+async fn migrate_table(db: &lancedb::Connection, schema: &Schema) -> Result<()> {
+    let table = db
+        .open_table(TABLE_NAME)
+        .execute()
+        .await
+        .context("Failed to open papers table")?;
+    let existing = table.schema().await.context("Failed to read papers table schema")?;
+    let missing: Vec<Field> = schema
+        .fields()
+        .iter()
+        .filter(|f| existing.field_with_name(f.name()).is_err())
+        .map(|f| f.as_ref().clone())
+        .collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    tracing::info!(
+        "Migrating papers table: adding columns {:?}",
+        missing.iter().map(|f| f.name().as_str()).collect::<Vec<_>>()
+    );
+    table
+        .add_columns(NewColumnTransform::AllNulls(Arc::new(Schema::new(missing))), None)
+        .await
+        .context("Failed to add new columns to papers table")?;
+    Ok(())
+}
+
 impl VectorStore {
-    /// Create or open a LanceDB database at the given path.
-    pub async fn create_or_open(path: &Path) -> Result<Self> {
+    /// Create or open a LanceDB database at the given path, storing
+    /// `embedding_dim`-long vectors. An existing table must match it.
+    pub async fn create_or_open(path: &Path, embedding_dim: usize) -> Result<Self> {
         std::fs::create_dir_all(path)
             .context("Failed to create LanceDB directory")?;
 
@@ -56,7 +169,7 @@ impl VectorStore {
             .await
             .context("Failed to connect to LanceDB")?;
 
-        let schema = make_schema();
+        let schema = make_schema(embedding_dim);
 
         // Create table if it doesn't exist
         let tables = db.table_names().execute().await
@@ -66,9 +179,151 @@ impl VectorStore {
                 .execute()
                 .await
                 .context("Failed to create papers table")?;
+        } else {
+            check_embedding_dim(&db, embedding_dim).await?;
+            migrate_table(&db, &schema).await?;
+        }
+
+        Ok(Self {
+            db,
+            schema,
+            embedding_dim,
+            distance_type: DistanceType::Cosine,
+            embedding_model: MOCK_EMBEDDING_MODEL.to_string(),
+            tag_languages: false,
+        })
+    }
+
+    /// Open an existing store for reading without creating or migrating
+    /// anything, e.g. another machine's data directory. Fails if the papers
+    /// table is missing, lacks one of the original columns, or holds
+    /// embeddings of a different size than `embedding_dim`.
+    pub async fn open_read_only(path: &Path, embedding_dim: usize) -> Result<Self> {
+        let db = lancedb::connect(path.to_str().context("LanceDB path is not valid UTF-8")?)
+            .execute()
+            .await
+            .context("Failed to connect to LanceDB")?;
+        let tables = db.table_names().execute().await
+            .context("Failed to list tables")?;
+        if !tables.contains(&TABLE_NAME.to_string()) {
+            anyhow::bail!("No papers table in {}", path.display());
         }
+        check_embedding_dim(&db, embedding_dim).await?;
+
+        let schema = make_schema(embedding_dim);
+        let table = db
+            .open_table(TABLE_NAME)
+            .execute()
+            .await
+            .context("Failed to open papers table")?;
+        let existing = table.schema().await.context("Failed to read papers table schema")?;
+        // Columns from `indexed_at` on may be missing from older tables
+        let mismatched: Vec<&str> = schema
+            .fields()
+            .iter()
+            .take_while(|f| f.name() != "indexed_at")
+            .filter(|f| {
+                existing
+                    .field_with_name(f.name())
+                    .map_or(true, |e| e.data_type() != f.data_type())
+            })
+            .map(|f| f.name().as_str())
+            .collect();
+        anyhow::ensure!(
+            mismatched.is_empty(),
+            "Papers table in {} is missing or has unexpected types for columns {:?}",
+            path.display(),
+            mismatched
+        );
+
+        Ok(Self {
+            db,
+            schema,
+            embedding_dim,
+            distance_type: DistanceType::Cosine,
+            embedding_model: MOCK_EMBEDDING_MODEL.to_string(),
+            tag_languages: false,
+        })
+    }
 
-        Ok(Self { db, schema })
+    /// Metric for nearest-neighbor search and the ANN index (default cosine,
+    /// which SPECTER2 embeddings are trained for). Vectors are stored as-is;
+    /// cosine normalizes at query time, so none need rewriting on a switch,
+    /// but an existing ANN index must be rebuilt for the new metric.
+    pub fn with_distance_type(mut self, distance_type: DistanceType) -> Self {
+        self.distance_type = distance_type;
+        self
+    }
+
+    /// Name of the configured distance metric.
+    pub fn distance_name(&self) -> &'static str {
+        match self.distance_type {
+            DistanceType::Cosine => "cosine",
+            DistanceType::L2 => "l2",
+            DistanceType::Dot => "dot",
+            _ => "other",
+        }
+    }
+
+    /// The configured distance metric.
+    pub fn distance_type(&self) -> DistanceType {
+        self.distance_type
+    }
+
+    /// Map a search distance to a 0–1 similarity, 1 being identical.
+    /// Cosine distance (1 − cos θ) and dot distance (1 − a·b, for unit
+    /// vectors) both span 0–2, so they scale linearly; L2 distances are
+    /// unbounded and map to 1 / (1 + d).
+    pub fn similarity(&self, distance: f32) -> f32 {
+        let similarity = match self.distance_type {
+            DistanceType::Cosine | DistanceType::Dot => 1.0 - distance / 2.0,
+            _ => 1.0 / (1.0 + distance.max(0.0)),
+        };
+        similarity.clamp(0.0, 1.0)
+    }
+
+    /// Label papers added from now on as embedded by `model` (default
+    /// `MOCK_EMBEDDING_MODEL`).
+    pub fn with_embedding_model(mut self, model: &str) -> Self {
+        self.embedding_model = model.to_string();
+        self
+    }
+
+    /// The model papers added from now on are labelled with.
+    pub fn embedding_model(&self) -> &str {
+        &self.embedding_model
+    }
+
+    /// Papers whose embeddings were recorded as made by a model other than
+    /// `model`. Papers indexed before models were recorded don't count.
+    pub async fn count_other_models(&self, model: &str) -> Result<usize> {
+        let table = self.table().await?;
+        table
+            .count_rows(Some(format!("embedding_model IS NOT NULL AND embedding_model <> {}", sql_string(model))))
+            .await
+            .context("Failed to count rows")
+    }
+
+    /// Store the language detected from each added paper's title and
+    /// abstract in the `language` column (null when undetectable).
+    pub fn with_language_tagging(mut self, enabled: bool) -> Self {
+        self.tag_languages = enabled;
+        self
+    }
+
+    /// Whether most stored embeddings are mock ones, which makes vector
+    /// scores noise. Papers indexed before models were recorded don't count.
+    pub async fn mostly_mock_embeddings(&self) -> Result<bool> {
+        let table = self.table().await?;
+        let total = table
+            .count_rows(Some("embedding_model IS NOT NULL".to_string()))
+            .await
+            .context("Failed to count rows")?;
+        let mock = table
+            .count_rows(Some(format!("embedding_model = {}", sql_string(MOCK_EMBEDDING_MODEL))))
+            .await
+            .context("Failed to count rows")?;
+        Ok(mock > 0 && mock * 2 > total)
     }
 
     /// Get a handle to the papers table.
@@ -80,13 +335,48 @@ impl VectorStore {
             .context("Failed to open papers table")
     }
 
-    /// Add a paper with its embedding to the vector store.
+    /// Add a paper with its embedding to the vector store. This always
+    /// appends a row; use `upsert` when the paper may already be stored.
+    #[cfg(test)]
     pub async fn add_paper(&self, paper: &PaperResult, embedding: &[f32]) -> Result<()> {
+        let batch = self.paper_batch(paper, embedding, &StoredText::default())?;
+        self.add_batch(batch).await
+    }
+
+    /// Add a paper with `text`, replacing any existing row with the same ID
+    /// in a single merge-insert commit, so readers never see the paper
+    /// missing and an invalid paper leaves the stored one untouched. The row
+    /// is written as given: text left unset is cleared. Returns whether the
+    /// row is new.
+    pub async fn upsert(&self, paper: &PaperResult, embedding: &[f32], text: &StoredText) -> Result<bool> {
+        let batch = self.paper_batch(paper, embedding, text)?;
         let table = self.table().await?;
+        let mut merge = table.merge_insert(&["id"]);
+        merge.when_matched_update_all(None).when_not_matched_insert_all();
+        let result = merge
+            .execute(Box::new(RecordBatchIterator::new(vec![Ok(batch)], self.schema.clone())))
+            .await
+            .context("Failed to upsert paper")?;
+        Ok(result.num_inserted_rows > 0)
+    }
 
+    fn paper_batch(&self, paper: &PaperResult, embedding: &[f32], text: &StoredText) -> Result<RecordBatch> {
         let authors_json = serde_json::to_string(&paper.authors).unwrap_or_default();
+        let subjects_json = (!paper.subjects.is_empty())
+            .then(|| serde_json::to_string(&paper.subjects).unwrap_or_default());
+        let language = self
+            .tag_languages
+            .then(|| language::detect(&paper.title, paper.abstract_text.as_deref()))
+            .flatten();
 
-        let batch = RecordBatch::try_new(
+        anyhow::ensure!(
+            embedding.len() == self.embedding_dim,
+            "Embedding for {} has {} values; the vector store expects {}",
+            paper.id,
+            embedding.len(),
+            self.embedding_dim
+        );
+        RecordBatch::try_new(
             self.schema.clone(),
             vec![
                 Arc::new(StringArray::from(vec![paper.id.as_str()])),
@@ -103,13 +393,23 @@ impl VectorStore {
                 Arc::new(
                     FixedSizeListArray::from_iter_primitive::<Float32Type, _, _>(
                         std::iter::once(Some(embedding.iter().map(|&v| Some(v)))),
-                        EMBEDDING_DIMENSION as i32,
+                        self.embedding_dim as i32,
                     ),
                 ),
+                Arc::new(Int64Array::from(vec![Some(chrono::Utc::now().timestamp())])),
+                Arc::new(StringArray::from(vec![Some(self.embedding_model.as_str())])),
+                Arc::new(StringArray::from(vec![text.raw_json.as_deref()])),
+                Arc::new(StringArray::from(vec![language])),
+                Arc::new(StringArray::from(vec![text.body.as_deref()])),
+                Arc::new(StringArray::from(vec![subjects_json.as_deref()])),
             ],
         )
-        .context("Failed to create RecordBatch")?;
+        .context("Failed to create RecordBatch")
+    }
 
+    #[cfg(test)]
+    async fn add_batch(&self, batch: RecordBatch) -> Result<()> {
+        let table = self.table().await?;
         let batches = RecordBatchIterator::new(vec![Ok(batch)], self.schema.clone());
         table
             .add(Box::new(batches))
@@ -121,18 +421,59 @@ impl VectorStore {
     }
 
     /// Search for similar papers by embedding vector. Returns (id, distance) pairs.
+    #[cfg(test)]
     pub async fn search_similar(
         &self,
         embedding: &[f32],
         limit: usize,
+    ) -> Result<Vec<(String, f32)>> {
+        self.search_similar_excluding(embedding, limit, &[]).await
+    }
+
+    /// Nearest-neighbor search that never returns the `exclude` IDs. They are
+    /// filtered before the top-`limit` cut, so results are backfilled.
+    pub async fn search_similar_excluding(
+        &self,
+        embedding: &[f32],
+        limit: usize,
+        exclude: &[String],
+    ) -> Result<Vec<(String, f32)>> {
+        self.search_similar_where(embedding, limit, exclude, None).await
+    }
+
+    /// Nearest-neighbor search restricted to rows matching `predicate`, also
+    /// applied before the top-`limit` cut.
+    pub async fn search_similar_where(
+        &self,
+        embedding: &[f32],
+        limit: usize,
+        exclude: &[String],
+        predicate: Option<&str>,
     ) -> Result<Vec<(String, f32)>> {
         let table = self.table().await?;
 
-        let mut results_stream = table
+        let mut query = table
             .query()
             .nearest_to(embedding)
             .context("Failed to set up vector search")?
-            .limit(limit)
+            .distance_type(self.distance_type)
+            .limit(limit);
+        let mut clauses = Vec::new();
+        if !exclude.is_empty() {
+            let ids = exclude
+                .iter()
+                .map(|id| sql_string(id))
+                .collect::<Vec<_>>()
+                .join(", ");
+            clauses.push(format!("id NOT IN ({})", ids));
+        }
+        if let Some(predicate) = predicate {
+            clauses.push(format!("({})", predicate));
+        }
+        if !clauses.is_empty() {
+            query = query.only_if(clauses.join(" AND "));
+        }
+        let mut results_stream = query
             .execute()
             .await
             .context("Failed to execute vector search")?;
@@ -150,6 +491,9 @@ impl VectorStore {
 
             for i in 0..batch.num_rows() {
                 let id = id_col.value(i).to_string();
+                if exclude.contains(&id) {
+                    continue;
+                }
                 let distance = dist_col.map(|d| d.value(i)).unwrap_or(0.0);
                 results.push((id, distance));
             }
@@ -161,7 +505,7 @@ impl VectorStore {
     pub async fn get_paper(&self, id: &str) -> Result<Option<PaperResult>> {
         let table = self.table().await?;
 
-        let filter = format!("id = '{}'", id.replace('\'', "''"));
+        let filter = format!("id = {}", sql_string(id));
         let mut results_stream = table
             .query()
             .only_if(filter)
@@ -181,14 +525,354 @@ impl VectorStore {
         }
     }
 
+    /// The text stored with a paper; empty when it isn't indexed.
+    pub async fn stored_text(&self, id: &str) -> Result<StoredText> {
+        let table = self.table().await?;
+        let mut results_stream = table
+            .query()
+            .only_if(format!("id = {}", sql_string(id)))
+            .select(Select::columns(&["raw_json", "body"]))
+            .limit(1)
+            .execute()
+            .await
+            .context("Failed to query by ID")?;
+
+        let Some(batch) = results_stream.next().await else {
+            return Ok(StoredText::default());
+        };
+        let batch = batch.context("Failed to read query result")?;
+        if batch.num_rows() == 0 {
+            return Ok(StoredText::default());
+        }
+        let text = |name: &str| -> Result<Option<String>> {
+            let col = batch
+                .column_by_name(name)
+                .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+                .with_context(|| format!("Missing {} column", name))?;
+            Ok((!col.is_null(0)).then(|| col.value(0).to_string()))
+        };
+        Ok(StoredText { raw_json: text("raw_json")?, body: text("body")? })
+    }
+
+    /// The stored body text of every paper that has one, by ID.
+    pub async fn all_bodies(&self) -> Result<HashMap<String, String>> {
+        let total = self.count().await?;
+        if total == 0 {
+            return Ok(HashMap::new());
+        }
+        let table = self.table().await?;
+        let mut results_stream = table
+            .query()
+            .only_if("body IS NOT NULL")
+            .select(Select::columns(&["id", "body"]))
+            .limit(total)
+            .execute()
+            .await
+            .context("Failed to scan body text")?;
+
+        let mut bodies = HashMap::new();
+        while let Some(batch) = results_stream.next().await {
+            let batch = batch.context("Failed to read query result")?;
+            let column = |name: &str| -> Result<StringArray> {
+                batch
+                    .column_by_name(name)
+                    .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+                    .cloned()
+                    .with_context(|| format!("Missing {} column", name))
+            };
+            let (ids, body) = (column("id")?, column("body")?);
+            for row in 0..batch.num_rows() {
+                bodies.insert(ids.value(row).to_string(), body.value(row).to_string());
+            }
+        }
+        Ok(bodies)
+    }
+
+    /// Get the stored embedding for a paper by its ID.
+    pub async fn get_embedding(&self, id: &str) -> Result<Option<Vec<f32>>> {
+        let table = self.table().await?;
+
+        let filter = format!("id = {}", sql_string(id));
+        let mut results_stream = table
+            .query()
+            .only_if(filter)
+            .limit(1)
+            .execute()
+            .await
+            .context("Failed to query by ID")?;
+
+        let Some(batch) = results_stream.next().await else {
+            return Ok(None);
+        };
+        let batch = batch.context("Failed to read query result")?;
+        if batch.num_rows() == 0 {
+            return Ok(None);
+        }
+        batch_row_embedding(&batch, 0)
+    }
+
     /// Delete a paper by ID.
     pub async fn delete(&self, id: &str) -> Result<()> {
         let table = self.table().await?;
-        let filter = format!("id = '{}'", id.replace('\'', "''"));
+        let filter = format!("id = {}", sql_string(id));
         table.delete(&filter).await.context("Failed to delete")?;
         Ok(())
     }
 
+    /// Return the IDs of all papers matching a SQL predicate.
+    pub async fn ids_matching(&self, predicate: &str) -> Result<Vec<String>> {
+        self.collect_ids(Some(predicate)).await
+    }
+
+    /// The subset of `ids` already stored.
+    pub async fn existing_ids(&self, ids: &[String]) -> Result<HashSet<String>> {
+        self.filter_ids(ids, "true").await
+    }
+
+    /// The subset of `ids` whose rows match `predicate`.
+    pub async fn filter_ids(&self, ids: &[String], predicate: &str) -> Result<HashSet<String>> {
+        if ids.is_empty() {
+            return Ok(HashSet::new());
+        }
+        let list = ids.iter().map(|id| sql_string(id)).collect::<Vec<_>>().join(", ");
+        let matching = self
+            .collect_ids(Some(&format!("id IN ({}) AND ({})", list, predicate)))
+            .await?;
+        Ok(matching.into_iter().collect())
+    }
+
+    /// IDs of papers matching `predicate` (all papers if `None`).
+    async fn collect_ids(&self, predicate: Option<&str>) -> Result<Vec<String>> {
+        let table = self.table().await?;
+        let total = table
+            .count_rows(predicate.map(str::to_string))
+            .await
+            .context("Failed to count matching rows")?;
+        if total == 0 {
+            return Ok(Vec::new());
+        }
+
+        let mut query = table.query().select(Select::columns(&["id"])).limit(total);
+        if let Some(predicate) = predicate {
+            query = query.only_if(predicate);
+        }
+        let mut results_stream = query
+            .execute()
+            .await
+            .context("Failed to query by predicate")?;
+
+        let mut ids = Vec::with_capacity(total);
+        while let Some(batch) = results_stream.next().await {
+            let batch = batch.context("Failed to read query result")?;
+            let id_col = batch
+                .column_by_name("id")
+                .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+                .context("Missing id column")?;
+            ids.extend((0..batch.num_rows()).map(|i| id_col.value(i).to_string()));
+        }
+        Ok(ids)
+    }
+
+    /// Delete all papers matching a SQL predicate.
+    pub async fn delete_where(&self, predicate: &str) -> Result<()> {
+        let table = self.table().await?;
+        table.delete(predicate).await.context("Failed to delete")?;
+        Ok(())
+    }
+
+    /// Number of papers per stored language code; untagged papers count
+    /// under `None`.
+    pub async fn language_counts(&self) -> Result<BTreeMap<Option<String>, usize>> {
+        let table = self.table().await?;
+        let total = self.count().await?;
+        let mut counts = BTreeMap::new();
+        if total == 0 {
+            return Ok(counts);
+        }
+        let mut results_stream = table
+            .query()
+            .select(Select::columns(&["language"]))
+            .limit(total)
+            .execute()
+            .await
+            .context("Failed to scan languages")?;
+        while let Some(batch) = results_stream.next().await {
+            let batch = batch.context("Failed to read query result")?;
+            let col = batch
+                .column_by_name("language")
+                .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+                .context("Missing language column")?;
+            for row in 0..batch.num_rows() {
+                let language = (!col.is_null(row)).then(|| col.value(row).to_string());
+                *counts.entry(language).or_insert(0) += 1;
+            }
+        }
+        Ok(counts)
+    }
+
+    /// Build (or rebuild) an IVF-PQ ANN index on `embedding`.
+    ///
+    /// Without it every vector search scans all rows, which is exact but grows
+    /// linearly with the corpus. With it, searches only probe the partitions
+    /// nearest the query and compare compressed (product-quantized) vectors:
+    /// much faster on large corpora, at the cost of approximate recall. More
+    /// partitions make each probe cheaper but miss more true neighbours;
+    /// `None` lets LanceDB choose (about the square root of the row count).
+    /// Rows added after the build are still found by a flat scan until the
+    /// index is rebuilt. Training needs at least `MIN_ROWS_FOR_VECTOR_INDEX` rows.
+    pub async fn create_vector_index(&self, num_partitions: Option<u32>) -> Result<()> {
+        let rows = self.count().await?;
+        if rows < MIN_ROWS_FOR_VECTOR_INDEX {
+            anyhow::bail!(
+                "Need at least {} papers to build a vector index (have {})",
+                MIN_ROWS_FOR_VECTOR_INDEX,
+                rows
+            );
+        }
+        let table = self.table().await?;
+        let mut builder = IvfPqIndexBuilder::default().distance_type(self.distance_type);
+        if let Some(n) = num_partitions {
+            builder = builder.num_partitions(n);
+        }
+        table
+            .create_index(&["embedding"], Index::IvfPq(builder))
+            .replace(true)
+            .execute()
+            .await
+            .context("Failed to build vector index")?;
+        Ok(())
+    }
+
+    /// Whether an ANN index exists on `embedding`.
+    #[cfg(test)]
+    pub async fn has_vector_index(&self) -> Result<bool> {
+        let table = self.table().await?;
+        let indices = table.list_indices().await.context("Failed to list indices")?;
+        Ok(indices.iter().any(|i| i.columns.iter().any(|c| c == "embedding")))
+    }
+
+    /// The distance metric the ANN index on `embedding` was built for, or
+    /// `None` when there's no index.
+    pub async fn vector_index_distance(&self) -> Result<Option<DistanceType>> {
+        let table = self.table().await?;
+        let indices = table.list_indices().await.context("Failed to list indices")?;
+        let Some(index) = indices.iter().find(|i| i.columns.iter().any(|c| c == "embedding")) else {
+            return Ok(None);
+        };
+        let stats = table.index_stats(&index.name).await.context("Failed to read vector index stats")?;
+        Ok(stats.and_then(|s| s.distance_type))
+    }
+
+    /// Drop the papers table and recreate it empty. Returns how many papers
+    /// it held.
+    pub async fn clear(&self) -> Result<usize> {
+        let removed = self.count().await?;
+        self.db
+            .drop_table(TABLE_NAME, &[])
+            .await
+            .context("Failed to drop papers table")?;
+        self.db
+            .create_empty_table(TABLE_NAME, self.schema.clone())
+            .execute()
+            .await
+            .context("Failed to recreate papers table")?;
+        Ok(removed)
+    }
+
+    /// A page of papers in ID order: `limit` papers after skipping `offset`.
+    /// LanceDB scans have no stable order, so this sorts the ID column and
+    /// fetches just the page's rows.
+    pub async fn list_papers(&self, offset: usize, limit: usize) -> Result<Vec<PaperResult>> {
+        let ids = self.sorted_ids().await?;
+        let page: Vec<String> = ids.into_iter().skip(offset).take(limit).collect();
+        self.papers_by_ids(&page).await
+    }
+
+    /// Every paper ID in the store, sorted.
+    pub async fn sorted_ids(&self) -> Result<Vec<String>> {
+        let mut ids = self.collect_ids(None).await?;
+        ids.sort();
+        Ok(ids)
+    }
+
+    /// The papers with the given IDs, sorted by ID; IDs not in the store
+    /// are left out.
+    pub async fn papers_by_ids(&self, ids: &[String]) -> Result<Vec<PaperResult>> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let list = ids.iter().map(|id| sql_string(id)).collect::<Vec<_>>().join(", ");
+        let table = self.table().await?;
+        let mut results_stream = table
+            .query()
+            .only_if(format!("id IN ({})", list))
+            .limit(ids.len())
+            .execute()
+            .await
+            .context("Failed to query page")?;
+
+        let mut papers = Vec::with_capacity(ids.len());
+        while let Some(batch) = results_stream.next().await {
+            let batch = batch.context("Failed to read query result")?;
+            for row in 0..batch.num_rows() {
+                papers.push(batch_row_to_paper(&batch, row)?);
+            }
+        }
+        papers.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(papers)
+    }
+
+    /// Read every paper in the store (without embeddings).
+    pub async fn all_papers(&self) -> Result<Vec<PaperResult>> {
+        let total = self.count().await?;
+        if total == 0 {
+            return Ok(Vec::new());
+        }
+        let table = self.table().await?;
+        let mut results_stream = table
+            .query()
+            .limit(total)
+            .execute()
+            .await
+            .context("Failed to scan papers table")?;
+
+        let mut papers = Vec::with_capacity(total);
+        while let Some(batch) = results_stream.next().await {
+            let batch = batch.context("Failed to read query result")?;
+            for row in 0..batch.num_rows() {
+                papers.push(batch_row_to_paper(&batch, row)?);
+            }
+        }
+        Ok(papers)
+    }
+
+    /// Read every paper in the store with its stored embedding. Papers
+    /// without one are skipped.
+    pub async fn all_papers_with_embeddings(&self) -> Result<Vec<(PaperResult, Vec<f32>)>> {
+        let total = self.count().await?;
+        if total == 0 {
+            return Ok(Vec::new());
+        }
+        let table = self.table().await?;
+        let mut results_stream = table
+            .query()
+            .limit(total)
+            .execute()
+            .await
+            .context("Failed to scan papers table")?;
+
+        let mut papers = Vec::with_capacity(total);
+        while let Some(batch) = results_stream.next().await {
+            let batch = batch.context("Failed to read query result")?;
+            for row in 0..batch.num_rows() {
+                if let Some(embedding) = batch_row_embedding(&batch, row)? {
+                    papers.push((batch_row_to_paper(&batch, row)?, embedding));
+                }
+            }
+        }
+        Ok(papers)
+    }
+
     /// Get the total number of papers in the store.
     pub async fn count(&self) -> Result<usize> {
         let table = self.table().await?;
@@ -199,6 +883,23 @@ impl VectorStore {
     }
 }
 
+/// The embedding stored in a RecordBatch row, if any.
+fn batch_row_embedding(batch: &RecordBatch, row: usize) -> Result<Option<Vec<f32>>> {
+    let col = batch
+        .column_by_name("embedding")
+        .and_then(|c| c.as_any().downcast_ref::<FixedSizeListArray>())
+        .context("Missing embedding column")?;
+    if col.is_null(row) {
+        return Ok(None);
+    }
+    let values = col.value(row);
+    let floats = values
+        .as_any()
+        .downcast_ref::<arrow_array::Float32Array>()
+        .context("Embedding column is not Float32")?;
+    Ok(Some(floats.values().to_vec()))
+}
+
 /// Extract a PaperResult from a RecordBatch at the given row index.
 fn batch_row_to_paper(batch: &RecordBatch, row: usize) -> Result<PaperResult> {
     let get_str = |name: &str| -> Option<String> {
@@ -225,6 +926,9 @@ fn batch_row_to_paper(batch: &RecordBatch, row: usize) -> Result<PaperResult> {
     let authors: Vec<String> = get_str("authors_json")
         .and_then(|s| serde_json::from_str(&s).ok())
         .unwrap_or_default();
+    let subjects: Vec<String> = get_str("subjects_json")
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default();
 
     Ok(PaperResult {
         id: get_str("id").unwrap_or_default(),
@@ -238,13 +942,22 @@ fn batch_row_to_paper(batch: &RecordBatch, row: usize) -> Result<PaperResult> {
         url: get_str("url").unwrap_or_default(),
         pdf_url: get_str("pdf_url"),
         citation_count: get_i32("citation_count").map(|c| c as u32),
+        orcids: Vec::new(),
+        subjects,
     })
 }
 
+/// Quote `value` as a SQL string literal for LanceDB filters. LanceDB's
+/// filter dialect treats backslashes literally, so doubling single quotes is
+/// the only escaping needed; parentheses, `%` and `\` are inert inside quotes.
+pub(crate) fn sql_string(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::embed::specter::mock_embedding;
+    use crate::embed::specter::{mock_embedding, EMBEDDING_DIMENSION};
     use tempfile::TempDir;
 
     fn sample_paper(id: &str, title: &str) -> PaperResult {
@@ -260,20 +973,22 @@ mod tests {
             url: "https://example.com".to_string(),
             pdf_url: None,
             citation_count: Some(10),
+            orcids: Vec::new(),
+            subjects: Vec::new(),
         }
     }
 
     #[tokio::test]
     async fn test_vectordb_roundtrip() {
         let tmp = TempDir::new().unwrap();
-        let store = VectorStore::create_or_open(tmp.path()).await.unwrap();
+        let store = VectorStore::create_or_open(tmp.path(), EMBEDDING_DIMENSION).await.unwrap();
 
         let paper1 = sample_paper("test:001", "Holographic Entanglement in AdS/CFT");
-        let emb1 = mock_embedding(&paper1.title);
+        let emb1 = mock_embedding(&paper1.title, EMBEDDING_DIMENSION);
         store.add_paper(&paper1, &emb1).await.unwrap();
 
         let paper2 = sample_paper("test:002", "Quantum Error Correction Codes");
-        let emb2 = mock_embedding(&paper2.title);
+        let emb2 = mock_embedding(&paper2.title, EMBEDDING_DIMENSION);
         store.add_paper(&paper2, &emb2).await.unwrap();
 
         assert_eq!(store.count().await.unwrap(), 2);
@@ -290,9 +1005,271 @@ mod tests {
         assert_eq!(got.title, "Holographic Entanglement in AdS/CFT");
         assert_eq!(got.year, Some(2024));
 
+        // Stored embedding round-trips
+        let stored = store.get_embedding("test:001").await.unwrap().unwrap();
+        assert_eq!(stored, emb1);
+        assert!(store.get_embedding("test:missing").await.unwrap().is_none());
+
         // Delete
         store.delete("test:001").await.unwrap();
         assert_eq!(store.count().await.unwrap(), 1);
         assert!(store.get_paper("test:001").await.unwrap().is_none());
     }
+
+    #[tokio::test]
+    async fn test_list_papers_pages_in_id_order() {
+        let tmp = TempDir::new().unwrap();
+        let store = VectorStore::create_or_open(tmp.path(), EMBEDDING_DIMENSION).await.unwrap();
+        for id in ["p3", "p1", "p5", "p2", "p4"] {
+            let paper = sample_paper(id, &format!("Paper {}", id));
+            store.add_paper(&paper, &mock_embedding(&paper.title, EMBEDDING_DIMENSION)).await.unwrap();
+        }
+
+        let ids = |papers: Vec<PaperResult>| papers.into_iter().map(|p| p.id).collect::<Vec<_>>();
+        assert_eq!(ids(store.list_papers(0, 2).await.unwrap()), vec!["p1", "p2"]);
+        assert_eq!(ids(store.list_papers(2, 2).await.unwrap()), vec!["p3", "p4"]);
+        assert_eq!(ids(store.list_papers(4, 2).await.unwrap()), vec!["p5"]);
+        assert!(store.list_papers(10, 2).await.unwrap().is_empty());
+        assert_eq!(store.list_papers(0, 1).await.unwrap()[0].title, "Paper p1");
+    }
+
+    #[tokio::test]
+    async fn test_search_similar_excluding() {
+        let tmp = TempDir::new().unwrap();
+        let store = VectorStore::create_or_open(tmp.path(), EMBEDDING_DIMENSION).await.unwrap();
+        let titles = [
+            "Holographic Entanglement in AdS/CFT",
+            "Quantum Error Correction Codes",
+            "Black Hole Information Paradox",
+            "Surface Codes for Fault Tolerance",
+            "Island Formula and Page Curves",
+        ];
+        for (i, title) in titles.iter().enumerate() {
+            let paper = sample_paper(&format!("test:{:03}", i), title);
+            store.add_paper(&paper, &mock_embedding(title, EMBEDDING_DIMENSION)).await.unwrap();
+        }
+
+        let query = mock_embedding(titles[0], EMBEDDING_DIMENSION);
+        let unfiltered = store.search_similar(&query, 2).await.unwrap();
+        assert_eq!(unfiltered[0].0, "test:000");
+
+        // The excluded top neighbors are replaced by the next-nearest papers
+        let exclude = vec![unfiltered[0].0.clone(), unfiltered[1].0.clone(), "it's:missing".to_string()];
+        let results = store.search_similar_excluding(&query, 3, &exclude).await.unwrap();
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|(id, _)| !exclude.contains(id)));
+    }
+
+    #[test]
+    fn test_sql_string_escaping() {
+        assert_eq!(sql_string("doi:10.1000/abc"), "'doi:10.1000/abc'");
+        assert_eq!(sql_string("doi:10.1002/(SICI)1097-0258'98"), "'doi:10.1002/(SICI)1097-0258''98'");
+        assert_eq!(sql_string(r"doi:10.1000/a\b"), r"'doi:10.1000/a\b'");
+        assert_eq!(sql_string("' OR '1'='1"), "''' OR ''1''=''1'");
+    }
+
+    #[tokio::test]
+    async fn test_awkward_doi_ids_roundtrip() {
+        let tmp = TempDir::new().unwrap();
+        let store = VectorStore::create_or_open(tmp.path(), EMBEDDING_DIMENSION).await.unwrap();
+
+        let ids = [
+            "doi:10.1002/(SICI)1097-0258(19980815/30)17:15/16<1661::AID-SIM968>3.0.CO;2-2",
+            "doi:10.1000/o'brien.2020",
+            r"doi:10.1000/back\slash\",
+            "doi:10.1000/x' OR '1'='1",
+        ];
+        for id in ids {
+            store.add_paper(&sample_paper(id, id), &mock_embedding(id, EMBEDDING_DIMENSION)).await.unwrap();
+        }
+        let plain = sample_paper("test:plain", "Plain");
+        store.add_paper(&plain, &mock_embedding("Plain", EMBEDDING_DIMENSION)).await.unwrap();
+
+        for id in ids {
+            let paper = store.get_paper(id).await.unwrap().unwrap();
+            assert_eq!(paper.id, id);
+        }
+
+        let exclude: Vec<String> = ids.iter().map(|s| s.to_string()).collect();
+        let results = store
+            .search_similar_excluding(&mock_embedding("Plain", EMBEDDING_DIMENSION), 10, &exclude)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0, "test:plain");
+
+        // An injection-shaped ID deletes only itself
+        store.delete(ids[3]).await.unwrap();
+        assert_eq!(store.count().await.unwrap(), ids.len());
+        assert!(store.get_paper(ids[3]).await.unwrap().is_none());
+        for id in &ids[..3] {
+            store.delete(id).await.unwrap();
+        }
+        assert_eq!(store.count().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_upsert_replaces_existing_row() {
+        let tmp = TempDir::new().unwrap();
+        let store = VectorStore::create_or_open(tmp.path(), EMBEDDING_DIMENSION).await.unwrap();
+        let paper = sample_paper("test:1", "Original Title");
+        assert!(store.upsert(&paper, &mock_embedding(&paper.title, EMBEDDING_DIMENSION), &StoredText::default()).await.unwrap());
+        let renamed = PaperResult { title: "Renamed".to_string(), ..paper.clone() };
+        assert!(!store.upsert(&renamed, &mock_embedding(&renamed.title, EMBEDDING_DIMENSION), &StoredText::default()).await.unwrap());
+        assert_eq!(store.count().await.unwrap(), 1);
+        assert_eq!(store.get_paper("test:1").await.unwrap().unwrap().title, "Renamed");
+
+        // A bad embedding is rejected before the stored row is touched
+        assert!(store.upsert(&paper, &[0.0; 4], &StoredText::default()).await.is_err());
+        assert_eq!(store.get_paper("test:1").await.unwrap().unwrap().title, "Renamed");
+    }
+
+    #[tokio::test]
+    async fn test_stored_text_kept_with_row() {
+        let tmp = TempDir::new().unwrap();
+        let store = VectorStore::create_or_open(tmp.path(), EMBEDDING_DIMENSION).await.unwrap();
+        let paper = sample_paper("test:1", "Raw Record");
+        let embedding = mock_embedding(&paper.title, EMBEDDING_DIMENSION);
+        store.upsert(&paper, &embedding, &StoredText::default()).await.unwrap();
+        assert_eq!(store.stored_text("test:1").await.unwrap(), StoredText::default());
+
+        let raw = r#"{"title":"It's \"raw\"","ids":[1,2]}"#;
+        let text = StoredText { raw_json: Some(raw.to_string()), body: Some("Full body text.".to_string()) };
+        store.upsert(&paper, &embedding, &text).await.unwrap();
+        assert_eq!(store.stored_text("test:1").await.unwrap(), text);
+        assert_eq!(store.get_paper("test:1").await.unwrap().unwrap().title, "Raw Record");
+        assert_eq!(store.stored_text("missing").await.unwrap(), StoredText::default());
+
+        let other = sample_paper("test:2", "No Body");
+        store.upsert(&other, &mock_embedding(&other.title, EMBEDDING_DIMENSION), &StoredText::default()).await.unwrap();
+        let bodies = store.all_bodies().await.unwrap();
+        assert_eq!(bodies.len(), 1);
+        assert_eq!(bodies["test:1"], "Full body text.");
+    }
+
+    #[tokio::test]
+    async fn test_create_vector_index() {
+        let tmp = TempDir::new().unwrap();
+        let store = VectorStore::create_or_open(tmp.path(), EMBEDDING_DIMENSION).await.unwrap();
+        let paper = sample_paper("test:0", "Paper 0");
+        store.add_paper(&paper, &mock_embedding(&paper.title, EMBEDDING_DIMENSION)).await.unwrap();
+        assert!(store.create_vector_index(None).await.is_err());
+
+        for i in 1..MIN_ROWS_FOR_VECTOR_INDEX {
+            let paper = sample_paper(&format!("test:{}", i), &format!("Paper {}", i));
+            store.add_paper(&paper, &mock_embedding(&paper.title, EMBEDDING_DIMENSION)).await.unwrap();
+        }
+        assert!(!store.has_vector_index().await.unwrap());
+        store.create_vector_index(Some(2)).await.unwrap();
+        assert!(store.has_vector_index().await.unwrap());
+
+        let results = store.search_similar(&mock_embedding("Paper 7", EMBEDDING_DIMENSION), 5).await.unwrap();
+        assert_eq!(results.len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_configured_dimension_and_mismatch() {
+        let tmp = TempDir::new().unwrap();
+        {
+            let store = VectorStore::create_or_open(tmp.path(), 384).await.unwrap();
+            let paper = sample_paper("test:1", "Small Model Paper");
+            store.add_paper(&paper, &mock_embedding(&paper.title, 384)).await.unwrap();
+            assert!(store.add_paper(&paper, &mock_embedding(&paper.title, EMBEDDING_DIMENSION)).await.is_err());
+            let results = store.search_similar(&mock_embedding(&paper.title, 384), 1).await.unwrap();
+            assert_eq!(results[0].0, "test:1");
+        }
+
+        let err = VectorStore::create_or_open(tmp.path(), EMBEDDING_DIMENSION).await.err().unwrap();
+        let message = err.to_string();
+        assert!(message.contains("384-dimensional"), "{}", message);
+        assert!(message.contains("PAPER_SEARCH_EMBEDDING_DIM is 768"), "{}", message);
+        assert!(VectorStore::create_or_open(tmp.path(), 384).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_subjects_round_trip() {
+        let tmp = TempDir::new().unwrap();
+        let store = VectorStore::create_or_open(tmp.path(), EMBEDDING_DIMENSION).await.unwrap();
+        let paper = PaperResult {
+            subjects: vec!["Humans".to_string(), "Neoplasms / genetics".to_string()],
+            ..sample_paper("pmid:1", "MeSH Tagged")
+        };
+        let embedding = mock_embedding(&paper.title, EMBEDDING_DIMENSION);
+        store.upsert(&paper, &embedding, &StoredText::default()).await.unwrap();
+        store.add_paper(&sample_paper("test:2", "Untagged"), &embedding).await.unwrap();
+
+        assert_eq!(store.get_paper("pmid:1").await.unwrap().unwrap().subjects, paper.subjects);
+        assert!(store.get_paper("test:2").await.unwrap().unwrap().subjects.is_empty());
+        let listed = store.papers_by_ids(&["pmid:1".to_string()]).await.unwrap();
+        assert_eq!(listed[0].subjects, paper.subjects);
+    }
+
+    #[tokio::test]
+    async fn test_open_read_only_validates_without_migrating() {
+        let columns = |fields: &[&str]| {
+            let full = make_schema(EMBEDDING_DIMENSION);
+            Arc::new(Schema::new(
+                fields.iter().map(|name| full.field_with_name(name).unwrap().clone()).collect::<Vec<_>>(),
+            ))
+        };
+        let base = ["id", "title", "abstract_text", "authors_json", "year", "source", "doi", "arxiv_id", "url", "pdf_url", "citation_count", "embedding"];
+
+        // An older table lacking the later columns opens as it is
+        let old = TempDir::new().unwrap();
+        let db = lancedb::connect(old.path().to_str().unwrap()).execute().await.unwrap();
+        db.create_empty_table(TABLE_NAME, columns(&base)).execute().await.unwrap();
+        let store = VectorStore::open_read_only(old.path(), EMBEDDING_DIMENSION).await.unwrap();
+        assert_eq!(store.count().await.unwrap(), 0);
+        let table = db.open_table(TABLE_NAME).execute().await.unwrap();
+        assert!(table.schema().await.unwrap().field_with_name("body").is_err());
+        assert!(VectorStore::open_read_only(old.path(), 384).await.is_err());
+
+        let broken = TempDir::new().unwrap();
+        let db = lancedb::connect(broken.path().to_str().unwrap()).execute().await.unwrap();
+        db.create_empty_table(TABLE_NAME, columns(&base[..1])).execute().await.unwrap();
+        let err = VectorStore::open_read_only(broken.path(), EMBEDDING_DIMENSION).await.err().unwrap();
+        assert!(err.to_string().contains("title"), "{}", err);
+
+        let empty = TempDir::new().unwrap();
+        assert!(VectorStore::open_read_only(empty.path(), EMBEDDING_DIMENSION).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_mock_embeddings_detected_by_model_label() {
+        let tmp = TempDir::new().unwrap();
+        let mock = VectorStore::create_or_open(tmp.path(), EMBEDDING_DIMENSION).await.unwrap();
+        assert!(!mock.mostly_mock_embeddings().await.unwrap());
+        let paper = sample_paper("test:1", "Mock Embedded");
+        mock.add_paper(&paper, &mock_embedding(&paper.title, EMBEDDING_DIMENSION)).await.unwrap();
+        assert!(mock.mostly_mock_embeddings().await.unwrap());
+
+        let real = mock.clone().with_embedding_model("specter2");
+        for id in ["test:2", "test:3"] {
+            let paper = sample_paper(id, id);
+            real.add_paper(&paper, &mock_embedding(id, EMBEDDING_DIMENSION)).await.unwrap();
+        }
+        assert!(!real.mostly_mock_embeddings().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_cosine_self_match_ranks_first() {
+        let tmp = TempDir::new().unwrap();
+        let store = VectorStore::create_or_open(tmp.path(), 4).await.unwrap();
+        // Under L2, the short "near" vector sits closest to the scaled query;
+        // under cosine only direction counts, so the self-match wins.
+        store.add_paper(&sample_paper("self", "Self"), &[10.0, 0.0, 0.0, 0.0]).await.unwrap();
+        store.add_paper(&sample_paper("near", "Near"), &[0.7, 0.7, 0.0, 0.0]).await.unwrap();
+        let query = [1.0, 0.0, 0.0, 0.0];
+
+        let cosine = store.search_similar(&query, 2).await.unwrap();
+        assert_eq!(cosine[0].0, "self");
+        assert!(cosine[0].1.abs() < 1e-5);
+
+        let l2 = store.clone().with_distance_type(DistanceType::L2);
+        assert_eq!(l2.search_similar(&query, 2).await.unwrap()[0].0, "near");
+
+        assert_eq!(store.similarity(0.0), 1.0);
+        assert_eq!(store.similarity(2.0), 0.0);
+        assert_eq!(l2.similarity(1.0), 0.5);
+    }
 }
@@ -1,24 +1,34 @@
 use std::sync::Arc;
 use rmcp::{
-    handler::server::tool::ToolRouter, handler::server::wrapper::Parameters,
+    handler::server::tool::{ToolCallContext, ToolRoute, ToolRouter}, handler::server::wrapper::Parameters,
     model::*, tool, tool_handler, tool_router,
     transport::stdio, ErrorData as McpError, ServerHandler, ServiceExt,
 };
 use schemars::JsonSchema;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use tokio::sync::Mutex;
 use tracing_subscriber::EnvFilter;
 
 mod apis;
+mod cache;
 mod config;
 mod embed;
+mod enrich;
+mod export;
 mod index;
+#[cfg(test)]
+mod mock_http;
+#[cfg(test)]
+mod mock_source;
+mod pdf;
+mod response;
 mod search;
 
 use apis::PaperSource;
 use config::Config;
 use embed::specter;
 use index::LocalIndex;
+use response::{Diagnostics, SourceWarning};
 
 // ── Parameter structs ───────────────────────────────────────────────────────
 
@@ -28,8 +38,24 @@ struct SearchPapersParams {
     query: String,
     #[schemars(description = "Filter to specific sources (e.g. [\"arxiv\", \"inspire\"])")]
     sources: Option<Vec<String>>,
-    #[schemars(description = "Maximum results to return (default 10, max 100)")]
+    #[schemars(description = "Maximum results to return (default 10, max 100; both configurable)")]
     max_results: Option<u32>,
+    #[schemars(description = "Number of results to skip per source, for paging (default 0, max 1000). Sources whose API supports it page server-side; the rest fetch from the start and skip client-side.")]
+    offset: Option<u32>,
+    #[schemars(description = "Annotate results with CrossRef retraction status (uses the enrichment budget)")]
+    check_retractions: Option<bool>,
+    #[schemars(description = "Restrict to a language (ISO 639-1 code, e.g. \"en\"). Honored by doaj and europepmc; other sources are searched unfiltered and listed in warnings.")]
+    language: Option<String>,
+    #[schemars(description = "Match only in one field: \"title\", \"author\" or \"abstract\" (default: all fields). Honored by arxiv; other sources search all fields and are listed in warnings.")]
+    field: Option<String>,
+    #[schemars(description = "If fewer results than this are found, retry once with a broadened query (quotes and operators dropped, terms ORed) and merge the results; the broadened query is reported in diagnostics")]
+    min_results: Option<u32>,
+    #[schemars(description = "Shrink the response by cutting author lists after the first few results (PAPER_SEARCH_FULL_AUTHOR_RESULTS, default 5) to \"First Author et al.\" plus an author_count")]
+    summarize_authors: Option<bool>,
+    #[schemars(description = "Restrict to subject categories (e.g. [\"hep-th\", \"cs.LG\"]); a paper in any of them matches. Honored by arxiv; other sources ignore the filter and are listed in warnings.")]
+    categories: Option<Vec<String>>,
+    #[schemars(description = "Result order: \"citations\" (default: most cited first, then newest), \"year\" (newest first, then most cited) or \"relevance\" (the sources' own orders interleaved round-robin: each source's top hit, then each source's second, and so on). The order used is reported in diagnostics as rank_by.")]
+    rank_by: Option<String>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -38,6 +64,16 @@ struct GetPaperParams {
     id: String,
     #[schemars(description = "Force a specific source to query")]
     source: Option<String>,
+    #[schemars(description = "Include which source answered, whether it came from the local index, a match confidence for bare IDs, and each source tried with its outcome (also reported when the paper is not found)")]
+    verbose: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct GetPapersParams {
+    #[schemars(description = "Paper IDs with prefix (arxiv:ID, doi:ID, s2:ID, etc.), at most 500")]
+    ids: Vec<String>,
+    #[schemars(description = "Force a specific source to query for IDs not in the local index")]
+    source: Option<String>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -46,6 +82,84 @@ struct RelationParams {
     id: String,
     #[schemars(description = "Specific source to query")]
     source: Option<String>,
+    #[schemars(description = "Maximum results to return (default 25, max 500). semantic_scholar pages through large sets; other sources return a single page.")]
+    max_results: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct GetCitationsParams {
+    #[schemars(description = "Paper ID to look up citations for")]
+    id: String,
+    #[schemars(description = "Specific source to query")]
+    source: Option<String>,
+    #[schemars(description = "Maximum results to return (default 25, max 500). semantic_scholar pages through large sets; other sources return a single page.")]
+    max_results: Option<u32>,
+    #[schemars(description = "Leave out citing papers that share an author with the cited paper (matched by ORCID when both records have one, else by name: surname plus first name or initial). Applied after max_results, so fewer results may be returned")]
+    exclude_self_citations: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct CouplingParams {
+    #[schemars(description = "First paper ID")]
+    id_a: String,
+    #[schemars(description = "Second paper ID")]
+    id_b: String,
+    #[schemars(description = "Specific source to fetch references from")]
+    source: Option<String>,
+    #[schemars(description = "Maximum references fetched per paper (default 200, max 500)")]
+    max_references: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct CitationGraphParams {
+    #[schemars(description = "Seed paper ID")]
+    id: String,
+    #[schemars(description = "Hops to expand from the seed (1-3, default 1)")]
+    depth: Option<u32>,
+    #[schemars(description = "Edges to follow: 'citations', 'references', or 'both' (default)")]
+    direction: Option<String>,
+    #[schemars(description = "Specific source to query")]
+    source: Option<String>,
+    #[schemars(description = "Citations/references fetched per paper and direction (default 10, max 100; both configurable)")]
+    max_per_node: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct CanonicalizeParams {
+    #[schemars(description = "Paper IDs to group (alternative to query)")]
+    ids: Option<Vec<String>>,
+    #[schemars(description = "Search query whose raw, undeduplicated results are grouped (alternative to ids)")]
+    query: Option<String>,
+    #[schemars(description = "Results requested from the federated search when using query (default 10 unless configured, max 50)")]
+    max_results: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct MoreByAuthorsParams {
+    #[schemars(description = "Seed paper ID whose authors to look up")]
+    id: String,
+    #[schemars(description = "Source to fetch the seed paper from")]
+    source: Option<String>,
+    #[schemars(description = "Maximum number of seed authors to query (default 3, max 10)")]
+    max_authors: Option<u32>,
+    #[schemars(description = "Maximum results to return (default 10 unless configured, max 50)")]
+    max_results: Option<u32>,
+    #[schemars(description = "Sort order: 'citations' (default) or 'recent'")]
+    sort: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct SearchByAuthorParams {
+    #[schemars(description = "Author name, e.g. \"Juan Maldacena\" or \"Maldacena, J.\"; a full first name disambiguates best")]
+    author: String,
+    #[schemars(description = "Only return papers published in or after this year")]
+    year_min: Option<u32>,
+    #[schemars(description = "Only return papers published in or before this year")]
+    year_max: Option<u32>,
+    #[schemars(description = "Maximum results to return (default 10, max 100; both configurable)")]
+    max_results: Option<u32>,
+    #[schemars(description = "Filter to specific sources (e.g. [\"arxiv\", \"inspire\"])")]
+    sources: Option<Vec<String>>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -54,16 +168,50 @@ struct SearchLocalParams {
     query: String,
     #[schemars(description = "Search mode: 'hybrid' (default), 'keyword', 'vector'")]
     mode: Option<String>,
-    #[schemars(description = "Maximum results (default 10, max 100)")]
+    #[schemars(description = "Maximum results (default 10, max 100; both configurable)")]
     limit: Option<u32>,
+    #[schemars(description = "Hybrid mode: multiplier on the BM25 channel's RRF contribution (default 1.0)")]
+    keyword_weight: Option<f32>,
+    #[schemars(description = "Hybrid mode: multiplier on the vector channel's RRF contribution (default 1.0)")]
+    vector_weight: Option<f32>,
+    #[schemars(description = "Only return papers indexed from this source (e.g. 'arxiv')")]
+    source: Option<String>,
+    #[schemars(description = "Only return papers published in or after this year")]
+    year_min: Option<i32>,
+    #[schemars(description = "Only return papers published in or before this year")]
+    year_max: Option<i32>,
+    #[schemars(description = "Only return papers whose title and abstract were detected as this language at index time (ISO 639-1 code, e.g. 'en')")]
+    language: Option<String>,
+    #[schemars(description = "Re-rank candidates with Maximal Marginal Relevance to avoid near-duplicate results")]
+    diversify: Option<bool>,
+    #[schemars(description = "With diversify: relevance/novelty tradeoff from 0 (most diverse) to 1 (pure relevance), default 0.5")]
+    lambda: Option<f32>,
+    #[schemars(description = "Cut author lists after the first few results to \"First Author et al.\" plus an author_count")]
+    summarize_authors: Option<bool>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
 struct SearchSimilarParams {
     #[schemars(description = "Query text to find similar papers")]
     query: String,
-    #[schemars(description = "Maximum results (default 10, max 100)")]
+    #[schemars(description = "Maximum results (default 10, max 100; both configurable)")]
+    limit: Option<u32>,
+    #[schemars(description = "Paper IDs to leave out of the results (e.g. already read)")]
+    exclude_ids: Option<Vec<String>>,
+    #[schemars(description = "Re-rank candidates with Maximal Marginal Relevance to avoid near-duplicate results")]
+    diversify: Option<bool>,
+    #[schemars(description = "With diversify: relevance/novelty tradeoff from 0 (most diverse) to 1 (pure relevance), default 0.5")]
+    lambda: Option<f32>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct RecommendParams {
+    #[schemars(description = "ID of an already-indexed paper")]
+    id: String,
+    #[schemars(description = "Maximum results (default 10, max 100; both configurable)")]
     limit: Option<u32>,
+    #[schemars(description = "Paper IDs to leave out of the results (e.g. already read)")]
+    exclude_ids: Option<Vec<String>>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -74,22 +222,158 @@ struct IndexPaperParams {
     source: Option<String>,
 }
 
+#[derive(Debug, Deserialize, JsonSchema)]
+struct UpdatePaperParams {
+    #[schemars(description = "ID of an indexed paper to re-fetch and re-index")]
+    id: String,
+    #[schemars(description = "Source to re-fetch from (default: chosen by ID prefix)")]
+    source: Option<String>,
+}
+
 #[derive(Debug, Deserialize, JsonSchema)]
 struct IndexFromQueryParams {
     #[schemars(description = "Search query to find papers to index")]
     query: String,
     #[schemars(description = "Source to search")]
     source: Option<String>,
-    #[schemars(description = "Maximum papers to index (default 10, max 50)")]
+    #[schemars(description = "Maximum papers to index (default 10 unless configured, max 50)")]
     max_results: Option<u32>,
 }
 
+#[derive(Debug, Deserialize, JsonSchema)]
+struct PruneIndexParams {
+    #[schemars(description = "Remove papers indexed before this date (YYYY-MM-DD or RFC 3339)")]
+    indexed_before: Option<String>,
+    #[schemars(description = "Remove papers with fewer citations than this (unknown counts are removed)")]
+    min_citations: Option<u32>,
+    #[schemars(description = "Remove papers from these sources")]
+    sources: Option<Vec<String>>,
+    #[schemars(description = "Only report what would be removed (default true)")]
+    dry_run: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct MergeDuplicatesParams {
+    #[schemars(description = "Only report the duplicate groups found (default true)")]
+    dry_run: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct ImportFromDirParams {
+    #[schemars(description = "Another paper-search data directory (containing lance/) whose indexed papers to import, relative to this server's data directory")]
+    path: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct ImportJsonlParams {
+    #[schemars(description = "JSON Lines file to read, relative to the data directory, with one paper per line, in the same shape tools return (id, title, authors, abstract_text, year, source, doi, arxiv_id, url, pdf_url, citation_count)")]
+    path: String,
+    #[schemars(description = "Replace papers that are already indexed instead of skipping them (default false)")]
+    overwrite: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct ExportJsonlParams {
+    #[schemars(description = "File to write, relative to the data directory, one paper per line; replaced if it exists")]
+    path: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct ListIndexedParams {
+    #[schemars(description = "Number of papers to skip, in ID order (default 0)")]
+    offset: Option<u32>,
+    #[schemars(description = "Papers per page (default 10, max 100; both configurable)")]
+    limit: Option<u32>,
+}
+
+const READ_ONLY_NOTICE: &str =
+    "Read-only mode: the local index can be searched but not modified; tools that write (indexing, exports, downloads, cache clearing) are disabled.";
+
+/// Value `clear_index` requires for `confirm`.
+const CLEAR_INDEX_CONFIRMATION: &str = "yes-delete-everything";
+
+/// URI prefix of indexed papers exposed as MCP resources.
+const PAPER_RESOURCE_PREFIX: &str = "paper://local/";
+/// Resources per `resources/list` page.
+const RESOURCE_PAGE_SIZE: usize = 100;
+/// Papers read from the index per page by `export_jsonl`.
+const EXPORT_PAGE_SIZE: usize = 500;
+/// Papers `import_jsonl` parses, embeds and enqueues at a time.
+const IMPORT_CHUNK_SIZE: usize = 500;
+
+/// Largest `offset` `search_papers` accepts; sources that skip client-side
+/// fetch everything before it.
+const MAX_SEARCH_OFFSET: u32 = 1000;
+
+/// Papers `citation_graph` stops adding at.
+const MAX_GRAPH_NODES: usize = 500;
+
+/// Background auto-index tasks allowed at once; searches beyond this don't
+/// auto-index their results.
+const MAX_AUTO_INDEX_TASKS: usize = 2;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct ClearIndexParams {
+    #[schemars(description = "Must be exactly \"yes-delete-everything\"")]
+    confirm: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct ReindexVectorsParams {
+    #[schemars(description = "IVF partitions: more is faster per query but lowers recall (default: configured or LanceDB's choice)")]
+    num_partitions: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct ExportParams {
+    #[schemars(description = "Paper IDs to export (resolved from the local index first, then sources)")]
+    ids: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct ResolveDoiParams {
+    #[schemars(description = "DOI to resolve (bare, doi: prefixed, or a doi.org URL)")]
+    doi: String,
+}
+
 #[derive(Debug, Deserialize, JsonSchema)]
 struct GetPdfUrlParams {
     #[schemars(description = "DOI of the paper")]
     doi: String,
 }
 
+#[derive(Debug, Deserialize, JsonSchema)]
+struct HealthCheckParams {
+    #[schemars(description = "How long to wait for each source, in milliseconds (default 5000, max 30000)")]
+    timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct GetCslJsonParams {
+    #[schemars(description = "DOI (bare, doi: prefixed, or a doi.org URL) or any paper ID with prefix")]
+    id: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct GetRawParams {
+    #[schemars(description = "ID of an indexed paper")]
+    id: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct DownloadPdfParams {
+    #[schemars(description = "Paper ID with prefix (arxiv:ID, doi:ID, inspire:ID, s2:ID, etc.)")]
+    id: Option<String>,
+    #[schemars(description = "DOI of the paper, if no id is given")]
+    doi: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct FindOpenAccessParams {
+    #[schemars(description = "Paper ID with prefix (arxiv:ID, doi:ID, inspire:ID, s2:ID, etc.)")]
+    id: String,
+}
+
 // ── Server ──────────────────────────────────────────────────────────────────
 
 #[derive(Clone)]
@@ -97,16 +381,54 @@ pub struct PaperSearchServer {
     tool_router: ToolRouter<Self>,
     config: Arc<Config>,
     sources: Arc<Vec<Arc<dyn PaperSource>>>,
+    /// Write access, shared with `index_queue`'s writer. Reads go through
+    /// `reader`; additions go through `index_queue`, while updates, pruning,
+    /// clearing and re-embedding lock this directly.
     local_index: Arc<Mutex<LocalIndex>>,
+    reader: index::ReadHandle,
+    index_queue: index::queue::IndexQueue,
     unpaywall: Option<Arc<apis::unpaywall::UnpaywallClient>>,
+    crossref: Arc<apis::crossref::CrossRefClient>,
+    search_cache: Arc<cache::SearchCache>,
+    /// Source response cache; `None` when disabled.
+    disk_cache: Option<Arc<cache::DiskCache>>,
+    /// Per-source limits on concurrent citation/reference requests, keyed by
+    /// lowercased source name.
+    relation_permits: Arc<std::collections::HashMap<String, Arc<tokio::sync::Semaphore>>>,
+    /// Slots for running auto-index tasks, `MAX_AUTO_INDEX_TASKS` in all.
+    auto_index_permits: Arc<tokio::sync::Semaphore>,
+    /// Behind a blocking mutex: inference runs on the blocking thread pool.
+    #[cfg(feature = "onnx")]
+    embedder: Option<Arc<std::sync::Mutex<SpecterModel>>>,
 }
 
 #[tool_router]
 impl PaperSearchServer {
     pub async fn create() -> anyhow::Result<Self> {
         let config = Config::from_env();
-        let sources = config.build_sources();
+        apis::set_strict_json(config.strict_json);
+        apis::set_retry_policy(config.http_retries, config.http_retry_base_delay);
+        if let Some(dir) = &config.record_dir {
+            tracing::warn!("Recording raw source responses to {}", dir.display());
+        }
+        apis::record::set_record_dir(config.record_dir.clone());
+        let mut sources = config.build_sources();
         let unpaywall = config.build_unpaywall().map(Arc::new);
+        let disk_cache = cache::DiskCache::new(config.data_dir.join("cache"), config.disk_cache_ttl)
+            .with_max_bytes(config.disk_cache_max_bytes);
+        // Read-only mode neither fills nor prunes the response cache
+        let disk_cache = (disk_cache.enabled() && !config.read_only).then(|| Arc::new(disk_cache));
+        if let Some(disk_cache) = &disk_cache {
+            match disk_cache.prune() {
+                Ok(0) => {}
+                Ok(removed) => tracing::info!("Pruned {} expired or excess cached responses", removed),
+                Err(e) => tracing::warn!("Failed to prune response cache: {}", e),
+            }
+            sources = sources
+                .into_iter()
+                .map(|s| Arc::new(apis::cached::CachedSource::new(s, disk_cache.clone())) as Arc<dyn PaperSource>)
+                .collect();
+        }
 
         tracing::info!(
             "Initialized {} paper sources, data_dir={}",
@@ -114,189 +436,904 @@ impl PaperSearchServer {
             config.data_dir.display()
         );
 
-        let local_index = LocalIndex::create_or_open(&config.data_dir).await?;
+        #[cfg(feature = "onnx")]
+        let embedder = load_embedder(
+            &config.data_dir.join("models"),
+            config.embedding_cache_size,
+            !config.read_only,
+        ).await;
+        // A model that disagrees with the configured dimension would fail
+        // every write; refuse to start instead
+        #[cfg(feature = "onnx")]
+        if let Some(embedder) = &embedder {
+            let probe = run_embedder(embedder, |model| model.embed_text("embedding dimension probe")).await?;
+            anyhow::ensure!(
+                probe.vector.len() == config.embedding_dim,
+                "SPECTER2 produces {}-dimensional embeddings but PAPER_SEARCH_EMBEDDING_DIM is {}",
+                probe.vector.len(),
+                config.embedding_dim
+            );
+        }
+        #[cfg(feature = "onnx")]
+        let embedding_model = if embedder.is_some() { "specter2" } else { index::vectordb::MOCK_EMBEDDING_MODEL };
+        #[cfg(not(feature = "onnx"))]
+        let embedding_model = index::vectordb::MOCK_EMBEDDING_MODEL;
+        #[cfg(not(feature = "onnx"))]
+        tracing::info!("Built without the `onnx` feature; indexing and search use mock embeddings");
+
+        let local_index = if config.read_only {
+            LocalIndex::open_read_only(&config.data_dir, config.embedding_dim).await?
+        } else {
+            LocalIndex::create_or_open(&config.data_dir, config.embedding_dim).await?
+        };
+        let mut local_index = local_index
+            .with_clean_abstracts(config.clean_abstracts)
+            .with_language_tagging(config.tag_languages)
+            .with_distance_type(config.vector_distance)
+            .with_embedding_model(embedding_model)
+            .with_vector_index(config.vector_index_threshold, config.vector_index_partitions);
+        // An ANN index built under another PAPER_SEARCH_VECTOR_DISTANCE is rebuilt now
+        if !config.read_only {
+            local_index.maybe_build_vector_index().await;
+        }
 
-        Ok(Self {
-            tool_router: Self::tool_router(),
-            config: Arc::new(config),
-            sources: Arc::new(sources),
-            local_index: Arc::new(Mutex::new(local_index)),
-            unpaywall,
-        })
+        let mut server = Self::new(config, sources, local_index, unpaywall);
+        server.disk_cache = disk_cache;
+        #[cfg(feature = "onnx")]
+        {
+            server.embedder = embedder;
+        }
+
+        Ok(server)
     }
 
-    #[tool(description = "List available paper sources and their status")]
+    #[tool(description = "List available paper sources and their status", annotations(read_only_hint = true, open_world_hint = false))]
     async fn list_sources(&self) -> Result<CallToolResult, McpError> {
         let statuses = self.config.source_status();
         let json = serde_json::to_string_pretty(&statuses)
             .map_err(|e| McpError::internal_error(format!("Serialization error: {}", e), None))?;
+        let mut content = vec![Content::text(json)];
+        if self.config.read_only {
+            content.push(Content::text(READ_ONLY_NOTICE));
+        }
+        Ok(CallToolResult::success(content))
+    }
+
+    #[tool(description = "Describe every available tool: its description, parameters (type, required, default) and side effects (whether it reads the network or writes anything)", annotations(read_only_hint = true, open_world_hint = false))]
+    async fn describe_tools(&self) -> Result<CallToolResult, McpError> {
+        let mut tools = self.tool_router.list_all();
+        tools.sort_by(|a, b| a.name.cmp(&b.name));
+        let described: Vec<serde_json::Value> = tools.iter().map(describe_tool).collect();
+        let json = serde_json::to_string_pretty(&described)
+            .map_err(|e| McpError::internal_error(format!("Serialization error: {}", e), None))?;
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    #[tool(description = "Check which enabled sources are reachable right now by sending each a cheap live request concurrently. Returns per-source reachable, latency_ms and error.", annotations(read_only_hint = true, open_world_hint = true))]
+    async fn health_check(
+        &self,
+        Parameters(params): Parameters<HealthCheckParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let timeout = std::time::Duration::from_millis(params.timeout_ms.unwrap_or(5000).min(30_000));
+        let health = search::health_check(&self.sources, timeout).await;
+        let json = serde_json::to_string_pretty(&health)
+            .map_err(|e| McpError::internal_error(format!("Serialization error: {}", e), None))?;
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    #[tool(description = "Show search result cache statistics (hits, misses, live entries, TTL)", annotations(read_only_hint = true, open_world_hint = false))]
+    async fn search_cache_stats(&self) -> Result<CallToolResult, McpError> {
+        let json = serde_json::to_string_pretty(&self.search_cache.stats())
+            .map_err(|e| McpError::internal_error(format!("Serialization error: {}", e), None))?;
         Ok(CallToolResult::success(vec![Content::text(json)]))
     }
 
-    #[tool(description = "Search papers across all enabled sources. Returns deduplicated, ranked results.")]
+    #[tool(description = "Search papers across all enabled sources. Returns deduplicated, ranked results. Identical searches are served from a short-lived cache.", annotations(read_only_hint = true, open_world_hint = true))]
     async fn search_papers(
         &self,
         Parameters(params): Parameters<SearchPapersParams>,
     ) -> Result<CallToolResult, McpError> {
-        let max = params.max_results.unwrap_or(10).min(100);
-        let results = search::federated_search(
-            &self.sources,
-            &params.query,
-            max,
-            params.sources.as_deref(),
-        )
-        .await;
+        let max = self.max_results(params.max_results, self.config.max_results_cap);
+        let started = std::time::Instant::now();
+        let offset = params.offset.unwrap_or(0);
+        if offset > MAX_SEARCH_OFFSET {
+            return Err(McpError::invalid_params(
+                format!("offset {} exceeds the maximum of {}", offset, MAX_SEARCH_OFFSET),
+                None,
+            ));
+        }
+        let language = params.language.as_deref().map(str::trim).filter(|l| !l.is_empty());
+        let categories = params.categories.as_deref().filter(|c| c.iter().any(|c| !c.trim().is_empty()));
+        let field = match params.field.as_deref().map(str::trim) {
+            None | Some("") | Some("all") => None,
+            Some(name) => Some(apis::SearchField::parse(name).ok_or_else(|| {
+                McpError::invalid_params(
+                    format!("Unknown field '{}': expected title, author, abstract or all", name),
+                    None,
+                )
+            })?),
+        };
+        let rank_by = match params.rank_by.as_deref().map(str::trim) {
+            None | Some("") => search::RankBy::default(),
+            Some(name) => search::RankBy::parse(name).ok_or_else(|| {
+                McpError::invalid_params(
+                    format!("Unknown rank_by '{}': expected citations, year or relevance", name),
+                    None,
+                )
+            })?,
+        };
+        let signature = cache::SearchSignature::new(&params.query, params.sources.as_deref(), max, offset)
+            .with_language(language)
+            .with_field(field)
+            .with_categories(categories)
+            .with_rank_by(rank_by);
+        let options = search::QueryOptions { language, field, categories, rank_by };
+        let cached = self.search_cache.get(&signature);
+        let cache_hit = cached.is_some();
+        let mut warnings = Vec::new();
+        let mut empty_sources = None;
+        let mut results = match cached {
+            Some(results) => results,
+            None => {
+                let federated = search::federated_search(
+                    &self.sources,
+                    &params.query,
+                    max,
+                    offset,
+                    params.sources.as_deref(),
+                    options,
+                    self.config.source_timeout,
+                )
+                .await;
+                // Partial result sets aren't cached, so a retry can pick up
+                // the sources that failed.
+                if federated.source_errors.is_empty() {
+                    self.search_cache.insert(signature, federated.papers.clone());
+                }
+                if self.config.auto_index {
+                    self.spawn_auto_index(&federated.papers);
+                }
+                warnings = federated.source_errors
+                    .into_iter()
+                    .map(|(source, error)| SourceWarning { source, error })
+                    .collect();
+                empty_sources = Some(federated.empty_sources);
+                federated.papers
+            }
+        };
 
-        let json = serde_json::to_string_pretty(&results)
-            .map_err(|e| McpError::internal_error(format!("{}", e), None))?;
-        Ok(CallToolResult::success(vec![Content::text(json)]))
+        // One broadening retry when the query was too strict
+        let mut broadened_query = None;
+        let min_results = params.min_results.unwrap_or(0).min(max) as usize;
+        if results.len() < min_results {
+            if let Some(broadened) = search::broaden_query(&params.query) {
+                tracing::info!("Only {} results for '{}'; retrying as '{}'", results.len(), params.query, broadened);
+                let federated = search::federated_search(
+                    &self.sources,
+                    &broadened,
+                    max,
+                    offset,
+                    params.sources.as_deref(),
+                    options,
+                    self.config.source_timeout,
+                )
+                .await;
+                warnings.extend(
+                    federated.source_errors
+                        .into_iter()
+                        .map(|(source, error)| SourceWarning { source, error: format!("broadened search: {}", error) }),
+                );
+                results = search::merge_results(results, federated.papers, max as usize, rank_by);
+                broadened_query = Some(broadened);
+            }
+        }
+
+        let years_filled = if self.config.fill_missing_years {
+            Some(enrich::fill_missing_years(self.crossref.as_ref(), &mut results, self.config.enrichment_budget).await)
+        } else {
+            None
+        };
+
+        let active = search::active_sources(&self.sources, params.sources.as_deref());
+        if let Some(lang) = language {
+            for source in search::language_unsupported(&active, lang) {
+                warnings.push(SourceWarning {
+                    source,
+                    error: format!("language filter '{}' not supported; results are unfiltered", lang),
+                });
+            }
+        }
+        if let Some(categories) = categories {
+            for source in search::category_unsupported(&active, categories) {
+                warnings.push(SourceWarning {
+                    source,
+                    error: format!("category filter {:?} not supported; results are unfiltered", categories),
+                });
+            }
+        }
+        if let Some(field) = field {
+            for source in search::field_unsupported(&active, field) {
+                warnings.push(SourceWarning {
+                    source,
+                    error: format!("field '{}' not supported; searched all fields", field.as_str()),
+                });
+            }
+        }
+
+        let mut diagnostics = Diagnostics::new();
+        diagnostics.insert("elapsed_ms".into(), (started.elapsed().as_millis() as u64).into());
+        diagnostics.insert("max_results".into(), max.into());
+        diagnostics.insert("offset".into(), offset.into());
+        diagnostics.insert("cache_hit".into(), cache_hit.into());
+        diagnostics.insert("rank_by".into(), rank_by.as_str().into());
+        // Only known for fresh searches; cached result sets don't record it.
+        if let Some(empty) = empty_sources {
+            diagnostics.insert("empty_sources".into(), empty.into());
+        }
+        if let Some(broadened) = broadened_query {
+            diagnostics.insert("broadened_query".into(), broadened.into());
+        }
+        if let Some(filled) = years_filled {
+            diagnostics.insert("years_filled".into(), filled.into());
+        }
+
+        let summarize = params.summarize_authors.unwrap_or(false);
+        if params.check_retractions.unwrap_or(false) {
+            let annotated = enrich::annotate_retractions(
+                &self.crossref,
+                results,
+                self.config.enrichment_budget,
+            )
+            .await;
+            return self.summarized_response("search_papers", &annotated, summarize, diagnostics, &warnings);
+        }
+        self.summarized_response("search_papers", &results, summarize, diagnostics, &warnings)
     }
 
-    #[tool(description = "Get full metadata for a paper by ID (arxiv:ID, doi:ID, inspire:ID, s2:ID, etc.)")]
+    #[tool(description = "Get full metadata for a paper by ID (arxiv:ID, doi:ID, inspire:ID, s2:ID, etc.). Set verbose to see which source answered and each source's outcome (found, not_found, error, skipped_by_filter). When PAPER_SEARCH_REFRESH_MISSING_FIELDS is set, an indexed record lacking those fields is refreshed from its source and stored back.", annotations(read_only_hint = true, open_world_hint = true))]
     async fn get_paper(
         &self,
         Parameters(params): Parameters<GetPaperParams>,
     ) -> Result<CallToolResult, McpError> {
-        let id = &params.id;
-        let target_source = params.source.as_deref().or_else(|| {
-            if id.starts_with("arxiv:") { Some("arxiv") }
-            else if id.starts_with("inspire:") { Some("inspire") }
-            else if id.starts_with("s2:") { Some("semantic_scholar") }
-            else if id.starts_with("ads:") { Some("ads") }
-            else if id.starts_with("doi:") { Some("crossref") }
-            else if id.starts_with("pmid:") { Some("europepmc") }
-            else if id.starts_with("doaj:") { Some("doaj") }
-            else if id.starts_with("vixra:") { Some("vixra") }
-            else if id.starts_with("openalex:") { Some("openalex") }
-            else { None }
-        });
-
-        // Check local index first
-        {
-            let idx = self.local_index.lock().await;
-            if let Ok(Some(paper)) = idx.get_paper(id).await {
-                let json = serde_json::to_string_pretty(&paper)
-                    .map_err(|e| McpError::internal_error(format!("{}", e), None))?;
+        let verbose = params.verbose.unwrap_or(false);
+        let lookup = match self.lookup_paper_traced(&params.id, params.source.as_deref(), false).await {
+            Ok(lookup) => lookup,
+            Err(attempts) if verbose => {
+                let json = serde_json::to_string_pretty(&serde_json::json!({
+                    "id": params.id,
+                    "found": false,
+                    "attempts": attempts,
+                }))
+                .map_err(|e| McpError::internal_error(format!("{}", e), None))?;
                 return Ok(CallToolResult::success(vec![Content::text(json)]));
             }
+            Err(_) => {
+                return Ok(CallToolResult::success(vec![Content::text(
+                    format!("Paper not found: {}", params.id),
+                )]));
+            }
+        };
+        if lookup.refreshed {
+            self.store_refreshed(&lookup.paper).await;
         }
 
-        for src in self.sources.iter() {
-            if let Some(target) = target_source {
-                if !src.name().eq_ignore_ascii_case(target) {
-                    continue;
+        let json = if verbose {
+            serde_json::to_string_pretty(&lookup)
+        } else {
+            serde_json::to_string_pretty(&lookup.paper)
+        }
+        .map_err(|e| McpError::internal_error(format!("{}", e), None))?;
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    #[tool(description = "Resolve a DOI against CrossRef, OpenAlex, Semantic Scholar and Unpaywall concurrently and merge their records field by field. Returns the merged paper and sources_used.", annotations(read_only_hint = true, open_world_hint = true))]
+    async fn resolve_doi(
+        &self,
+        Parameters(params): Parameters<ResolveDoiParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let doi = normalize_doi(&params.doi);
+        if doi.is_empty() {
+            return Err(McpError::invalid_params("doi must not be empty".to_string(), None));
+        }
+        let doi_id = format!("doi:{}", doi);
+        let s2_id = format!("DOI:{}", doi);
+        let (crossref, openalex, s2, pdf_url) = tokio::join!(
+            self.fetch_from_named("crossref", &doi_id),
+            self.fetch_from_named("openalex", &doi_id),
+            self.fetch_from_named("semantic_scholar", &s2_id),
+            async {
+                let client = self.unpaywall.as_ref()?;
+                match client.get_pdf_url(doi).await {
+                    Ok(url) => url,
+                    Err(e) => {
+                        tracing::warn!("Unpaywall failed for {}: {}", doi, e);
+                        None
+                    }
                 }
+            },
+        );
+
+        let records: Vec<apis::PaperResult> = [crossref, openalex, s2].into_iter().flatten().collect();
+        let mut sources_used: Vec<String> = records.iter().map(|p| p.source.clone()).collect();
+        let Some(mut merged) = search::merge_records(&records) else {
+            return Ok(CallToolResult::success(vec![Content::text(
+                format!("No source returned a record for DOI: {}", doi),
+            )]));
+        };
+        merged.id = doi_id;
+        merged.source = "merged".to_string();
+        merged.doi = Some(doi.to_string());
+        if let Some(url) = pdf_url {
+            merged.pdf_url = Some(url);
+            sources_used.push("unpaywall".to_string());
+        }
+
+        let json = serde_json::to_string_pretty(&serde_json::json!({
+            "paper": merged,
+            "sources_used": sources_used,
+        }))
+        .map_err(|e| McpError::internal_error(format!("Serialization error: {}", e), None))?;
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    #[tool(description = "Get metadata for many papers at once. Locally indexed papers are answered from the index; the rest are grouped by source, using batch endpoints where available (semantic_scholar).", annotations(read_only_hint = true, open_world_hint = true))]
+    async fn get_papers(
+        &self,
+        Parameters(params): Parameters<GetPapersParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let ids = params.ids;
+        if ids.len() > 500 {
+            return Err(McpError::invalid_params("At most 500 IDs per call".to_string(), None));
+        }
+        let mut found: Vec<Option<apis::PaperResult>> = vec![None; ids.len()];
+
+        // Local index first, then group the rest by the source that owns them
+        let mut groups: std::collections::HashMap<usize, Vec<usize>> = std::collections::HashMap::new();
+        let mut unrouted = Vec::new();
+        let requested = params.source.as_deref();
+        for (i, id) in ids.iter().enumerate() {
+            if let Ok(Some(paper)) = self.reader.get_paper(id).await {
+                found[i] = Some(paper);
+                continue;
+            }
+            let targets = if requested.is_some() { requested.as_slice() } else { sources_for_id(id) };
+            let routed = targets.iter().find_map(|t| self.sources.iter().position(|s| s.name().eq_ignore_ascii_case(t)));
+            match routed {
+                Some(src) => groups.entry(src).or_default().push(i),
+                None => unrouted.push(i),
             }
-            match src.get_paper(id).await {
-                Ok(Some(paper)) => {
-                    let json = serde_json::to_string_pretty(&paper)
-                        .map_err(|e| McpError::internal_error(format!("{}", e), None))?;
-                    return Ok(CallToolResult::success(vec![Content::text(json)]));
+        }
+
+        for (src, positions) in groups {
+            let source = &self.sources[src];
+            let group_ids: Vec<String> = positions.iter().map(|&i| ids[i].clone()).collect();
+            match source.get_papers(&group_ids).await {
+                Ok(papers) => {
+                    for (i, paper) in positions.into_iter().zip(papers) {
+                        found[i] = paper;
+                    }
                 }
-                Ok(None) => continue,
                 Err(e) => {
-                    tracing::warn!("Source {} failed for get_paper: {}", src.name(), e);
-                    continue;
+                    tracing::warn!("Batch lookup on {} failed; trying IDs individually: {}", source.name(), e);
+                    unrouted.extend(positions);
                 }
             }
         }
+        for i in unrouted {
+            found[i] = self.lookup_paper(&ids[i], params.source.as_deref()).await.map(|l| l.paper);
+        }
 
-        Ok(CallToolResult::success(vec![Content::text(
-            format!("Paper not found: {}", id),
-        )]))
+        let not_found: Vec<&String> = ids.iter().zip(&found).filter(|(_, p)| p.is_none()).map(|(id, _)| id).collect();
+        let papers: Vec<apis::PaperResult> = found.into_iter().flatten().collect();
+        let json = serde_json::to_string_pretty(&serde_json::json!({
+            "papers": papers,
+            "not_found": not_found,
+        }))
+        .map_err(|e| McpError::internal_error(format!("Serialization error: {}", e), None))?;
+        Ok(CallToolResult::success(vec![Content::text(json)]))
     }
 
-    #[tool(description = "Get papers that cite a given paper")]
+    #[tool(description = "Get papers that cite a given paper", annotations(read_only_hint = true, open_world_hint = true))]
     async fn get_citations(
         &self,
-        Parameters(params): Parameters<RelationParams>,
+        Parameters(params): Parameters<GetCitationsParams>,
     ) -> Result<CallToolResult, McpError> {
-        let results = self.query_relation(&params.id, params.source.as_deref(), |src, id| {
-            Box::pin(src.get_citations(id))
-        }).await;
-        let json = serde_json::to_string_pretty(&results)
-            .map_err(|e| McpError::internal_error(format!("{}", e), None))?;
-        Ok(CallToolResult::success(vec![Content::text(json)]))
+        let limit = params.max_results.unwrap_or(25).min(500);
+        let source = params.source.as_deref();
+        let exclude_self = params.exclude_self_citations.unwrap_or(false);
+        let (mut results, cited) = tokio::join!(
+            self.query_relation(&params.id, source, move |src, id| {
+                Box::pin(src.get_citations_limited(id, limit))
+            }),
+            async {
+                if exclude_self {
+                    self.lookup_paper(&params.id, source).await
+                } else {
+                    None
+                }
+            },
+        );
+
+        let mut diagnostics = Diagnostics::new();
+        if exclude_self {
+            // The cited paper's authors are needed to recognise self-citations
+            let cited = cited
+                .ok_or_else(|| McpError::invalid_params(format!("Paper not found: {}", params.id), None))?
+                .paper;
+            let before = results.len();
+            results.retain(|citing| !search::shares_author(&cited, citing));
+            diagnostics.insert("self_citations_excluded".into(), (before - results.len()).into());
+        }
+        self.results_response("get_citations", &results, diagnostics)
     }
 
-    #[tool(description = "Get papers referenced by a given paper")]
+    #[tool(description = "Get papers referenced by a given paper", annotations(read_only_hint = true, open_world_hint = true))]
     async fn get_references(
         &self,
         Parameters(params): Parameters<RelationParams>,
     ) -> Result<CallToolResult, McpError> {
-        let results = self.query_relation(&params.id, params.source.as_deref(), |src, id| {
-            Box::pin(src.get_references(id))
+        let limit = params.max_results.unwrap_or(25).min(500);
+        let results = self.query_relation(&params.id, params.source.as_deref(), move |src, id| {
+            Box::pin(src.get_references_limited(id, limit))
         }).await;
-        let json = serde_json::to_string_pretty(&results)
-            .map_err(|e| McpError::internal_error(format!("{}", e), None))?;
-        Ok(CallToolResult::success(vec![Content::text(json)]))
+        self.results_response("get_references", &results, Diagnostics::new())
     }
 
-    #[tool(description = "Search locally indexed papers using keyword, vector, or hybrid search. Mode: 'hybrid' (default), 'keyword', 'vector'")]
-    async fn search_local(
+    #[tool(description = "Get a paper's citations and references in one call. Both are fetched concurrently; each list is deduplicated and limited to max_results. Each result carries relation 'citation' (cites the paper) or 'reference' (cited by it)", annotations(read_only_hint = true, open_world_hint = true))]
+    async fn get_relations(
         &self,
-        Parameters(params): Parameters<SearchLocalParams>,
+        Parameters(params): Parameters<RelationParams>,
     ) -> Result<CallToolResult, McpError> {
-        let limit = params.limit.unwrap_or(10).min(100) as usize;
-        let idx = self.local_index.lock().await;
-
-        let mode_str = params.mode.as_deref().unwrap_or("hybrid");
-        let embedding = specter::mock_embedding(&params.query);
+        let limit = params.max_results.unwrap_or(25).min(500);
+        let source = params.source.as_deref();
+        let (citations, references) = tokio::join!(
+            self.query_relation(&params.id, source, move |src, id| {
+                Box::pin(src.get_citations_limited(id, limit))
+            }),
+            self.query_relation(&params.id, source, move |src, id| {
+                Box::pin(src.get_references_limited(id, limit))
+            }),
+        );
+        let citations = search::dedup_relations(citations, limit as usize);
+        let references = search::dedup_relations(references, limit as usize);
 
-        let search_mode = match mode_str {
-            "keyword" => index::hybrid::SearchMode::KeywordOnly { query: &params.query },
-            "vector" => index::hybrid::SearchMode::VectorOnly { embedding: &embedding },
-            _ => index::hybrid::SearchMode::Hybrid { query: &params.query, embedding: &embedding },
-        };
+        let mut diagnostics = Diagnostics::new();
+        diagnostics.insert("citations".into(), citations.len().into());
+        diagnostics.insert("references".into(), references.len().into());
+        let related: Vec<RelatedPaper> = citations
+            .into_iter()
+            .map(|paper| RelatedPaper { relation: "citation", paper })
+            .chain(references.into_iter().map(|paper| RelatedPaper { relation: "reference", paper }))
+            .collect();
+        self.results_response("get_relations", &related, diagnostics)
+    }
 
-        let scored = idx.search(search_mode, limit).await
-            .map_err(|e| McpError::internal_error(format!("Search failed: {}", e), None))?;
+    #[tool(description = "Bibliographic coupling of two papers: how many references they share, a normalized coupling coefficient (shared / sqrt(refs_a * refs_b)), and the shared references", annotations(read_only_hint = true, open_world_hint = true))]
+    async fn coupling_score(
+        &self,
+        Parameters(params): Parameters<CouplingParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let limit = params.max_references.unwrap_or(200).min(500);
+        let source = params.source.as_deref();
+        let (refs_a, refs_b) = tokio::join!(
+            self.query_relation(&params.id_a, source, move |src, id| {
+                Box::pin(src.get_references_limited(id, limit))
+            }),
+            self.query_relation(&params.id_b, source, move |src, id| {
+                Box::pin(src.get_references_limited(id, limit))
+            }),
+        );
 
-        let papers = index::hybrid::resolve_results(&idx.vector, &scored).await
-            .map_err(|e| McpError::internal_error(format!("Failed to resolve results: {}", e), None))?;
+        let mut missing = Vec::new();
+        if refs_a.is_empty() {
+            missing.push(params.id_a.as_str());
+        }
+        if refs_b.is_empty() {
+            missing.push(params.id_b.as_str());
+        }
+        if !missing.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "No references available for {} from any source; cannot compute coupling",
+                missing.join(" and "),
+            ))]));
+        }
 
-        let json = serde_json::to_string_pretty(&papers)
-            .map_err(|e| McpError::internal_error(format!("{}", e), None))?;
+        let coupling = search::bibliographic_coupling(&refs_a, &refs_b);
+        let json = serde_json::to_string_pretty(&serde_json::json!({
+            "id_a": params.id_a,
+            "id_b": params.id_b,
+            "coupling": coupling,
+        }))
+        .map_err(|e| McpError::internal_error(format!("Serialization error: {}", e), None))?;
         Ok(CallToolResult::success(vec![Content::text(json)]))
     }
 
-    #[tool(description = "Search for semantically similar papers in the local index using SPECTER2 embeddings")]
-    async fn search_similar(
+    #[tool(description = "Map the citation network around a paper: breadth-first expansion of citations and/or references up to 3 hops, returning node metadata and an edge list where each edge points from the citing paper to the cited one. Stops adding papers at 500 nodes.", annotations(read_only_hint = true, open_world_hint = true))]
+    async fn citation_graph(
         &self,
-        Parameters(params): Parameters<SearchSimilarParams>,
+        Parameters(params): Parameters<CitationGraphParams>,
     ) -> Result<CallToolResult, McpError> {
-        let limit = params.limit.unwrap_or(10).min(100) as usize;
-        let idx = self.local_index.lock().await;
-        let embedding = specter::mock_embedding(&params.query);
+        use futures::stream::StreamExt;
 
-        let results = idx.vector.search_similar(&embedding, limit).await
-            .map_err(|e| McpError::internal_error(format!("Vector search failed: {}", e), None))?;
+        let depth = params.depth.unwrap_or(1);
+        if !(1..=3).contains(&depth) {
+            return Err(McpError::invalid_params("depth must be between 1 and 3".to_string(), None));
+        }
+        let direction = params.direction.as_deref().unwrap_or("both").to_lowercase();
+        let (citations, references) = match direction.as_str() {
+            "citations" => (true, false),
+            "references" => (false, true),
+            "both" => (true, true),
+            other => {
+                return Err(McpError::invalid_params(
+                    format!("Unknown direction '{}'; use 'citations', 'references' or 'both'", other),
+                    None,
+                ))
+            }
+        };
+        let limit = self.max_results(params.max_per_node, self.config.max_results_cap);
+        let source = params.source.as_deref();
 
-        let mut papers = Vec::new();
-        for (id, _distance) in &results {
-            if let Ok(Some(paper)) = idx.vector.get_paper(id).await {
-                papers.push(paper);
+        let seed = match self.lookup_paper(&params.id, source).await {
+            Some(lookup) => lookup.paper,
+            None => return Err(McpError::resource_not_found(format!("Paper not found: {}", params.id), None)),
+        };
+        let mut index: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+        index.insert(params.id.clone(), 0);
+        index.insert(seed.id.clone(), 0);
+        let mut nodes = vec![seed];
+        let mut edges: Vec<(String, String)> = Vec::new();
+        let mut seen_edges: std::collections::HashSet<(String, String)> = std::collections::HashSet::new();
+        let mut truncated = false;
+        let mut frontier = vec![params.id.clone()];
+
+        for _ in 0..depth {
+            if frontier.is_empty() {
+                break;
+            }
+            let expanded: Vec<_> = futures::stream::iter(std::mem::take(&mut frontier).into_iter().map(|id| async move {
+                let (cited_by, cites) = tokio::join!(
+                    async {
+                        if !citations {
+                            return Vec::new();
+                        }
+                        self.query_relation(&id, source, move |src, id| {
+                            Box::pin(src.get_citations_limited(id, limit))
+                        }).await
+                    },
+                    async {
+                        if !references {
+                            return Vec::new();
+                        }
+                        self.query_relation(&id, source, move |src, id| {
+                            Box::pin(src.get_references_limited(id, limit))
+                        }).await
+                    },
+                );
+                (id, cited_by, cites)
+            }))
+            .buffered(self.config.graph_concurrency)
+            .collect()
+            .await;
+
+            let mut next = Vec::new();
+            for (id, cited_by, cites) in expanded {
+                let node_id = nodes[index[&id]].id.clone();
+                let neighbors = cited_by.into_iter().map(|p| (p, true)).chain(cites.into_iter().map(|p| (p, false)));
+                for (paper, is_citer) in neighbors {
+                    let neighbor_id = match index.get(&paper.id) {
+                        Some(&i) => nodes[i].id.clone(),
+                        None if nodes.len() >= MAX_GRAPH_NODES => {
+                            truncated = true;
+                            continue;
+                        }
+                        None => {
+                            index.insert(paper.id.clone(), nodes.len());
+                            next.push(paper.id.clone());
+                            let neighbor_id = paper.id.clone();
+                            nodes.push(paper);
+                            neighbor_id
+                        }
+                    };
+                    let edge = if is_citer {
+                        (neighbor_id, node_id.clone())
+                    } else {
+                        (node_id.clone(), neighbor_id)
+                    };
+                    if edge.0 != edge.1 && seen_edges.insert(edge.clone()) {
+                        edges.push(edge);
+                    }
+                }
             }
+            frontier = next;
         }
 
-        let json = serde_json::to_string_pretty(&papers)
-            .map_err(|e| McpError::internal_error(format!("{}", e), None))?;
+        let edges: Vec<serde_json::Value> = edges
+            .into_iter()
+            .map(|(from, to)| serde_json::json!({ "from": from, "to": to }))
+            .collect();
+        let json = serde_json::to_string_pretty(&serde_json::json!({
+            "seed": params.id,
+            "depth": depth,
+            "direction": direction,
+            "node_count": nodes.len(),
+            "edge_count": edges.len(),
+            "truncated": truncated,
+            "nodes": nodes,
+            "edges": edges,
+        }))
+        .map_err(|e| McpError::internal_error(format!("Serialization error: {}", e), None))?;
         Ok(CallToolResult::success(vec![Content::text(json)]))
     }
 
-    #[tool(description = "Fetch a paper from an API source and add it to the local index with embedding")]
-    async fn index_paper(
+    #[tool(description = "Group papers (given by ids, or the raw results of a query) into logical papers by DOI, arXiv ID and title, returning one merged canonical record per group, preferring the published version, with its member IDs", annotations(read_only_hint = true, open_world_hint = true))]
+    async fn canonicalize(
         &self,
-        Parameters(params): Parameters<IndexPaperParams>,
+        Parameters(params): Parameters<CanonicalizeParams>,
     ) -> Result<CallToolResult, McpError> {
-        let mut paper = None;
+        let mut diagnostics = Diagnostics::new();
+        let papers = match (&params.ids, &params.query) {
+            (Some(ids), None) => {
+                let (papers, missing) = self.resolve_papers(ids).await;
+                if !missing.is_empty() {
+                    diagnostics.insert("missing_ids".into(), missing.into());
+                }
+                papers
+            }
+            (None, Some(query)) => {
+                let max = self.max_results(params.max_results, 50);
+                search::federated_search_raw(&self.sources, query, max, 0, None, Default::default(), self.config.source_timeout)
+                    .await
+                    .papers
+            }
+            _ => {
+                return Err(McpError::invalid_params(
+                    "Provide exactly one of ids or query".to_string(),
+                    None,
+                ))
+            }
+        };
+        let groups = search::canonicalize(papers, search::DEFAULT_TITLE_SIMILARITY);
+        self.results_response("canonicalize", &groups, diagnostics)
+    }
+
+    #[tool(description = "Find papers by an author across author-capable sources (arxiv, crossref, openalex, semantic_scholar, inspire, ...), deduplicated and ranked by how confidently the name matches, then recency. Reports the name variants matched and whether results may mix different people.", annotations(read_only_hint = true, open_world_hint = true))]
+    async fn search_by_author(
+        &self,
+        Parameters(params): Parameters<SearchByAuthorParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let author = params.author.trim();
+        if author.is_empty() {
+            return Err(McpError::invalid_params("author must not be empty".to_string(), None));
+        }
+        let max = self.max_results(params.max_results, self.config.max_results_cap);
+        let query = apis::AuthorQuery::new(author).with_years(params.year_min, params.year_max);
+        let found = search::author_search(
+            &self.sources,
+            &query,
+            max,
+            params.sources.as_deref(),
+            self.config.source_timeout,
+        )
+        .await;
+
+        let mut warnings: Vec<SourceWarning> = found.source_errors
+            .into_iter()
+            .map(|(source, error)| SourceWarning { source, error })
+            .collect();
+        if found.ambiguous {
+            // A warning rather than a diagnostic so bare output carries it too
+            warnings.push(SourceWarning {
+                source: "search_by_author".to_string(),
+                error: format!(
+                    "\"{}\" may match more than one person ({}); check name_confidence and matched_author",
+                    author,
+                    found.name_variants.join("; "),
+                ),
+            });
+        }
+        for (source, profile) in &found.other_profiles {
+            warnings.push(SourceWarning {
+                source: source.clone(),
+                error: format!(
+                    "also has an author profile named \"{}\" whose works aren't included",
+                    profile,
+                ),
+            });
+        }
+        let mut diagnostics = Diagnostics::new();
+        diagnostics.insert("name_variants".into(), found.name_variants.into());
+        diagnostics.insert("ambiguous".into(), found.ambiguous.into());
+        self.results_response_with_warnings("search_by_author", &found.results, diagnostics, &warnings)
+    }
+
+    #[tool(description = "Find other works by a paper's authors across author-capable sources", annotations(read_only_hint = true, open_world_hint = true))]
+    async fn more_by_authors(
+        &self,
+        Parameters(params): Parameters<MoreByAuthorsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let seed = self.lookup_paper(&params.id, params.source.as_deref()).await
+            .ok_or_else(|| McpError::invalid_params(format!("Paper not found: {}", params.id), None))?
+            .paper;
+
+        let max_authors = params.max_authors.unwrap_or(3).min(10) as usize;
+        let max = self.max_results(params.max_results, 50);
+        let sort = match params.sort.as_deref() {
+            Some("recent") => search::AuthorSort::Recent,
+            _ => search::AuthorSort::Citations,
+        };
+
+        let results = search::more_by_authors(&self.sources, &seed, max_authors, max, sort).await;
+        self.results_response("more_by_authors", &results, Diagnostics::new())
+    }
+
+    #[tool(description = "Search locally indexed papers using keyword, vector, or hybrid search. Mode: 'hybrid' (default), 'keyword', 'vector'. Keyword queries accept \"quoted phrases\", field prefixes (title:, abstract_text:, authors:), AND/OR and -excluded terms; a query that isn't valid syntax is searched as a plain phrase and a warning says so. Each result carries its rrf_score, bm25_score, vector_distance and data_source, plus a snippet with <b>-highlighted matched context for keyword matches", annotations(read_only_hint = true, open_world_hint = false))]
+    async fn search_local(
+        &self,
+        Parameters(params): Parameters<SearchLocalParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let limit = self.max_results(params.limit, self.config.max_results_cap) as usize;
+        let embedding = self.embed_query(&params.query).await;
+        let idx = &self.reader;
+
+        let mut mode_str = params.mode.as_deref().unwrap_or("hybrid");
+        let mut warnings = Vec::new();
+        if mode_str != "keyword" && matches!(idx.vector.mostly_mock_embeddings().await, Ok(true)) {
+            if self.config.mock_embedding_keyword_fallback {
+                warnings.push(SourceWarning {
+                    source: "local_index".to_string(),
+                    error: format!("index holds mock embeddings; ran '{}' search as keyword", mode_str),
+                });
+                mode_str = "keyword";
+            } else {
+                warnings.push(SourceWarning {
+                    source: "local_index".to_string(),
+                    error: "index holds mock embeddings; vector scores are not meaningful".to_string(),
+                });
+            }
+        }
+
+        let query = idx.fulltext.parse(&params.query)
+            .map_err(|e| McpError::internal_error(format!("Failed to parse query: {}", e), None))?;
+        if mode_str != "vector" && query.sanitized {
+            warnings.push(SourceWarning {
+                source: "local_index".to_string(),
+                error: "query isn't valid search syntax; searched it as a phrase with special characters removed".to_string(),
+            });
+        }
+
+        let search_mode = match mode_str {
+            "keyword" => index::hybrid::SearchMode::KeywordOnly { query: &query },
+            "vector" => index::hybrid::SearchMode::VectorOnly { embedding: &embedding },
+            _ => index::hybrid::SearchMode::Hybrid {
+                query: &query,
+                embedding: &embedding,
+                keyword_weight: params.keyword_weight.unwrap_or(1.0).max(0.0),
+                vector_weight: params.vector_weight.unwrap_or(1.0).max(0.0),
+            },
+        };
+
+        let filter = index::LocalFilter {
+            source: params.source.map(|s| s.trim().to_lowercase()).filter(|s| !s.is_empty()),
+            year_min: params.year_min,
+            year_max: params.year_max,
+            language: params.language.map(|l| l.trim().to_lowercase()).filter(|l| !l.is_empty()),
+        };
+        let diversify = params.diversify.unwrap_or(false);
+        let fetch = if diversify { limit * index::mmr::CANDIDATE_FACTOR } else { limit };
+        let scored = idx.search_filtered(search_mode, fetch, &filter).await
+            .map_err(|e| McpError::internal_error(format!("Search failed: {}", e), None))?;
+
+        let mut papers = index::hybrid::resolve_scored(&idx.vector, &scored).await
+            .map_err(|e| McpError::internal_error(format!("Failed to resolve results: {}", e), None))?;
+        if diversify {
+            let ids: Vec<String> = papers.iter().map(|p| p.paper.id.clone()).collect();
+            // Weigh candidates by the score that ranked them: BM25 alone in
+            // keyword mode, where the query embedding played no part
+            let relevance: Vec<f32> = papers
+                .iter()
+                .map(|p| match mode_str {
+                    "keyword" => p.bm25_score.unwrap_or(0.0),
+                    "vector" => p.vector_distance.map_or(0.0, |d| idx.vector.similarity(d)),
+                    _ => p.rrf_score,
+                })
+                .collect();
+            let picked = self.diversify(&ids, &relevance, limit, params.lambda).await?;
+            papers = picked.into_iter().map(|i| papers[i].clone()).collect();
+        }
+        if mode_str != "vector" {
+            if let Err(e) = index::hybrid::add_snippets(&idx.fulltext, &query, &mut papers) {
+                tracing::warn!("Failed to build snippets: {}", e);
+            }
+        }
+
+        let summarize = params.summarize_authors.unwrap_or(false);
+        self.summarized_response("search_local", &papers, summarize, Diagnostics::new(), &warnings)
+    }
+
+    #[tool(description = "Search for semantically similar papers in the local index using SPECTER2 embeddings. Each result carries its vector distance under the configured metric (PAPER_SEARCH_VECTOR_DISTANCE: cosine by default, l2 or dot) and a 0-1 similarity derived from it (1 - d/2 for cosine and dot, 1/(1+d) for l2); results are sorted by similarity, highest first, unless diversified.", annotations(read_only_hint = true, open_world_hint = false))]
+    async fn search_similar(
+        &self,
+        Parameters(params): Parameters<SearchSimilarParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let limit = self.max_results(params.limit, self.config.max_results_cap) as usize;
+        let embedding = self.embed_query(&params.query).await;
+        let idx = &self.reader;
+
+        let exclude = params.exclude_ids.unwrap_or_default();
+        let diversify = params.diversify.unwrap_or(false);
+        let fetch = if diversify { limit * index::mmr::CANDIDATE_FACTOR } else { limit };
+        let mut results = idx.vector.search_similar_excluding(&embedding, fetch, &exclude).await
+            .map_err(|e| McpError::internal_error(format!("Vector search failed: {}", e), None))?;
+        if diversify {
+            let ids: Vec<String> = results.iter().map(|(id, _)| id.clone()).collect();
+            let relevance: Vec<f32> = results.iter().map(|(_, distance)| idx.vector.similarity(*distance)).collect();
+            let picked = self.diversify(&ids, &relevance, limit, params.lambda).await?;
+            results = picked.into_iter().map(|i| results[i].clone()).collect();
+        }
+
+        let mut papers = Vec::new();
+        for (id, distance) in &results {
+            if let Ok(Some(paper)) = idx.vector.get_paper(id).await {
+                papers.push(SimilarPaper { paper, distance: *distance, similarity: idx.vector.similarity(*distance) });
+            }
+        }
+        if !diversify {
+            // MMR order is deliberate; otherwise make the ranking explicit
+            papers.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+        }
+
+        let mut diagnostics = Diagnostics::new();
+        diagnostics.insert("metric".into(), idx.vector.distance_name().into());
+        self.results_response("search_similar", &papers, diagnostics)
+    }
+
+    #[tool(description = "Recommend locally indexed papers most similar to an already-indexed paper", annotations(read_only_hint = true, open_world_hint = false))]
+    async fn recommend(
+        &self,
+        Parameters(params): Parameters<RecommendParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let limit = self.max_results(params.limit, self.config.max_results_cap) as usize;
+        let idx = &self.reader;
+
+        let embedding = idx.vector.get_embedding(&params.id).await
+            .map_err(|e| McpError::internal_error(format!("Failed to read embedding: {}", e), None))?
+            .ok_or_else(|| McpError::invalid_params(format!("Paper not indexed: {}", params.id), None))?;
+
+        // The seed paper is its own nearest neighbor, so always exclude it
+        let mut exclude = params.exclude_ids.unwrap_or_default();
+        exclude.push(params.id.clone());
+        let results = idx.vector.search_similar_excluding(&embedding, limit, &exclude).await
+            .map_err(|e| McpError::internal_error(format!("Vector search failed: {}", e), None))?;
+
+        let mut papers = Vec::new();
+        for (id, _distance) in &results {
+            if let Ok(Some(paper)) = idx.vector.get_paper(id).await {
+                papers.push(paper);
+            }
+        }
+
+        self.results_response("recommend", &papers, Diagnostics::new())
+    }
+
+    #[tool(description = "Fetch a paper from an API source and add it to the local index with embedding", annotations(read_only_hint = false, open_world_hint = true))]
+    async fn index_paper(
+        &self,
+        Parameters(params): Parameters<IndexPaperParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let mut paper = None;
         for src in self.sources.iter() {
             if let Some(ref target) = params.source {
                 if !src.name().eq_ignore_ascii_case(target) {
                     continue;
                 }
             }
-            match src.get_paper(&params.id).await {
+            let fetched = if self.config.store_raw_json {
+                src.get_paper_with_raw(&params.id).await
+            } else {
+                src.get_paper(&params.id).await.map(|p| p.map(|p| (p, None)))
+            };
+            match fetched {
                 Ok(Some(p)) => { paper = Some(p); break; }
                 Ok(None) => continue,
                 Err(e) => {
@@ -306,40 +1343,122 @@ impl PaperSearchServer {
             }
         }
 
-        let paper = paper.ok_or_else(|| {
+        let (paper, raw) = paper.ok_or_else(|| {
             McpError::invalid_params(format!("Paper not found: {}", params.id), None)
         })?;
 
-        let mut idx = self.local_index.lock().await;
-        idx.index_paper_mock(&paper).await
+        let embedding = self.embed_paper(&paper).await
             .map_err(|e| McpError::internal_error(format!("Indexing failed: {}", e), None))?;
+        let entry = index::queue::IndexEntry {
+            embedding,
+            paper: paper.clone(),
+            text: index::vectordb::StoredText { raw_json: raw.map(|raw| raw.to_string()), ..Default::default() },
+            replaces: Vec::new(),
+        };
+        let outcome = self.index_queue.index(vec![entry], false).await
+            .map_err(|e| McpError::internal_error(format!("Indexing failed: {}", e), None))?;
+        if let Some((_, e)) = outcome.failed.first() {
+            return Err(McpError::internal_error(format!("Indexing failed: {}", e), None));
+        }
+
+        if self.config.json_envelope {
+            return self.results_response("index_paper", std::slice::from_ref(&paper), Diagnostics::new());
+        }
 
         Ok(CallToolResult::success(vec![Content::text(
             format!("Indexed: {} - {}", paper.id, paper.title),
         )]))
     }
 
-    #[tool(description = "Search for papers and bulk-index all results into the local index")]
+    #[tool(description = "Re-fetch an indexed paper from its source and re-index it, reporting changed citation count and year", annotations(read_only_hint = false, open_world_hint = true))]
+    async fn update_paper(
+        &self,
+        Parameters(params): Parameters<UpdatePaperParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let old = self.reader.get_paper(&params.id).await
+            .map_err(|e| McpError::internal_error(format!("Index lookup failed: {}", e), None))?
+            .ok_or_else(|| McpError::invalid_params(
+                format!("Paper {} is not in the local index; use index_paper first", params.id),
+                None,
+            ))?;
+
+        let fresh = self.fetch_fresh(&params.id, params.source.as_deref()).await.ok_or_else(|| {
+            McpError::invalid_params(format!("Paper not found in sources: {}", params.id), None)
+        })?;
+        // Keep the indexed ID even if the source reports another form of it.
+        let paper = apis::PaperResult { id: old.id.clone(), ..fresh.paper };
+
+        let embedding = self.embed_paper(&paper).await
+            .map_err(|e| McpError::internal_error(format!("Re-indexing failed: {}", e), None))?;
+        self.local_index.lock().await.update_paper(&paper, &embedding).await
+            .map_err(|e| McpError::internal_error(format!("Re-indexing failed: {}", e), None))?;
+
+        let show = |v: Option<u32>| v.map_or_else(|| "unknown".to_string(), |v| v.to_string());
+        let diff = |field: &str, before: Option<u32>, after: Option<u32>| {
+            if before == after {
+                format!("{}: {} (unchanged)", field, show(after))
+            } else {
+                format!("{}: {} -> {}", field, show(before), show(after))
+            }
+        };
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "Updated: {} - {} (from {})\n{}\n{}",
+            paper.id,
+            paper.title,
+            fresh.source,
+            diff("citation_count", old.citation_count, paper.citation_count),
+            diff("year", old.year, paper.year),
+        ))]))
+    }
+
+    #[tool(description = "Search for papers and bulk-index all results into the local index. If the call is cancelled, papers indexed so far are kept.", annotations(read_only_hint = false, open_world_hint = true))]
     async fn index_from_query(
         &self,
         Parameters(params): Parameters<IndexFromQueryParams>,
+        context: rmcp::service::RequestContext<rmcp::RoleServer>,
     ) -> Result<CallToolResult, McpError> {
-        let max = params.max_results.unwrap_or(10).min(50);
+        let cancelled = || McpError::internal_error("index_from_query was cancelled".to_string(), None);
+        let max = self.max_results(params.max_results, 50);
         let source_filter = params.source.map(|s| vec![s]);
 
         let papers = search::federated_search(
             &self.sources,
             &params.query,
             max,
+            0,
             source_filter.as_deref(),
-        ).await;
+            Default::default(),
+            self.config.source_timeout,
+        ).await.papers;
 
-        let mut idx = self.local_index.lock().await;
-        let mut indexed = 0;
+        let mut batch = Vec::with_capacity(papers.len());
         for paper in &papers {
-            if idx.index_paper_mock(paper).await.is_ok() {
-                indexed += 1;
+            if context.ct.is_cancelled() {
+                return Err(cancelled());
             }
+            let embedding = self.embed_paper(paper).await
+                .map_err(|e| McpError::internal_error(format!("Indexing failed: {}", e), None))?;
+            batch.push((paper.clone(), embedding));
+        }
+        // Dropping the queued request on cancellation stops it after the
+        // paper being written; earlier papers stay committed
+        let outcome = tokio::select! {
+            outcome = self.index_queue.index(batch, false) => outcome
+                .map_err(|e| McpError::internal_error(format!("Indexing failed: {}", e), None))?,
+            _ = context.ct.cancelled() => return Err(cancelled()),
+        };
+        for (id, e) in &outcome.failed {
+            tracing::warn!("Indexing failed for {}: {}", id, e);
+        }
+        let indexed_papers: Vec<_> = papers.iter()
+            .filter(|p| outcome.indexed.contains(&p.id))
+            .collect();
+        let indexed = indexed_papers.len();
+
+        if self.config.json_envelope {
+            let mut diagnostics = Diagnostics::new();
+            diagnostics.insert("attempted".into(), papers.len().into());
+            return self.results_response("index_from_query", &indexed_papers, diagnostics);
         }
 
         Ok(CallToolResult::success(vec![Content::text(
@@ -347,7 +1466,561 @@ impl PaperSearchServer {
         )]))
     }
 
-    #[tool(description = "Find open-access PDF URL for a paper via Unpaywall (requires DOI)")]
+    #[tool(description = "Prune the local index by indexing date, citation threshold, or source. All given criteria must match. Dry run by default.", annotations(read_only_hint = false, open_world_hint = false))]
+    async fn prune_index(
+        &self,
+        Parameters(params): Parameters<PruneIndexParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let indexed_before = params.indexed_before.as_deref()
+            .map(parse_timestamp)
+            .transpose()
+            .map_err(|e| McpError::invalid_params(e, None))?;
+        let criteria = index::PruneCriteria {
+            indexed_before,
+            min_citations: params.min_citations,
+            sources: params.sources.unwrap_or_default(),
+        };
+        if criteria.is_empty() {
+            return Err(McpError::invalid_params(
+                "At least one prune criterion is required".to_string(),
+                None,
+            ));
+        }
+        let dry_run = params.dry_run.unwrap_or(true);
+
+        let mut idx = self.local_index.lock().await;
+        let ids = idx.prune(&criteria, dry_run).await
+            .map_err(|e| McpError::internal_error(format!("Prune failed: {}", e), None))?;
+
+        let verb = if dry_run { "Would remove" } else { "Removed" };
+        Ok(CallToolResult::success(vec![Content::text(
+            format!("{} {} papers: {}", verb, ids.len(), ids.join(", ")),
+        )]))
+    }
+
+    #[tool(description = "Find indexed papers that are the same work (shared DOI or arXiv ID, or matching titles, as in search deduplication), keep one merged record per group with the richest metadata (re-embedded when its text changed; stored raw records and PDF text carried over) and delete the rest. Reports each group with whether it was merged, so one failing group doesn't hide the others; dry_run (default true) only reports them.", annotations(read_only_hint = false, open_world_hint = false))]
+    async fn merge_duplicates(
+        &self,
+        Parameters(params): Parameters<MergeDuplicatesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let dry_run = params.dry_run.unwrap_or(true);
+        // Scan and group through the read handle so indexing isn't blocked
+        // while titles are compared pairwise.
+        let papers = self.reader.vector.all_papers().await
+            .map_err(|e| McpError::internal_error(format!("Failed to read index: {}", e), None))?;
+        let scanned = papers.len();
+        let originals: std::collections::HashMap<String, apis::PaperResult> =
+            papers.iter().map(|p| (p.id.clone(), p.clone())).collect();
+        let groups = tokio::task::spawn_blocking(move || search::canonicalize(papers, search::DEFAULT_TITLE_SIMILARITY))
+            .await
+            .map_err(|e| McpError::internal_error(format!("Grouping failed: {}", e), None))?;
+        let duplicates: Vec<search::CanonicalGroup> = groups.into_iter().filter(|g| g.members.len() > 1).collect();
+
+        // Per kept ID: None once merged, or why the group wasn't
+        let mut statuses: std::collections::HashMap<String, Option<String>> = std::collections::HashMap::new();
+        if !dry_run {
+            let mut entries = Vec::new();
+            for group in &duplicates {
+                let kept = &group.canonical;
+                // Stored raw records and body text carry over from whichever
+                // member had them, the kept record's own first
+                let mut text = index::vectordb::StoredText::default();
+                let mut members: Vec<&String> = vec![&kept.id];
+                members.extend(group.members.iter().filter(|id| **id != kept.id));
+                let mut read_error = None;
+                for id in members {
+                    match self.reader.vector.stored_text(id).await {
+                        Ok(stored) => text = text.or(stored),
+                        Err(e) => read_error = Some(format!("Failed to read stored text of {}: {}", id, e)),
+                    }
+                }
+                if let Some(e) = read_error {
+                    statuses.insert(kept.id.clone(), Some(e));
+                    continue;
+                }
+
+                let unchanged = originals.get(&kept.id).is_some_and(|original| {
+                    original.title == kept.title && original.abstract_text == kept.abstract_text
+                });
+                let stored = if unchanged {
+                    self.reader.vector.get_embedding(&kept.id).await.ok().flatten()
+                } else {
+                    None
+                };
+                let embedding = match stored {
+                    Some(embedding) => embedding,
+                    None => match self.embed_paper(kept).await {
+                        Ok(embedding) => embedding,
+                        Err(e) => {
+                            statuses.insert(kept.id.clone(), Some(e.to_string()));
+                            continue;
+                        }
+                    },
+                };
+                entries.push(index::queue::IndexEntry {
+                    paper: kept.clone(),
+                    embedding,
+                    text,
+                    replaces: group.members.iter().filter(|id| **id != kept.id).cloned().collect(),
+                });
+            }
+            let outcome = self.index_queue.index(entries, false).await
+                .map_err(|e| McpError::internal_error(format!("Merging failed: {}", e), None))?;
+            statuses.extend(outcome.indexed.into_iter().map(|id| (id, None)));
+            statuses.extend(outcome.failed.into_iter().map(|(id, e)| (id, Some(e))));
+        }
+
+        let mut removed = 0;
+        let groups: Vec<serde_json::Value> = duplicates
+            .iter()
+            .map(|g| {
+                let merged: Vec<&String> = g.members.iter().filter(|id| **id != g.canonical.id).collect();
+                let mut report = serde_json::json!({ "kept": g.canonical.id, "title": g.canonical.title, "merged": merged });
+                if !dry_run {
+                    match statuses.get(&g.canonical.id) {
+                        Some(None) => {
+                            removed += merged.len();
+                            report["status"] = "merged".into();
+                        }
+                        Some(Some(e)) => {
+                            report["status"] = "failed".into();
+                            report["error"] = e.as_str().into();
+                        }
+                        None => {
+                            report["status"] = "failed".into();
+                            report["error"] = "Not processed".into();
+                        }
+                    }
+                }
+                report
+            })
+            .collect();
+        let json = serde_json::to_string_pretty(&serde_json::json!({
+            "dry_run": dry_run,
+            "scanned": scanned,
+            "duplicate_groups": groups.len(),
+            "removed": removed,
+            "groups": groups,
+        }))
+        .map_err(|e| McpError::internal_error(format!("Serialization error: {}", e), None))?;
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    #[tool(description = "Import every paper indexed in another data directory (e.g. copied from another machine) into this index, keeping its stored embeddings rather than recomputing them. Papers with an ID already indexed here are replaced and reported as conflicts. Both indexes must use the same embedding dimension and model. The directory must be inside this server's data directory; give its path relative to it.", annotations(read_only_hint = false, open_world_hint = false))]
+    async fn import_from_dir(
+        &self,
+        Parameters(params): Parameters<ImportFromDirParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let dir = existing_data_dir_path(&self.config.data_dir, params.path.trim())
+            .map_err(|e| McpError::invalid_params(e, None))?;
+        let lance_dir = dir.join("lance");
+        if !lance_dir.is_dir() {
+            return Err(McpError::invalid_params(
+                format!("{} is not a paper-search data directory (no lance/ index)", dir.display()),
+                None,
+            ));
+        }
+        let same_dir = match (dir.canonicalize(), self.config.data_dir.canonicalize()) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => false,
+        };
+        if same_dir {
+            return Err(McpError::invalid_params("Cannot import the index into itself".to_string(), None));
+        }
+
+        // Opening checks the other store's schema and embedding dimension
+        // against ours, and never writes to it
+        let other = index::vectordb::VectorStore::open_read_only(&lance_dir, self.config.embedding_dim).await
+            .map_err(|e| McpError::invalid_params(format!("Cannot import from {}: {:#}", dir.display(), e), None))?;
+        // Imported rows are labelled with our model, so vectors from another
+        // one (mock ones in particular) would pass for ours
+        let model = self.reader.vector.embedding_model();
+        let foreign = other.count_other_models(model).await
+            .map_err(|e| McpError::internal_error(format!("Failed to read {}: {}", dir.display(), e), None))?;
+        if foreign > 0 {
+            return Err(McpError::invalid_params(
+                format!(
+                    "Cannot import from {}: {} of its papers were embedded by another model than this server's ({})",
+                    dir.display(),
+                    foreign,
+                    model
+                ),
+                None,
+            ));
+        }
+        let papers = other.all_papers_with_embeddings().await
+            .map_err(|e| McpError::internal_error(format!("Failed to read {}: {}", dir.display(), e), None))?;
+        let ids: Vec<String> = papers.iter().map(|(p, _)| p.id.clone()).collect();
+        let mut conflicts: Vec<String> = self.reader.vector.existing_ids(&ids).await
+            .map_err(|e| McpError::internal_error(format!("Failed to check existing papers: {}", e), None))?
+            .into_iter()
+            .collect();
+        conflicts.sort();
+
+        let outcome = self.index_queue.index(papers, false).await
+            .map_err(|e| McpError::internal_error(format!("Import failed: {}", e), None))?;
+        let failed: Vec<serde_json::Value> = outcome
+            .failed
+            .iter()
+            .map(|(id, e)| serde_json::json!({ "id": id, "error": e }))
+            .collect();
+
+        let json = serde_json::to_string_pretty(&serde_json::json!({
+            "path": dir.display().to_string(),
+            "found": ids.len(),
+            "imported": outcome.indexed.len(),
+            "conflicts": conflicts,
+            "failed": failed,
+        }))
+        .map_err(|e| McpError::internal_error(format!("Serialization error: {}", e), None))?;
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    #[tool(description = "Index papers from a JSON Lines file without contacting any source: one paper object per line, as returned by the search tools. Papers are embedded like index_paper. Already-indexed IDs are skipped (without embedding them) unless overwrite is set; blank lines are ignored and repeated IDs after the first are reported as errors. Reports imported and skipped counts and, for lines that fail, the line number and error. The file must be inside the data directory; give its path relative to it.", annotations(read_only_hint = false, open_world_hint = false))]
+    async fn import_jsonl(
+        &self,
+        Parameters(params): Parameters<ImportJsonlParams>,
+    ) -> Result<CallToolResult, McpError> {
+        use tokio::io::AsyncBufReadExt;
+
+        let path = existing_data_dir_path(&self.config.data_dir, params.path.trim())
+            .map_err(|e| McpError::invalid_params(e, None))?;
+        let file = tokio::fs::File::open(&path).await
+            .map_err(|e| McpError::invalid_params(format!("Cannot read {}: {}", path.display(), e), None))?;
+        let mut reader = tokio::io::BufReader::new(file).lines();
+        let skip_existing = !params.overwrite.unwrap_or(false);
+
+        let mut errors: Vec<(usize, String)> = Vec::new();
+        // Line of each ID's first occurrence; later ones are reported as duplicates
+        let mut lines: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+        let (mut imported, mut skipped) = (0, 0);
+        let mut line_no = 0;
+        let mut done = false;
+        while !done {
+            let mut chunk = Vec::new();
+            while chunk.len() < IMPORT_CHUNK_SIZE {
+                let line = match reader.next_line().await {
+                    Ok(Some(line)) => line,
+                    Ok(None) => {
+                        done = true;
+                        break;
+                    }
+                    Err(e) => return Err(McpError::invalid_params(format!("Cannot read {}: {}", path.display(), e), None)),
+                };
+                line_no += 1;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let paper: apis::PaperResult = match serde_json::from_str(&line) {
+                    Ok(paper) => paper,
+                    Err(e) => {
+                        errors.push((line_no, format!("invalid paper JSON: {}", e)));
+                        continue;
+                    }
+                };
+                if paper.id.trim().is_empty() || paper.title.trim().is_empty() {
+                    errors.push((line_no, "paper needs a non-empty id and title".to_string()));
+                    continue;
+                }
+                if let Some(first) = lines.get(&paper.id) {
+                    errors.push((line_no, format!("duplicate of line {}", first)));
+                    continue;
+                }
+                lines.insert(paper.id.clone(), line_no);
+                chunk.push(paper);
+            }
+
+            // Only papers that will be written are embedded
+            if skip_existing {
+                let ids: Vec<String> = chunk.iter().map(|p| p.id.clone()).collect();
+                let existing = self.reader.vector.existing_ids(&ids).await
+                    .map_err(|e| McpError::internal_error(format!("Failed to check indexed papers: {}", e), None))?;
+                skipped += existing.len();
+                chunk.retain(|p| !existing.contains(&p.id));
+            }
+            if chunk.is_empty() {
+                continue;
+            }
+            // Embedded like index_paper, with the mock only when no model is
+            // loaded, and written through the queue rather than the index
+            // directly, so imports never mix vectors from two embedders or
+            // race other writes
+            let mut batch = Vec::with_capacity(chunk.len());
+            for paper in chunk {
+                match self.embed_paper(&paper).await {
+                    Ok(embedding) => batch.push((paper, embedding)),
+                    Err(e) => errors.push((lines.get(&paper.id).copied().unwrap_or(0), e.to_string())),
+                }
+            }
+            let outcome = self.index_queue.index(batch, skip_existing).await
+                .map_err(|e| McpError::internal_error(format!("Import failed: {}", e), None))?;
+            imported += outcome.indexed.len();
+            skipped += outcome.skipped.len();
+            errors.extend(outcome.failed.iter().map(|(id, e)| (lines.get(id).copied().unwrap_or(0), e.clone())));
+        }
+        errors.sort();
+        let errors: Vec<serde_json::Value> = errors
+            .into_iter()
+            .map(|(line, error)| serde_json::json!({ "line": line, "error": error }))
+            .collect();
+
+        let json = serde_json::to_string_pretty(&serde_json::json!({
+            "path": path.display().to_string(),
+            "imported": imported,
+            "skipped": skipped,
+            "errored": errors.len(),
+            "errors": errors,
+        }))
+        .map_err(|e| McpError::internal_error(format!("Serialization error: {}", e), None))?;
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    #[tool(description = "Back up the local index as JSON Lines in a file under the data directory: every indexed paper, in ID order, one object per line in the format import_jsonl reads. Embeddings are not included; importing re-embeds the papers. Returns the number of papers written.", annotations(read_only_hint = false, open_world_hint = false))]
+    async fn export_jsonl(
+        &self,
+        Parameters(params): Parameters<ExportJsonlParams>,
+    ) -> Result<CallToolResult, McpError> {
+        use tokio::io::AsyncWriteExt;
+
+        let path = data_dir_path(&self.config.data_dir, params.path.trim())
+            .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+        let failed = |e: &dyn std::fmt::Display| {
+            McpError::internal_error(format!("Failed to write {}: {}", path.display(), e), None)
+        };
+        // Write beside the target and rename once complete, so a failed
+        // export never replaces an earlier backup with a partial one.
+        let partial = path.with_extension("jsonl.part");
+        let file = tokio::fs::File::create(&partial).await.map_err(|e| failed(&e))?;
+        let mut out = tokio::io::BufWriter::new(file);
+
+        let result = async {
+            // Snapshot the IDs once; papers removed while exporting are left out
+            let ids = self.reader.vector.sorted_ids().await?;
+            let mut written = 0;
+            for chunk in ids.chunks(EXPORT_PAGE_SIZE) {
+                let page = self.reader.vector.papers_by_ids(chunk).await?;
+                for paper in &page {
+                    let mut line = serde_json::to_vec(paper)?;
+                    line.push(b'\n');
+                    out.write_all(&line).await?;
+                }
+                written += page.len();
+            }
+            out.flush().await?;
+            Ok::<_, anyhow::Error>(written)
+        }
+        .await;
+        let written = match result {
+            Ok(written) => written,
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&partial).await;
+                return Err(failed(&e));
+            }
+        };
+        tokio::fs::rename(&partial, &path).await.map_err(|e| failed(&e))?;
+
+        let json = serde_json::to_string_pretty(&serde_json::json!({
+            "path": path.display().to_string(),
+            "written": written,
+        }))
+        .map_err(|e| McpError::internal_error(format!("Serialization error: {}", e), None))?;
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    #[tool(description = "Browse the local index: a page of indexed papers in ID order, with the total count", annotations(read_only_hint = true, open_world_hint = false))]
+    async fn list_indexed(
+        &self,
+        Parameters(params): Parameters<ListIndexedParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let offset = params.offset.unwrap_or(0) as usize;
+        let limit = self.max_results(params.limit, self.config.max_results_cap) as usize;
+        let vector = &self.reader.vector;
+        let total = vector.count().await
+            .map_err(|e| McpError::internal_error(format!("Failed to count papers: {}", e), None))?;
+        let papers = vector.list_papers(offset, limit).await
+            .map_err(|e| McpError::internal_error(format!("Failed to list papers: {}", e), None))?;
+
+        let json = serde_json::to_string_pretty(&serde_json::json!({
+            "total": total,
+            "offset": offset,
+            "papers": papers,
+        }))
+        .map_err(|e| McpError::internal_error(format!("Serialization error: {}", e), None))?;
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    #[tool(description = "Summarize the local index: total papers and how many were detected in each language (ISO 639-1 codes; 'unknown' for papers whose language couldn't be detected or that were indexed before tagging)", annotations(read_only_hint = true, open_world_hint = false))]
+    async fn index_stats(&self) -> Result<CallToolResult, McpError> {
+        let vector = &self.reader.vector;
+        let total = vector.count().await
+            .map_err(|e| McpError::internal_error(format!("Failed to count papers: {}", e), None))?;
+        let languages: serde_json::Map<String, serde_json::Value> = vector.language_counts().await
+            .map_err(|e| McpError::internal_error(format!("Failed to count languages: {}", e), None))?
+            .into_iter()
+            .map(|(language, count)| (language.unwrap_or_else(|| "unknown".to_string()), count.into()))
+            .collect();
+
+        let json = serde_json::to_string_pretty(&serde_json::json!({
+            "total": total,
+            "languages": languages,
+        }))
+        .map_err(|e| McpError::internal_error(format!("Serialization error: {}", e), None))?;
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    #[tool(description = "Delete all cached source responses (get_paper and search results kept on disk), forcing fresh upstream requests", annotations(read_only_hint = false, open_world_hint = false))]
+    async fn clear_cache(&self) -> Result<CallToolResult, McpError> {
+        let Some(disk_cache) = &self.disk_cache else {
+            return Ok(CallToolResult::success(vec![Content::text(
+                "Response cache is disabled (PAPER_SEARCH_CACHE_TTL_SECS=0)",
+            )]));
+        };
+        let removed = disk_cache.clear()
+            .map_err(|e| McpError::internal_error(format!("Failed to clear cache: {}", e), None))?;
+        Ok(CallToolResult::success(vec![Content::text(
+            format!("Removed {} cached responses", removed),
+        )]))
+    }
+
+    #[tool(description = "Delete every paper from the local index. Irreversible; requires confirm=\"yes-delete-everything\".", annotations(read_only_hint = false, open_world_hint = false))]
+    async fn clear_index(
+        &self,
+        Parameters(params): Parameters<ClearIndexParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if params.confirm != CLEAR_INDEX_CONFIRMATION {
+            return Err(McpError::invalid_params(
+                format!("Refusing to clear the index: confirm must be \"{}\"", CLEAR_INDEX_CONFIRMATION),
+                None,
+            ));
+        }
+        let (papers, documents) = self.local_index.lock().await.clear().await
+            .map_err(|e| McpError::internal_error(format!("Clearing index failed: {}", e), None))?;
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "Cleared local index: removed {} papers ({} fulltext documents)",
+            papers, documents,
+        ))]))
+    }
+
+    #[tool(description = "Build or rebuild the approximate nearest-neighbour index on paper embeddings. Speeds up vector search on large indexes at some cost in recall.", annotations(read_only_hint = false, open_world_hint = false))]
+    async fn reindex_vectors(
+        &self,
+        Parameters(params): Parameters<ReindexVectorsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let mut idx = self.local_index.lock().await;
+        idx.rebuild_vector_index(params.num_partitions).await
+            .map_err(|e| McpError::internal_error(format!("Vector index build failed: {}", e), None))?;
+        let count = idx.count().await.unwrap_or(0);
+        Ok(CallToolResult::success(vec![Content::text(
+            format!("Built vector index over {} papers", count),
+        )]))
+    }
+
+    #[tool(description = "Export papers as BibTeX entries for a reference manager", annotations(read_only_hint = true, open_world_hint = true))]
+    async fn export_bibtex(
+        &self,
+        Parameters(params): Parameters<ExportParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let (papers, missing) = self.resolve_papers(&params.ids).await;
+        let mut text = export::to_bibtex(&papers);
+        if !missing.is_empty() {
+            text.push_str(&format!("% Not found: {}\n", missing.join(", ")));
+        }
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    #[tool(description = "Get BibTeX for papers, preferring the source's own entry (INSPIRE, ADS, DOI content negotiation) and generating one otherwise", annotations(read_only_hint = true, open_world_hint = true))]
+    async fn get_bibtex(
+        &self,
+        Parameters(params): Parameters<ExportParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let mut text = String::new();
+        let mut generated = Vec::new();
+        let mut missing = Vec::new();
+        for id in &params.ids {
+            if let Some(bibtex) = self.native_bibtex(id).await {
+                text.push_str(&bibtex);
+                text.push_str("\n\n");
+                continue;
+            }
+            match self.lookup_paper(id, None).await {
+                Some(lookup) => generated.push(lookup.paper),
+                None => missing.push(id.clone()),
+            }
+        }
+        // Generate the fallbacks together so their citation keys stay unique.
+        text.push_str(&export::to_bibtex(&generated));
+        if !missing.is_empty() {
+            text.push_str(&format!("% Not found: {}\n", missing.join(", ")));
+        }
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    #[tool(description = "Get a paper's metadata as a CSL-JSON item for citation processors. Uses DOI content negotiation when the paper has a DOI, generating the item from the resolved record otherwise; reports which it used and any required CSL fields (type, title, author, issued) still missing.", annotations(read_only_hint = true, open_world_hint = true))]
+    async fn get_csl_json(
+        &self,
+        Parameters(params): Parameters<GetCslJsonParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let id = params.id.trim();
+        let bare = normalize_doi(id);
+        let mut paper = None;
+        let doi = if bare.starts_with("10.") {
+            Some(bare.to_string())
+        } else {
+            paper = self.lookup_paper(id, None).await.map(|l| l.paper);
+            paper.as_ref().and_then(|p| p.doi.clone())
+        };
+
+        let mut negotiated = None;
+        if let Some(doi) = &doi {
+            match self.crossref.get_csl_json(doi).await {
+                Ok(Some(item)) if export::missing_csl_fields(&item).is_empty() => negotiated = Some(item),
+                Ok(Some(item)) => tracing::warn!(
+                    "CSL-JSON for {} lacks {:?}; generating from metadata",
+                    doi,
+                    export::missing_csl_fields(&item)
+                ),
+                Ok(None) => {}
+                Err(e) => tracing::warn!("CSL-JSON content negotiation failed for {}: {}", doi, e),
+            }
+        }
+
+        let (item, provider) = match negotiated {
+            Some(item) => (item, "doi.org"),
+            None => {
+                let paper = match paper {
+                    Some(paper) => paper,
+                    None => self.lookup_paper(id, None).await.map(|l| l.paper).ok_or_else(|| {
+                        McpError::resource_not_found(format!("Paper not found: {}", id), None)
+                    })?,
+                };
+                (export::to_csl_json(&paper), "generated")
+            }
+        };
+        let json = serde_json::to_string_pretty(&serde_json::json!({
+            "id": id,
+            "provider": provider,
+            "missing_fields": export::missing_csl_fields(&item),
+            "csl": item,
+        }))
+        .map_err(|e| McpError::internal_error(format!("Serialization error: {}", e), None))?;
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    #[tool(description = "Export papers as RIS records for EndNote/Zotero. IDs that could not be resolved are listed in a second content item, never in the RIS text", annotations(read_only_hint = true, open_world_hint = true))]
+    async fn export_ris(
+        &self,
+        Parameters(params): Parameters<ExportParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let (papers, missing) = self.resolve_papers(&params.ids).await;
+        // RIS has no comment syntax, so any other line breaks strict importers
+        let mut content = vec![Content::text(export::to_ris(&papers))];
+        if !missing.is_empty() {
+            content.push(Content::text(format!("Not found: {}", missing.join(", "))));
+        }
+        Ok(CallToolResult::success(content))
+    }
+
+    #[tool(description = "Find open-access PDF URL for a paper via Unpaywall (requires DOI)", annotations(read_only_hint = true, open_world_hint = true))]
     async fn get_pdf_url(
         &self,
         Parameters(params): Parameters<GetPdfUrlParams>,
@@ -369,19 +2042,650 @@ impl PaperSearchServer {
             Err(e) => Err(McpError::internal_error(format!("Unpaywall error: {}", e), None)),
         }
     }
-}
 
-impl PaperSearchServer {
-    /// Helper: query citations or references from the best matching source.
-    async fn query_relation<F>(
+    #[tool(description = "Return the source's original JSON record stored for an indexed paper. Records are kept only while PAPER_SEARCH_STORE_RAW_JSON is enabled, for papers fetched by ID (index_paper, index_pdf) from sources with a JSON record endpoint (semantic_scholar, openalex, crossref, inspire); papers indexed from search results have none", annotations(read_only_hint = true, open_world_hint = false))]
+    async fn get_raw(
         &self,
-        id: &str,
-        source: Option<&str>,
-        f: F,
-    ) -> Vec<apis::PaperResult>
-    where
-        F: for<'a> Fn(
-            &'a Arc<dyn PaperSource>,
+        Parameters(params): Parameters<GetRawParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let raw = self.reader.vector.stored_text(&params.id).await
+            .map_err(|e| McpError::internal_error(format!("Failed to read raw record: {}", e), None))?
+            .raw_json
+            .ok_or_else(|| McpError::resource_not_found(format!("No raw record stored for {}", params.id), None))?;
+        // Stored compact; re-render for readability
+        let json = serde_json::from_str::<serde_json::Value>(&raw)
+            .and_then(|value| serde_json::to_string_pretty(&value))
+            .unwrap_or(raw);
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    #[tool(description = "Download a paper's open-access PDF into the data directory (data_dir/pdfs/{id}.pdf, with characters unsafe in file names escaped). The URL is resolved as in find_open_access; redirects are followed, and responses that aren't PDFs or exceed the size limit (PAPER_SEARCH_PDF_MAX_MB, default 50) are rejected. Returns the local path and size in bytes.", annotations(read_only_hint = false, open_world_hint = true))]
+    async fn download_pdf(
+        &self,
+        Parameters(params): Parameters<DownloadPdfParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let pdf = self.fetch_pdf(params).await?;
+        let json = serde_json::to_string_pretty(&serde_json::json!({
+            "id": pdf.paper.id,
+            "provider": pdf.provider,
+            "pdf_url": pdf.url,
+            "path": pdf.path.display().to_string(),
+            "bytes": pdf.bytes,
+        }))
+        .map_err(|e| McpError::internal_error(format!("Serialization error: {}", e), None))?;
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    #[tool(description = "Download a paper's open-access PDF (as download_pdf), extract its text, and index the paper with that body text so local keyword search also matches the full paper, at a lower weight than title and abstract matches. Requires the pdf-text build feature.", annotations(read_only_hint = false, open_world_hint = true))]
+    async fn index_pdf(
+        &self,
+        Parameters(params): Parameters<DownloadPdfParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let pdf = self.fetch_pdf(params).await?;
+        let path = pdf.path.clone();
+        let body = tokio::task::spawn_blocking(move || pdf::extract_text(&path))
+            .await
+            .map_err(|e| McpError::internal_error(format!("PDF text extraction crashed: {}", e), None))?
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        let body = body.split_whitespace().collect::<Vec<_>>().join(" ");
+        if body.is_empty() {
+            return Err(McpError::internal_error(
+                format!("No text found in {} (scanned PDF?)", pdf.path.display()),
+                None,
+            ));
+        }
+
+        let embedding = self.embed_paper(&pdf.paper).await
+            .map_err(|e| McpError::internal_error(format!("Indexing failed: {}", e), None))?;
+        let entry = index::queue::IndexEntry {
+            embedding,
+            paper: pdf.paper.clone(),
+            text: index::vectordb::StoredText {
+                raw_json: pdf.raw.map(|raw| raw.to_string()),
+                body: Some(body.clone()),
+            },
+            replaces: Vec::new(),
+        };
+        let outcome = self.index_queue.index(vec![entry], false).await
+            .map_err(|e| McpError::internal_error(format!("Indexing failed: {}", e), None))?;
+        if let Some((_, e)) = outcome.failed.first() {
+            return Err(McpError::internal_error(format!("Indexing failed: {}", e), None));
+        }
+
+        let json = serde_json::to_string_pretty(&serde_json::json!({
+            "id": pdf.paper.id,
+            "title": pdf.paper.title,
+            "path": pdf.path.display().to_string(),
+            "bytes": pdf.bytes,
+            "body_chars": body.chars().count(),
+        }))
+        .map_err(|e| McpError::internal_error(format!("Serialization error: {}", e), None))?;
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    #[tool(description = "Find an open-access PDF for any paper ID. Resolves the paper, then tries Unpaywall (by DOI), the paper's own PDF link, OpenAlex, and arXiv, returning the first URL that answers and which provider supplied it.", annotations(read_only_hint = true, open_world_hint = true))]
+    async fn find_open_access(
+        &self,
+        Parameters(params): Parameters<FindOpenAccessParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let lookup = self.lookup_paper(&params.id, None).await
+            .ok_or_else(|| McpError::invalid_params(format!("Paper not found: {}", params.id), None))?;
+        let paper = lookup.paper;
+        let doi = paper.doi.as_deref().map(normalize_doi).filter(|d| !d.is_empty());
+        let (found, tried) = self.resolve_open_access(&paper).await;
+
+        let (provider, pdf_url) = found.unzip();
+        let json = serde_json::to_string_pretty(&serde_json::json!({
+            "id": paper.id,
+            "doi": doi,
+            "pdf_url": pdf_url,
+            "provider": provider,
+            "tried": tried,
+        }))
+        .map_err(|e| McpError::internal_error(format!("Serialization error: {}", e), None))?;
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+}
+
+impl PaperSearchServer {
+    /// Build a server from already-constructed parts.
+    fn new(
+        config: Config,
+        sources: Vec<Arc<dyn PaperSource>>,
+        local_index: LocalIndex,
+        unpaywall: Option<Arc<apis::unpaywall::UnpaywallClient>>,
+    ) -> Self {
+        let crossref = Arc::new(
+            apis::crossref::CrossRefClient::new(&config.http).with_subtitles(config.crossref_subtitles),
+        );
+        let search_cache = Arc::new(cache::SearchCache::new(config.search_cache_ttl));
+        let relation_permits = sources
+            .iter()
+            .map(|src| {
+                let permits = Arc::new(tokio::sync::Semaphore::new(config.relation_concurrency_per_source));
+                (src.name().to_lowercase(), permits)
+            })
+            .collect();
+        let reader = local_index.read_handle();
+        let local_index = Arc::new(Mutex::new(local_index));
+        let index_queue = index::queue::IndexQueue::spawn(
+            local_index.clone(),
+            config.index_batch_size,
+            config.index_fair_queue,
+        );
+        let mut tool_router = instrument_tools(Self::tool_router());
+        for name in &config.disabled_tools {
+            if tool_router.has_route(name) {
+                tool_router.remove_route(name);
+            } else {
+                tracing::warn!("PAPER_SEARCH_DISABLED_TOOLS names unknown tool '{}'", name);
+            }
+        }
+        if !cfg!(feature = "pdf-text") {
+            tool_router.remove_route("index_pdf");
+        }
+        if config.read_only {
+            for tool in tool_router.list_all() {
+                if writes(&tool) {
+                    tool_router.remove_route(&tool.name);
+                }
+            }
+        } else {
+            // get_paper stores refreshed records back and search_papers indexes
+            // its results, so neither is read-only when that is switched on
+            let indexing = [("get_paper", !config.refresh_missing_fields.is_empty()), ("search_papers", config.auto_index)];
+            for (name, _) in indexing.into_iter().filter(|(_, on)| *on) {
+                if let Some(annotations) = tool_router.map.get_mut(name).and_then(|route| route.attr.annotations.as_mut()) {
+                    annotations.read_only_hint = Some(false);
+                }
+            }
+        }
+        Self {
+            tool_router,
+            crossref,
+            search_cache,
+            disk_cache: None,
+            relation_permits: Arc::new(relation_permits),
+            auto_index_permits: Arc::new(tokio::sync::Semaphore::new(MAX_AUTO_INDEX_TASKS)),
+            config: Arc::new(config),
+            sources: Arc::new(sources),
+            local_index,
+            reader,
+            index_queue,
+            unpaywall,
+            #[cfg(feature = "onnx")]
+            embedder: None,
+        }
+    }
+
+    /// Helper: the requested result count, or the configured default, capped at `cap`.
+    fn max_results(&self, requested: Option<u32>, cap: u32) -> u32 {
+        requested.unwrap_or(self.config.default_max_results).min(cap)
+    }
+
+    /// Helper: serialize a tool's result list, wrapped in the typed envelope
+    /// when `PAPER_SEARCH_JSON_ENVELOPE` is enabled.
+    fn results_response<T: Serialize>(
+        &self,
+        kind: &str,
+        results: &[T],
+        diagnostics: Diagnostics,
+    ) -> Result<CallToolResult, McpError> {
+        let json = response::render(kind, results, diagnostics, self.config.json_envelope)
+            .map_err(|e| McpError::internal_error(format!("{}", e), None))?;
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    /// Helper: like `results_response`, also reporting sources that failed.
+    fn results_response_with_warnings<T: Serialize>(
+        &self,
+        kind: &str,
+        results: &[T],
+        diagnostics: Diagnostics,
+        warnings: &[SourceWarning],
+    ) -> Result<CallToolResult, McpError> {
+        let json = response::render_with_warnings(kind, results, diagnostics, warnings, self.config.json_envelope)
+            .map_err(|e| McpError::internal_error(format!("{}", e), None))?;
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    /// Helper: like `results_response_with_warnings`, optionally collapsing
+    /// author lists past the configured position first.
+    fn summarized_response<T: Serialize>(
+        &self,
+        kind: &str,
+        results: &[T],
+        summarize: bool,
+        diagnostics: Diagnostics,
+        warnings: &[SourceWarning],
+    ) -> Result<CallToolResult, McpError> {
+        if !summarize {
+            return self.results_response_with_warnings(kind, results, diagnostics, warnings);
+        }
+        let mut values = results
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| McpError::internal_error(format!("{}", e), None))?;
+        response::summarize_authors(&mut values, self.config.full_author_results);
+        self.results_response_with_warnings(kind, &values, diagnostics, warnings)
+    }
+
+    /// Helper: embed a paper from its title and abstract. Uses SPECTER2 when the
+    /// `onnx` feature is on and the model loaded, otherwise a deterministic mock.
+    /// A failing model is an error rather than a mock vector, which would be
+    /// stored labelled as the model's.
+    async fn embed_paper(&self, paper: &apis::PaperResult) -> anyhow::Result<Vec<f32>> {
+        let cleaned = paper.abstract_text.as_deref()
+            .filter(|_| self.config.clean_abstracts)
+            .map(embed::preprocess::strip_section_labels);
+        let abstract_text = cleaned.as_deref().or(paper.abstract_text.as_deref());
+
+        #[cfg(feature = "onnx")]
+        if let Some(embedder) = &self.embedder {
+            let (title, abstract_text) = (paper.title.clone(), abstract_text.map(str::to_string));
+            return run_embedder(embedder, move |model| model.embed(&title, abstract_text.as_deref()))
+                .await
+                .map(|embedding| embedding.vector)
+                .map_err(|e| anyhow::anyhow!("SPECTER2 embedding failed for {}: {}", paper.id, e));
+        }
+        let text = format!("{} {}", paper.title, abstract_text.unwrap_or(""));
+        Ok(specter::mock_embedding(&text, self.config.embedding_dim))
+    }
+
+    /// Helper: embed a free-text query like `embed_paper`. Queries aren't
+    /// stored, so a failing model falls back to the mock.
+    async fn embed_query(&self, query: &str) -> Vec<f32> {
+        #[cfg(feature = "onnx")]
+        if let Some(embedder) = &self.embedder {
+            let query = query.to_string();
+            match run_embedder(embedder, move |model| model.embed_text(&query)).await {
+                Ok(embedding) => return embedding.vector,
+                Err(e) => tracing::warn!("SPECTER2 query embedding failed; falling back to mock embedding: {}", e),
+            }
+        }
+        specter::mock_embedding(query, self.config.embedding_dim)
+    }
+
+    /// Helper: MMR-select `limit` of the candidate `ids`, weighing each by its
+    /// ranking score in `relevance`, returning their indices in pick order.
+    async fn diversify(
+        &self,
+        ids: &[String],
+        relevance: &[f32],
+        limit: usize,
+        lambda: Option<f32>,
+    ) -> Result<Vec<usize>, McpError> {
+        let lambda = lambda.unwrap_or(index::mmr::DEFAULT_LAMBDA);
+        index::mmr::diversify(&self.reader.vector, ids, relevance, limit, lambda).await
+            .map_err(|e| McpError::internal_error(format!("Failed to diversify results: {}", e), None))
+    }
+
+    /// Helper: one page of indexed papers as resources. The cursor is the
+    /// offset into the index's ID order.
+    async fn resource_page(&self, cursor: Option<&str>) -> Result<ListResourcesResult, McpError> {
+        let offset = match cursor {
+            Some(cursor) => cursor
+                .parse::<usize>()
+                .map_err(|_| McpError::invalid_params(format!("Invalid cursor: {}", cursor), None))?,
+            None => 0,
+        };
+        let vector = &self.reader.vector;
+        let total = vector.count().await
+            .map_err(|e| McpError::internal_error(format!("Failed to count papers: {}", e), None))?;
+        let papers = vector.list_papers(offset, RESOURCE_PAGE_SIZE).await
+            .map_err(|e| McpError::internal_error(format!("Failed to list papers: {}", e), None))?;
+
+        let next = offset + papers.len();
+        let resources = papers
+            .into_iter()
+            .map(|paper| {
+                let mut resource = RawResource::new(format!("{}{}", PAPER_RESOURCE_PREFIX, paper.id), paper.id.clone());
+                resource.title = Some(paper.title.clone());
+                resource.description = Some(match paper.year {
+                    Some(year) => format!("{} ({})", paper.authors.join(", "), year),
+                    None => paper.authors.join(", "),
+                });
+                resource.mime_type = Some("application/json".to_string());
+                resource.no_annotation()
+            })
+            .collect();
+        let mut result = ListResourcesResult::with_all_items(resources);
+        if next < total {
+            result.next_cursor = Some(next.to_string());
+        }
+        Ok(result)
+    }
+
+    /// Helper: the indexed paper behind a `paper://local/{id}` URI, as JSON.
+    async fn read_paper_resource(&self, uri: &str) -> Result<ReadResourceResult, McpError> {
+        let id = uri
+            .strip_prefix(PAPER_RESOURCE_PREFIX)
+            .filter(|id| !id.is_empty())
+            .ok_or_else(|| McpError::resource_not_found(format!("Unknown resource: {}", uri), None))?;
+        let paper = self.reader.get_paper(id).await
+            .map_err(|e| McpError::internal_error(format!("Failed to read paper: {}", e), None))?
+            .ok_or_else(|| McpError::resource_not_found(format!("Paper not indexed: {}", id), None))?;
+        let json = serde_json::to_string_pretty(&paper)
+            .map_err(|e| McpError::internal_error(format!("Serialization error: {}", e), None))?;
+
+        let mut contents = ResourceContents::text(json, uri);
+        if let ResourceContents::TextResourceContents { mime_type, .. } = &mut contents {
+            *mime_type = Some("application/json".to_string());
+        }
+        Ok(ReadResourceResult { contents: vec![contents] })
+    }
+
+    /// Helper: index search results in the background without delaying the response.
+    /// Papers already in the index are skipped; at most `auto_index_limit` are indexed.
+    /// Returns `None` without indexing when `MAX_AUTO_INDEX_TASKS` are already running.
+    fn spawn_auto_index(&self, papers: &[apis::PaperResult]) -> Option<tokio::task::JoinHandle<usize>> {
+        let Ok(permit) = self.auto_index_permits.clone().try_acquire_owned() else {
+            tracing::debug!("Auto-index busy; not indexing {} results", papers.len());
+            return None;
+        };
+        let papers: Vec<_> = papers.iter().take(self.config.auto_index_limit).cloned().collect();
+        let server = self.clone();
+        Some(tokio::spawn(async move {
+            let _permit = permit;
+            let mut batch = Vec::new();
+            for paper in &papers {
+                if matches!(server.reader.get_paper(&paper.id).await, Ok(Some(_))) {
+                    continue;
+                }
+                match server.embed_paper(paper).await {
+                    Ok(embedding) => batch.push((paper.clone(), embedding)),
+                    Err(e) => tracing::warn!("Auto-index skipped {}: {}", paper.id, e),
+                }
+            }
+            if batch.is_empty() {
+                return 0;
+            }
+            let outcome = match server.index_queue.index(batch, true).await {
+                Ok(outcome) => outcome,
+                Err(e) => {
+                    tracing::warn!("Auto-index failed: {}", e);
+                    return 0;
+                }
+            };
+            for (id, e) in &outcome.failed {
+                tracing::warn!("Auto-index failed for {}: {}", id, e);
+            }
+            if !outcome.indexed.is_empty() {
+                tracing::info!("Auto-indexed {} papers: {}", outcome.indexed.len(), outcome.indexed.join(", "));
+            }
+            outcome.indexed.len()
+        }))
+    }
+
+    /// Helper: resolve a paper from the local index first, then from the sources.
+    async fn lookup_paper(&self, id: &str, source: Option<&str>) -> Option<PaperLookup> {
+        self.lookup_paper_traced(id, source, false).await.ok()
+    }
+
+    /// Like `lookup_paper`, but on a miss returns each source's outcome.
+    /// With `include_raw`, a paper fetched from a source carries the
+    /// source's JSON record from the same request.
+    async fn lookup_paper_traced(
+        &self,
+        id: &str,
+        source: Option<&str>,
+        include_raw: bool,
+    ) -> Result<PaperLookup, Vec<SourceAttempt>> {
+        let target_source = source;
+
+        // Check local index first
+        let local = self.reader.get_paper(id).await.ok().flatten();
+        if let Some(paper) = local {
+            let missing = missing_fields(&paper, &self.config.refresh_missing_fields);
+            if !missing.is_empty() {
+                tracing::debug!("Local record for {} lacks {}; refreshing from source", id, missing.join(", "));
+                if let Some(fresh) = self.fetch_fresh(id, target_source).await {
+                    let merged = fill_missing(paper, fresh.paper);
+                    return Ok(PaperLookup { paper: merged, refreshed: true, ..fresh });
+                }
+            }
+            return Ok(PaperLookup {
+                source: "local_index".to_string(),
+                from_local_index: true,
+                confidence: None,
+                attempts: Vec::new(),
+                refreshed: false,
+                raw: None,
+                paper,
+            });
+        }
+
+        match self.fetch_from_sources_traced(id, target_source, include_raw).await {
+            (Some(lookup), _) => Ok(lookup),
+            (None, attempts) => Err(attempts),
+        }
+    }
+
+    /// Helper: replace a locally indexed paper with a refreshed record.
+    async fn store_refreshed(&self, paper: &apis::PaperResult) {
+        if self.config.read_only {
+            return;
+        }
+        let error = match self.embed_paper(paper).await {
+            Ok(embedding) => match self.index_queue.index(vec![(paper.clone(), embedding)], false).await {
+                Ok(outcome) => outcome.failed.into_iter().next().map(|(_, e)| e),
+                Err(e) => Some(e.to_string()),
+            },
+            Err(e) => Some(e.to_string()),
+        };
+        if let Some(e) = error {
+            tracing::warn!("Failed to store refreshed record for {}: {}", paper.id, e);
+        }
+    }
+
+    /// Helper: fetch a paper from the sources, restricted to `target_source`
+    /// if given, else to the sources the ID's prefix routes to.
+    async fn fetch_from_sources(&self, id: &str, target_source: Option<&str>) -> Option<PaperLookup> {
+        self.fetch_from_sources_traced(id, target_source, false).await.0
+    }
+
+    /// Helper: `fetch_from_sources`, bypassing cached `get_paper` responses,
+    /// for refreshes that must see the sources' current record.
+    async fn fetch_fresh(&self, id: &str, target_source: Option<&str>) -> Option<PaperLookup> {
+        if let Some(disk_cache) = &self.disk_cache {
+            for source in self.sources.iter() {
+                disk_cache.remove(source.name(), "get_paper", id);
+            }
+        }
+        self.fetch_from_sources(id, target_source).await
+    }
+
+    /// Helper: `fetch_from_sources`, also returning the outcome of every
+    /// source in the order tried. With `include_raw`, the answer carries the
+    /// source's JSON record, fetched in the same request as the paper.
+    async fn fetch_from_sources_traced(
+        &self,
+        id: &str,
+        target_source: Option<&str>,
+        include_raw: bool,
+    ) -> (Option<PaperLookup>, Vec<SourceAttempt>) {
+        let targets = if target_source.is_some() { target_source.as_slice() } else { sources_for_id(id) };
+        let rank = |src: &Arc<dyn PaperSource>| targets.iter().position(|t| src.name().eq_ignore_ascii_case(t));
+        let mut attempts = Vec::new();
+        let mut routed: Vec<&Arc<dyn PaperSource>> = Vec::new();
+        for src in self.sources.iter() {
+            if targets.is_empty() || rank(src).is_some() {
+                routed.push(src);
+            } else {
+                let source = src.name().to_string();
+                attempts.push(SourceAttempt { source, outcome: "skipped_by_filter", error: None });
+            }
+        }
+        // Ask the routed sources in order of preference
+        routed.sort_by_key(|src| rank(src));
+        for src in routed {
+            let attempt = |outcome, error| SourceAttempt { source: src.name().to_string(), outcome, error };
+            let fetched = if include_raw {
+                src.get_paper_with_raw(id).await
+            } else {
+                src.get_paper(id).await.map(|p| p.map(|p| (p, None)))
+            };
+            match fetched {
+                Ok(Some((paper, raw))) => {
+                    attempts.push(attempt("found", None));
+                    let confidence = if sources_for_id(id).is_empty() {
+                        Some(match_confidence(id, &paper))
+                    } else {
+                        None
+                    };
+                    let lookup = PaperLookup {
+                        source: src.name().to_string(),
+                        from_local_index: false,
+                        confidence,
+                        attempts,
+                        refreshed: false,
+                        raw,
+                        paper,
+                    };
+                    return (Some(lookup), Vec::new());
+                }
+                Ok(None) => attempts.push(attempt("not_found", None)),
+                Err(e) => {
+                    tracing::warn!("Source {} failed for get_paper: {}", src.name(), e);
+                    attempts.push(attempt("error", Some(e.to_string())));
+                }
+            }
+        }
+        (None, attempts)
+    }
+
+    /// Helper: fetch a paper from the source called `name`, if it is enabled.
+    async fn fetch_from_named(&self, name: &str, id: &str) -> Option<apis::PaperResult> {
+        let src = self.sources.iter().find(|s| s.name() == name)?;
+        match src.get_paper(id).await {
+            Ok(paper) => paper,
+            Err(e) => {
+                tracing::warn!("Source {} failed for get_paper: {}", name, e);
+                None
+            }
+        }
+    }
+
+    /// Helper: resolve several IDs, returning the papers found and the IDs that were not.
+    async fn resolve_papers(&self, ids: &[String]) -> (Vec<apis::PaperResult>, Vec<String>) {
+        let mut papers = Vec::new();
+        let mut missing = Vec::new();
+        for id in ids {
+            match self.lookup_paper(id, None).await {
+                Some(lookup) => papers.push(lookup.paper),
+                None => missing.push(id.clone()),
+            }
+        }
+        (papers, missing)
+    }
+
+    /// Fetch BibTeX from the first source an ID's prefix routes to that has a
+    /// native endpoint for it.
+    async fn native_bibtex(&self, id: &str) -> Option<String> {
+        for name in sources_for_id(id) {
+            let Some(source) = self.sources.iter().find(|s| s.name() == *name) else { continue };
+            match source.get_bibtex(id).await {
+                Ok(Some(bibtex)) => return Some(bibtex),
+                Ok(None) => {}
+                Err(e) => tracing::warn!("Source {} failed for get_bibtex: {}", name, e),
+            }
+        }
+        None
+    }
+
+    /// Helper: resolve a paper by ID or DOI and download its open-access PDF
+    /// to `data_dir/pdfs`.
+    async fn fetch_pdf(&self, params: DownloadPdfParams) -> Result<DownloadedPdf, McpError> {
+        let id = match (params.id, params.doi) {
+            (Some(id), _) => id,
+            (None, Some(doi)) => format!("doi:{}", normalize_doi(&doi)),
+            (None, None) => return Err(McpError::invalid_params("Provide an id or a doi".to_string(), None)),
+        };
+        let lookup = self.lookup_paper_traced(&id, None, self.config.store_raw_json).await
+            .map_err(|_| McpError::invalid_params(format!("Paper not found: {}", id), None))?;
+        let (paper, raw) = (lookup.paper, lookup.raw);
+        let (found, tried) = self.resolve_open_access(&paper).await;
+        let Some((provider, url)) = found else {
+            return Err(McpError::resource_not_found(
+                format!("No open-access PDF found for {} (tried: {})", paper.id, tried.join(", ")),
+                None,
+            ));
+        };
+
+        let path = pdf::pdf_path(&self.config.data_dir.join("pdfs"), &paper.id);
+        let client = apis::http_client(&self.config.http, "paper-search-mcp/0.1");
+        let bytes = pdf::download(&client, &url, &path, self.config.pdf_max_bytes).await
+            .map_err(|e| McpError::internal_error(format!("Failed to download {}: {}", url, e), None))?;
+        Ok(DownloadedPdf { paper, raw, provider, url, path, bytes })
+    }
+
+    /// Helper: the first reachable open-access PDF URL for `paper` and the
+    /// provider that supplied it, trying Unpaywall, the record's own link,
+    /// OpenAlex and arXiv in turn; also returns the providers tried. A link
+    /// that doesn't answer with a 2xx status passes to the next provider.
+    async fn resolve_open_access(
+        &self,
+        paper: &apis::PaperResult,
+    ) -> (Option<(&'static str, String)>, Vec<&'static str>) {
+        let client = apis::http_client(&self.config.http, "paper-search-mcp/0.1");
+        let reachable = |url: Option<String>| {
+            let client = &client;
+            async move {
+                let url = url?;
+                if pdf::is_reachable(client, &url).await {
+                    Some(url)
+                } else {
+                    tracing::debug!("Skipping unreachable PDF link {}", url);
+                    None
+                }
+            }
+        };
+        let doi = paper.doi.as_deref().map(normalize_doi).filter(|d| !d.is_empty());
+        let mut tried = Vec::new();
+        if let (Some(unpaywall), Some(doi)) = (&self.unpaywall, doi) {
+            tried.push("unpaywall");
+            let url = match unpaywall.get_pdf_url(doi).await {
+                Ok(url) => url,
+                Err(e) => {
+                    tracing::warn!("Unpaywall failed for {}: {}", doi, e);
+                    None
+                }
+            };
+            if let Some(url) = reachable(url).await {
+                return (Some(("unpaywall", url)), tried);
+            }
+        }
+        tried.push("record");
+        if let Some(url) = reachable(paper.pdf_url.clone()).await {
+            return (Some(("record", url)), tried);
+        }
+        if let Some(doi) = doi {
+            tried.push("openalex");
+            let url = self.fetch_from_named("openalex", &format!("doi:{}", doi)).await.and_then(|p| p.pdf_url);
+            if let Some(url) = reachable(url).await {
+                return (Some(("openalex", url)), tried);
+            }
+        }
+        if let Some(arxiv_id) = &paper.arxiv_id {
+            tried.push("arxiv");
+            if let Some(url) = reachable(Some(arxiv_pdf_url(arxiv_id))).await {
+                return (Some(("arxiv", url)), tried);
+            }
+        }
+        (None, tried)
+    }
+
+    /// Helper: query citations or references from the best matching source.
+    async fn query_relation<F>(
+        &self,
+        id: &str,
+        source: Option<&str>,
+        f: F,
+    ) -> Vec<apis::PaperResult>
+    where
+        F: for<'a> Fn(
+            &'a Arc<dyn PaperSource>,
             &'a str,
         ) -> std::pin::Pin<
             Box<dyn std::future::Future<Output = Result<Vec<apis::PaperResult>, apis::SourceError>> + Send + 'a>,
@@ -393,7 +2697,13 @@ impl PaperSearchServer {
                     continue;
                 }
             }
-            match f(src, id).await {
+            let permit = match self.relation_permits.get(&src.name().to_lowercase()) {
+                Some(permits) => permits.clone().acquire_owned().await.ok(),
+                None => None,
+            };
+            let result = f(src, id).await;
+            drop(permit);
+            match result {
                 Ok(results) if !results.is_empty() => return results,
                 Ok(_) => continue,
                 Err(e) => {
@@ -406,22 +2716,430 @@ impl PaperSearchServer {
     }
 }
 
+/// The loaded SPECTER2 model with its embedding cache.
+#[cfg(feature = "onnx")]
+type SpecterModel = embed::cache::CachedEmbedder<specter::SpecterEmbedder>;
+
+/// Run `embed` against the model on the blocking thread pool, so CPU-bound
+/// ONNX inference doesn't stall the async workers serving other requests.
+#[cfg(feature = "onnx")]
+async fn run_embedder<F>(embedder: &Arc<std::sync::Mutex<SpecterModel>>, embed: F) -> anyhow::Result<specter::Embedding>
+where
+    F: FnOnce(&mut SpecterModel) -> anyhow::Result<specter::Embedding> + Send + 'static,
+{
+    let embedder = embedder.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut model = embedder.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        embed(&mut model)
+    })
+    .await?
+}
+
+/// Download (if needed and `download` is set) and load the SPECTER2 model,
+/// or `None` to fall back to mock embeddings.
+#[cfg(feature = "onnx")]
+async fn load_embedder(
+    model_dir: &std::path::Path,
+    cache_size: usize,
+    download: bool,
+) -> Option<Arc<std::sync::Mutex<SpecterModel>>> {
+    let downloaded = if download { specter::download_model(model_dir).await.map(|_| ()) } else { Ok(()) };
+    let loaded = match downloaded {
+        Ok(()) => specter::SpecterEmbedder::new(model_dir),
+        Err(e) => Err(e),
+    };
+    match loaded {
+        Ok(embedder) => {
+            tracing::info!("SPECTER2 embedder loaded from {}", model_dir.display());
+            Some(Arc::new(std::sync::Mutex::new(embed::cache::CachedEmbedder::new(embedder, cache_size))))
+        }
+        Err(e) => {
+            tracing::warn!("SPECTER2 unavailable; falling back to mock embeddings: {}", e);
+            None
+        }
+    }
+}
+
+/// A PDF stored by `fetch_pdf`.
+struct DownloadedPdf {
+    paper: apis::PaperResult,
+    /// The source's JSON record, with `store_raw_json` on and the paper
+    /// fetched from a source that has one.
+    raw: Option<serde_json::Value>,
+    /// Open-access provider that supplied the URL.
+    provider: &'static str,
+    url: String,
+    path: std::path::PathBuf,
+    bytes: u64,
+}
+
+/// A `search_similar` result with its distance from the query.
+#[derive(Debug, Serialize)]
+struct SimilarPaper {
+    #[serde(flatten)]
+    paper: apis::PaperResult,
+    /// Raw distance under the configured metric; lower is closer.
+    distance: f32,
+    /// 0–1, higher is more similar; see `VectorStore::similarity`.
+    similarity: f32,
+}
+
+/// A resolved paper together with where it came from.
+#[derive(Debug, Serialize)]
+struct PaperLookup {
+    paper: apis::PaperResult,
+    /// Source name that answered, or `local_index` for cached papers.
+    source: String,
+    from_local_index: bool,
+    /// Match confidence in [0, 1]; only set when a bare ID had to be resolved.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    confidence: Option<f64>,
+    /// Sources tried, in order, before the answer; empty for local hits.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    attempts: Vec<SourceAttempt>,
+    /// Whether this fills in a partial local record; only `get_paper`
+    /// stores such records back.
+    #[serde(skip)]
+    refreshed: bool,
+    /// The answering source's JSON record, when asked for and the source
+    /// has one.
+    #[serde(skip)]
+    raw: Option<serde_json::Value>,
+}
+
+/// A `get_relations` result: a paper citing the requested one (`citation`)
+/// or cited by it (`reference`).
+#[derive(Debug, Serialize)]
+struct RelatedPaper {
+    relation: &'static str,
+    #[serde(flatten)]
+    paper: apis::PaperResult,
+}
+
+/// How one source fared in a paper lookup.
+#[derive(Debug, Clone, Serialize)]
+struct SourceAttempt {
+    source: String,
+    /// "found", "not_found", "error", or "skipped_by_filter".
+    outcome: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Names of the requested fields that are empty on `paper`.
+fn missing_fields<'a>(paper: &apis::PaperResult, fields: &'a [String]) -> Vec<&'a str> {
+    fields
+        .iter()
+        .map(String::as_str)
+        .filter(|field| match *field {
+            "abstract" | "abstract_text" => paper.abstract_text.is_none(),
+            "authors" => paper.authors.is_empty(),
+            "year" => paper.year.is_none(),
+            "doi" => paper.doi.is_none(),
+            "arxiv_id" => paper.arxiv_id.is_none(),
+            "pdf_url" => paper.pdf_url.is_none(),
+            "citation_count" => paper.citation_count.is_none(),
+            _ => false,
+        })
+        .collect()
+}
+
+/// Fill the empty fields of a local record from a fresh fetch. Citation
+/// counts always take the fresh value since they go stale.
+fn fill_missing(mut local: apis::PaperResult, fresh: apis::PaperResult) -> apis::PaperResult {
+    if local.authors.is_empty() {
+        local.authors = fresh.authors;
+    }
+    local.abstract_text = local.abstract_text.or(fresh.abstract_text);
+    local.year = local.year.or(fresh.year);
+    local.doi = local.doi.or(fresh.doi);
+    local.arxiv_id = local.arxiv_id.or(fresh.arxiv_id);
+    local.pdf_url = local.pdf_url.or(fresh.pdf_url);
+    local.citation_count = fresh.citation_count.or(local.citation_count);
+    local
+}
+
+/// Machine-readable summary of a tool: parameters from its input schema plus
+/// side effects from its annotations.
+fn describe_tool(tool: &Tool) -> serde_json::Value {
+    let schema = &tool.input_schema;
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(|r| r.as_array())
+        .map(|r| r.iter().filter_map(|v| v.as_str()).collect())
+        .unwrap_or_default();
+    let mut parameters = Vec::new();
+    if let Some(properties) = schema.get("properties").and_then(|p| p.as_object()) {
+        for (name, property) in properties {
+            parameters.push(serde_json::json!({
+                "name": name,
+                "type": schema_type(property),
+                "required": required.contains(&name.as_str()),
+                "default": property.get("default"),
+                "description": property.get("description"),
+            }));
+        }
+    }
+    serde_json::json!({
+        "name": tool.name,
+        "description": tool.description,
+        "parameters": parameters,
+        "side_effects": {
+            "reads_network": reads_network(tool),
+            "writes": writes(tool),
+        },
+    })
+}
+
+/// Whether a tool writes anything (the local index, the cache, or files in
+/// the data directory), from its `read_only_hint` annotation. Tools without
+/// the annotation count as writing, so read-only mode never lets one through.
+fn writes(tool: &Tool) -> bool {
+    tool.annotations.as_ref().and_then(|a| a.read_only_hint) != Some(true)
+}
+
+/// Whether a tool queries remote sources, from its `open_world_hint` annotation.
+fn reads_network(tool: &Tool) -> bool {
+    tool.annotations.as_ref().and_then(|a| a.open_world_hint).unwrap_or(true)
+}
+
+/// JSON Schema type of a property, ignoring the `null` that optional
+/// parameters add, e.g. `"string"` or `"array<string>"`.
+fn schema_type(property: &serde_json::Value) -> String {
+    let base = match property.get("type") {
+        Some(serde_json::Value::String(t)) => t.clone(),
+        Some(serde_json::Value::Array(types)) => types
+            .iter()
+            .filter_map(|t| t.as_str())
+            .filter(|t| *t != "null")
+            .collect::<Vec<_>>()
+            .join("|"),
+        _ => return "any".to_string(),
+    };
+    match property.get("items") {
+        Some(items) if base == "array" => format!("array<{}>", schema_type(items)),
+        _ => base,
+    }
+}
+
+/// Argument keys whose values are never logged.
+const REDACTED_ARGUMENTS: &[&str] = &["confirm", "email", "api_key", "token"];
+/// Logged string arguments are cut to this many characters.
+const MAX_LOGGED_ARGUMENT_CHARS: usize = 200;
+
+/// Wrap every route so each call logs the tool name, sanitized arguments,
+/// response size and elapsed time at info level.
+fn instrument_tools<S: Send + Sync + 'static>(router: ToolRouter<S>) -> ToolRouter<S> {
+    let mut instrumented = ToolRouter::new();
+    for route in router {
+        let inner = route.call.clone();
+        instrumented.add_route(ToolRoute::new_dyn(route.attr, move |context: ToolCallContext<'_, S>| {
+            let inner = inner.clone();
+            futures::FutureExt::boxed(async move {
+                let tool = context.name.to_string();
+                let args = sanitize_arguments(context.arguments.as_ref());
+                let started = std::time::Instant::now();
+                let result = inner(context).await;
+                let elapsed_ms = started.elapsed().as_millis() as u64;
+                match &result {
+                    Ok(output) => {
+                        let result_bytes: usize = output.content
+                            .iter()
+                            .filter_map(|c| c.as_text())
+                            .map(|t| t.text.len())
+                            .sum();
+                        tracing::info!(
+                            tool = %tool,
+                            args = %args,
+                            result_bytes,
+                            elapsed_ms,
+                            is_error = output.is_error.unwrap_or(false),
+                            "tool call"
+                        );
+                    }
+                    Err(e) => tracing::info!(tool = %tool, args = %args, elapsed_ms, error = %e.message, "tool call failed"),
+                }
+                result
+            })
+        }));
+    }
+    instrumented
+}
+
+/// Compact JSON of a tool call's arguments for logging, with secrets redacted
+/// and long strings truncated.
+fn sanitize_arguments(arguments: Option<&JsonObject>) -> String {
+    fn sanitize(value: &serde_json::Value) -> serde_json::Value {
+        match value {
+            serde_json::Value::String(s) if s.chars().count() > MAX_LOGGED_ARGUMENT_CHARS => {
+                let cut: String = s.chars().take(MAX_LOGGED_ARGUMENT_CHARS).collect();
+                format!("{}…", cut).into()
+            }
+            serde_json::Value::Array(items) => items.iter().map(sanitize).collect(),
+            serde_json::Value::Object(map) => serde_json::Value::Object(
+                map.iter()
+                    .map(|(k, v)| {
+                        let v = if REDACTED_ARGUMENTS.contains(&k.as_str()) { "[redacted]".into() } else { sanitize(v) };
+                        (k.clone(), v)
+                    })
+                    .collect(),
+            ),
+            other => other.clone(),
+        }
+    }
+    match arguments {
+        Some(args) => sanitize(&serde_json::Value::Object(args.clone())).to_string(),
+        None => "{}".to_string(),
+    }
+}
+
+/// Strip `doi:` and resolver URL prefixes from a DOI.
+fn normalize_doi(doi: &str) -> &str {
+    let doi = doi.trim();
+    ["https://doi.org/", "http://doi.org/", "https://dx.doi.org/", "doi:"]
+        .iter()
+        .find_map(|prefix| doi.strip_prefix(prefix))
+        .unwrap_or(doi)
+}
+
+/// arXiv's PDF link for an ID, with any `arXiv:` prefix or version dropped.
+fn arxiv_pdf_url(arxiv_id: &str) -> String {
+    format!("https://arxiv.org/pdf/{}", search::normalize_arxiv_id(arxiv_id))
+}
+
+/// Check that a user-supplied path is relative to the data directory and
+/// doesn't climb out of it with `..`.
+fn relative_to_data_dir(path: &str) -> Result<&std::path::Path, String> {
+    use std::path::Component;
+
+    let relative = std::path::Path::new(path);
+    if path.is_empty() || !relative.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir)) {
+        return Err(format!("'{}' must be a path relative to the data directory, without '..'", path));
+    }
+    Ok(relative)
+}
+
+/// Resolve a user-supplied relative file path under `data_dir`, creating its
+/// parent directories. Absolute paths, `..` and symlinks leading outside the
+/// data directory are rejected.
+fn data_dir_path(data_dir: &std::path::Path, path: &str) -> Result<std::path::PathBuf, String> {
+    let full = data_dir.join(relative_to_data_dir(path)?);
+    let (Some(parent), Some(name)) = (full.parent(), full.file_name()) else {
+        return Err(format!("'{}' does not name a file", path));
+    };
+    let root = data_dir.canonicalize().map_err(|e| format!("Cannot resolve {}: {}", data_dir.display(), e))?;
+    // Check the part that already exists before creating anything under it
+    let existing = parent.ancestors().find(|dir| dir.exists()).unwrap_or(data_dir);
+    let existing = existing.canonicalize().map_err(|e| format!("Cannot resolve {}: {}", existing.display(), e))?;
+    if !existing.starts_with(&root) {
+        return Err(format!("'{}' resolves outside the data directory", path));
+    }
+    std::fs::create_dir_all(parent).map_err(|e| format!("Cannot create {}: {}", parent.display(), e))?;
+    let parent = parent.canonicalize().map_err(|e| format!("Cannot resolve {}: {}", parent.display(), e))?;
+    let resolved = parent.join(name);
+    let is_link = resolved.symlink_metadata().is_ok_and(|m| m.file_type().is_symlink());
+    if !parent.starts_with(&root) || is_link {
+        return Err(format!("'{}' resolves outside the data directory", path));
+    }
+    Ok(resolved)
+}
+
+/// Resolve a user-supplied relative path to an existing file or directory
+/// under `data_dir`, following symlinks. Absolute paths, `..` and symlinks
+/// leading outside the data directory are rejected, so errors never reveal
+/// what exists elsewhere on the host.
+fn existing_data_dir_path(data_dir: &std::path::Path, path: &str) -> Result<std::path::PathBuf, String> {
+    let full = data_dir.join(relative_to_data_dir(path)?);
+    let root = data_dir.canonicalize().map_err(|e| format!("Cannot resolve {}: {}", data_dir.display(), e))?;
+    let resolved = full.canonicalize().map_err(|e| format!("Cannot read '{}': {}", path, e))?;
+    if !resolved.starts_with(&root) {
+        return Err(format!("'{}' resolves outside the data directory", path));
+    }
+    Ok(resolved)
+}
+
+/// Parse a `YYYY-MM-DD` or RFC 3339 date into a Unix timestamp (seconds).
+fn parse_timestamp(s: &str) -> Result<i64, String> {
+    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(s) {
+        return Ok(dt.timestamp());
+    }
+    chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .map(|d| d.and_time(chrono::NaiveTime::MIN).and_utc().timestamp())
+        .map_err(|_| format!("Invalid date '{}': expected YYYY-MM-DD or RFC 3339", s))
+}
+
+/// Map a prefixed paper ID to the sources that can serve it, in order of
+/// preference. Empty for unprefixed IDs.
+fn sources_for_id(id: &str) -> &'static [&'static str] {
+    if id.starts_with("arxiv:") { &["arxiv"] }
+    else if id.starts_with("inspire:") { &["inspire"] }
+    else if id.starts_with("s2:") { &["semantic_scholar"] }
+    else if id.starts_with("ads:") { &["ads"] }
+    else if id.starts_with("doi:") { &["crossref"] }
+    else if id.starts_with("pmid:") { &["pubmed", "europepmc"] }
+    else if id.starts_with("doaj:") { &["doaj"] }
+    else if id.starts_with("vixra:") { &["vixra"] }
+    else if id.starts_with("openalex:") { &["openalex"] }
+    else if id.starts_with("repec:") { &["repec"] }
+    else if id.starts_with("scholar:") { &["scholar"] }
+    else if id.starts_with("zenodo:") { &["zenodo"] }
+    else { &[] }
+}
+
+/// Score how well a bare ID (or title) matches the record a source returned.
+fn match_confidence(query: &str, paper: &apis::PaperResult) -> f64 {
+    let query = query.trim().to_lowercase();
+    let bare_id = paper.id.split_once(':').map(|(_, rest)| rest).unwrap_or(&paper.id);
+    let exact = [Some(bare_id), paper.doi.as_deref(), paper.arxiv_id.as_deref()]
+        .into_iter()
+        .flatten()
+        .any(|candidate| candidate.eq_ignore_ascii_case(&query));
+    if exact {
+        return 1.0;
+    }
+    strsim::normalized_levenshtein(&query, &paper.title.to_lowercase())
+        .max(strsim::normalized_levenshtein(&query, &bare_id.to_lowercase()))
+}
+
 #[tool_handler]
 impl ServerHandler for PaperSearchServer {
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
             protocol_version: ProtocolVersion::V_2024_11_05,
-            capabilities: ServerCapabilities::builder().enable_tools().build(),
+            capabilities: ServerCapabilities::builder().enable_tools().enable_resources().build(),
             server_info: Implementation::from_build_env(),
-            instructions: Some(
-                "Search, index, and retrieve scientific papers across open journals. \
-                 Supports arXiv, INSPIRE-HEP, Semantic Scholar, OpenAlex, CrossRef, \
-                 NASA ADS, Europe PMC, DOAJ, and viXra. Local hybrid search with \
-                 BM25 + SPECTER2 embeddings."
-                    .into(),
-            ),
+            instructions: Some({
+                let mut text = "Search, index, and retrieve scientific papers across open journals. \
+                     Supports arXiv, INSPIRE-HEP, Semantic Scholar, OpenAlex, CrossRef, \
+                     NASA ADS, Europe PMC, PubMed, DOAJ, Zenodo, Google Scholar, viXra, and RePEc. \
+                     Local hybrid search with \
+                     BM25 + SPECTER2 embeddings."
+                    .to_string();
+                if self.config.read_only {
+                    text.push(' ');
+                    text.push_str(READ_ONLY_NOTICE);
+                }
+                text
+            }),
         }
     }
+
+    /// Indexed papers as `paper://local/{id}` resources, paged in ID order.
+    async fn list_resources(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        _context: rmcp::service::RequestContext<rmcp::RoleServer>,
+    ) -> Result<ListResourcesResult, McpError> {
+        self.resource_page(request.as_ref().and_then(|r| r.cursor.as_deref())).await
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParams,
+        _context: rmcp::service::RequestContext<rmcp::RoleServer>,
+    ) -> Result<ReadResourceResult, McpError> {
+        self.read_paper_resource(&request.uri).await
+    }
 }
 
 #[tokio::main]
@@ -440,3 +3158,1199 @@ async fn main() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use apis::{PaperResult, SourceError};
+    use mock_http::Response;
+    use mock_source::MockSource;
+    use tempfile::TempDir;
+
+    fn sample_paper(id: &str, title: &str) -> PaperResult {
+        PaperResult {
+            id: id.to_string(),
+            title: title.to_string(),
+            authors: vec!["Test Author".to_string()],
+            abstract_text: Some("Test abstract".to_string()),
+            year: Some(2024),
+            source: "mock".to_string(),
+            doi: Some("10.1234/mock".to_string()),
+            arxiv_id: None,
+            url: "https://example.com".to_string(),
+            pdf_url: None,
+            citation_count: None,
+            orcids: Vec::new(),
+            subjects: Vec::new(),
+        }
+    }
+
+    /// Source that answers every lookup with a fixed paper.
+    fn mock_source() -> MockSource {
+        MockSource::new("mock")
+            .on_search(|query| vec![sample_paper("mock:search", query)])
+            .on_paper(|id| Some(PaperResult { citation_count: Some(42), ..sample_paper(id, "Mock Paper") }))
+    }
+
+    /// A paper with its own DOI, unlike `sample_paper`'s shared one.
+    fn work(id: &str, doi_suffix: &str) -> PaperResult {
+        PaperResult { doi: Some(format!("10.1234/{}", doi_suffix)), ..sample_paper(id, id) }
+    }
+
+    /// A one-page PDF showing `text` in Helvetica.
+    #[cfg(feature = "pdf-text")]
+    fn minimal_pdf(text: &str) -> Vec<u8> {
+        let content = format!("BT /F1 12 Tf 72 712 Td ({}) Tj ET", text);
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents 4 0 R /Resources << /Font << /F1 5 0 R >> >> >>".to_string(),
+            format!("<< /Length {} >>\nstream\n{}\nendstream", content.len(), content),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_string(),
+        ];
+        let mut pdf = b"%PDF-1.4\n".to_vec();
+        let mut offsets = Vec::new();
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).as_bytes());
+        }
+        let xref = pdf.len();
+        pdf.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+        for offset in offsets {
+            pdf.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+        }
+        pdf.extend_from_slice(
+            format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref).as_bytes(),
+        );
+        pdf
+    }
+
+    /// A server over the index in `tmp` (created if needed, unless read-only),
+    /// with the built-in default configuration adjusted by `configure`.
+    async fn test_server(
+        tmp: &TempDir,
+        sources: Vec<Arc<dyn PaperSource>>,
+        configure: impl FnOnce(&mut Config),
+    ) -> PaperSearchServer {
+        let mut config = Config::defaults_in(tmp.path());
+        configure(&mut config);
+        let local_index = if config.read_only {
+            LocalIndex::open_read_only(tmp.path(), config.embedding_dim).await.unwrap()
+        } else {
+            LocalIndex::create_or_open(tmp.path(), config.embedding_dim).await.unwrap()
+        };
+        PaperSearchServer::new(config, sources, local_index, None)
+    }
+
+    #[tokio::test]
+    async fn test_citation_graph_expands_and_dedups() {
+        let tmp = TempDir::new().unwrap();
+        // a cites b and c, b cites c and d, and e cites a
+        let source = MockSource::new("graph")
+            .on_paper(|id| Some(sample_paper(id, id)))
+            .on_citations(|id| match id {
+                "a" => vec![sample_paper("e", "e")],
+                "b" | "c" => vec![sample_paper("a", "a")],
+                _ => vec![],
+            })
+            .on_references(|id| {
+                let refs: &[&str] = match id {
+                    "a" => &["b", "c"],
+                    "b" => &["c", "d"],
+                    _ => &[],
+                };
+                refs.iter().map(|r| sample_paper(r, r)).collect()
+            });
+        let server = test_server(&tmp, vec![Arc::new(source)], |_| {}).await;
+        let graph = |depth, direction: &str| Parameters(CitationGraphParams {
+            id: "a".to_string(),
+            depth: Some(depth),
+            direction: Some(direction.to_string()),
+            source: None,
+            max_per_node: None,
+        });
+        let edges = |json: &serde_json::Value| {
+            let mut edges: Vec<String> = json["edges"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|e| format!("{}->{}", e["from"].as_str().unwrap(), e["to"].as_str().unwrap()))
+                .collect();
+            edges.sort();
+            edges
+        };
+
+        let result = server.citation_graph(graph(2, "references")).await.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(json["node_count"], 4);
+        assert_eq!(edges(&json), vec!["a->b", "a->c", "b->c", "b->d"]);
+        assert_eq!(json["truncated"], false);
+
+        // Citing and cited edges of the same pair collapse into one
+        let result = server.citation_graph(graph(1, "both")).await.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(json["node_count"], 4);
+        assert_eq!(edges(&json), vec!["a->b", "a->c", "e->a"]);
+
+        assert!(server.citation_graph(graph(4, "both")).await.is_err());
+        assert!(server.citation_graph(graph(1, "sideways")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_citation_graph_caps_nodes_breadth_first() {
+        let tmp = TempDir::new().unwrap();
+        // Every paper references 30 new ones, so the graph grows past
+        // `MAX_GRAPH_NODES` within two hops
+        let source = MockSource::new("fanout")
+            .on_paper(|id| Some(sample_paper(id, id)))
+            .on_references(|id| (0..30).map(|i| sample_paper(&format!("{}.{}", id, i), id)).collect());
+        let server = test_server(&tmp, vec![Arc::new(source)], |_| {}).await;
+
+        let result = server.citation_graph(Parameters(CitationGraphParams {
+            id: "r".to_string(),
+            depth: Some(2),
+            direction: Some("references".to_string()),
+            source: None,
+            max_per_node: Some(30),
+        })).await.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(json["node_count"], MAX_GRAPH_NODES);
+        assert_eq!(json["truncated"], true);
+
+        // The whole first hop is kept before any of the second
+        let nodes: Vec<&str> = json["nodes"].as_array().unwrap().iter().map(|n| n["id"].as_str().unwrap()).collect();
+        assert_eq!(nodes[0], "r");
+        assert!(nodes[1..31].iter().all(|id| id.matches('.').count() == 1));
+        assert!(nodes[31..].iter().all(|id| id.matches('.').count() == 2));
+
+        // Edges point from the citing paper to the reference, and only
+        // between kept nodes
+        let kept: std::collections::HashSet<&str> = nodes.iter().copied().collect();
+        let edges = json["edges"].as_array().unwrap();
+        assert_eq!(edges.len(), MAX_GRAPH_NODES - 1);
+        for edge in edges {
+            let (from, to) = (edge["from"].as_str().unwrap(), edge["to"].as_str().unwrap());
+            assert!(kept.contains(from) && kept.contains(to));
+            assert_eq!(to.rsplit_once('.').unwrap().0, from);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_citation_graph_respects_concurrency_bounds() {
+        // (graph_concurrency, per-source limit, expected peak)
+        for (graph, per_source, expected) in [(8, 2, 2), (3, 8, 3)] {
+            let tmp = TempDir::new().unwrap();
+            // The seed "root" has six citing papers, each cited by none
+            let source = Arc::new(
+                MockSource::new("gated")
+                    .on_paper(|id| Some(work(id, id)))
+                    .on_citations(|id| match id {
+                        "root" => (0..6).map(|i| work(&format!("citer:{}", i), &format!("citer{}", i))).collect(),
+                        _ => vec![],
+                    })
+                    .with_delay(std::time::Duration::from_millis(20)),
+            );
+            let server = test_server(&tmp, vec![source.clone()], |config| {
+                config.graph_concurrency = graph;
+                config.relation_concurrency_per_source = per_source;
+            })
+            .await;
+
+            let result = server.citation_graph(Parameters(CitationGraphParams {
+                id: "root".to_string(),
+                depth: Some(2),
+                direction: Some("citations".to_string()),
+                source: None,
+                max_per_node: None,
+            })).await.unwrap();
+            let json: serde_json::Value = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+            assert_eq!(json["node_count"], 7);
+            assert_eq!(source.peak.load(std::sync::atomic::Ordering::SeqCst), expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_merge_duplicates_keeps_one_merged_record() {
+        let tmp = TempDir::new().unwrap();
+        let server = test_server(&tmp, vec![Arc::new(mock_source())], |_| {}).await;
+        let papers = [
+            PaperResult { abstract_text: None, citation_count: Some(40), source: "crossref".to_string(), ..work("doi:10.1234/dup", "dup") },
+            PaperResult { arxiv_id: Some("2301.00001".to_string()), ..work("arxiv:2301.00001", "dup") },
+            work("doi:10.1234/other", "other"),
+        ];
+        for (i, paper) in papers.iter().enumerate() {
+            let paper = PaperResult { title: if i == 2 { "Unrelated Work".to_string() } else { "Duplicated Work".to_string() }, ..paper.clone() };
+            let embedding = server.embed_paper(&paper).await.unwrap();
+            // Only the arXiv copy has PDF text
+            let text = index::vectordb::StoredText {
+                body: (i == 1).then(|| "Appendix on traversable wormholes".to_string()),
+                ..Default::default()
+            };
+            server.local_index.lock().await.index_paper_with(&paper, &embedding, text).await.unwrap();
+        }
+        let merge = |dry_run| Parameters(MergeDuplicatesParams { dry_run: Some(dry_run) });
+
+        let result = server.merge_duplicates(merge(true)).await.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(json["duplicate_groups"], 1);
+        assert_eq!(json["removed"], 0);
+        assert!(json["groups"][0].get("status").is_none());
+        assert_eq!(server.local_index.lock().await.count().await.unwrap(), 3);
+
+        let result = server.merge_duplicates(merge(false)).await.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(json["removed"], 1);
+        assert_eq!(json["groups"][0]["status"], "merged");
+        assert_eq!(server.local_index.lock().await.count().await.unwrap(), 2);
+        let kept_id = json["groups"][0]["kept"].as_str().unwrap();
+        let kept = server.reader.vector.get_paper(kept_id).await.unwrap().unwrap();
+        assert_eq!(kept.citation_count, Some(40));
+        assert_eq!(kept.arxiv_id.as_deref(), Some("2301.00001"));
+        assert!(kept.abstract_text.is_some());
+        let body = server.reader.vector.stored_text(kept_id).await.unwrap().body;
+        assert_eq!(body.as_deref(), Some("Appendix on traversable wormholes"));
+
+        let result = server.merge_duplicates(merge(false)).await.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(json["duplicate_groups"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_import_from_dir_keeps_embeddings() {
+        let tmp = TempDir::new().unwrap();
+        let server = test_server(&tmp, vec![Arc::new(mock_source())], |_| {}).await;
+        {
+            let other_dir = tmp.path().join("imports").join("laptop");
+            let mut other = LocalIndex::create_or_open(&other_dir, specter::EMBEDDING_DIMENSION).await.unwrap();
+            for (i, id) in ["other:1", "shared:1"].into_iter().enumerate() {
+                // Distinct from anything embed_paper would produce for these papers
+                let embedding = vec![i as f32 + 0.5; specter::EMBEDDING_DIMENSION];
+                other.index_paper(&work(id, id), &embedding).await.unwrap();
+            }
+        }
+
+        for id in ["local:1", "shared:1"] {
+            let paper = work(id, id);
+            let embedding = server.embed_paper(&paper).await.unwrap();
+            server.local_index.lock().await.index_paper(&paper, &embedding).await.unwrap();
+        }
+
+        let params = |path: &str| Parameters(ImportFromDirParams { path: path.to_string() });
+        let result = server.import_from_dir(params("imports/laptop")).await.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(json["found"], 2);
+        assert_eq!(json["imported"], 2);
+        assert_eq!(json["conflicts"], serde_json::json!(["shared:1"]));
+        assert_eq!(server.local_index.lock().await.count().await.unwrap(), 3);
+        for (id, value) in [("other:1", 0.5), ("shared:1", 1.5)] {
+            let embedding = server.reader.vector.get_embedding(id).await.unwrap().unwrap();
+            assert!(embedding.iter().all(|&v| v == value), "{}", id);
+        }
+
+        assert!(server.import_from_dir(params("nowhere")).await.is_err());
+        assert!(server.import_from_dir(params(".")).await.is_err());
+
+        // Directories outside the data directory are refused without looking at them
+        let outside = TempDir::new().unwrap();
+        std::fs::create_dir(outside.path().join("lance")).unwrap();
+        let err = server.import_from_dir(params(&outside.path().display().to_string())).await.unwrap_err();
+        assert!(err.message.contains("relative to the data directory"), "{}", err.message);
+        assert!(server.import_from_dir(params("imports/../../elsewhere")).await.is_err());
+
+        // A lance/ directory without a papers table is rejected, not initialized
+        let empty = tmp.path().join("empty");
+        std::fs::create_dir_all(empty.join("lance")).unwrap();
+        assert!(server.import_from_dir(params("empty")).await.is_err());
+        assert_eq!(std::fs::read_dir(empty.join("lance")).unwrap().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_import_from_dir_rejects_another_model() {
+        let tmp = TempDir::new().unwrap();
+        let server = test_server(&tmp, vec![Arc::new(mock_source())], |_| {}).await;
+        {
+            let mut other = LocalIndex::create_or_open(&tmp.path().join("other"), specter::EMBEDDING_DIMENSION).await.unwrap()
+                .with_embedding_model("specter2");
+            other.index_paper(&work("other:1", "1"), &vec![0.5; specter::EMBEDDING_DIMENSION]).await.unwrap();
+        }
+
+        let err = server.import_from_dir(Parameters(ImportFromDirParams { path: "other".to_string() })).await.unwrap_err();
+        assert!(err.message.contains("another model"), "{}", err.message);
+        assert_eq!(server.local_index.lock().await.count().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_import_jsonl_reports_failures_by_line() {
+        let tmp = TempDir::new().unwrap();
+        let server = test_server(&tmp, vec![Arc::new(mock_source())], |_| {}).await;
+        let existing = work("jsonl:existing", "existing");
+        // Distinct from anything embed_paper would produce for the paper
+        let embedding = vec![0.5; specter::EMBEDDING_DIMENSION];
+        server.local_index.lock().await.index_paper(&existing, &embedding).await.unwrap();
+
+        let line = |paper: &PaperResult| serde_json::to_string(paper).unwrap();
+        let file = tmp.path().join("papers.jsonl");
+        let contents = [
+            line(&work("jsonl:1", "one")),
+            "{not json".to_string(),
+            String::new(),
+            line(&PaperResult { title: " ".to_string(), ..work("jsonl:untitled", "untitled") }),
+            line(&existing),
+            line(&work("jsonl:2", "two")),
+            line(&work("jsonl:1", "one again")),
+        ]
+        .join("\n");
+        std::fs::write(&file, contents).unwrap();
+
+        let params = |overwrite| Parameters(ImportJsonlParams { path: "papers.jsonl".to_string(), overwrite });
+        let result = server.import_jsonl(params(None)).await.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(json["imported"], 2);
+        assert_eq!(json["skipped"], 1);
+        assert_eq!(json["errored"], 3);
+        let lines: Vec<u64> = json["errors"].as_array().unwrap().iter().map(|e| e["line"].as_u64().unwrap()).collect();
+        assert_eq!(lines, vec![2, 4, 7]);
+        assert_eq!(json["errors"][2]["error"], "duplicate of line 1");
+        assert_eq!(server.local_index.lock().await.count().await.unwrap(), 3);
+        // The skipped paper kept its row
+        assert_eq!(server.reader.vector.get_embedding("jsonl:existing").await.unwrap(), Some(embedding.clone()));
+
+        // Files outside the data directory can't be read
+        let outside = TempDir::new().unwrap().path().join("papers.jsonl");
+        let err = server.import_jsonl(Parameters(ImportJsonlParams { path: outside.display().to_string(), overwrite: None })).await.unwrap_err();
+        assert!(err.message.contains("relative to the data directory"), "{}", err.message);
+
+        let result = server.import_jsonl(params(Some(true))).await.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(json["imported"], 3);
+        assert_eq!(json["skipped"], 0);
+        assert_eq!(server.local_index.lock().await.count().await.unwrap(), 3);
+        assert_ne!(server.reader.vector.get_embedding("jsonl:existing").await.unwrap(), Some(embedding));
+    }
+
+    #[tokio::test]
+    async fn test_export_jsonl_round_trips_through_import() {
+        let tmp = TempDir::new().unwrap();
+        let server = test_server(&tmp, vec![Arc::new(mock_source())], |_| {}).await;
+        for i in 0..3 {
+            let paper = work(&format!("export:{}", i), &format!("export{}", i));
+            let embedding = server.embed_paper(&paper).await.unwrap();
+            server.local_index.lock().await.index_paper(&paper, &embedding).await.unwrap();
+        }
+
+        let file = tmp.path().join("backup.jsonl");
+        let result = server.export_jsonl(Parameters(ExportJsonlParams { path: "backup.jsonl".to_string() })).await.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(json["written"], 3);
+        let contents = std::fs::read_to_string(&file).unwrap();
+        let ids: Vec<String> = contents
+            .lines()
+            .map(|l| serde_json::from_str::<PaperResult>(l).unwrap().id)
+            .collect();
+        assert_eq!(ids, vec!["export:0", "export:1", "export:2"]);
+        let outside = TempDir::new().unwrap().path().join("backup.jsonl");
+        assert!(server.export_jsonl(Parameters(ExportJsonlParams { path: outside.display().to_string() })).await.is_err());
+
+        let other = TempDir::new().unwrap();
+        let restored = test_server(&other, vec![Arc::new(mock_source())], |_| {}).await;
+        std::fs::copy(&file, other.path().join("backup.jsonl")).unwrap();
+        let result = restored.import_jsonl(Parameters(ImportJsonlParams { path: "backup.jsonl".to_string(), overwrite: None })).await.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(json["imported"], 3);
+    }
+
+    #[tokio::test]
+    async fn test_verbose_lookup_names_answering_source() {
+        let tmp = TempDir::new().unwrap();
+        let server = test_server(&tmp, vec![Arc::new(mock_source())], |_| {}).await;
+
+        let lookup = server.lookup_paper("mock:1", None).await.unwrap();
+        assert_eq!(lookup.source, "mock");
+        assert!(!lookup.from_local_index);
+
+        let cached = sample_paper("mock:1", "Cached Paper");
+        let embedding = server.embed_paper(&cached).await.unwrap();
+        server.local_index.lock().await
+            .index_paper(&cached, &embedding)
+            .await
+            .unwrap();
+
+        let lookup = server.lookup_paper("mock:1", None).await.unwrap();
+        let json = serde_json::to_value(&lookup).unwrap();
+        assert_eq!(json["source"], "local_index");
+        assert_eq!(json["from_local_index"], true);
+        assert_eq!(json["paper"]["title"], "Cached Paper");
+    }
+
+    #[tokio::test]
+    async fn test_describe_tools_reports_parameters_and_side_effects() {
+        let tmp = TempDir::new().unwrap();
+        let server = test_server(&tmp, vec![Arc::new(mock_source())], |_| {}).await;
+        let result = server.describe_tools().await.unwrap();
+        let tools: Vec<serde_json::Value> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        let tool = |name: &str| tools.iter().find(|t| t["name"] == name).unwrap().clone();
+
+        let index_paper = tool("index_paper");
+        assert_eq!(index_paper["side_effects"]["writes"], true);
+        assert_eq!(index_paper["side_effects"]["reads_network"], true);
+
+        let search_local = tool("search_local");
+        assert_eq!(search_local["side_effects"]["writes"], false);
+        assert_eq!(search_local["side_effects"]["reads_network"], false);
+
+        for tool in server.tool_router.list_all() {
+            let annotations = tool.annotations.as_ref();
+            assert!(
+                annotations.is_some_and(|a| a.read_only_hint.is_some() && a.open_world_hint.is_some()),
+                "{} has no side-effect annotations",
+                tool.name
+            );
+        }
+
+        let search_papers = tool("search_papers");
+        let param = |name: &str| {
+            search_papers["parameters"].as_array().unwrap().iter().find(|p| p["name"] == name).unwrap().clone()
+        };
+        assert_eq!(param("query")["type"], "string");
+        assert_eq!(param("query")["required"], true);
+        assert_eq!(param("max_results")["type"], "integer");
+        assert_eq!(param("max_results")["required"], false);
+        assert!(param("max_results")["default"].is_null());
+        assert_eq!(param("sources")["type"], "array<string>");
+    }
+
+    #[test]
+    fn test_logged_arguments_redacted_and_truncated() {
+        let args = serde_json::json!({
+            "query": "x".repeat(300),
+            "confirm": "yes-delete-everything",
+            "ids": ["arxiv:1", "doi:10.1/a"],
+            "max_results": 10,
+        });
+        let logged: serde_json::Value =
+            serde_json::from_str(&sanitize_arguments(args.as_object())).unwrap();
+        assert_eq!(logged["query"].as_str().unwrap().chars().count(), MAX_LOGGED_ARGUMENT_CHARS + 1);
+        assert_eq!(logged["confirm"], "[redacted]");
+        assert_eq!(logged["ids"], serde_json::json!(["arxiv:1", "doi:10.1/a"]));
+        assert_eq!(logged["max_results"], 10);
+        assert_eq!(sanitize_arguments(None), "{}");
+    }
+
+    #[test]
+    fn test_normalize_doi_strips_prefixes() {
+        assert_eq!(normalize_doi(" https://doi.org/10.1234/Mock "), "10.1234/Mock");
+        assert_eq!(normalize_doi("doi:10.1234/mock"), "10.1234/mock");
+        assert_eq!(normalize_doi("10.1234/mock"), "10.1234/mock");
+    }
+
+    #[test]
+    fn test_data_dir_path_stays_inside() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().canonicalize().unwrap();
+        assert_eq!(data_dir_path(tmp.path(), "backups/a.jsonl").unwrap(), root.join("backups").join("a.jsonl"));
+        assert!(root.join("backups").is_dir());
+        for bad in ["", "/etc/passwd", "../a.jsonl", "backups/../../a.jsonl", "."] {
+            assert!(data_dir_path(tmp.path(), bad).is_err(), "{:?}", bad);
+        }
+
+        #[cfg(unix)]
+        {
+            let outside = TempDir::new().unwrap();
+            std::os::unix::fs::symlink(outside.path(), tmp.path().join("link")).unwrap();
+            assert!(data_dir_path(tmp.path(), "link/a.jsonl").is_err());
+            // Nothing is created outside on the way to rejecting the path
+            assert!(data_dir_path(tmp.path(), "link/new/sub/out.jsonl").is_err());
+            assert!(!outside.path().join("new").exists());
+            std::os::unix::fs::symlink(outside.path().join("a.jsonl"), tmp.path().join("file.jsonl")).unwrap();
+            assert!(data_dir_path(tmp.path(), "file.jsonl").is_err());
+
+            // Reads follow symlinks, but only to places inside the data directory
+            std::fs::write(outside.path().join("a.jsonl"), "").unwrap();
+            assert!(existing_data_dir_path(tmp.path(), "link/a.jsonl").is_err());
+            std::fs::write(root.join("backups").join("b.jsonl"), "").unwrap();
+            std::os::unix::fs::symlink(root.join("backups"), tmp.path().join("inner")).unwrap();
+            assert_eq!(existing_data_dir_path(tmp.path(), "inner/b.jsonl").unwrap(), root.join("backups").join("b.jsonl"));
+        }
+        assert!(existing_data_dir_path(tmp.path(), "/etc/passwd").is_err());
+        assert!(existing_data_dir_path(tmp.path(), "backups/missing.jsonl").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_search_papers_rejects_offset_past_max() {
+        let tmp = TempDir::new().unwrap();
+        let server = test_server(&tmp, vec![Arc::new(mock_source())], |_| {}).await;
+        let params = |offset| Parameters(SearchPapersParams {
+            query: "entanglement".to_string(),
+            sources: None,
+            max_results: Some(10),
+            offset: Some(offset),
+            check_retractions: None,
+            language: None,
+            field: None,
+            min_results: None,
+            summarize_authors: None,
+            categories: None,
+            rank_by: None,
+        });
+
+        assert!(server.search_papers(params(MAX_SEARCH_OFFSET)).await.is_ok());
+        let err = server.search_papers(params(u32::MAX)).await.unwrap_err();
+        assert!(err.message.contains("offset"), "{}", err.message);
+    }
+
+    #[tokio::test]
+    async fn test_min_results_broadens_strict_query() {
+        let tmp = TempDir::new().unwrap();
+        // One paper for a quoted query and three for an ORed one
+        let source = MockSource::new("strict").on_search(|query| {
+            let titles: &[&str] = if query.contains(" OR ") {
+                &["Holographic Entanglement Entropy", "Entanglement Wedge Reconstruction", "Holographic Complexity"]
+            } else {
+                &["Holographic Entanglement Entropy"]
+            };
+            titles
+                .iter()
+                .enumerate()
+                .map(|(i, title)| PaperResult { doi: Some(format!("10.1234/strict.{}", i)), ..sample_paper(&format!("strict:{}", i), title) })
+                .collect()
+        });
+        let server = test_server(&tmp, vec![Arc::new(source)], |config| config.json_envelope = true).await;
+        let params = |min_results| Parameters(SearchPapersParams {
+            query: "\"holographic entanglement\"".to_string(),
+            sources: None,
+            max_results: Some(10),
+            offset: None,
+            check_retractions: None,
+            language: None,
+            field: None,
+            min_results,
+            summarize_authors: None,
+            categories: None,
+            rank_by: None,
+        });
+
+        let result = server.search_papers(params(None)).await.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(json["count"], 1);
+        assert!(json["diagnostics"].get("broadened_query").is_none());
+
+        let result = server.search_papers(params(Some(3))).await.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(json["count"], 3);
+        assert_eq!(json["diagnostics"]["broadened_query"], "holographic OR entanglement");
+    }
+
+    #[tokio::test]
+    async fn test_verbose_not_found_explains_each_source() {
+        let tmp = TempDir::new().unwrap();
+        let sources: Vec<Arc<dyn PaperSource>> = vec![
+            Arc::new(MockSource::new("counting")),
+            Arc::new(MockSource::new("failing").failing(|| SourceError::Parse("unavailable".to_string()))),
+        ];
+        let server = test_server(&tmp, sources, |_| {}).await;
+        let params = |source: Option<&str>| Parameters(GetPaperParams {
+            id: "nowhere:1".to_string(),
+            source: source.map(str::to_string),
+            verbose: Some(true),
+        });
+
+        let result = server.get_paper(params(None)).await.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(json["found"], false);
+        assert_eq!(json["attempts"], serde_json::json!([
+            {"source": "counting", "outcome": "not_found"},
+            {"source": "failing", "outcome": "error", "error": "Parse error: unavailable"},
+        ]));
+
+        let result = server.get_paper(params(Some("failing"))).await.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(json["attempts"][0]["outcome"], "skipped_by_filter");
+        assert_eq!(json["attempts"][1]["outcome"], "error");
+    }
+
+    #[tokio::test]
+    async fn test_pmid_served_by_europepmc_without_pubmed() {
+        let tmp = TempDir::new().unwrap();
+        let europepmc = MockSource::new("europepmc").on_paper(|id| Some(sample_paper(id, "Europe PMC Record")));
+        let sources: Vec<Arc<dyn PaperSource>> = vec![Arc::new(mock_source()), Arc::new(europepmc)];
+        let server = test_server(&tmp, sources, |_| {}).await;
+
+        let lookup = server.lookup_paper("pmid:12345", None).await.unwrap();
+        assert_eq!(lookup.source, "europepmc");
+        assert_eq!(lookup.paper.title, "Europe PMC Record");
+        assert_eq!(lookup.attempts[0].outcome, "skipped_by_filter");
+
+        // PubMed is preferred when both are enabled
+        let pubmed = MockSource::new("pubmed").on_paper(|id| Some(sample_paper(id, "PubMed Record")));
+        let europepmc = MockSource::new("europepmc").on_paper(|id| Some(sample_paper(id, "Europe PMC Record")));
+        let sources: Vec<Arc<dyn PaperSource>> = vec![Arc::new(europepmc), Arc::new(pubmed)];
+        let tmp = TempDir::new().unwrap();
+        let server = test_server(&tmp, sources, |_| {}).await;
+        assert_eq!(server.lookup_paper("pmid:12345", None).await.unwrap().source, "pubmed");
+    }
+
+    #[tokio::test]
+    async fn test_indexed_papers_listed_and_read_as_resources() {
+        let tmp = TempDir::new().unwrap();
+        let server = test_server(&tmp, vec![Arc::new(mock_source())], |_| {}).await;
+        for (id, title) in [("arxiv:2", "Second Paper"), ("arxiv:1", "First Paper")] {
+            let paper = sample_paper(id, title);
+            let embedding = server.embed_paper(&paper).await.unwrap();
+            server.local_index.lock().await.index_paper(&paper, &embedding).await.unwrap();
+        }
+
+        let page = server.resource_page(None).await.unwrap();
+        let uris: Vec<&str> = page.resources.iter().map(|r| r.raw.uri.as_str()).collect();
+        assert_eq!(uris, vec!["paper://local/arxiv:1", "paper://local/arxiv:2"]);
+        assert_eq!(page.resources[0].raw.title.as_deref(), Some("First Paper"));
+        assert!(page.next_cursor.is_none());
+
+        let read = server.read_paper_resource("paper://local/arxiv:2").await.unwrap();
+        let ResourceContents::TextResourceContents { text, mime_type, .. } = &read.contents[0] else {
+            panic!("expected text contents");
+        };
+        assert_eq!(mime_type.as_deref(), Some("application/json"));
+        let paper: PaperResult = serde_json::from_str(text).unwrap();
+        assert_eq!(paper.title, "Second Paper");
+
+        assert!(server.read_paper_resource("paper://local/arxiv:9").await.is_err());
+        assert!(server.read_paper_resource("file:///etc/passwd").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_raw_source_record_stored_when_enabled() {
+        for store_raw_json in [false, true] {
+            let tmp = TempDir::new().unwrap();
+            let source = Arc::new(
+                MockSource::new("raw")
+                    .on_paper(|id| Some(PaperResult { source: "raw".to_string(), ..sample_paper(id, "Raw Paper") }))
+                    .with_raw(|id| serde_json::json!({
+                        "id": id,
+                        "title": "Raw Paper",
+                        "unmodelled": { "funding": ["grant 1", "grant 2"], "note": "It's kept" },
+                    })),
+            );
+            let server = test_server(&tmp, vec![source.clone()], |config| config.store_raw_json = store_raw_json).await;
+
+            server.index_paper(Parameters(IndexPaperParams { id: "raw:1".to_string(), source: None })).await.unwrap();
+            let raw = server.get_raw(Parameters(GetRawParams { id: "raw:1".to_string() })).await;
+            if !store_raw_json {
+                assert!(raw.is_err());
+                continue;
+            }
+            let expected = source.get_paper_with_raw("raw:1").await.unwrap().unwrap().1.unwrap();
+            let json: serde_json::Value = serde_json::from_str(&raw.unwrap().content[0].as_text().unwrap().text).unwrap();
+            assert_eq!(json, expected);
+
+            // Re-indexing from a record without raw JSON keeps the stored one
+            server.update_paper(Parameters(UpdatePaperParams { id: "raw:1".to_string(), source: None })).await.unwrap();
+            let raw = server.get_raw(Parameters(GetRawParams { id: "raw:1".to_string() })).await.unwrap();
+            let json: serde_json::Value = serde_json::from_str(&raw.content[0].as_text().unwrap().text).unwrap();
+            assert_eq!(json, expected);
+        }
+    }
+
+    #[cfg(feature = "pdf-text")]
+    #[tokio::test]
+    async fn test_index_pdf_body_survives_update() {
+        let pdf_base = mock_http::serve(|request| {
+            Some(match request.path() {
+                "/paper.pdf" => Response::new(200)
+                    .header("Content-Type", "application/pdf")
+                    .body(minimal_pdf("Appendix B applies the construction to traversable wormholes.")),
+                _ => Response::new(404),
+            })
+        })
+        .await;
+        let tmp = TempDir::new().unwrap();
+        let source = Arc::new(
+            MockSource::new("pdf")
+                .on_paper(move |id| Some(PaperResult {
+                    source: "pdf".to_string(),
+                    pdf_url: Some(format!("{}/paper.pdf", pdf_base)),
+                    ..sample_paper(id, "Quantum Error Correction Codes")
+                }))
+                .with_raw(|id| serde_json::json!({ "id": id })),
+        );
+        let server = test_server(&tmp, vec![source.clone()], |config| config.store_raw_json = true).await;
+
+        let result = server.index_pdf(Parameters(DownloadPdfParams { id: Some("pdf:1".to_string()), doi: None })).await.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert!(json["body_chars"].as_u64().unwrap() > 0);
+        let body = server.reader.vector.stored_text("pdf:1").await.unwrap().body.unwrap();
+        assert!(body.contains("wormholes"), "extracted body: {}", body);
+        assert_eq!(server.reader.fulltext.search("wormholes", 10).unwrap().len(), 1);
+        // The raw record came with the paper, in one lookup
+        assert_eq!(source.lookups.load(std::sync::atomic::Ordering::SeqCst), 1);
+        let raw = server.reader.vector.stored_text("pdf:1").await.unwrap().raw_json.unwrap();
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&raw).unwrap(), serde_json::json!({ "id": "pdf:1" }));
+
+        // Re-fetching the metadata keeps the body searchable
+        server.update_paper(Parameters(UpdatePaperParams { id: "pdf:1".to_string(), source: None })).await.unwrap();
+        assert_eq!(server.reader.fulltext.search("wormholes", 10).unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_citations_excludes_self_citations() {
+        let tmp = TempDir::new().unwrap();
+        // One of two citing papers shares an author with the cited one
+        let source = MockSource::new("self_citing")
+            .on_paper(|id| {
+                let authors = vec!["Juan Maldacena".to_string(), "Leonard Susskind".to_string()];
+                let orcids = vec![None, Some("0000-0001-0000-0001".to_string())];
+                Some(PaperResult { authors, orcids, ..work(id, "cited") })
+            })
+            .on_citations(|_| vec![
+                PaperResult { authors: vec!["Maldacena, J.".to_string(), "Someone New".to_string()], ..work("self:1", "s1") },
+                PaperResult { authors: vec!["Ahmed Almheiri".to_string()], ..work("other:1", "o1") },
+                // A namesake of the second author, told apart by ORCID iD
+                PaperResult {
+                    authors: vec!["L. Susskind".to_string()],
+                    orcids: vec![Some("0000-0001-0000-0002".to_string())],
+                    subjects: Vec::new(),
+                    ..work("other:2", "o2")
+                },
+            ]);
+        let server = test_server(&tmp, vec![Arc::new(source)], |_| {}).await;
+        let params = |exclude_self_citations| Parameters(GetCitationsParams {
+            id: "self_citing:cited".to_string(),
+            source: None,
+            max_results: None,
+            exclude_self_citations,
+        });
+        let ids = |result: CallToolResult| -> Vec<String> {
+            let json: serde_json::Value = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+            json.as_array().unwrap().iter().map(|p| p["id"].as_str().unwrap().to_string()).collect()
+        };
+
+        assert_eq!(ids(server.get_citations(params(None)).await.unwrap()), vec!["self:1", "other:1", "other:2"]);
+        assert_eq!(ids(server.get_citations(params(Some(true))).await.unwrap()), vec!["other:1", "other:2"]);
+    }
+
+    #[tokio::test]
+    async fn test_get_relations_returns_both_sides_deduped() {
+        let tmp = TempDir::new().unwrap();
+        // Citation and reference lists repeat works under other IDs
+        let source = MockSource::new("relations")
+            .on_citations(|_| vec![work("arxiv:c1", "c1"), work("doi:c1", "c1"), work("arxiv:c2", "c2")])
+            .on_references(|_| vec![work("arxiv:r1", "r1"), work("arxiv:r1", "r1")]);
+        let server = test_server(&tmp, vec![Arc::new(source)], |_| {}).await;
+
+        let result = server.get_relations(Parameters(RelationParams {
+            id: "arxiv:seed".to_string(),
+            source: None,
+            max_results: None,
+        })).await.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        let ids = |relation: &str| -> Vec<String> {
+            json.as_array().unwrap().iter()
+                .filter(|p| p["relation"] == relation)
+                .map(|p| p["id"].as_str().unwrap().to_string())
+                .collect()
+        };
+        assert_eq!(ids("citation"), vec!["arxiv:c1", "arxiv:c2"]);
+        assert_eq!(ids("reference"), vec!["arxiv:r1"]);
+    }
+
+    #[tokio::test]
+    async fn test_search_similar_reports_sorted_similarity() {
+        let tmp = TempDir::new().unwrap();
+        let server = test_server(&tmp, vec![Arc::new(mock_source())], |_| {}).await;
+        for (id, title) in [
+            ("local:1", "Holographic entanglement entropy"),
+            ("local:2", "Entanglement entropy in black holes"),
+            ("local:3", "Protein folding kinetics"),
+        ] {
+            let paper = sample_paper(id, title);
+            let embedding = server.embed_paper(&paper).await.unwrap();
+            server.local_index.lock().await.index_paper(&paper, &embedding).await.unwrap();
+        }
+
+        let result = server.search_similar(Parameters(SearchSimilarParams {
+            query: "Holographic entanglement entropy".to_string(),
+            limit: Some(3),
+            exclude_ids: None,
+            diversify: None,
+            lambda: None,
+        })).await.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        let results = json.as_array().unwrap();
+        assert_eq!(results.len(), 3);
+        let similarities: Vec<f64> = results.iter().map(|r| r["similarity"].as_f64().unwrap()).collect();
+        assert!(similarities.iter().all(|s| (0.0..=1.0).contains(s)));
+        assert!(similarities.windows(2).all(|w| w[0] >= w[1]));
+        assert!(results.iter().all(|r| r["distance"].is_number() && r["id"].is_string()));
+    }
+
+    #[tokio::test]
+    async fn test_find_open_access_falls_back_through_providers() {
+        let tmp = TempDir::new().unwrap();
+        let pdf_base = mock_http::serve(|request| {
+            Some(match request.path() {
+                "/live.pdf" => Response::new(200).header("Content-Type", "application/pdf"),
+                _ => Response::new(404),
+            })
+        })
+        .await;
+        let live = format!("{}/live.pdf", pdf_base);
+        let openalex = MockSource::new("openalex").on_paper({
+            let live = live.clone();
+            move |id| (id == "doi:10.1234/dead").then(|| PaperResult { pdf_url: Some(live.clone()), ..sample_paper(id, "OA Copy") })
+        });
+        let server = test_server(&tmp, vec![Arc::new(mock_source()), Arc::new(openalex)], |_| {}).await;
+        let with_pdf = PaperResult {
+            pdf_url: Some(live.clone()),
+            ..sample_paper("local:1", "Paper With PDF")
+        };
+        let dead_link = PaperResult {
+            pdf_url: Some(format!("{}/dead.pdf", pdf_base)),
+            doi: Some("10.1234/dead".to_string()),
+            ..sample_paper("local:2", "Paper With Dead Link")
+        };
+        for paper in [&with_pdf, &dead_link] {
+            let embedding = server.embed_paper(paper).await.unwrap();
+            server.local_index.lock().await.index_paper(paper, &embedding).await.unwrap();
+        }
+        let find = |id: &str| {
+            let server = server.clone();
+            let id = id.to_string();
+            async move {
+                let result = server.find_open_access(Parameters(FindOpenAccessParams { id })).await.unwrap();
+                serde_json::from_str::<serde_json::Value>(&result.content[0].as_text().unwrap().text).unwrap()
+            }
+        };
+
+        let json = find("local:1").await;
+        assert_eq!(json["provider"], "record");
+        assert_eq!(json["pdf_url"], live);
+
+        // The record's own link is dead, so OpenAlex supplies the PDF
+        let json = find("local:2").await;
+        assert_eq!(json["provider"], "openalex");
+        assert_eq!(json["pdf_url"], live);
+        assert_eq!(json["tried"], serde_json::json!(["record", "openalex"]));
+
+        let json = find("mock:1").await;
+        assert!(json["pdf_url"].is_null());
+    }
+
+    #[test]
+    fn test_arxiv_pdf_url_normalizes_ids() {
+        assert_eq!(arxiv_pdf_url("arXiv:2301.00001"), "https://arxiv.org/pdf/2301.00001");
+        assert_eq!(arxiv_pdf_url("arxiv:2301.00001v2"), "https://arxiv.org/pdf/2301.00001");
+        assert_eq!(arxiv_pdf_url("hep-th/9711200"), "https://arxiv.org/pdf/hep-th/9711200");
+    }
+
+    #[tokio::test]
+    async fn test_export_ris_keeps_missing_ids_out_of_the_records() {
+        let tmp = TempDir::new().unwrap();
+        let source = MockSource::new("mock").on_paper(|id| (id == "mock:1").then(|| sample_paper(id, "Found Paper")));
+        let server = test_server(&tmp, vec![Arc::new(source)], |_| {}).await;
+
+        let result = server.export_ris(Parameters(ExportParams {
+            ids: vec!["mock:1".to_string(), "mock:2".to_string()],
+        })).await.unwrap();
+        let ris = &result.content[0].as_text().unwrap().text;
+        assert!(ris.contains("TI  - Found Paper"));
+        for line in ris.lines().filter(|l| !l.trim().is_empty()) {
+            let tag = line.as_bytes();
+            let valid = tag.len() >= 6
+                && tag[0].is_ascii_uppercase()
+                && (tag[1].is_ascii_uppercase() || tag[1].is_ascii_digit())
+                && &tag[2..6] == b"  - ";
+            assert!(valid, "not a RIS tag line: {:?}", line);
+        }
+        assert_eq!(result.content[1].as_text().unwrap().text, "Not found: mock:2");
+    }
+
+    #[tokio::test]
+    async fn test_get_papers_keeps_input_order_across_local_and_sources() {
+        let tmp = TempDir::new().unwrap();
+        let server = test_server(&tmp, vec![Arc::new(mock_source())], |_| {}).await;
+        let local = sample_paper("local:1", "Indexed Paper");
+        let embedding = server.embed_paper(&local).await.unwrap();
+        server.local_index.lock().await.index_paper(&local, &embedding).await.unwrap();
+
+        let params = GetPapersParams {
+            ids: vec!["mock:2".to_string(), "local:1".to_string()],
+            source: None,
+        };
+        let result = server.get_papers(Parameters(params)).await.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(json["papers"][0]["id"], "mock:2");
+        assert_eq!(json["papers"][0]["citation_count"], 42);
+        assert_eq!(json["papers"][1]["title"], "Indexed Paper");
+        assert_eq!(json["not_found"], serde_json::json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_mock_embedded_index_warns_or_falls_back_to_keyword() {
+        let tmp = TempDir::new().unwrap();
+        let mut server = test_server(&tmp, vec![Arc::new(mock_source())], |_| {}).await;
+        let paper = sample_paper("local:1", "Holographic Entanglement Entropy");
+        let embedding = server.embed_paper(&paper).await.unwrap();
+        server.local_index.lock().await.index_paper(&paper, &embedding).await.unwrap();
+        let params = || Parameters(SearchLocalParams {
+            query: "holographic".to_string(),
+            mode: Some("vector".to_string()),
+            limit: None,
+            keyword_weight: None,
+            vector_weight: None,
+            source: None,
+            year_min: None,
+            year_max: None,
+            language: None,
+            diversify: None,
+            lambda: None,
+            summarize_authors: None,
+        });
+
+        let result = server.search_local(params()).await.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(json["results"][0]["data_source"], "vector");
+        assert!(json["warnings"][0]["error"].as_str().unwrap().contains("mock embeddings"));
+
+        let mut config = (*server.config).clone();
+        config.mock_embedding_keyword_fallback = true;
+        server.config = Arc::new(config);
+        let result = server.search_local(params()).await.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(json["results"][0]["data_source"], "keyword");
+        assert!(json["warnings"][0]["error"].as_str().unwrap().contains("as keyword"));
+    }
+
+    #[tokio::test]
+    async fn test_partial_local_record_refreshed_from_source() {
+        let tmp = TempDir::new().unwrap();
+        let mut server = test_server(&tmp, vec![Arc::new(mock_source())], |_| {}).await;
+        let partial = sample_paper("mock:7", "Cached Paper");
+        assert!(partial.citation_count.is_none());
+        let embedding = server.embed_paper(&partial).await.unwrap();
+        server.local_index.lock().await.index_paper(&partial, &embedding).await.unwrap();
+
+        // Default: the local record is returned as-is
+        let lookup = server.lookup_paper("mock:7", None).await.unwrap();
+        assert!(lookup.from_local_index);
+        assert_eq!(lookup.paper.citation_count, None);
+
+        let mut config = (*server.config).clone();
+        config.refresh_missing_fields = vec!["citation_count".to_string()];
+        server.config = Arc::new(config);
+
+        // Other lookups see the refreshed record but leave the index alone
+        let lookup = server.lookup_paper("mock:7", None).await.unwrap();
+        assert_eq!(lookup.source, "mock");
+        assert_eq!(lookup.paper.title, "Cached Paper");
+        assert_eq!(lookup.paper.citation_count, Some(42));
+        assert_eq!(server.reader.get_paper("mock:7").await.unwrap().unwrap().citation_count, None);
+
+        server.get_paper(Parameters(GetPaperParams { id: "mock:7".to_string(), source: None, verbose: None })).await.unwrap();
+        let idx = server.local_index.lock().await;
+        let stored = idx.get_paper("mock:7").await.unwrap().unwrap();
+        assert_eq!(stored.citation_count, Some(42));
+        assert_eq!(idx.count().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_paper_writes_only_when_refreshing() {
+        let get_paper_writes = |server: &PaperSearchServer| {
+            server.tool_router.list_all().iter().find(|t| t.name == "get_paper").map(writes)
+        };
+        let tmp = TempDir::new().unwrap();
+        let server = test_server(&tmp, vec![Arc::new(mock_source())], |_| {}).await;
+        assert_eq!(get_paper_writes(&server), Some(false));
+
+        let tmp = TempDir::new().unwrap();
+        let server = test_server(&tmp, vec![Arc::new(mock_source())], |cfg| {
+            cfg.refresh_missing_fields = vec!["citation_count".to_string()];
+        }).await;
+        assert_eq!(get_paper_writes(&server), Some(true));
+
+        // Read-only servers keep get_paper and never store refreshed records
+        let tmp = TempDir::new().unwrap();
+        drop(test_server(&tmp, Vec::new(), |_| {}).await);
+        let server = test_server(&tmp, vec![Arc::new(mock_source())], |cfg| {
+            cfg.refresh_missing_fields = vec!["citation_count".to_string()];
+            cfg.read_only = true;
+        }).await;
+        assert_eq!(get_paper_writes(&server), Some(false));
+    }
+
+    #[tokio::test]
+    async fn test_search_papers_writes_only_when_auto_indexing() {
+        let search_writes = |server: &PaperSearchServer| {
+            server.tool_router.list_all().iter().find(|t| t.name == "search_papers").map(writes)
+        };
+        let tmp = TempDir::new().unwrap();
+        let server = test_server(&tmp, vec![Arc::new(mock_source())], |_| {}).await;
+        assert_eq!(search_writes(&server), Some(false));
+
+        let tmp = TempDir::new().unwrap();
+        let server = test_server(&tmp, vec![Arc::new(mock_source())], |cfg| cfg.auto_index = true).await;
+        assert_eq!(search_writes(&server), Some(true));
+    }
+
+    #[tokio::test]
+    async fn test_refresh_bypasses_cached_responses() {
+        let tmp = TempDir::new().unwrap();
+        let fetches = Arc::new(std::sync::atomic::AtomicU32::new(0));
+        let counter = fetches.clone();
+        let source = MockSource::new("mock").on_paper(move |id| {
+            let count = counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+            Some(PaperResult { citation_count: Some(count), ..sample_paper(id, "Counted") })
+        });
+        let disk_cache = Arc::new(cache::DiskCache::new(tmp.path().join("cache"), std::time::Duration::from_secs(3600)));
+        let cached: Arc<dyn PaperSource> = Arc::new(apis::cached::CachedSource::new(Arc::new(source), disk_cache.clone()));
+        let mut server = test_server(&tmp, vec![cached], |_| {}).await;
+        server.disk_cache = Some(disk_cache);
+
+        let first = server.fetch_from_sources("mock:1", None).await.unwrap();
+        assert_eq!(first.paper.citation_count, Some(1));
+        let cached = server.fetch_from_sources("mock:1", None).await.unwrap();
+        assert_eq!(cached.paper.citation_count, Some(1));
+
+        let fresh = server.fetch_fresh("mock:1", None).await.unwrap();
+        assert_eq!(fresh.paper.citation_count, Some(2));
+        // ...and the fresh record replaces the cached one
+        let cached = server.fetch_from_sources("mock:1", None).await.unwrap();
+        assert_eq!(cached.paper.citation_count, Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_update_paper_reindexes_fresh_metadata() {
+        let tmp = TempDir::new().unwrap();
+        let server = test_server(&tmp, vec![Arc::new(mock_source())], |_| {}).await;
+        let params = |id: &str| Parameters(UpdatePaperParams { id: id.to_string(), source: None });
+
+        assert!(server.update_paper(params("mock:7")).await.is_err());
+
+        let stale = sample_paper("mock:7", "Cached Paper");
+        let embedding = server.embed_paper(&stale).await.unwrap();
+        server.local_index.lock().await.index_paper(&stale, &embedding).await.unwrap();
+
+        let result = server.update_paper(params("mock:7")).await.unwrap();
+        let text = &result.content[0].as_text().unwrap().text;
+        assert!(text.contains("citation_count: unknown -> 42"));
+        assert!(text.contains("year: 2024 (unchanged)"));
+
+        let idx = server.local_index.lock().await;
+        let stored = idx.get_paper("mock:7").await.unwrap().unwrap();
+        assert_eq!(stored.title, "Mock Paper");
+        assert_eq!(stored.citation_count, Some(42));
+        assert_eq!(idx.count().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_clear_index_requires_confirmation() {
+        let tmp = TempDir::new().unwrap();
+        let server = test_server(&tmp, vec![Arc::new(mock_source())], |_| {}).await;
+        let paper = sample_paper("mock:1", "Indexed Paper");
+        let embedding = server.embed_paper(&paper).await.unwrap();
+        server.local_index.lock().await.index_paper(&paper, &embedding).await.unwrap();
+
+        let params = |confirm: &str| Parameters(ClearIndexParams { confirm: confirm.to_string() });
+        assert!(server.clear_index(params("yes")).await.is_err());
+        assert_eq!(server.local_index.lock().await.count().await.unwrap(), 1);
+
+        server.clear_index(params(CLEAR_INDEX_CONFIRMATION)).await.unwrap();
+        assert_eq!(server.local_index.lock().await.count().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_disabled_tools_are_not_routed() {
+        let tmp = TempDir::new().unwrap();
+        let server = test_server(&tmp, vec![Arc::new(mock_source())], |config| {
+            config.disabled_tools = vec!["clear_index".to_string(), "prune_index".to_string()];
+        })
+        .await;
+
+        let listed: Vec<String> = server.tool_router.list_all().into_iter().map(|t| t.name.to_string()).collect();
+        assert!(!listed.iter().any(|name| name == "clear_index" || name == "prune_index"));
+        assert!(listed.iter().any(|name| name == "search_papers"));
+        // Calls are dispatched through the router, which rejects unknown tools.
+        assert!(!server.tool_router.has_route("clear_index"));
+        assert!(!server.tool_router.has_route("prune_index"));
+    }
+
+    #[tokio::test]
+    async fn test_read_only_mode_disables_mutating_tools() {
+        let tmp = TempDir::new().unwrap();
+        let paper = sample_paper("mock:1", "Holographic Entanglement");
+        {
+            let server = test_server(&tmp, vec![Arc::new(mock_source())], |_| {}).await;
+            let embedding = server.embed_paper(&paper).await.unwrap();
+            server.local_index.lock().await.index_paper(&paper, &embedding).await.unwrap();
+        }
+
+        let server = test_server(&tmp, vec![Arc::new(mock_source())], |config| config.read_only = true).await;
+
+        for name in ["index_paper", "update_paper", "prune_index", "clear_index", "import_jsonl", "export_jsonl", "download_pdf", "clear_cache"] {
+            assert!(!server.tool_router.has_route(name), "{} should be disabled", name);
+        }
+        assert!(server.get_info().instructions.unwrap().contains("Read-only"));
+
+        assert!(server.tool_router.has_route("search_local"));
+        assert!(server.tool_router.has_route("get_paper"));
+        let lookup = server.lookup_paper("mock:1", None).await.unwrap();
+        assert!(lookup.from_local_index);
+        let sources = server.list_sources().await.unwrap();
+        assert_eq!(sources.content.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_auto_index_grows_local_index() {
+        let tmp = TempDir::new().unwrap();
+        let server = test_server(&tmp, vec![Arc::new(mock_source())], |config| config.auto_index = true).await;
+
+        assert_eq!(server.local_index.lock().await.count().await.unwrap(), 0);
+
+        let results = search::federated_search(&server.sources, "holography", 10, 0, None, Default::default(), server.config.source_timeout).await.papers;
+        assert!(!results.is_empty());
+        let indexed = server.spawn_auto_index(&results).unwrap().await.unwrap();
+        assert_eq!(indexed, 1);
+        assert_eq!(server.local_index.lock().await.count().await.unwrap(), 1);
+
+        // Already-indexed papers are skipped on the next search
+        let indexed = server.spawn_auto_index(&results).unwrap().await.unwrap();
+        assert_eq!(indexed, 0);
+
+        // With every task slot taken, results aren't queued behind them
+        let _busy = server.auto_index_permits.clone().acquire_many_owned(MAX_AUTO_INDEX_TASKS as u32).await.unwrap();
+        assert!(server.spawn_auto_index(&results).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_equivalent_searches_share_cache_entry() {
+        let tmp = TempDir::new().unwrap();
+        let source = Arc::new(MockSource::new("counting").on_search(|query| vec![sample_paper("counting:1", query)]));
+        let server = test_server(&tmp, vec![source.clone()], |_| {}).await;
+
+        let params = |query: &str, sources: &[&str]| SearchPapersParams {
+            query: query.to_string(),
+            sources: Some(sources.iter().map(|s| s.to_string()).collect()),
+            max_results: Some(10),
+            offset: None,
+            check_retractions: None,
+            language: None,
+            field: None,
+            min_results: None,
+            summarize_authors: None,
+            categories: None,
+            rank_by: None,
+        };
+        server.search_papers(Parameters(params("Holographic  Entanglement", &["counting", "arxiv"]))).await.unwrap();
+        server.search_papers(Parameters(params("holographic entanglement", &["arxiv", "Counting"]))).await.unwrap();
+
+        assert_eq!(source.lookups.load(std::sync::atomic::Ordering::SeqCst), 1);
+        let stats = server.search_cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (1, 1, 1));
+    }
+
+    #[test]
+    fn test_match_confidence() {
+        let paper = sample_paper("mock:abc", "Quantum Error Correction");
+        assert_eq!(match_confidence("10.1234/mock", &paper), 1.0);
+        assert!(match_confidence("quantum error correction", &paper) > 0.9);
+        assert!(match_confidence("unrelated", &paper) < 0.5);
+    }
+}
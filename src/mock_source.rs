@@ -0,0 +1,154 @@
+//! A configurable `PaperSource` for tests that drive searches and tools
+//! without a network. Every lookup finds nothing until given a handler.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::apis::{AuthorQuery, AuthorWorks, PaperResult, PaperSource, SourceError};
+
+type Handler<T> = Box<dyn Fn(&str) -> T + Send + Sync>;
+
+pub struct MockSource {
+    name: &'static str,
+    search: Handler<Vec<PaperResult>>,
+    paper: Handler<Option<PaperResult>>,
+    citations: Handler<Vec<PaperResult>>,
+    references: Handler<Vec<PaperResult>>,
+    authors: Box<dyn Fn(&AuthorQuery) -> AuthorWorks + Send + Sync>,
+    raw: Option<Handler<serde_json::Value>>,
+    error: Option<Box<dyn Fn() -> SourceError + Send + Sync>>,
+    language_filter: bool,
+    delay: Option<Duration>,
+    /// Lookups that reached the source.
+    pub lookups: AtomicUsize,
+    in_flight: AtomicUsize,
+    /// Most lookups ever in flight at once.
+    pub peak: AtomicUsize,
+}
+
+impl MockSource {
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            search: Box::new(|_| Vec::new()),
+            paper: Box::new(|_| None),
+            citations: Box::new(|_| Vec::new()),
+            references: Box::new(|_| Vec::new()),
+            authors: Box::new(|_| AuthorWorks::default()),
+            raw: None,
+            error: None,
+            language_filter: false,
+            delay: None,
+            lookups: AtomicUsize::new(0),
+            in_flight: AtomicUsize::new(0),
+            peak: AtomicUsize::new(0),
+        }
+    }
+
+    /// Answer searches with `search(query)`, truncated to the requested count.
+    pub fn on_search(mut self, search: impl Fn(&str) -> Vec<PaperResult> + Send + Sync + 'static) -> Self {
+        self.search = Box::new(search);
+        self
+    }
+
+    pub fn on_paper(mut self, paper: impl Fn(&str) -> Option<PaperResult> + Send + Sync + 'static) -> Self {
+        self.paper = Box::new(paper);
+        self
+    }
+
+    pub fn on_citations(mut self, citations: impl Fn(&str) -> Vec<PaperResult> + Send + Sync + 'static) -> Self {
+        self.citations = Box::new(citations);
+        self
+    }
+
+    pub fn on_references(mut self, references: impl Fn(&str) -> Vec<PaperResult> + Send + Sync + 'static) -> Self {
+        self.references = Box::new(references);
+        self
+    }
+
+    pub fn on_author(mut self, authors: impl Fn(&AuthorQuery) -> AuthorWorks + Send + Sync + 'static) -> Self {
+        self.authors = Box::new(authors);
+        self
+    }
+
+    /// Serve `raw(id)` as the source's JSON record alongside each paper.
+    pub fn with_raw(mut self, raw: impl Fn(&str) -> serde_json::Value + Send + Sync + 'static) -> Self {
+        self.raw = Some(Box::new(raw));
+        self
+    }
+
+    /// Fail every lookup with `error()`.
+    pub fn failing(mut self, error: impl Fn() -> SourceError + Send + Sync + 'static) -> Self {
+        self.error = Some(Box::new(error));
+        self
+    }
+
+    /// Support language filtering by appending `LANG:<code>` to queries.
+    pub fn with_language_filter(mut self) -> Self {
+        self.language_filter = true;
+        self
+    }
+
+    /// Hold every lookup for `delay` before answering.
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// Run one lookup: wait out the delay, record concurrency and apply the
+    /// configured failure.
+    async fn lookup<T>(&self, answer: impl FnOnce() -> T) -> Result<T, SourceError> {
+        self.lookups.fetch_add(1, Ordering::SeqCst);
+        let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.peak.fetch_max(now, Ordering::SeqCst);
+        if let Some(delay) = self.delay {
+            tokio::time::sleep(delay).await;
+        }
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+        match &self.error {
+            Some(error) => Err(error()),
+            None => Ok(answer()),
+        }
+    }
+}
+
+#[async_trait]
+impl PaperSource for MockSource {
+    fn name(&self) -> &str { self.name }
+
+    async fn search(&self, query: &str, max_results: u32) -> Result<Vec<PaperResult>, SourceError> {
+        self.lookup(|| {
+            let mut results = (self.search)(query);
+            results.truncate(max_results as usize);
+            results
+        })
+        .await
+    }
+
+    async fn get_paper(&self, id: &str) -> Result<Option<PaperResult>, SourceError> {
+        self.lookup(|| (self.paper)(id)).await
+    }
+
+    async fn get_citations(&self, id: &str) -> Result<Vec<PaperResult>, SourceError> {
+        self.lookup(|| (self.citations)(id)).await
+    }
+
+    async fn get_references(&self, id: &str) -> Result<Vec<PaperResult>, SourceError> {
+        self.lookup(|| (self.references)(id)).await
+    }
+
+    async fn search_by_author(&self, query: &AuthorQuery, _max_results: u32) -> Result<AuthorWorks, SourceError> {
+        self.lookup(|| (self.authors)(query)).await
+    }
+
+    fn language_query(&self, query: &str, language: &str) -> Option<String> {
+        self.language_filter.then(|| format!("{} LANG:{}", query, language))
+    }
+
+    async fn get_paper_with_raw(&self, id: &str) -> Result<Option<(PaperResult, Option<serde_json::Value>)>, SourceError> {
+        let raw = self.raw.as_ref().map(|raw| raw(id));
+        Ok(self.get_paper(id).await?.map(|paper| (paper, raw)))
+    }
+}
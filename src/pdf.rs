@@ -0,0 +1,181 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use reqwest::header::CONTENT_TYPE;
+use tokio::io::AsyncWriteExt;
+
+use crate::apis::SendExt;
+
+/// Largest PDF stored when `PAPER_SEARCH_PDF_MAX_MB` is not set.
+pub const DEFAULT_MAX_BYTES: u64 = 50 * 1024 * 1024;
+
+/// Where a paper's PDF is stored under `dir`: the ID with ASCII letters,
+/// digits, `-` and non-leading `.` kept and every other byte written as
+/// `_XX` (hex), so distinct IDs never share a file.
+pub fn pdf_path(dir: &Path, id: &str) -> PathBuf {
+    let mut name = String::with_capacity(id.len());
+    for (i, byte) in id.bytes().enumerate() {
+        if byte.is_ascii_alphanumeric() || byte == b'-' || (byte == b'.' && i > 0) {
+            name.push(byte as char);
+        } else {
+            name.push_str(&format!("_{:02X}", byte));
+        }
+    }
+    dir.join(format!("{}.pdf", name))
+}
+
+/// Whether `url` answers with a 2xx status. Asks with HEAD first and
+/// falls back to GET for servers that refuse HEAD; no body is read.
+pub async fn is_reachable(client: &reqwest::Client, url: &str) -> bool {
+    for method in [reqwest::Method::HEAD, reqwest::Method::GET] {
+        match client.request(method, url).send_checked().await {
+            Ok(response) if response.status().is_success() => return true,
+            Ok(_) => {}
+            Err(e) => tracing::debug!("{} unreachable: {}", url, e),
+        }
+    }
+    false
+}
+
+/// Stream the PDF at `url` (following redirects) to `dest`, returning its
+/// size in bytes. The response must be declared a PDF, or be untyped and
+/// start with the `%PDF-` signature, and may not exceed `max_bytes`. The
+/// file is written beside `dest` and renamed into place once complete, so
+/// a failed download never leaves a partial PDF behind.
+pub async fn download(client: &reqwest::Client, url: &str, dest: &Path, max_bytes: u64) -> Result<u64> {
+    let mut response = client.get(url).send_checked().await?;
+    if !response.status().is_success() {
+        bail!("HTTP {} from {}", response.status(), response.url());
+    }
+    let content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_lowercase();
+    let declared_pdf = content_type.contains("pdf");
+    if !declared_pdf && !content_type.is_empty() && !content_type.starts_with("application/octet-stream") {
+        bail!("{} is not a PDF (Content-Type: {})", response.url(), content_type);
+    }
+    if let Some(length) = response.content_length().filter(|&len| len > max_bytes) {
+        bail!("PDF is {} bytes; the limit is {}", length, max_bytes);
+    }
+
+    if let Some(dir) = dest.parent() {
+        tokio::fs::create_dir_all(dir).await.with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    let partial = dest.with_extension("pdf.part");
+    let result = async {
+        let mut file = tokio::fs::File::create(&partial).await?;
+        let mut written = 0u64;
+        let mut head: Vec<u8> = Vec::with_capacity(5);
+        while let Some(chunk) = response.chunk().await? {
+            written += chunk.len() as u64;
+            if written > max_bytes {
+                bail!("PDF exceeds the {} byte limit", max_bytes);
+            }
+            if head.len() < 5 {
+                head.extend(chunk.iter().take(5 - head.len()));
+            }
+            file.write_all(&chunk).await?;
+        }
+        if !declared_pdf && head != b"%PDF-" {
+            bail!("{} did not return a PDF", url);
+        }
+        file.flush().await?;
+        Ok::<_, anyhow::Error>(written)
+    }
+    .await;
+
+    match result {
+        Ok(written) => {
+            tokio::fs::rename(&partial, dest).await.with_context(|| format!("Failed to write {}", dest.display()))?;
+            Ok(written)
+        }
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&partial).await;
+            Err(e)
+        }
+    }
+}
+
+/// Extract the text of a downloaded PDF. CPU-bound, and the extractor may
+/// panic on malformed files, so call it from `spawn_blocking`.
+#[cfg(feature = "pdf-text")]
+pub fn extract_text(path: &Path) -> Result<String> {
+    let bytes = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    pdf_extract::extract_text_from_mem(&bytes)
+        .map_err(|e| anyhow::anyhow!("Failed to extract text from {}: {}", path.display(), e))
+}
+
+#[cfg(not(feature = "pdf-text"))]
+pub fn extract_text(_path: &Path) -> Result<String> {
+    bail!("PDF text extraction needs the `pdf-text` build feature")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apis::{http_client, HttpTuning};
+    use crate::mock_http::{self, Response};
+
+    /// Serves `/paper` as a redirect to `/paper.pdf`, `/page` as HTML,
+    /// `/big` as an untyped PDF larger than the tests' limit, and
+    /// `/get-only.pdf` to GET but not HEAD.
+    async fn mock_server() -> String {
+        mock_http::serve(|request| {
+            Some(match request.path() {
+                "/paper" => Response::new(302).header("Location", "/paper.pdf"),
+                "/paper.pdf" => Response::new(200).header("Content-Type", "application/pdf").body("%PDF-1.7 body"),
+                "/page" => Response::new(200).header("Content-Type", "text/html").body("<html></html>"),
+                "/big" => Response::new(200).body([b"%PDF-".as_slice(), &[b'x'; 2048]].concat()),
+                "/get-only.pdf" if request.method == "HEAD" => Response::new(405),
+                "/get-only.pdf" => Response::new(200).header("Content-Type", "application/pdf").body("%PDF-1.7 body"),
+                _ => Response::new(404),
+            })
+        })
+        .await
+    }
+
+    #[test]
+    fn test_pdf_path_escapes_ids() {
+        let dir = Path::new("/data/pdfs");
+        assert_eq!(pdf_path(dir, "arxiv:2301.12345"), dir.join("arxiv_3A2301.12345.pdf"));
+        assert_eq!(pdf_path(dir, "doi:10.1000/x y"), dir.join("doi_3A10.1000_2Fx_20y.pdf"));
+        assert_eq!(pdf_path(dir, "../etc/passwd"), dir.join("_2E._2Fetc_2Fpasswd.pdf"));
+        // IDs that differ only in escaped characters stay distinct
+        assert_ne!(pdf_path(dir, "doi:10.1/a_b"), pdf_path(dir, "doi:10.1/a/b"));
+        assert_ne!(pdf_path(dir, "doi:10.1/a_b"), pdf_path(dir, "doi:10.1/a b"));
+    }
+
+    #[tokio::test]
+    async fn test_download_follows_redirects_and_rejects_non_pdfs() {
+        let base = mock_server().await;
+        let tmp = tempfile::TempDir::new().unwrap();
+        let client = http_client(&HttpTuning::default(), "paper-search-mcp/test");
+
+        let dest = tmp.path().join("pdfs").join("paper.pdf");
+        let bytes = download(&client, &format!("{}/paper", base), &dest, 1024).await.unwrap();
+        assert_eq!(bytes, 13);
+        assert_eq!(std::fs::read(&dest).unwrap(), b"%PDF-1.7 body");
+
+        let page = tmp.path().join("page.pdf");
+        assert!(download(&client, &format!("{}/page", base), &page, 1024).await.is_err());
+        assert!(!page.exists());
+
+        let big = tmp.path().join("big.pdf");
+        let err = download(&client, &format!("{}/big", base), &big, 1024).await.unwrap_err();
+        assert!(err.to_string().contains("limit"));
+        assert!(!big.exists());
+        assert!(!big.with_extension("pdf.part").exists());
+    }
+
+    #[tokio::test]
+    async fn test_is_reachable_falls_back_to_get() {
+        let base = mock_server().await;
+        let client = http_client(&HttpTuning::default(), "paper-search-mcp/test");
+        assert!(is_reachable(&client, &format!("{}/paper", base)).await);
+        assert!(is_reachable(&client, &format!("{}/get-only.pdf", base)).await);
+        assert!(!is_reachable(&client, &format!("{}/missing.pdf", base)).await);
+    }
+}
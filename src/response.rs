@@ -0,0 +1,197 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// Free-form metadata attached to an enveloped response (timing, truncation, ...).
+pub type Diagnostics = Map<String, Value>;
+
+/// A source that failed while producing a result list, so coverage is incomplete.
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceWarning {
+    pub source: String,
+    pub error: String,
+}
+
+/// Typed envelope wrapping a tool's result list.
+#[derive(Debug, Serialize)]
+pub struct Envelope<'a, T: Serialize> {
+    pub kind: &'a str,
+    pub count: usize,
+    pub results: &'a [T],
+    pub diagnostics: Diagnostics,
+    /// Present (possibly empty) for tools that can lose a source.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warnings: Option<&'a [SourceWarning]>,
+}
+
+/// Bare output of tools that can lose a source: results plus the sources
+/// that failed, so the caller can tell when the results are partial.
+#[derive(Debug, Serialize)]
+struct PartialResults<'a, T: Serialize> {
+    results: &'a [T],
+    warnings: &'a [SourceWarning],
+}
+
+/// Collapse the `authors` of every result after the first `keep_full` to
+/// `["First Author et al."]`, recording the original length in `author_count`.
+/// Scored local results carry their authors under `paper`. Results with at
+/// most one author are left alone.
+pub fn summarize_authors(results: &mut [Value], keep_full: usize) {
+    for result in results.iter_mut().skip(keep_full) {
+        let target = if result.get("paper").is_some_and(Value::is_object) {
+            &mut result["paper"]
+        } else {
+            result
+        };
+        let Some(obj) = target.as_object_mut() else { continue };
+        let Some(Value::Array(authors)) = obj.get("authors") else { continue };
+        if authors.len() <= 1 {
+            continue;
+        }
+        let count = authors.len();
+        let first = authors[0].as_str().unwrap_or_default();
+        let summary = Value::Array(vec![format!("{} et al.", first).into()]);
+        obj.insert("authors".into(), summary);
+        obj.insert("author_count".into(), count.into());
+    }
+}
+
+/// Serialize a result list, either bare or wrapped in an `Envelope`.
+pub fn render<T: Serialize>(
+    kind: &str,
+    results: &[T],
+    diagnostics: Diagnostics,
+    envelope: bool,
+) -> serde_json::Result<String> {
+    if envelope {
+        serde_json::to_string_pretty(&Envelope {
+            kind,
+            count: results.len(),
+            results,
+            diagnostics,
+            warnings: None,
+        })
+    } else {
+        serde_json::to_string_pretty(results)
+    }
+}
+
+/// Like `render`, but reports failed sources under a `warnings` key. The key
+/// is always present, even when nothing failed, so the output has one shape.
+pub fn render_with_warnings<T: Serialize>(
+    kind: &str,
+    results: &[T],
+    diagnostics: Diagnostics,
+    warnings: &[SourceWarning],
+    envelope: bool,
+) -> serde_json::Result<String> {
+    if envelope {
+        serde_json::to_string_pretty(&Envelope {
+            kind,
+            count: results.len(),
+            results,
+            diagnostics,
+            warnings: Some(warnings),
+        })
+    } else {
+        serde_json::to_string_pretty(&PartialResults { results, warnings })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apis::PaperResult;
+
+    fn paper(id: &str) -> PaperResult {
+        PaperResult {
+            id: id.to_string(),
+            title: "Title".to_string(),
+            authors: vec![],
+            abstract_text: None,
+            year: Some(2024),
+            source: "test".to_string(),
+            doi: None,
+            arxiv_id: None,
+            url: "".to_string(),
+            pdf_url: None,
+            citation_count: None,
+            orcids: Vec::new(),
+            subjects: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_search_papers_envelope_shape() {
+        let papers = vec![paper("a"), paper("b")];
+        let mut diagnostics = Diagnostics::new();
+        diagnostics.insert("elapsed_ms".into(), 12.into());
+
+        let json = render("search_papers", &papers, diagnostics, true).unwrap();
+        let value: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["kind"], "search_papers");
+        assert_eq!(value["count"], 2);
+        assert_eq!(value["results"][1]["id"], "b");
+        assert_eq!(value["diagnostics"]["elapsed_ms"], 12);
+        assert!(value.get("warnings").is_none());
+    }
+
+    #[test]
+    fn test_warnings_reported_when_sources_fail() {
+        let papers = vec![paper("a")];
+        let warnings = vec![SourceWarning { source: "inspire".into(), error: "timed out".into() }];
+
+        let json = render_with_warnings("search_papers", &papers, Diagnostics::new(), &warnings, false).unwrap();
+        let value: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["results"][0]["id"], "a");
+        assert_eq!(value["warnings"][0]["source"], "inspire");
+
+        let json = render_with_warnings("search_papers", &papers, Diagnostics::new(), &warnings, true).unwrap();
+        let value: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["warnings"][0]["error"], "timed out");
+    }
+
+    #[test]
+    fn test_warning_output_shape_independent_of_failures() {
+        let papers = vec![paper("a")];
+        for envelope in [false, true] {
+            let json = render_with_warnings("search_papers", &papers, Diagnostics::new(), &[], envelope).unwrap();
+            let value: Value = serde_json::from_str(&json).unwrap();
+            assert_eq!(value["results"][0]["id"], "a");
+            assert_eq!(value["warnings"], serde_json::json!([]));
+        }
+    }
+
+    #[test]
+    fn test_authors_collapsed_beyond_full_positions() {
+        let mut papers: Vec<Value> = (0..3)
+            .map(|i| {
+                let mut p = paper(&i.to_string());
+                p.authors = vec!["Maldacena, J.".into(), "Susskind, L.".into(), "Witten, E.".into()];
+                serde_json::to_value(p).unwrap()
+            })
+            .collect();
+        let mut solo = paper("solo");
+        solo.authors = vec!["Hawking, S.".into()];
+        papers.push(serde_json::to_value(solo).unwrap());
+        let scored = serde_json::json!({"paper": papers[1].clone(), "rrf_score": 0.5});
+        papers.push(scored);
+
+        summarize_authors(&mut papers, 1);
+        assert_eq!(papers[0]["authors"].as_array().unwrap().len(), 3);
+        assert!(papers[0].get("author_count").is_none());
+        assert_eq!(papers[1]["authors"], serde_json::json!(["Maldacena, J. et al."]));
+        assert_eq!(papers[1]["author_count"], 3);
+        assert_eq!(papers[2]["authors"], serde_json::json!(["Maldacena, J. et al."]));
+        assert_eq!(papers[3]["authors"], serde_json::json!(["Hawking, S."]));
+        assert!(papers[3].get("author_count").is_none());
+        assert_eq!(papers[4]["paper"]["author_count"], 3);
+    }
+
+    #[test]
+    fn test_bare_output_is_plain_array() {
+        let papers = vec![paper("a")];
+        let json = render("search_papers", &papers, Diagnostics::new(), false).unwrap();
+        let value: Value = serde_json::from_str(&json).unwrap();
+        assert!(value.is_array());
+    }
+}
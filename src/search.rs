@@ -1,25 +1,246 @@
 use std::sync::Arc;
-use crate::apis::{PaperResult, PaperSource};
+use std::time::Duration;
+use serde::Serialize;
+use crate::apis::{AuthorQuery, PaperResult, PaperSource, SearchField, SourceError};
 
-/// Perform federated search across multiple sources in parallel,
-/// deduplicate by DOI and title similarity, and rank results.
-pub async fn federated_search(
-    sources: &[Arc<dyn PaperSource>],
-    query: &str,
-    max_results: u32,
+/// How long `federated_search` waits for any single source.
+pub const DEFAULT_SOURCE_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// The sources a federated search with `source_filter` would query.
+pub fn active_sources<'a>(
+    sources: &'a [Arc<dyn PaperSource>],
     source_filter: Option<&[String]>,
-) -> Vec<PaperResult> {
-    let active_sources: Vec<_> = sources
+) -> Vec<&'a Arc<dyn PaperSource>> {
+    sources
         .iter()
         .filter(|s| {
             source_filter
                 .map(|f| f.iter().any(|name| name.eq_ignore_ascii_case(s.name())))
                 .unwrap_or(true)
         })
+        .collect()
+}
+
+/// Names of the sources among `active` that can't filter by language.
+pub fn language_unsupported(active: &[&Arc<dyn PaperSource>], language: &str) -> Vec<String> {
+    active
+        .iter()
+        .filter(|s| s.language_query("", language).is_none())
+        .map(|s| s.name().to_string())
+        .collect()
+}
+
+/// Names of the sources among `active` that can't restrict a search to `field`.
+pub fn field_unsupported(active: &[&Arc<dyn PaperSource>], field: SearchField) -> Vec<String> {
+    active
+        .iter()
+        .filter(|s| s.field_query("", field).is_none())
+        .map(|s| s.name().to_string())
+        .collect()
+}
+
+/// Names of the sources among `active` that can't filter by subject category.
+pub fn category_unsupported(active: &[&Arc<dyn PaperSource>], categories: &[String]) -> Vec<String> {
+    active
+        .iter()
+        .filter(|s| s.category_query("", categories).is_none())
+        .map(|s| s.name().to_string())
+        .collect()
+}
+
+/// Per-source query restrictions for a federated search. Sources that don't
+/// support a restriction are searched without it.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct QueryOptions<'a> {
+    /// ISO 639-1 language code.
+    pub language: Option<&'a str>,
+    pub field: Option<SearchField>,
+    /// Subject categories (arXiv's "hep-th", "cs.LG", ...); a paper in any matches.
+    pub categories: Option<&'a [String]>,
+    /// Order of the merged results.
+    pub rank_by: RankBy,
+}
+
+/// How federated results are ordered after deduplication.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RankBy {
+    /// Most cited first, then newest.
+    #[default]
+    Citations,
+    /// Newest first, then most cited.
+    Year,
+    /// Sources' own order, interleaved round-robin: every source's top hit,
+    /// then every source's second, and so on. Sources don't share a relevance
+    /// score, so a paper found by several sources takes its best rank.
+    Relevance,
+}
+
+impl RankBy {
+    /// Parse `citations`, `year` or `relevance` (case-insensitive).
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "citations" => Some(Self::Citations),
+            "year" => Some(Self::Year),
+            "relevance" => Some(Self::Relevance),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Citations => "citations",
+            Self::Year => "year",
+            Self::Relevance => "relevance",
+        }
+    }
+}
+
+impl QueryOptions<'_> {
+    /// `query` rewritten for `source`.
+    fn query_for(&self, source: &dyn PaperSource, query: &str) -> String {
+        let mut query = query.to_string();
+        if let Some(rewritten) = self.field.and_then(|field| source.field_query(&query, field)) {
+            query = rewritten;
+        }
+        if let Some(rewritten) = self.categories.and_then(|cats| source.category_query(&query, cats)) {
+            query = rewritten;
+        }
+        if let Some(rewritten) = self.language.and_then(|lang| source.language_query(&query, lang)) {
+            query = rewritten;
+        }
+        query
+    }
+}
+
+/// Merged results of a federated search, with the sources that failed.
+#[derive(Debug, Default)]
+pub struct FederatedResults {
+    pub papers: Vec<PaperResult>,
+    /// (source name, error message) for each source that returned nothing
+    /// because it errored; non-empty means coverage was incomplete.
+    pub source_errors: Vec<(String, String)>,
+    /// Sources that answered successfully but had nothing for the query.
+    pub empty_sources: Vec<String>,
+}
+
+/// Perform federated search across multiple sources in parallel,
+/// deduplicate by DOI and title similarity, and rank results.
+/// `offset` is applied per source before merging. `options` restrictions
+/// (language, field) apply where a source supports them. Sources that take
+/// longer than `timeout` are abandoned and reported in `source_errors`.
+pub async fn federated_search(
+    sources: &[Arc<dyn PaperSource>],
+    query: &str,
+    max_results: u32,
+    offset: u32,
+    source_filter: Option<&[String]>,
+    options: QueryOptions<'_>,
+    timeout: Duration,
+) -> FederatedResults {
+    let mut results = federated_search_raw(sources, query, max_results, offset, source_filter, options, timeout).await;
+    results.papers = deduplicate_and_rank(results.papers, max_results as usize, DEFAULT_TITLE_SIMILARITY, options.rank_by);
+    results
+}
+
+/// A looser form of `query` for when it found too little: quotes, grouping,
+/// boolean operators, field prefixes and `+` marks are dropped and the
+/// remaining terms are ORed. Negated terms (`-term`, `NOT term`, or inside a
+/// negated phrase or group) are left out rather than turned into positive
+/// ones. `None` if that changes nothing.
+pub fn broaden_query(query: &str) -> Option<String> {
+    let terms: Vec<&str> = query_terms(query)
+        .into_iter()
+        .filter(|(_, negated)| !negated)
+        .map(|(term, _)| term)
         .collect();
+    let broadened = terms.join(" OR ");
+    if broadened.is_empty() || broadened == query.trim() {
+        None
+    } else {
+        Some(broadened)
+    }
+}
+
+/// The bare terms of a search query, each with whether it is negated.
+/// Operators, quotes, parentheses and `field:` prefixes are consumed; a
+/// leading `-`, `NOT` or `ANDNOT` negates the next term, phrase or group.
+fn query_terms(query: &str) -> Vec<(&str, bool)> {
+    let mut terms = Vec::new();
+    // Negation of each open group, innermost last
+    let mut groups: Vec<bool> = Vec::new();
+    // Negation of the open phrase, if inside quotes
+    let mut phrase: Option<bool> = None;
+    let mut pending = false;
+    let mut rest = query;
+    loop {
+        rest = rest.trim_start();
+        let Some(c) = rest.chars().next() else { break };
+        let outer = phrase.unwrap_or_else(|| groups.last().copied().unwrap_or(false));
+        match c {
+            '"' => {
+                phrase = match phrase {
+                    Some(_) => None,
+                    None => Some(outer || std::mem::take(&mut pending)),
+                };
+                rest = &rest[1..];
+            }
+            '(' if phrase.is_none() => {
+                groups.push(outer || std::mem::take(&mut pending));
+                rest = &rest[1..];
+            }
+            ')' if phrase.is_none() => {
+                groups.pop();
+                rest = &rest[1..];
+            }
+            '-' if phrase.is_none() => {
+                pending = true;
+                rest = &rest[1..];
+            }
+            '+' => rest = &rest[1..],
+            _ => {
+                let end = rest
+                    .find(|c: char| c.is_whitespace() || matches!(c, '"' | '(' | ')'))
+                    .unwrap_or(rest.len());
+                let word = &rest[..end];
+                rest = &rest[end..];
+                if phrase.is_none() && matches!(word, "AND" | "OR" | "NOT" | "ANDNOT") {
+                    pending |= matches!(word, "NOT" | "ANDNOT");
+                    continue;
+                }
+                let term = word.split_once(':').map_or(word, |(prefix, rest)| {
+                    if prefix.chars().all(|c| c.is_ascii_alphabetic()) { rest } else { word }
+                });
+                if !term.is_empty() {
+                    terms.push((term, outer || std::mem::take(&mut pending)));
+                }
+            }
+        }
+    }
+    terms
+}
+
+/// Combine two result sets, deduplicating across them and re-ranking.
+pub fn merge_results(primary: Vec<PaperResult>, extra: Vec<PaperResult>, limit: usize, rank_by: RankBy) -> Vec<PaperResult> {
+    let mut all = primary;
+    all.extend(extra);
+    deduplicate_and_rank(all, limit, DEFAULT_TITLE_SIMILARITY, rank_by)
+}
+
+/// Like `federated_search`, but returns every source's results as-is,
+/// duplicates included.
+pub async fn federated_search_raw(
+    sources: &[Arc<dyn PaperSource>],
+    query: &str,
+    max_results: u32,
+    offset: u32,
+    source_filter: Option<&[String]>,
+    options: QueryOptions<'_>,
+    timeout: Duration,
+) -> FederatedResults {
+    let active_sources = active_sources(sources, source_filter);
 
     if active_sources.is_empty() {
-        return Vec::new();
+        return FederatedResults::default();
     }
 
     // Query all sources in parallel
@@ -28,67 +249,637 @@ pub async fn federated_search(
         .iter()
         .map(|source| {
             let source = Arc::clone(source);
-            let query = query.to_string();
-            tokio::spawn(async move { source.search(&query, per_source).await })
+            let query = options.query_for(source.as_ref(), query);
+            let name = source.name().to_string();
+            (name, tokio::spawn(async move {
+                tokio::time::timeout(timeout, source.search_page(&query, per_source, offset)).await
+            }))
         })
         .collect();
 
+    let mut all_results = Vec::new();
+    let mut source_errors = Vec::new();
+    let mut empty_sources = Vec::new();
+    for (name, handle) in futures {
+        match handle.await {
+            Ok(Ok(Ok(results))) if results.is_empty() => empty_sources.push(name),
+            Ok(Ok(Ok(results))) => all_results.extend(results),
+            Ok(Ok(Err(e @ SourceError::RateLimited { .. }))) => {
+                tracing::warn!("Source {} is rate limited; results may be incomplete: {}", name, e);
+                source_errors.push((name, e.to_string()));
+            }
+            Ok(Ok(Err(e))) => {
+                tracing::warn!("Source {} search failed: {}", name, e);
+                source_errors.push((name, e.to_string()));
+            }
+            Ok(Err(_)) => {
+                tracing::warn!("Source {} timed out after {:?}", name, timeout);
+                source_errors.push((name, format!("timed out after {}s", timeout.as_secs_f32())));
+            }
+            Err(e) => {
+                tracing::warn!("Source {} task panicked: {}", name, e);
+                source_errors.push((name, format!("task failed: {}", e)));
+            }
+        }
+    }
+
+    FederatedResults {
+        papers: all_results,
+        source_errors,
+        empty_sources,
+    }
+}
+
+/// Whether a source answered a live request, and how fast.
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceHealth {
+    pub name: String,
+    pub reachable: bool,
+    pub latency_ms: u64,
+    pub error: Option<String>,
+}
+
+/// Ping every source concurrently, giving each at most `timeout`.
+pub async fn health_check(sources: &[Arc<dyn PaperSource>], timeout: Duration) -> Vec<SourceHealth> {
+    let checks = sources.iter().map(|source| async move {
+        let started = std::time::Instant::now();
+        let outcome = tokio::time::timeout(timeout, source.ping()).await;
+        let latency_ms = started.elapsed().as_millis() as u64;
+        let error = match outcome {
+            Ok(Ok(())) => None,
+            Ok(Err(e)) => Some(e.to_string()),
+            Err(_) => Some(format!("timed out after {}ms", timeout.as_millis())),
+        };
+        SourceHealth {
+            name: source.name().to_string(),
+            reachable: error.is_none(),
+            latency_ms,
+            error,
+        }
+    });
+    futures::future::join_all(checks).await
+}
+
+/// Ordering for author-based results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthorSort {
+    Recent,
+    Citations,
+}
+
+/// Find other works by the seed paper's authors across author-capable sources.
+/// Queries at most `max_authors` authors; the seed paper itself is excluded.
+pub async fn more_by_authors(
+    sources: &[Arc<dyn PaperSource>],
+    seed: &PaperResult,
+    max_authors: usize,
+    max_results: u32,
+    sort: AuthorSort,
+) -> Vec<PaperResult> {
+    let authors: Vec<&String> = seed.authors.iter().take(max_authors).collect();
+    if authors.is_empty() || sources.is_empty() {
+        return Vec::new();
+    }
+
+    let per_query = (max_results * 2 / authors.len() as u32).max(5);
+    let mut futures = Vec::new();
+    for author in &authors {
+        for source in sources {
+            let source = Arc::clone(source);
+            let query = AuthorQuery::new(author);
+            futures.push(tokio::spawn(async move {
+                let name = source.name().to_string();
+                (name, source.search_by_author(&query, per_query).await)
+            }));
+        }
+    }
+
     let mut all_results = Vec::new();
     for handle in futures {
         match handle.await {
-            Ok(Ok(results)) => all_results.extend(results),
-            Ok(Err(e)) => tracing::warn!("Source search failed: {}", e),
-            Err(e) => tracing::warn!("Source task panicked: {}", e),
+            Ok((_, Ok(works))) => all_results.extend(works.papers),
+            Ok((name, Err(e))) => tracing::warn!("Source {} author search failed: {}", name, e),
+            Err(e) => tracing::warn!("Author search task panicked: {}", e),
         }
     }
 
-    // Deduplicate and rank
-    deduplicate_and_rank(all_results, max_results as usize)
+    let seed_title = normalize_title(&seed.title);
+    all_results.retain(|p| !is_same_paper(seed, &seed_title, p));
+    // Drop namesakes' works: those whose authors named like a queried one
+    // all carry a different ORCID iD
+    let queried: Vec<AuthorId> = author_ids(seed).into_iter().take(max_authors).collect();
+    all_results.retain(|p| {
+        let named: Vec<AuthorId> = author_ids(p)
+            .into_iter()
+            .filter(|a| queried.iter().any(|q| name_confidence(&q.0, &a.0) >= 0.8))
+            .collect();
+        named.is_empty() || named.iter().any(|a| queried.iter().any(|q| same_person(q, a)))
+    });
+
+    let mut ranked = deduplicate_and_rank(all_results, usize::MAX, DEFAULT_TITLE_SIMILARITY, RankBy::Citations);
+    if sort == AuthorSort::Recent {
+        ranked.sort_by(|a, b| {
+            b.year.unwrap_or(0).cmp(&a.year.unwrap_or(0))
+                .then_with(|| b.citation_count.unwrap_or(0).cmp(&a.citation_count.unwrap_or(0)))
+        });
+    }
+    ranked.truncate(max_results as usize);
+    ranked
+}
+
+/// A paper from an author search, with how confidently one of its authors
+/// is the requested person.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuthorMatch {
+    #[serde(flatten)]
+    pub paper: PaperResult,
+    /// 1.0 for the same full name, 0.8 for surname and first initial, 0.5
+    /// when only the surname can be compared.
+    pub name_confidence: f64,
+    /// The name as it appears on the paper.
+    pub matched_author: String,
+}
+
+/// Merged author search results, best name matches first.
+#[derive(Debug, Default)]
+pub struct AuthorSearchResults {
+    pub results: Vec<AuthorMatch>,
+    /// Distinct spellings of the matched author across results.
+    pub name_variants: Vec<String>,
+    /// Whether the results may mix different people or miss some: some
+    /// matched on surname alone, different full first names matched, or a
+    /// source passed over other profiles matching the name.
+    pub ambiguous: bool,
+    /// (source name, profile name) for each author profile a source passed
+    /// over for a better-ranked one although the name matches; its works
+    /// aren't included.
+    pub other_profiles: Vec<(String, String)>,
+    /// (source name, error message) for each source whose author search failed.
+    pub source_errors: Vec<(String, String)>,
 }
 
-/// Deduplicate results by DOI (exact) and title similarity, then rank.
-fn deduplicate_and_rank(mut results: Vec<PaperResult>, limit: usize) -> Vec<PaperResult> {
+/// Search every author-capable source for works by the queried author,
+/// keep papers published within its year range (which sources also apply
+/// where they can; papers without a year are dropped when a bound is set),
+/// dedup as in `federated_search`, and rank by name match confidence, then
+/// recency.
+pub async fn author_search(
+    sources: &[Arc<dyn PaperSource>],
+    author: &AuthorQuery,
+    max_results: u32,
+    source_filter: Option<&[String]>,
+    timeout: Duration,
+) -> AuthorSearchResults {
+    let per_source = (max_results * 2).max(10);
+    let handles: Vec<_> = active_sources(sources, source_filter)
+        .into_iter()
+        .map(|source| {
+            let source = Arc::clone(source);
+            let author = author.clone();
+            (source.name().to_string(), tokio::spawn(async move {
+                tokio::time::timeout(timeout, source.search_by_author(&author, per_source)).await
+            }))
+        })
+        .collect();
+
+    let query = split_name(&author.name);
+    let mut papers = Vec::new();
+    let mut other_profiles = Vec::new();
+    let mut source_errors = Vec::new();
+    for (name, handle) in handles {
+        match handle.await {
+            Ok(Ok(Ok(works))) => {
+                papers.extend(works.papers);
+                other_profiles.extend(
+                    works.candidates
+                        .into_iter()
+                        .skip(1)
+                        .filter(|c| name_confidence(&query, &split_name(c)) >= 0.8)
+                        .map(|c| (name.clone(), c)),
+                );
+            }
+            Ok(Ok(Err(e))) => source_errors.push((name, e.to_string())),
+            Ok(Err(_)) => source_errors.push((name, format!("timed out after {}s", timeout.as_secs_f32()))),
+            Err(e) => source_errors.push((name, format!("task failed: {}", e))),
+        }
+    }
+    papers.retain(|p| author.contains_year(p.year));
+
+    let mut results: Vec<AuthorMatch> = deduplicate_and_rank(papers, usize::MAX, DEFAULT_TITLE_SIMILARITY, RankBy::Citations)
+        .into_iter()
+        .filter_map(|paper| {
+            let (matched_author, name_confidence) = paper
+                .authors
+                .iter()
+                .map(|a| (a, name_confidence(&query, &split_name(a))))
+                .max_by(|a, b| a.1.total_cmp(&b.1))?;
+            let matched_author = matched_author.clone();
+            (name_confidence > 0.0).then_some(AuthorMatch {
+                matched_author,
+                paper,
+                name_confidence,
+            })
+        })
+        .collect();
+    results.sort_by(|a, b| {
+        b.name_confidence
+            .total_cmp(&a.name_confidence)
+            .then_with(|| b.paper.year.unwrap_or(0).cmp(&a.paper.year.unwrap_or(0)))
+    });
+    results.truncate(max_results as usize);
+
+    let mut name_variants: Vec<String> = results.iter().map(|m| m.matched_author.clone()).collect();
+    name_variants.sort();
+    name_variants.dedup();
+    let mut full_given: Vec<String> = results
+        .iter()
+        .filter_map(|m| split_name(&m.matched_author).0.into_iter().next())
+        .filter(|g| g.chars().count() > 1)
+        .collect();
+    full_given.sort();
+    full_given.dedup();
+    let ambiguous = full_given.len() > 1
+        || results.iter().any(|m| m.name_confidence < 0.6)
+        || !other_profiles.is_empty();
+
+    AuthorSearchResults { results, name_variants, ambiguous, other_profiles, source_errors }
+}
+
+/// Whether the two papers share an author. See `same_person`.
+pub fn shares_author(a: &PaperResult, b: &PaperResult) -> bool {
+    let b = author_ids(b);
+    author_ids(a).iter().any(|author| b.iter().any(|other| same_person(author, other)))
+}
+
+/// An author's split name and ORCID iD, if known.
+type AuthorId<'a> = ((Vec<String>, String), Option<&'a str>);
+
+fn author_ids(paper: &PaperResult) -> Vec<AuthorId<'_>> {
+    paper.authors.iter().enumerate().map(|(i, name)| (split_name(name), paper.orcid(i))).collect()
+}
+
+/// Whether two authors are the same person: by ORCID iD when both have one,
+/// otherwise by the same surname and first names that agree at least on the
+/// initial, which can conflate namesakes.
+fn same_person(a: &AuthorId, b: &AuthorId) -> bool {
+    match (a.1, b.1) {
+        (Some(a), Some(b)) => a == b,
+        _ => name_confidence(&a.0, &b.0) >= 0.8,
+    }
+}
+
+/// Lowercase surname particles, as in "van der Waals" or "de la Cruz".
+const SURNAME_PARTICLES: &[&str] = &[
+    "al", "bin", "da", "dal", "das", "de", "degli", "del", "della", "den", "der", "di", "do", "dos", "du",
+    "el", "ibn", "la", "le", "st", "te", "ten", "ter", "van", "von", "zu",
+];
+
+/// Split "Last, First M." or "First M. Last" into lowercase given names and
+/// surname, dropping periods. Particles before the last name belong to the
+/// surname ("Johannes van der Waals" has surname "van der waals"), unless
+/// nothing would be left of the given names.
+fn split_name(name: &str) -> (Vec<String>, String) {
+    let clean = |s: &str| -> Vec<String> {
+        s.split(|c: char| c.is_whitespace() || c == '.')
+            .filter(|p| !p.is_empty())
+            .map(|p| p.to_lowercase())
+            .collect()
+    };
+    match name.split_once(',') {
+        Some((last, first)) => (clean(first), clean(last).join(" ")),
+        None => {
+            let mut parts = clean(name);
+            let mut surname = parts.pop().into_iter().collect::<Vec<_>>();
+            while parts.len() > 1 && parts.last().is_some_and(|p| SURNAME_PARTICLES.contains(&p.as_str())) {
+                surname.insert(0, parts.pop().unwrap_or_default());
+            }
+            (parts, surname.join(" "))
+        }
+    }
+}
+
+/// How likely `candidate` names the same person as `query`: 0 when the
+/// surnames differ or the first names clearly conflict.
+fn name_confidence(query: &(Vec<String>, String), candidate: &(Vec<String>, String)) -> f64 {
+    let ((q_given, q_surname), (c_given, c_surname)) = (query, candidate);
+    if q_surname.is_empty() || q_surname != c_surname {
+        return 0.0;
+    }
+    let (Some(q_first), Some(c_first)) = (q_given.first(), c_given.first()) else {
+        return 0.5;
+    };
+    if q_given == c_given {
+        return 1.0;
+    }
+    let is_full = |s: &String| s.chars().count() > 1;
+    if is_full(q_first) && is_full(c_first) && q_first != c_first {
+        return 0.0;
+    }
+    if q_first.chars().next() == c_first.chars().next() {
+        0.8
+    } else {
+        0.0
+    }
+}
+
+/// Whether `other` is the seed paper under a different source's record.
+fn is_same_paper(seed: &PaperResult, seed_title: &str, other: &PaperResult) -> bool {
+    if other.id == seed.id {
+        return true;
+    }
+    let same_id = |a: &Option<String>, b: &Option<String>| {
+        matches!((a, b), (Some(x), Some(y)) if x.eq_ignore_ascii_case(y))
+    };
+    same_id(&seed.doi, &other.doi)
+        || same_id(&seed.arxiv_id, &other.arxiv_id)
+        || normalize_title(&other.title) == seed_title
+}
+
+/// Bibliographic coupling between two papers: the references they share.
+#[derive(Debug, Serialize)]
+pub struct Coupling {
+    /// Distinct references of each paper after identifier normalization.
+    pub references_a: usize,
+    pub references_b: usize,
+    pub shared_count: usize,
+    /// `shared / sqrt(references_a * references_b)`: 1.0 for identical
+    /// reference lists, 0.0 when nothing is shared.
+    pub coefficient: f64,
+    pub shared: Vec<PaperResult>,
+}
+
+/// Compare two reference lists, matching entries that share a DOI, arXiv ID
+/// or normalized title, so the same work from different sources lines up
+/// even when each record carries different identifiers.
+pub fn bibliographic_coupling(refs_a: &[PaperResult], refs_b: &[PaperResult]) -> Coupling {
+    let works_a = distinct_works(refs_a);
+    let works_b = distinct_works(refs_b);
+    let keys_a: std::collections::HashSet<&String> = works_a.iter().flat_map(|(_, keys)| keys).collect();
+    let shared: Vec<PaperResult> = works_b
+        .iter()
+        .filter(|(_, keys)| keys.iter().any(|k| keys_a.contains(k)))
+        .map(|(paper, _)| (*paper).clone())
+        .collect();
+
+    let denominator = ((works_a.len() * works_b.len()) as f64).sqrt();
+    Coupling {
+        references_a: works_a.len(),
+        references_b: works_b.len(),
+        shared_count: shared.len(),
+        coefficient: if denominator > 0.0 { shared.len() as f64 / denominator } else { 0.0 },
+        shared,
+    }
+}
+
+/// Source-independent identities of a referenced work: its normalized DOI,
+/// arXiv ID and title, whichever it has.
+fn reference_keys(paper: &PaperResult) -> Vec<String> {
+    let mut keys = Vec::with_capacity(3);
+    if let Some(doi) = &paper.doi {
+        let doi = doi.trim().to_lowercase();
+        let doi = ["https://doi.org/", "http://doi.org/", "https://dx.doi.org/", "http://dx.doi.org/", "doi:"]
+            .iter()
+            .find_map(|prefix| doi.strip_prefix(prefix))
+            .unwrap_or(&doi);
+        keys.push(format!("doi:{}", doi));
+    }
+    if let Some(arxiv_id) = &paper.arxiv_id {
+        keys.push(format!("arxiv:{}", normalize_arxiv_id(arxiv_id)));
+    }
+    let title = normalize_title(&paper.title);
+    if !title.is_empty() {
+        keys.push(format!("title:{}", title));
+    }
+    keys
+}
+
+/// Group a reference list into distinct works, in order: a record joins the
+/// first earlier work it shares any key with. Each work is its first record
+/// and the keys of every record grouped into it.
+fn distinct_works(papers: &[PaperResult]) -> Vec<(&PaperResult, std::collections::HashSet<String>)> {
+    let mut works: Vec<(&PaperResult, std::collections::HashSet<String>)> = Vec::new();
+    for paper in papers {
+        let keys = reference_keys(paper);
+        match works.iter_mut().find(|(_, seen)| keys.iter().any(|k| seen.contains(k))) {
+            Some((_, seen)) => seen.extend(keys),
+            None => works.push((paper, keys.into_iter().collect())),
+        }
+    }
+    works
+}
+
+/// Drop repeated works from a citation or reference list (sharing a DOI,
+/// arXiv ID or title), keeping the first record of each, and cut it to `limit`.
+pub fn dedup_relations(papers: Vec<PaperResult>, limit: usize) -> Vec<PaperResult> {
+    distinct_works(&papers)
+        .into_iter()
+        .take(limit)
+        .map(|(paper, _)| paper.clone())
+        .collect()
+}
+
+/// One logical paper: its canonical record and every record grouped into it.
+#[derive(Debug, Serialize)]
+pub struct CanonicalGroup {
+    pub canonical: PaperResult,
+    /// IDs of the grouped records, in input order.
+    pub members: Vec<String>,
+}
+
+/// Group records that are the same logical paper (shared DOI or arXiv ID,
+/// or matching titles, transitively) and build one canonical record per
+/// group: the published version when there is one, with gaps filled from
+/// the other members. Groups keep the order of their first member.
+pub fn canonicalize(papers: Vec<PaperResult>, title_threshold: f64) -> Vec<CanonicalGroup> {
+    let n = papers.len();
+    let mut parent: Vec<usize> = (0..n).collect();
+    fn find(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+
+    for i in 0..n {
+        for j in 0..i {
+            if same_work(&papers[i], &papers[j], title_threshold) {
+                let (ri, rj) = (find(&mut parent, i), find(&mut parent, j));
+                parent[ri.max(rj)] = ri.min(rj);
+            }
+        }
+    }
+
+    let mut groups: Vec<(usize, Vec<PaperResult>)> = Vec::new();
+    for (i, paper) in papers.into_iter().enumerate() {
+        let root = find(&mut parent, i);
+        match groups.iter_mut().find(|(r, _)| *r == root) {
+            Some((_, members)) => members.push(paper),
+            None => groups.push((root, vec![paper])),
+        }
+    }
+
+    groups
+        .into_iter()
+        .filter_map(|(_, members)| {
+            let ids = members.iter().map(|p| p.id.clone()).collect();
+            let mut ranked = members;
+            // Stable: ties keep input order
+            ranked.sort_by_key(|p| std::cmp::Reverse((is_published(p), metadata_score(p))));
+            Some(CanonicalGroup { canonical: merge_records(&ranked)?, members: ids })
+        })
+        .collect()
+}
+
+/// Whether two records are the same work by the dedup keys.
+fn same_work(a: &PaperResult, b: &PaperResult, title_threshold: f64) -> bool {
+    let same = |x: &Option<String>, y: &Option<String>, norm: fn(&str) -> String| match (x, y) {
+        (Some(x), Some(y)) => norm(x) == norm(y),
+        _ => false,
+    };
+    same(&a.doi, &b.doi, |d| d.trim().to_lowercase())
+        || same(&a.arxiv_id, &b.arxiv_id, normalize_arxiv_id)
+        || titles_match(&a.title, &b.title, title_threshold)
+}
+
+/// A journal/conference version rather than a preprint: it has a DOI that
+/// isn't one of arXiv's own (10.48550/...).
+fn is_published(p: &PaperResult) -> bool {
+    p.doi.as_deref().is_some_and(|d| !d.trim().to_lowercase().starts_with("10.48550/"))
+}
+
+/// Merge records of the same work, keeping the richest value of each field:
+/// the longest author list, Semantic Scholar's abstract (else the longest),
+/// the highest citation count, and otherwise the first record that has the
+/// field, so callers order `records` by preference. `None` when empty.
+pub fn merge_records(records: &[PaperResult]) -> Option<PaperResult> {
+    let mut merged = records.first()?.clone();
+    for paper in records.iter().skip(1) {
+        if merged.title.is_empty() {
+            merged.title = paper.title.clone();
+        }
+        if merged.url.is_empty() {
+            merged.url = paper.url.clone();
+        }
+        if paper.authors.len() > merged.authors.len() {
+            merged.authors = paper.authors.clone();
+        }
+        merged.year = merged.year.or(paper.year);
+        merged.doi = merged.doi.take().or_else(|| paper.doi.clone());
+        merged.arxiv_id = merged.arxiv_id.take().or_else(|| paper.arxiv_id.clone());
+        merged.pdf_url = merged.pdf_url.take().or_else(|| paper.pdf_url.clone());
+        merged.citation_count = merged.citation_count.max(paper.citation_count);
+    }
+    let longest = records
+        .iter()
+        .filter_map(|p| p.abstract_text.as_ref())
+        .max_by_key(|a| a.len());
+    merged.abstract_text = records
+        .iter()
+        .find(|p| p.source == "semantic_scholar")
+        .and_then(|p| p.abstract_text.clone())
+        .or_else(|| longest.cloned());
+    Some(merged)
+}
+
+/// Normalized title similarity at or above which two DOI-less records are merged.
+pub const DEFAULT_TITLE_SIMILARITY: f64 = 0.9;
+
+/// Deduplicate results by arXiv ID and DOI (exact) and title similarity
+/// (normalized Levenshtein ratio >= `title_threshold`), then order by `rank_by`.
+fn deduplicate_and_rank(
+    results: Vec<PaperResult>,
+    limit: usize,
+    title_threshold: f64,
+    rank_by: RankBy,
+) -> Vec<PaperResult> {
     if results.is_empty() {
         return results;
     }
 
-    let mut seen_dois: std::collections::HashSet<String> = std::collections::HashSet::new();
-    let mut deduped: Vec<PaperResult> = Vec::new();
+    // Each record's (rank within its source, order the source first appeared),
+    // taken before the richness sort below reorders them
+    let mut source_order: Vec<&str> = Vec::new();
+    let mut source_counts: Vec<usize> = Vec::new();
+    let positions: Vec<(usize, usize)> = results
+        .iter()
+        .map(|paper| {
+            let index = match source_order.iter().position(|s| *s == paper.source) {
+                Some(index) => index,
+                None => {
+                    source_order.push(&paper.source);
+                    source_counts.push(0);
+                    source_order.len() - 1
+                }
+            };
+            source_counts[index] += 1;
+            (source_counts[index] - 1, index)
+        })
+        .collect();
+    let mut ranked: Vec<((usize, usize), PaperResult)> = positions.into_iter().zip(results).collect();
+
+    let mut seen_dois: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+    let mut seen_arxiv: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+    let mut deduped: Vec<((usize, usize), PaperResult)> = Vec::new();
 
     // Sort by metadata richness first (prefer papers with more fields filled)
-    results.sort_by(|a, b| metadata_score(b).cmp(&metadata_score(a)));
-
-    for paper in results {
-        // Check DOI dedup
-        if let Some(ref doi) = paper.doi {
-            let doi_lower = doi.to_lowercase();
-            if seen_dois.contains(&doi_lower) {
-                continue;
-            }
-            seen_dois.insert(doi_lower);
-        } else {
-            // Check title similarity against existing
-            let normalized = normalize_title(&paper.title);
-            if deduped.iter().any(|p| {
-                let d = strsim::levenshtein(&normalized, &normalize_title(&p.title));
-                d < 5
-            }) {
-                continue;
-            }
+    ranked.sort_by_key(|(_, paper)| std::cmp::Reverse(metadata_score(paper)));
+
+    for (position, paper) in ranked {
+        // Check arXiv ID dedup (preprint records often lack a shared DOI)
+        let arxiv_key = paper.arxiv_id.as_deref().map(normalize_arxiv_id);
+        let doi_key = paper.doi.as_deref().map(str::to_lowercase);
+        let duplicate_of = arxiv_key
+            .as_ref()
+            .and_then(|key| seen_arxiv.get(key))
+            .or_else(|| doi_key.as_ref().and_then(|key| seen_dois.get(key)))
+            .copied()
+            .or_else(|| {
+                // Check title similarity against existing DOI-less records
+                if doi_key.is_some() {
+                    return None;
+                }
+                deduped.iter().position(|(_, p)| titles_match(&paper.title, &p.title, title_threshold))
+            });
+        if let Some(index) = duplicate_of {
+            deduped[index].0 = deduped[index].0.min(position);
+            continue;
+        }
+        if let Some(key) = arxiv_key {
+            seen_arxiv.insert(key, deduped.len());
         }
-        deduped.push(paper);
+        if let Some(key) = doi_key {
+            seen_dois.insert(key, deduped.len());
+        }
+        deduped.push((position, paper));
     }
 
-    // Rank: citation count descending, then year descending
-    deduped.sort_by(|a, b| {
-        let ca = a.citation_count.unwrap_or(0);
-        let cb = b.citation_count.unwrap_or(0);
-        cb.cmp(&ca)
-            .then_with(|| b.year.unwrap_or(0).cmp(&a.year.unwrap_or(0)))
-    });
+    let citations = |p: &PaperResult| p.citation_count.unwrap_or(0);
+    let year = |p: &PaperResult| p.year.unwrap_or(0);
+    match rank_by {
+        RankBy::Citations => deduped.sort_by(|(_, a), (_, b)| {
+            citations(b).cmp(&citations(a)).then_with(|| year(b).cmp(&year(a)))
+        }),
+        RankBy::Year => deduped.sort_by(|(_, a), (_, b)| {
+            year(b).cmp(&year(a)).then_with(|| citations(b).cmp(&citations(a)))
+        }),
+        // Round-robin: by rank within source, then by source order
+        RankBy::Relevance => deduped.sort_by_key(|(position, _)| *position),
+    }
+
+    deduped.into_iter().take(limit).map(|(_, paper)| paper).collect()
+}
 
-    deduped.truncate(limit);
-    deduped
+/// Normalize an arXiv ID for comparison: drop any `arxiv:` prefix and version suffix.
+pub fn normalize_arxiv_id(id: &str) -> String {
+    let id = id.trim().to_lowercase();
+    let id = id.strip_prefix("arxiv:").unwrap_or(&id);
+    match id.rsplit_once('v') {
+        Some((base, version)) if !base.is_empty() && !version.is_empty() && version.chars().all(|c| c.is_ascii_digit()) => {
+            base.to_string()
+        }
+        _ => id.to_string(),
+    }
 }
 
 /// Score metadata richness (higher = more complete).
@@ -104,6 +895,21 @@ fn metadata_score(p: &PaperResult) -> u32 {
     score
 }
 
+/// Whether two titles name the same work. A title with a subtitle
+/// ("Main: Sub") also matches its bare main title, since some sources drop
+/// subtitles; two different subtitles on the same main title do not match.
+fn titles_match(a: &str, b: &str, threshold: f64) -> bool {
+    let similar = |x: &str, y: &str| {
+        strsim::normalized_levenshtein(&normalize_title(x), &normalize_title(y)) >= threshold
+    };
+    similar(a, b) || similar(main_title(a), b) || similar(a, main_title(b))
+}
+
+/// The part of a title before its first ": " subtitle separator.
+fn main_title(title: &str) -> &str {
+    title.split_once(": ").map_or(title, |(main, _)| main)
+}
+
 fn normalize_title(title: &str) -> String {
     title
         .to_lowercase()
@@ -118,6 +924,8 @@ fn normalize_title(title: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::apis::{AuthorWorks, SourceError};
+    use crate::mock_source::MockSource;
 
     fn paper(id: &str, title: &str, doi: Option<&str>, citations: Option<u32>) -> PaperResult {
         PaperResult {
@@ -132,6 +940,8 @@ mod tests {
             url: "".to_string(),
             pdf_url: None,
             citation_count: citations,
+            orcids: Vec::new(),
+            subjects: Vec::new(),
         }
     }
 
@@ -142,8 +952,28 @@ mod tests {
             paper("arxiv:1", "Paper A (arxiv)", Some("10.1234/a"), None),
             paper("s2:2", "Paper B", Some("10.1234/b"), Some(5)),
         ];
-        let deduped = deduplicate_and_rank(results, 10);
+        let deduped = deduplicate_and_rank(results, 10, DEFAULT_TITLE_SIMILARITY, RankBy::Citations);
+        assert_eq!(deduped.len(), 2);
+    }
+
+    #[test]
+    fn test_dedup_by_arxiv_id() {
+        let mut s2 = paper("s2:1", "Holographic Entanglement Entropy", None, Some(12));
+        s2.arxiv_id = Some("hep-th/0603001".to_string());
+        s2.abstract_text = Some("We propose a holographic formula.".to_string());
+        let mut arxiv = paper("arxiv:hep-th/0603001v2", "Holographic derivation of entanglement entropy from AdS/CFT", None, None);
+        arxiv.arxiv_id = Some("hep-th/0603001v2".to_string());
+        let mut other = paper("arxiv:2101.00001", "Unrelated Preprint", None, None);
+        other.arxiv_id = Some("2101.00001v1".to_string());
+
+        let deduped = deduplicate_and_rank(vec![arxiv, s2, other], 10, DEFAULT_TITLE_SIMILARITY, RankBy::Citations);
         assert_eq!(deduped.len(), 2);
+        // The richer record wins
+        assert!(deduped.iter().any(|p| p.id == "s2:1"));
+        assert!(!deduped.iter().any(|p| p.id == "arxiv:hep-th/0603001v2"));
+
+        assert_eq!(normalize_arxiv_id("arXiv:2301.01234v3"), "2301.01234");
+        assert_eq!(normalize_arxiv_id("hep-th/9711200"), "hep-th/9711200");
     }
 
     #[test]
@@ -152,8 +982,138 @@ mod tests {
             paper("s2:1", "Quantum Error Correction Codes", None, Some(10)),
             paper("arxiv:1", "Quantum Error Correction codes", None, None),
         ];
-        let deduped = deduplicate_and_rank(results, 10);
+        let deduped = deduplicate_and_rank(results, 10, DEFAULT_TITLE_SIMILARITY, RankBy::Citations);
+        assert_eq!(deduped.len(), 1);
+    }
+
+    #[test]
+    fn test_dedup_matches_title_without_subtitle() {
+        let results = vec![
+            paper("doi:10.1000/book", "Gravitation: Foundations and Frontiers", Some("10.1000/book"), Some(40)),
+            paper("arxiv:1", "Gravitation", None, None),
+        ];
+        let deduped = deduplicate_and_rank(results, 10, DEFAULT_TITLE_SIMILARITY, RankBy::Citations);
         assert_eq!(deduped.len(), 1);
+        assert_eq!(deduped[0].id, "doi:10.1000/book");
+
+        // Different subtitles under one main title are different works
+        let results = vec![
+            paper("a", "Black Holes: Lecture Notes", None, None),
+            paper("b", "Black Holes: A Review of Observations", None, None),
+        ];
+        assert_eq!(deduplicate_and_rank(results, 10, DEFAULT_TITLE_SIMILARITY, RankBy::Citations).len(), 2);
+    }
+
+    #[test]
+    fn test_broaden_query_drops_operators_and_ors_terms() {
+        assert_eq!(
+            broaden_query("\"holographic entanglement\" AND ti:entropy -review").as_deref(),
+            Some("holographic OR entanglement OR entropy"),
+        );
+        assert_eq!(broaden_query("(black hole)").as_deref(), Some("black OR hole"));
+        assert_eq!(
+            broaden_query("qft NOT (lecture notes) ANDNOT review -\"gauge theory\" +ads").as_deref(),
+            Some("qft OR ads"),
+        );
+        assert_eq!(broaden_query("-review"), None);
+        assert_eq!(broaden_query("holography"), None);
+        assert_eq!(broaden_query("a OR b"), None);
+    }
+
+    #[test]
+    fn test_merge_records_prefers_richer_fields() {
+        let crossref = PaperResult {
+            abstract_text: Some("Short.".to_string()),
+            source: "crossref".to_string(),
+            ..paper("doi:10.1234/mock", "Mock Paper", Some("10.1234/mock"), Some(10))
+        };
+        let openalex = PaperResult {
+            authors: vec!["A. One".to_string(), "B. Two".to_string()],
+            abstract_text: Some("A much longer reconstructed abstract.".to_string()),
+            year: None,
+            source: "openalex".to_string(),
+            pdf_url: Some("https://example.org/oa.pdf".to_string()),
+            ..paper("openalex:W1", "", None, Some(25))
+        };
+        let s2 = PaperResult {
+            abstract_text: Some("The S2 abstract.".to_string()),
+            arxiv_id: Some("2401.00001".to_string()),
+            source: "semantic_scholar".to_string(),
+            ..paper("s2:abc", "Mock paper", None, Some(20))
+        };
+
+        let merged = merge_records(&[crossref.clone(), openalex.clone(), s2]).unwrap();
+        assert_eq!(merged.id, "doi:10.1234/mock");
+        assert_eq!(merged.title, "Mock Paper");
+        assert_eq!(merged.authors.len(), 2);
+        assert_eq!(merged.abstract_text.as_deref(), Some("The S2 abstract."));
+        assert_eq!(merged.arxiv_id.as_deref(), Some("2401.00001"));
+        assert_eq!(merged.pdf_url.as_deref(), Some("https://example.org/oa.pdf"));
+        assert_eq!(merged.citation_count, Some(25));
+
+        // Without S2 the longest abstract wins
+        let merged = merge_records(&[crossref, openalex]).unwrap();
+        assert_eq!(merged.abstract_text.as_deref(), Some("A much longer reconstructed abstract."));
+        assert!(merge_records(&[]).is_none());
+    }
+
+    #[test]
+    fn test_canonicalize_groups_versions_of_one_paper() {
+        let preprint = PaperResult {
+            arxiv_id: Some("2301.01234v1".to_string()),
+            abstract_text: Some("We study islands in evaporating black holes.".to_string()),
+            source: "arxiv".to_string(),
+            ..paper("arxiv:2301.01234v1", "Islands and Page Curves", Some("10.48550/arXiv.2301.01234"), None)
+        };
+        let published = PaperResult {
+            arxiv_id: Some("2301.01234".to_string()),
+            source: "crossref".to_string(),
+            ..paper("doi:10.1007/jhep05(2023)001", "Islands and Page curves", Some("10.1007/JHEP05(2023)001"), Some(31))
+        };
+        let duplicate = PaperResult {
+            source: "openalex".to_string(),
+            ..paper("openalex:W9", "Islands and Page Curves", Some("10.1007/jhep05(2023)001"), Some(35))
+        };
+        let unrelated = paper("s2:x", "Surface Codes for Quantum Memory", None, None);
+
+        let groups = canonicalize(vec![preprint, unrelated, published, duplicate], DEFAULT_TITLE_SIMILARITY);
+        assert_eq!(groups.len(), 2);
+
+        let group = &groups[0];
+        assert_eq!(group.members, vec!["arxiv:2301.01234v1", "doi:10.1007/jhep05(2023)001", "openalex:W9"]);
+        assert_eq!(group.canonical.id, "doi:10.1007/jhep05(2023)001");
+        assert_eq!(group.canonical.doi.as_deref(), Some("10.1007/JHEP05(2023)001"));
+        assert_eq!(group.canonical.citation_count, Some(35));
+        assert_eq!(
+            group.canonical.abstract_text.as_deref(),
+            Some("We study islands in evaporating black holes."),
+        );
+        assert_eq!(groups[1].members, vec!["s2:x"]);
+    }
+
+    #[test]
+    fn test_title_threshold_scales_with_length() {
+        // Short distinct titles stay apart despite a small edit distance
+        let results = vec![
+            paper("a", "Quantum Gravity", None, None),
+            paper("b", "Quantum Gravy", None, None),
+        ];
+        assert_eq!(deduplicate_and_rank(results, 10, DEFAULT_TITLE_SIMILARITY, RankBy::Citations).len(), 2);
+
+        // Long titles with minor variations merge
+        let results = vec![
+            paper("a", "A Holographic Derivation of Entanglement Entropy from AdS/CFT Correspondence", None, None),
+            paper("b", "Holographic derivation of entanglement entropy from the AdS/CFT correspondence", None, None),
+        ];
+        assert_eq!(deduplicate_and_rank(results.clone(), 10, DEFAULT_TITLE_SIMILARITY, RankBy::Citations).len(), 1);
+        assert_eq!(deduplicate_and_rank(results, 10, 0.95, RankBy::Citations).len(), 2);
+
+        // Punctuation and case differences alone always merge
+        let results = vec![
+            paper("a", "Quantum Error Correction: Codes!", None, None),
+            paper("b", "quantum error correction codes", None, None),
+        ];
+        assert_eq!(deduplicate_and_rank(results, 10, 1.0, RankBy::Citations).len(), 1);
     }
 
     #[test]
@@ -163,9 +1123,355 @@ mod tests {
             paper("b", "High Cited Different Title", None, Some(100)),
             paper("c", "Medium Cited Unique Paper", None, Some(50)),
         ];
-        let ranked = deduplicate_and_rank(results, 10);
+        let ranked = deduplicate_and_rank(results, 10, DEFAULT_TITLE_SIMILARITY, RankBy::Citations);
         assert_eq!(ranked[0].id, "b");
         assert_eq!(ranked[1].id, "c");
         assert_eq!(ranked[2].id, "a");
     }
+
+    #[test]
+    fn test_rank_by_year_and_relevance() {
+        let results = || vec![
+            PaperResult { year: Some(2020), ..paper("a", "Older Moderately Cited", None, Some(10)) },
+            PaperResult { year: Some(2024), ..paper("b", "Newest Rarely Cited", None, Some(5)) },
+            PaperResult { year: Some(2022), ..paper("c", "Middle Highly Cited", None, Some(50)) },
+        ];
+        let order = |rank_by| -> Vec<String> {
+            deduplicate_and_rank(results(), 10, DEFAULT_TITLE_SIMILARITY, rank_by)
+                .into_iter()
+                .map(|p| p.id)
+                .collect()
+        };
+        assert_eq!(order(RankBy::Citations), vec!["c", "a", "b"]);
+        assert_eq!(order(RankBy::Year), vec!["b", "c", "a"]);
+        // Equally rich records keep source order
+        assert_eq!(order(RankBy::Relevance), vec!["a", "b", "c"]);
+        assert_eq!(RankBy::parse(" Year "), Some(RankBy::Year));
+        assert_eq!(RankBy::parse("score"), None);
+    }
+
+    #[test]
+    fn test_relevance_interleaves_sources_by_rank() {
+        let from = |source: &str, id: &str, title: &str, doi: Option<&str>| PaperResult {
+            source: source.to_string(),
+            ..paper(id, title, doi, None)
+        };
+        let results = vec![
+            from("arxiv", "a1", "Attention Is All You Need", None),
+            from("arxiv", "a2", "Graph Neural Networks Survey", None),
+            from("arxiv", "a3", "Protein Folding With Transformers", Some("10.1/shared")),
+            from("openalex", "o1", "Dark Matter Halo Profiles", None),
+            from("openalex", "o2", "Protein Folding With Transformers", Some("10.1/shared")),
+        ];
+        let ids: Vec<String> = deduplicate_and_rank(results, 10, DEFAULT_TITLE_SIMILARITY, RankBy::Relevance)
+            .into_iter()
+            .map(|p| p.id)
+            .collect();
+        // The shared paper takes openalex's rank 2 rather than arxiv's rank 3
+        assert_eq!(ids, vec!["a1", "o1", "a2", "a3"]);
+    }
+
+    /// Source whose author search returns the seed plus one work per author.
+    fn author_source() -> MockSource {
+        MockSource::new("authors").on_author(|query| {
+            let author = query.name.as_str();
+            let mut seed = paper("authors:seed", "The Seed Paper", Some("10.1/seed"), Some(3));
+            seed.id = "authors:seed-copy".to_string();
+            let title = match author {
+                "Alice" => "Holographic Entanglement Entropy",
+                _ => "Surface Codes for Fault Tolerance",
+            };
+            let mut work = paper(&format!("authors:{}", author), title, None, Some(1));
+            work.authors = vec![author.to_string()];
+            vec![seed, work].into()
+        })
+    }
+
+    #[test]
+    fn test_bibliographic_coupling() {
+        let mut refs_a = vec![
+            paper("s2:1", "Black Hole Entropy", Some("10.1/BH"), None),
+            paper("s2:2", "Anti de Sitter Space and Holography", None, None),
+            paper("s2:3", "Unrelated Work", Some("10.1/x"), None),
+            paper("s2:4", "Quantum Error Correction", None, None),
+        ];
+        let mut arxiv_ref = paper("arxiv:hep-th/9711200v3", "The Large N Limit", None, None);
+        arxiv_ref.arxiv_id = Some("hep-th/9711200v3".to_string());
+        let mut arxiv_ref_b = paper("inspire:1", "Large N limit of superconformal theories", None, None);
+        arxiv_ref_b.arxiv_id = Some("arXiv:hep-th/9711200".to_string());
+        refs_a.push(arxiv_ref);
+        let refs_b = vec![
+            paper("inspire:2", "Black hole entropy", Some("https://doi.org/10.1/bh"), None),
+            paper("inspire:3", "ANTI DE SITTER SPACE AND HOLOGRAPHY", None, None),
+            arxiv_ref_b,
+            paper("inspire:4", "Something Else", Some("10.1/y"), None),
+        ];
+
+        let coupling = bibliographic_coupling(&refs_a, &refs_b);
+        assert_eq!((coupling.references_a, coupling.references_b), (5, 4));
+        assert_eq!(coupling.shared_count, 3);
+        assert!((coupling.coefficient - 3.0 / 20f64.sqrt()).abs() < 1e-9);
+        let shared: Vec<&str> = coupling.shared.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(shared, vec!["inspire:2", "inspire:3", "inspire:1"]);
+
+        let empty = bibliographic_coupling(&refs_a, &[]);
+        assert_eq!((empty.shared_count, empty.coefficient), (0, 0.0));
+    }
+
+    #[test]
+    fn test_references_match_on_any_shared_identifier() {
+        // One side knows the DOI, the other only the arXiv ID and a variant title
+        let mut with_doi = paper("crossref:1", "Holographic Codes", Some("doi:10.1/HC"), None);
+        with_doi.arxiv_id = Some("1503.06237".to_string());
+        let mut with_arxiv = paper("inspire:1", "Holographic quantum error-correcting codes", None, None);
+        with_arxiv.arxiv_id = Some("arXiv:1503.06237v2".to_string());
+        let by_title = paper("s2:1", "HOLOGRAPHIC CODES", None, None);
+
+        let coupling = bibliographic_coupling(&[with_doi.clone()], &[with_arxiv.clone(), by_title.clone()]);
+        assert_eq!(coupling.references_b, 2);
+        assert_eq!(coupling.shared_count, 2);
+
+        let deduped = dedup_relations(vec![with_doi, by_title, with_arxiv], 10);
+        let ids: Vec<&str> = deduped.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, vec!["crossref:1"]);
+    }
+
+    #[tokio::test]
+    async fn test_more_by_authors_excludes_seed() {
+        let sources: Vec<Arc<dyn PaperSource>> = vec![Arc::new(author_source())];
+        let mut seed = paper("s2:seed", "The Seed Paper", Some("10.1/seed"), Some(3));
+        seed.authors = vec!["Alice".to_string(), "Bob".to_string(), "Carol".to_string()];
+
+        let results = more_by_authors(&sources, &seed, 2, 10, AuthorSort::Citations).await;
+        let ids: Vec<&str> = results.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(results.len(), 2);
+        assert!(ids.contains(&"authors:Alice"));
+        assert!(ids.contains(&"authors:Bob"));
+        assert!(!ids.iter().any(|id| id.contains("seed")));
+    }
+
+    #[tokio::test]
+    async fn test_more_by_authors_drops_namesakes_by_orcid() {
+        let source = MockSource::new("authors").on_author(|query| {
+            let work = |id: &str, orcid: Option<&str>| PaperResult {
+                authors: vec![query.name.clone()],
+                orcids: vec![orcid.map(str::to_string)],
+                subjects: Vec::new(),
+                ..paper(id, &format!("Work {}", id), None, Some(1))
+            };
+            vec![
+                work("authors:same", Some("0000-0001-0000-0001")),
+                work("authors:namesake", Some("0000-0001-0000-0002")),
+                work("authors:unknown", None),
+            ]
+            .into()
+        });
+        let sources: Vec<Arc<dyn PaperSource>> = vec![Arc::new(source)];
+        let seed = PaperResult {
+            authors: vec!["Alice Smith".to_string()],
+            orcids: vec![Some("0000-0001-0000-0001".to_string())],
+            subjects: Vec::new(),
+            ..paper("s2:seed", "The Seed Paper", None, None)
+        };
+
+        let results = more_by_authors(&sources, &seed, 1, 10, AuthorSort::Citations).await;
+        let mut ids: Vec<&str> = results.iter().map(|p| p.id.as_str()).collect();
+        ids.sort();
+        assert_eq!(ids, vec!["authors:same", "authors:unknown"]);
+    }
+
+    #[test]
+    fn test_shares_author_prefers_orcid() {
+        let with = |name: &str, orcid: Option<&str>| PaperResult {
+            authors: vec![name.to_string()],
+            orcids: vec![orcid.map(str::to_string)],
+            subjects: Vec::new(),
+            ..paper("x:1", "T", None, None)
+        };
+        // Namesakes with different iDs aren't the same person
+        assert!(!shares_author(&with("Wei Zhang", Some("0000-0001-0000-0001")), &with("Zhang, Wei", Some("0000-0001-0000-0002"))));
+        // The same iD matches however the name is written
+        assert!(shares_author(&with("Wei Zhang", Some("0000-0001-0000-0001")), &with("张伟", Some("0000-0001-0000-0001"))));
+        // Without an iD on both sides, names decide
+        assert!(shares_author(&with("Wei Zhang", Some("0000-0001-0000-0001")), &with("W. Zhang", None)));
+    }
+
+    /// Source whose lookups always fail.
+    fn failing_source() -> MockSource {
+        MockSource::new("failing").failing(|| SourceError::Api("upstream timed out".to_string()))
+    }
+
+    #[tokio::test]
+    async fn test_federated_search_reports_source_errors() {
+        let sources: Vec<Arc<dyn PaperSource>> = vec![Arc::new(paged_source()), Arc::new(failing_source())];
+        let results = federated_search(&sources, "anything", 5, 0, None, QueryOptions::default(), DEFAULT_SOURCE_TIMEOUT).await;
+        assert_eq!(results.papers.len(), 5);
+        assert_eq!(results.source_errors.len(), 1);
+        assert_eq!(results.source_errors[0].0, "failing");
+        assert!(results.source_errors[0].1.contains("upstream timed out"));
+    }
+
+    #[tokio::test]
+    async fn test_coverage_gaps_distinguish_empty_from_failed() {
+        let sources: Vec<Arc<dyn PaperSource>> = vec![
+            Arc::new(paged_source()),
+            Arc::new(failing_source()),
+            Arc::new(author_source()),
+        ];
+        let results = federated_search(&sources, "anything", 5, 0, None, QueryOptions::default(), DEFAULT_SOURCE_TIMEOUT).await;
+        assert_eq!(results.papers.len(), 5);
+        assert_eq!(results.empty_sources, vec!["authors"]);
+        let failed: Vec<&str> = results.source_errors.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(failed, vec!["failing"]);
+    }
+
+    /// Source that filters by language and echoes the query it received as a title.
+    fn echo_language_source() -> MockSource {
+        MockSource::new("echo")
+            .on_search(|query| vec![paper("echo:1", query, None, None)])
+            .with_language_filter()
+    }
+
+    #[tokio::test]
+    async fn test_language_filter_only_applies_where_supported() {
+        let sources: Vec<Arc<dyn PaperSource>> = vec![Arc::new(echo_language_source()), Arc::new(paged_source())];
+        let options = QueryOptions { language: Some("de"), ..Default::default() };
+        let results = federated_search(&sources, "entanglement", 10, 0, None, options, DEFAULT_SOURCE_TIMEOUT).await;
+        let echoed = results.papers.iter().find(|p| p.id == "echo:1").unwrap();
+        assert_eq!(echoed.title, "entanglement LANG:de");
+        assert_eq!(results.papers.len(), 6);
+
+        let active = active_sources(&sources, None);
+        assert_eq!(language_unsupported(&active, "de"), vec!["paged"]);
+        let echo_only = active_sources(&sources, Some(&["echo".to_string()]));
+        assert!(language_unsupported(&echo_only, "de").is_empty());
+    }
+
+    /// Source that never answers within a test's patience.
+    fn hanging_source() -> MockSource {
+        MockSource::new("hanging").with_delay(Duration::from_secs(60))
+    }
+
+    #[tokio::test]
+    async fn test_slow_source_times_out_without_stalling_search() {
+        let sources: Vec<Arc<dyn PaperSource>> = vec![Arc::new(paged_source()), Arc::new(hanging_source())];
+        let started = std::time::Instant::now();
+        let results = federated_search(&sources, "anything", 5, 0, None, QueryOptions::default(), Duration::from_millis(50)).await;
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(results.papers.len(), 5);
+        assert_eq!(results.source_errors.len(), 1);
+        assert_eq!(results.source_errors[0].0, "hanging");
+        assert!(results.source_errors[0].1.contains("timed out"));
+    }
+
+    /// Source whose author search returns papers under several name forms.
+    fn name_source() -> MockSource {
+        MockSource::new("names").on_author(|_| {
+            let works = [
+                ("n1", "Black Hole Microstates", "J. Maldacena", 2019),
+                ("n2", "Wormholes and Ensembles", "Maldacena, Juan", 2021),
+                ("n3", "Unrelated Wetland Ecology", "Juanita Maldacena", 2022),
+                ("n4", "Holography Without Strings", "Someone Else", 2023),
+                ("n5", "Early Supergravity Work", "Juan Maldacena", 1997),
+            ];
+            works
+                .iter()
+                .map(|(id, title, author, year)| PaperResult {
+                    authors: vec!["Co Author".to_string(), author.to_string()],
+                    year: Some(*year),
+                    ..paper(id, title, None, None)
+                })
+                .collect::<Vec<_>>()
+                .into()
+        })
+    }
+
+    /// Source that resolves names to the first of several author profiles.
+    fn profile_source() -> MockSource {
+        MockSource::new("profiles").on_author(|query| AuthorWorks {
+            papers: vec![PaperResult {
+                authors: vec![query.name.clone()],
+                year: Some(2020),
+                ..paper("p1", "Chosen Profile Work", None, None)
+            }],
+            candidates: vec![query.name.clone(), "J. D. van der Waals".to_string(), "Jan Waals".to_string()],
+        })
+    }
+
+    #[tokio::test]
+    async fn test_author_search_reports_passed_over_profiles() {
+        let sources: Vec<Arc<dyn PaperSource>> = vec![Arc::new(profile_source())];
+        let query = AuthorQuery::new("Johannes Diderik van der Waals");
+        let results = author_search(&sources, &query, 10, None, DEFAULT_SOURCE_TIMEOUT).await;
+        assert_eq!(results.results.len(), 1);
+        // "Jan Waals" has another surname; the initials match
+        assert_eq!(results.other_profiles, vec![("profiles".to_string(), "J. D. van der Waals".to_string())]);
+        assert!(results.ambiguous);
+    }
+
+    #[test]
+    fn test_split_name_keeps_surname_particles() {
+        let waals = (vec!["johannes".to_string(), "diderik".to_string()], "van der waals".to_string());
+        assert_eq!(split_name("Johannes Diderik van der Waals"), waals);
+        assert_eq!(split_name("van der Waals, Johannes Diderik"), waals);
+        assert_eq!(split_name("Oscar de la Hoya").1, "de la hoya");
+        // A leading particle-like word stays a given name
+        assert_eq!(split_name("Van Morrison"), (vec!["van".to_string()], "morrison".to_string()));
+        assert_eq!(name_confidence(&split_name("J. D. van der Waals"), &split_name("van der Waals, Johannes")), 0.8);
+    }
+
+    #[tokio::test]
+    async fn test_author_search_ranks_by_name_confidence() {
+        let sources: Vec<Arc<dyn PaperSource>> = vec![Arc::new(name_source())];
+        let query = AuthorQuery::new("Juan Maldacena").with_years(Some(2000), None);
+        let results = author_search(&sources, &query, 10, None, DEFAULT_SOURCE_TIMEOUT).await;
+        let ids: Vec<&str> = results.results.iter().map(|m| m.paper.id.as_str()).collect();
+        // Exact name first, initials next; a different first name, another
+        // surname and the out-of-range year are dropped
+        assert_eq!(ids, vec!["n2", "n1"]);
+        assert_eq!(results.results[0].name_confidence, 1.0);
+        assert_eq!(results.results[1].matched_author, "J. Maldacena");
+        assert_eq!(results.name_variants, vec!["J. Maldacena", "Maldacena, Juan"]);
+        assert!(!results.ambiguous);
+        assert!(results.source_errors.is_empty());
+
+        // A bare surname can't tell Juan from Juanita
+        let results = author_search(&sources, &AuthorQuery::new("Maldacena"), 10, None, DEFAULT_SOURCE_TIMEOUT).await;
+        assert_eq!(results.results.len(), 4);
+        assert!(results.ambiguous);
+    }
+
+    #[tokio::test]
+    async fn test_health_check_reports_each_source() {
+        let sources: Vec<Arc<dyn PaperSource>> = vec![
+            Arc::new(paged_source()),
+            Arc::new(failing_source()),
+            Arc::new(hanging_source()),
+        ];
+        let started = std::time::Instant::now();
+        let health = health_check(&sources, Duration::from_millis(50)).await;
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let names: Vec<&str> = health.iter().map(|h| h.name.as_str()).collect();
+        assert_eq!(names, vec!["paged", "failing", "hanging"]);
+        assert!(health[0].reachable && health[0].error.is_none());
+        assert!(!health[1].reachable);
+        assert!(health[1].error.as_deref().unwrap().contains("upstream timed out"));
+        assert!(!health[2].reachable);
+        assert!(health[2].error.as_deref().unwrap().contains("timed out after 50ms"));
+    }
+
+    #[tokio::test]
+    async fn test_client_side_offset_skips_results() {
+        let source = paged_source();
+        let page = source.search_page("anything", 2, 3).await.unwrap();
+        let ids: Vec<&str> = page.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, vec!["p3", "p4"]);
+    }
+
+    /// Source with five fixed results and no server-side paging.
+    fn paged_source() -> MockSource {
+        MockSource::new("paged")
+            .on_search(|_| (0..5).map(|i| paper(&format!("p{}", i), &format!("Paper {}", i), None, None)).collect())
+    }
 }